@@ -0,0 +1,138 @@
+// Zip archives of directories, built in memory from entries the caller reads
+// a chunk at a time, and the entries of uploaded archives to extract. Kept free of
+// process_lib like path.rs.
+
+use std::io::{Cursor, Read, Write};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Most file content one archive may hold, before compression; the whole
+/// archive is built or unpacked in memory, so this bounds what a download or
+/// an extraction can cost
+pub const MAX_ARCHIVE_CONTENT_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Builds a zip archive one entry at a time. Entry names are relative and use
+/// `/`; a directory is named with a trailing `/` so an empty one survives
+pub struct ArchiveBuilder {
+    writer: ZipWriter<Cursor<Vec<u8>>>,
+    options: SimpleFileOptions,
+}
+
+impl ArchiveBuilder {
+    pub fn new() -> Self {
+        ArchiveBuilder {
+            writer: ZipWriter::new(Cursor::new(Vec::new())),
+            options: SimpleFileOptions::default().compression_method(CompressionMethod::Deflated),
+        }
+    }
+
+    pub fn add_directory(&mut self, name: &str) -> Result<(), String> {
+        self.writer
+            .add_directory(format!("{}/", name.trim_end_matches('/')), self.options)
+            .map_err(|e| format!("Failed to add directory '{}' to the archive: {}", name, e))
+    }
+
+    /// Begin the file `name`; its content follows in `write` calls, so a
+    /// file is added without holding all of it at once
+    pub fn start_file(&mut self, name: &str) -> Result<(), String> {
+        self.writer
+            .start_file(name, self.options)
+            .map_err(|e| format!("Failed to add '{}' to the archive: {}", name, e))
+    }
+
+    /// Append `chunk` to the file last started, `name`
+    pub fn write(&mut self, name: &str, chunk: &[u8]) -> Result<(), String> {
+        self.writer
+            .write_all(chunk)
+            .map_err(|e| format!("Failed to add '{}' to the archive: {}", name, e))
+    }
+
+    /// The finished archive's bytes
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        self.writer
+            .finish()
+            .map(Cursor::into_inner)
+            .map_err(|e| format!("Failed to finish the archive: {}", e))
+    }
+}
+
+/// One entry of an archive being extracted: a relative path with only plain
+/// names in it, and the content of a file
+pub struct ArchiveEntry {
+    pub path: String,
+    pub is_directory: bool,
+    pub content: Vec<u8>,
+}
+
+/// Every entry of the zip archive `bytes`, in archive order. An archive with
+/// an entry that would land outside the directory it is extracted into (an
+/// absolute path or a `..`) is refused as a whole, as is one that unpacks to
+/// more than MAX_ARCHIVE_CONTENT_BYTES.
+pub fn read_archive(bytes: &[u8]) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Not a zip archive: {}", e))?;
+
+    let mut entries = Vec::new();
+    let mut total = 0;
+    for index in 0..archive.len() {
+        let mut file = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", index, e))?;
+        let path = entry_path(file.name())?;
+        if path.is_empty() {
+            continue;
+        }
+        if file.is_dir() {
+            entries.push(ArchiveEntry {
+                path,
+                is_directory: true,
+                content: Vec::new(),
+            });
+            continue;
+        }
+
+        // The sizes in the archive are the archive's word for it; the read
+        // itself is cut off at the cap too
+        let allowed = MAX_ARCHIVE_CONTENT_BYTES - total;
+        let mut content = Vec::new();
+        (&mut file)
+            .take(allowed + 1)
+            .read_to_end(&mut content)
+            .map_err(|e| format!("Failed to unzip '{}': {}", path, e))?;
+        total += content.len() as u64;
+        if total > MAX_ARCHIVE_CONTENT_BYTES {
+            return Err(format!(
+                "Archive unpacks to more than {} MB",
+                MAX_ARCHIVE_CONTENT_BYTES / (1024 * 1024)
+            ));
+        }
+        entries.push(ArchiveEntry {
+            path,
+            is_directory: false,
+            content,
+        });
+    }
+    Ok(entries)
+}
+
+// The relative path an entry name stands for, without empty or `.` segments
+fn entry_path(name: &str) -> Result<String, String> {
+    if name.starts_with('/') || name.contains('\\') || name.contains('\0') {
+        return Err(format!("Archive entry '{}' is not a relative path", name));
+    }
+    let mut segments = Vec::new();
+    for segment in name.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                return Err(format!(
+                    "Archive entry '{}' points outside the destination",
+                    name
+                ))
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Ok(segments.join("/"))
+}
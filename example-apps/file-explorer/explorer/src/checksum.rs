@@ -0,0 +1,41 @@
+// File checksums, fed a file's bytes a chunk at a time. Kept free of
+// process_lib like path.rs.
+
+use sha2::{Digest, Sha256};
+
+use crate::ChecksumAlgorithm;
+
+/// A checksum being computed with one of the ChecksumAlgorithm choices
+pub enum Checksum {
+    Sha256(Sha256),
+    Md5(md5::Context),
+}
+
+impl Checksum {
+    pub fn new(algorithm: &ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => Checksum::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Md5 => Checksum::Md5(md5::Context::new()),
+        }
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        match self {
+            Checksum::Sha256(hasher) => hasher.update(chunk),
+            Checksum::Md5(context) => context.consume(chunk),
+        }
+    }
+
+    /// The checksum in lowercase hex
+    pub fn finish(self) -> String {
+        match self {
+            Checksum::Sha256(hasher) => hex::encode(hasher.finalize()),
+            Checksum::Md5(context) => format!("{:x}", context.compute()),
+        }
+    }
+}
+
+/// The SHA-256 of `bytes` in lowercase hex, for X-Checksum-SHA256
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
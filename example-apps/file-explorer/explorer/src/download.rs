@@ -0,0 +1,109 @@
+// Which URLs import_from_url fetches and what it names the file it saves.
+// Kept free of process_lib like path.rs.
+
+use url::Url;
+
+use crate::path::MAX_NAME_LEN;
+
+/// Largest body import_from_url saves; a longer download is refused
+pub const MAX_DOWNLOAD_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Parse a URL to download, refusing anything but http and https with a host
+pub fn parse_download_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid URL '{url}': {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!(
+            "Invalid URL '{url}': only http and https can be imported"
+        ));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(format!("Invalid URL '{url}': no host"));
+    }
+    Ok(parsed)
+}
+
+/// The name to save a download from `url` under: the filename in the
+/// response's Content-Disposition when it has one, else the last segment of
+/// the URL's path. None when neither gives a usable name, so the caller has
+/// to be told one
+pub fn download_filename(url: &Url, disposition: Option<&str>) -> Option<String> {
+    disposition
+        .and_then(disposition_filename)
+        .or_else(|| {
+            url.path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .map(percent_decode)
+        })
+        .and_then(|name| usable_name(&name))
+}
+
+/// A name the download may be saved under as given: one segment, not `.` or
+/// `..`, and no longer than a VFS name may be
+pub fn usable_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    let unusable = name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains(['/', '\\', '\0'])
+        || name.len() > MAX_NAME_LEN;
+    (!unusable).then(|| name.to_string())
+}
+
+/// The filename a Content-Disposition header names. The RFC 6266
+/// `filename*=UTF-8''...` form wins over a plain `filename=`, quoted or not
+fn disposition_filename(header: &str) -> Option<String> {
+    let mut plain = None;
+    for param in header.split(';').skip(1) {
+        let Some((key, value)) = param.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "filename*" => {
+                let encoded = value
+                    .split_once('\'')
+                    .filter(|(charset, _)| charset.eq_ignore_ascii_case("utf-8"))
+                    .and_then(|(_, rest)| rest.split_once('\''));
+                if let Some((_, encoded)) = encoded {
+                    return Some(percent_decode(encoded));
+                }
+            }
+            "filename" => {
+                let unquoted = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                plain = Some(unquoted.replace("\\\"", "\""));
+            }
+            _ => {}
+        }
+    }
+    plain
+}
+
+/// `%XX` escapes decoded; a bad escape is kept as written and bytes that are
+/// not UTF-8 become U+FFFD
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -1,17 +1,333 @@
+use app_error::AppError;
 use hyperprocess_macro::hyperprocess;
-use hyperware_process_lib::hyperapp::{add_response_header, get_path, send, SaveOptions};
+use hyperware_process_lib::http::client::{send_request_await_response, HttpClientError};
+use hyperware_process_lib::http::server::WsMessageType;
+use hyperware_process_lib::http::{Method, StatusCode};
+use hyperware_process_lib::hyperapp::{
+    add_response_header, get_path, get_request_header, send, set_response_status, sleep, source,
+    AppSendError, SaveOptions,
+};
 use hyperware_process_lib::logging::{debug, error, info, init_logging, Level};
-use hyperware_process_lib::our;
 use hyperware_process_lib::vfs::{
-    self, create_drive, vfs_request, FileType, VfsAction, VfsResponse,
+    self, create_drive, vfs_request, FileType, SeekFrom, VfsAction, VfsError, VfsResponse,
+};
+use hyperware_process_lib::{our, LazyLoadBlob, SendErrorKind};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+mod archive;
+use archive::{read_archive, ArchiveBuilder, MAX_ARCHIVE_CONTENT_BYTES};
+
+mod checksum;
+use checksum::{sha256_hex, Checksum};
+
+mod download;
+use download::{download_filename, parse_download_url, usable_name, MAX_DOWNLOAD_BYTES};
+
+mod mime;
+use mime::{detect_mime, MIME_SNIFF_BYTES};
+
+mod path;
+use path::{check_package, normalize_path, same_drive, MAX_NAME_LEN};
+
+mod progress;
+use progress::{Operations, ProgressStatus};
+
+mod range;
+use range::{parse_range, ByteRange};
+
+mod thumbnail;
+use thumbnail::{make_thumbnail, MAX_THUMBNAIL_DIMENSION, MAX_THUMBNAIL_SOURCE_BYTES};
+
+mod search;
+use search::{
+    glob_match, looks_binary, name_matches, wanted_extension, LineMatcher, MAX_HITS,
+    MAX_HITS_PER_FILE, MAX_SEARCH_DEPTH, MAX_SEARCH_VISITED,
 };
-use std::collections::HashMap;
+
+mod watch;
+use watch::{is_at_or_below, ChangeEvent, ChangeKind, Watchers};
 
 const ICON: &str = include_str!("./icon");
 const PROCESS_ID_LINK: &str = "explorer:file-explorer:sys";
 
+// Most entries list_directory_paged returns in one page
+const MAX_PAGE_LIMIT: u32 = 1000;
+
+// Most entries one get_tree call lists
+const MAX_TREE_ENTRIES: u32 = 10_000;
+
+// Bytes read from the VFS per chunk while searching or hashing a file's
+// contents
+const CONTENT_CHUNK_BYTES: u64 = 64 * 1024;
+
+// Bytes a file copy reads and writes at a time, so copying a file of any
+// size holds no more than this much of it
+const COPY_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+// Error code for a move or copy whose destination directory does not exist
+const PARENT_NOT_FOUND: &str = "parent_not_found";
+
+// Error code for a directory too big to download as one zip archive
+const ARCHIVE_TOO_LARGE: &str = "archive_too_large";
+
+// Error code for a share link that has been downloaded as many times as it
+// allows
+const LINK_EXHAUSTED: &str = "link_exhausted";
+
+// Error code for an import whose server failed or answered with an error
+// status other than 404
+const UPSTREAM_ERROR: &str = "upstream_error";
+
+// Error code for an import longer than MAX_DOWNLOAD_BYTES
+const DOWNLOAD_TOO_LARGE: &str = "download_too_large";
+
+// Error code for an operation stopped by cancel_operation
+const CANCELLED: &str = "cancelled";
+
+// How long import_from_url waits for the whole response
+const IMPORT_TIMEOUT_SECS: u64 = 60;
+
+// Where deleted entries wait to be restored or purged, on the home drive:
+// each under a directory named after its trash id
+const TRASH_DIR: &str = ".trash";
+
+// Where update_file keeps earlier contents while versioning is on, on the
+// home drive: one directory per file, named by a hash of its path, holding
+// one file per version named by when it was replaced
+const VERSIONS_DIR: &str = ".versions";
+
+// Versions kept per file when max_versions is unset
+const DEFAULT_MAX_VERSIONS: u32 = 5;
+
+// Most versions set_max_versions allows per file
+const MAX_MAX_VERSIONS: u32 = 100;
+
+// Where get_thumbnail caches what it made, on the home drive: one directory
+// per image, named by a hash of its path, so a change to the image can drop
+// them all
+const THUMBNAILS_DIR: &str = ".thumbnails";
+
+// Highest `(n)` ConflictPolicy::Rename tries before giving up
+const MAX_RENAME_SUFFIX: u32 = 1000;
+
+// Most bytes one read_file_range call returns
+const MAX_RANGE_BYTES: u64 = 4 * 1024 * 1024;
+
+// Most paths one batch_operation takes
+const MAX_BATCH_PATHS: usize = 1000;
+
+// Most favorites add_favorite keeps
+const MAX_FAVORITES: usize = 50;
+
+// Most tags set_tags puts on one path, and the longest tag it takes
+const MAX_TAGS_PER_PATH: usize = 32;
+const MAX_TAG_LEN: usize = 64;
+
+// Most entries the activity log keeps; the oldest go first
+const MAX_ACTIVITY_ENTRIES: usize = 1000;
+
+// Most files get_recent_files remembers; the least recently touched go first
+const MAX_RECENT_FILES: usize = 30;
+
+// Link ids of used-up shares remembered, so their links answer
+// link_exhausted rather than not_found; the oldest are forgotten first
+const MAX_EXHAUSTED_SHARES: usize = 1000;
+
+// normalize_path for handlers, also refusing the drives of other packages:
+// a path either rejects is the caller's mistake
+fn checked_path(path: &str) -> Result<String, AppError> {
+    let path = normalize_path(path).map_err(AppError::invalid_input)?;
+    check_package(&path, &our().package_id().to_string()).map_err(AppError::invalid_input)?;
+    Ok(path)
+}
+
+// Map a VFS failure to a shared error code by its variant
+fn vfs_error(context: &str, e: VfsError) -> AppError {
+    let code = match e {
+        VfsError::NoReadCap | VfsError::NoWriteCap | VfsError::AddCapFailed => {
+            app_error::NOT_PERMITTED
+        }
+        VfsError::ParseError { .. } => app_error::INVALID_INPUT,
+        VfsError::SendError(SendErrorKind::Timeout) => app_error::TIMEOUT,
+        _ => app_error::INTERNAL,
+    };
+    AppError::new(code, format!("{}: {}", context, e))
+}
+
+// Map the failed lookup of a path that should already be there. The VFS
+// answers a missing path with an IOError, so here that is not_found
+fn lookup_error(context: &str, e: VfsError) -> AppError {
+    match e {
+        VfsError::IOError(_) => AppError::not_found(format!("{}: {}", context, e)),
+        e => vfs_error(context, e),
+    }
+}
+
+// Map a request to the VFS that never got an answer
+fn send_error(context: &str, e: AppSendError) -> AppError {
+    let code = match &e {
+        AppSendError::SendError(e) if matches!(e.kind, SendErrorKind::Timeout) => {
+            app_error::TIMEOUT
+        }
+        _ => app_error::INTERNAL,
+    };
+    AppError::new(code, format!("{}: {}", context, e))
+}
+
+// Describe what is at `vfs_path` without reading it
+async fn file_info(vfs_path: &str) -> Result<FileInfo, AppError> {
+    let meta = vfs::metadata(vfs_path, Some(5))
+        .await
+        .map_err(|e| lookup_error(&format!("Failed to get metadata for '{}'", vfs_path), e))?;
+    Ok(FileInfo {
+        name: vfs_path.split('/').last().unwrap_or("").to_string(),
+        path: vfs_path.to_string(),
+        size: meta.len,
+        created: 0,
+        modified: 0,
+        is_directory: meta.file_type == FileType::Directory,
+        permissions: "rw".to_string(),
+    })
+}
+
+// The next chunk of the file `file`, open at `vfs_path` and `len` bytes
+// long: up to COPY_CHUNK_BYTES of it from `offset`. A file that ends before
+// `len` is an error rather than an empty chunk
+fn read_chunk(
+    file: &mut vfs::File,
+    vfs_path: &str,
+    offset: u64,
+    len: u64,
+) -> Result<Vec<u8>, AppError> {
+    let mut chunk = vec![0u8; (len - offset).min(COPY_CHUNK_BYTES) as usize];
+    let read = file
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| file.read_at(&mut chunk))
+        .map_err(|e| {
+            vfs_error(
+                &format!("Failed to read '{}' at offset {}", vfs_path, offset),
+                e,
+            )
+        })?;
+    if read == 0 {
+        return Err(AppError::internal(format!(
+            "'{}' ended at {} bytes, short of {}",
+            vfs_path, offset, len
+        )));
+    }
+    chunk.truncate(read);
+    Ok(chunk)
+}
+
+// The whole content of the file at `vfs_path`
+async fn read_contents(vfs_path: &str) -> Result<Vec<u8>, AppError> {
+    let file = vfs::open_file(vfs_path, false, Some(5))
+        .map_err(|e| lookup_error("Failed to open file", e))?;
+
+    match file.read() {
+        Ok(content) => Ok(content),
+        // An empty file can come back without any bytes to read
+        Err(e) => match file_info(vfs_path).await {
+            Ok(info) if info.size == 0 && !info.is_directory => Ok(Vec::new()),
+            _ => Err(vfs_error("Failed to read file", e)),
+        },
+    }
+}
+
+// The path a write to `vfs_path` should go to under `policy`: `vfs_path`
+// itself when it is free or may be replaced, else for Rename the first free
+// `name (n).ext` beside it. A directory is never replaced
+async fn resolve_conflict(vfs_path: &str, policy: ConflictPolicy) -> Result<String, AppError> {
+    let existing = match file_info(vfs_path).await {
+        Ok(existing) => existing,
+        Err(e) if e.code == app_error::NOT_FOUND => return Ok(vfs_path.to_string()),
+        Err(e) => return Err(e),
+    };
+    match policy {
+        ConflictPolicy::Overwrite if !existing.is_directory => Ok(vfs_path.to_string()),
+        ConflictPolicy::Overwrite | ConflictPolicy::Fail => Err(AppError::invalid_input(format!(
+            "'{}' already exists",
+            vfs_path
+        ))),
+        ConflictPolicy::Rename => first_free(vfs_path, numbered_name).await,
+    }
+}
+
+// The first path beside `vfs_path` that is free, named `name_for(name, n)`
+// for `n` from 1 up to MAX_RENAME_SUFFIX
+async fn first_free(
+    vfs_path: &str,
+    name_for: impl Fn(&str, u32) -> String,
+) -> Result<String, AppError> {
+    let (parent, name) = vfs_path.rsplit_once('/').unwrap_or(("", vfs_path));
+    for n in 1..=MAX_RENAME_SUFFIX {
+        let candidate = checked_path(&format!("{}/{}", parent, name_for(name, n)))?;
+        match file_info(&candidate).await {
+            Ok(_) => {}
+            Err(e) if e.code == app_error::NOT_FOUND => return Ok(candidate),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(AppError::invalid_input(format!(
+        "'{}' and its first {} numbered names already exist",
+        vfs_path, MAX_RENAME_SUFFIX
+    )))
+}
+
+// `name` split before its extension. A leading dot starts a name rather than
+// an extension, so `.env` has none
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name, ""),
+    }
+}
+
+// `name` with ` (n)` before its extension: `notes (2).txt`, `.env (2)`
+fn numbered_name(name: &str, n: u32) -> String {
+    let (stem, extension) = split_extension(name);
+    format!("{} ({}){}", stem, n, extension)
+}
+
+// The name of duplicate_file's `n`th copy of `name`: `report copy.pdf`, then
+// `report copy 2.pdf`. A directory's name has no extension to keep apart
+fn copy_name(name: &str, n: u32, is_directory: bool) -> String {
+    let (stem, extension) = if is_directory {
+        (name, "")
+    } else {
+        split_extension(name)
+    };
+    match n {
+        1 => format!("{} copy{}", stem, extension),
+        _ => format!("{} copy {}{}", stem, n, extension),
+    }
+}
+
+// A move or copy into a directory that does not exist fails up front with
+// PARENT_NOT_FOUND, rather than with whatever the VFS says about the write
+async fn require_parent(vfs_path: &str) -> Result<(), AppError> {
+    let parent = match vfs_path.rsplit_once('/') {
+        Some((parent, _)) if !parent.is_empty() => parent,
+        _ => return Ok(()),
+    };
+    let missing = || {
+        AppError::new(
+            PARENT_NOT_FOUND,
+            format!("Destination directory '{}' does not exist", parent),
+        )
+    };
+    match file_info(parent).await {
+        Ok(info) if info.is_directory => Ok(()),
+        Ok(_) => Err(missing()),
+        Err(e) if e.code == app_error::NOT_FOUND => Err(missing()),
+        Err(e) => Err(e),
+    }
+}
+
+// Field names match the WIT record and the UI (snake_case), so generated
+// caller_utils clients can decode it
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
 pub struct FileInfo {
     pub name: String,
     pub path: String,
@@ -22,18 +338,319 @@ pub struct FileInfo {
     pub permissions: String,
 }
 
+// How list_directory_paged orders entries. Type puts directories first, then
+// files by extension
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum SortBy {
+    Name,
+    Size,
+    Modified,
+    Type,
+}
+
+// One page of list_directory_paged, and how many entries there are in all
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DirectoryPage {
+    pub entries: Vec<FileInfo>,
+    pub total: u64,
+}
+
+// An entry and, for a directory, what get_tree listed below it, by name.
+// `truncated` is set when the walk's depth or entry limit stopped it listing
+// everything below; a directory at the deepest level is never read, so it is
+// always truncated
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TreeNode {
+    pub info: FileInfo,
+    pub children: Vec<TreeNode>,
+    pub truncated: bool,
+}
+
+// What extract_archive wrote, and the files it left alone because they
+// already existed and overwriting was not asked for
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ExtractReport {
+    pub created: Vec<FileInfo>,
+    pub skipped: Vec<String>,
+}
+
+// What search_files found; truncated when it stopped before looking at the
+// whole tree, at max_results or at one of the search limits
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SearchResults {
+    pub files: Vec<FileInfo>,
+    pub truncated: bool,
+}
+
+// One line content search found: the file, the line number from 1, and the
+// line itself, trimmed and clipped
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SearchHit {
+    pub file: FileInfo,
+    pub line: u32,
+    pub snippet: String,
+}
+
+// How move_path moved something: one VFS rename within a drive, or a copy
+// and delete where the VFS would not rename
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum MoveMethod {
+    Renamed,
+    Copied,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MoveOutcome {
+    pub info: FileInfo,
+    pub method: MoveMethod,
+}
+
+// One of our drives, with how many entries it holds and their total size in
+// bytes; truncated when the walk behind the counts stopped early
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DriveInfo {
+    pub name: String,
+    pub path: String,
+    pub entries: u64,
+    pub bytes: u64,
+    pub truncated: bool,
+}
+
+// What get_drive_usage added up: the total size in bytes of the files under a
+// path, and whether the walk stopped before seeing all of them
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DriveUsage {
+    pub bytes: u64,
+    pub truncated: bool,
+}
+
+// What is below a directory: the total size in bytes of its files, how many
+// files and directories there are at any depth, and the largest file.
+// truncated when the walk behind the counts stopped early; errors counts
+// what could not be read or is neither a file nor a directory
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DirStats {
+    pub total_bytes: u64,
+    pub file_count: u64,
+    pub directory_count: u64,
+    pub largest_file: Option<FileInfo>,
+    pub truncated: bool,
+    pub errors: u64,
+}
+
+impl DirStats {
+    fn add_file(&mut self, info: FileInfo) {
+        self.file_count += 1;
+        self.total_bytes += info.size;
+        if self
+            .largest_file
+            .as_ref()
+            .is_none_or(|largest| info.size > largest.size)
+        {
+            self.largest_file = Some(info);
+        }
+    }
+}
+
+// What create_file and upload_file do when their path is already taken:
+// replace the file there, refuse, or write to the first free `name (n).ext`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ConflictPolicy {
+    Overwrite,
+    Fail,
+    Rename,
+}
+
+// What batch_operation does to each path. Delete moves to the trash, as the
+// UI's delete does; Move and Copy put each path, under its own name, into
+// the destination directory
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum BatchOp {
+    Delete,
+    Move,
+    Copy,
+}
+
+// A path batch_operation could not act on, and why
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BatchFailure {
+    pub path: String,
+    pub error: AppError,
+}
+
+// The paths batch_operation acted on and the ones it could not, each in the
+// order given
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BatchResult {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<BatchFailure>,
+}
+
+// Something delete_file or delete_directory moved to the trash; deleted_at
+// is in milliseconds since the Unix epoch
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TrashItem {
+    pub id: String,
+    pub original_path: String,
+    pub is_directory: bool,
+    pub deleted_at: u64,
+}
+
+// An earlier content of a file: when update_file replaced it, in milliseconds
+// since the Unix epoch, and its size
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct VersionInfo {
+    pub timestamp: u64,
+    pub size: u64,
+}
+
+// Hashes get_checksum can compute
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Md5,
+}
+
+// What an activity log entry records being done
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Activity {
+    Created,
+    Deleted,
+    Modified,
+    Moved,
+    Shared,
+    Unshared,
+    Downloaded,
+}
+
+// One thing done to a path, in milliseconds since the Unix epoch. `from` is
+// the old path of a moved entry; `source` is "http" for a request from the
+// UI or HTTP API, else the process, on whichever node, that asked
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ActivityEntry {
+    pub timestamp: u64,
+    pub operation: Activity,
+    pub path: String,
+    pub from: Option<String>,
+    pub source: String,
+}
+
+// A pinned location, and whether anything is at its path now. A favorite
+// whose target was deleted or moved away is kept, stale, until it is removed
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Favorite {
+    pub path: String,
+    pub label: String,
+    pub exists: bool,
+}
+
+// A favorite as saved; whether it exists is looked up when it is listed
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct SavedFavorite {
+    path: String,
+    label: String,
+}
+
+// Who may fetch a share. Public shares are served to anyone over HTTP;
+// private ones only to the listed nodes, and our own, through
+// fetch_shared_file
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum AuthScheme {
     Public,
-    Private,
+    Private(Vec<String>),
+}
+
+// A shared path's link id and who may fetch it, when it was shared, how
+// many times it has been downloaded and how many downloads it allows in all.
+// Shares saved before these were kept load as 0 and unlimited
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Share {
+    id: String,
+    auth: AuthScheme,
+    #[serde(default)]
+    created: u64,
+    #[serde(default)]
+    hits: u64,
+    #[serde(default)]
+    max_downloads: Option<u32>,
+}
+
+// One share as list_shares and get_share_link report it; `created` is in
+// milliseconds since the epoch, and `downloads_remaining` is absent for a
+// share without a limit
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ShareInfo {
+    pub path: String,
+    pub id: String,
+    pub link: String,
+    pub auth: AuthScheme,
+    pub created: u64,
+    pub hits: u64,
+    pub max_downloads: Option<u32>,
+    pub downloads_remaining: Option<u32>,
 }
 
+// Saved on every change, so shares and the working directory survive a
+// restart. Fields missing from an older saved state load empty
 #[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
 struct FileExplorerState {
-    // HashMap to track shared files and their auth schemes
-    shared_files: HashMap<String, AuthScheme>,
-    // Current working directory for the user
+    // Shared paths, with their link ids and auth schemes
+    #[serde(default)]
+    shared_files: HashMap<String, Share>,
+    // Link id -> shared path, so a link is looked up without a scan; rebuilt
+    // from shared_files on load rather than saved twice
+    #[serde(skip)]
+    share_paths: HashMap<String, String>,
+    // Link ids of shares removed by their last download, oldest first, at
+    // most MAX_EXHAUSTED_SHARES of them
+    #[serde(default)]
+    exhausted_shares: VecDeque<String>,
+    // Current working directory for the user; empty until init sets it
+    #[serde(default)]
     cwd: String,
+    // What is in the trash, by trash id
+    #[serde(default)]
+    trash: HashMap<String, TrashItem>,
+    // Whether update_file keeps what it overwrites
+    #[serde(default)]
+    versioning_enabled: bool,
+    // Versions kept per file; None means DEFAULT_MAX_VERSIONS
+    #[serde(default)]
+    max_versions: Option<u32>,
+    // Pinned locations, in the order they were added
+    #[serde(default)]
+    favorites: Vec<SavedFavorite>,
+    // Path -> its tags, normalized and sorted; a path without tags has no
+    // entry
+    #[serde(default)]
+    tags: HashMap<String, Vec<String>>,
+    // Whether listings, trees and searches show entries whose names start
+    // with `.`; off until set_show_hidden turns it on
+    #[serde(default)]
+    show_hidden: bool,
+    // Whether they also show the explorer's own directories, the trash, kept
+    // versions and thumbnail cache, while show_hidden is on
+    #[serde(default)]
+    show_system: bool,
+    // What was done to files and shares, oldest first, at most
+    // MAX_ACTIVITY_ENTRIES of it
+    #[serde(default)]
+    activity_log: VecDeque<ActivityEntry>,
+    // Files read or written through the file handlers, each once with when
+    // it was last touched, most recent first
+    #[serde(default)]
+    recent_files: VecDeque<(String, u64)>,
+    // Whether set_track_recent turned recent files off; they are tracked
+    // until it does
+    #[serde(default)]
+    recent_opt_out: bool,
+    // WebSocket channels watching directories for changes; channels do not
+    // outlive a restart
+    #[serde(skip)]
+    watchers: Watchers,
+    // Long-running operations in progress; none outlive a restart
+    #[serde(skip)]
+    operations: Operations,
 }
 
 #[hyperprocess(
@@ -53,7 +670,7 @@ struct FileExplorerState {
             config: HttpBindingConfig::default().authenticated(false),
         }
     ],
-    save_config = SaveOptions::Never,
+    save_config = SaveOptions::OnDiff,
     wit_world = "file-explorer-sys-v0",
 )]
 impl FileExplorerState {
@@ -61,20 +678,27 @@ impl FileExplorerState {
     async fn init(&mut self) {
         init_logging(Level::DEBUG, Level::INFO, None, None, None).unwrap();
 
-        // Create home drive for the user
+        self.index_shares();
+        info!("Loaded {} shares", self.shared_files.len());
+
+        // Create home drive for the user; a working directory saved before a
+        // restart is kept
         let package_id = our().package_id();
-        match create_drive(package_id.clone(), "home", Some(5)) {
+        let home = match create_drive(package_id.clone(), "home", Some(5)) {
             Ok(home_path) => {
                 info!("Created home drive at: {}", home_path);
-                self.cwd = home_path;
+                home_path
             }
             Err(e) => {
                 error!(
                     "Failed to create home drive: {:?}. Using root as default.",
                     e
                 );
-                self.cwd = "/".to_string();
+                "/".to_string()
             }
+        };
+        if self.cwd.is_empty() {
+            self.cwd = home;
         }
 
         hyperware_process_lib::homepage::add_to_homepage(
@@ -85,414 +709,3538 @@ impl FileExplorerState {
         );
     }
 
+    #[local]
     #[http]
-    async fn list_directory(&mut self, path: String) -> Result<Vec<FileInfo>, String> {
+    async fn list_directory(&mut self, path: String) -> Result<Vec<FileInfo>, AppError> {
         info!("list_directory called with path: {}", path);
 
         // For root path, read from VFS root to get all drives
         let vfs_path = if path == "/" || path.is_empty() {
             "/".to_string()
         } else {
-            path.clone()
+            checked_path(&path)?
         };
 
         // Just list the current directory - no recursion
-        list_directory_contents(&vfs_path).await
+        list_directory_contents(&vfs_path, self.visibility()).await
     }
 
+    // The drives of this package, by name, each with its usage
+    #[local]
     #[http]
-    async fn create_file(&mut self, path: String, content: Vec<u8>) -> Result<FileInfo, String> {
-        info!("create_file called with path: {}", path);
-
-        let vfs_path = path.clone();
-        debug!("VFS path: {}", vfs_path);
-
-        // Create file and write content
-        let file = vfs::create_file(&vfs_path, Some(5))
-            .map_err(|e| format!("Failed to create file: {}", e))?;
-
-        file.write(&content)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
-
-        // Get metadata for response
-        let meta = file
-            .metadata()
-            .map_err(|e| format!("Failed to get metadata: {}", e))?;
+    async fn list_drives(&mut self) -> Result<Vec<DriveInfo>, AppError> {
+        info!("list_drives called");
 
-        Ok(FileInfo {
-            name: path.split('/').last().unwrap_or("").to_string(),
-            path,
-            size: meta.len,
-            created: 0,
-            modified: 0,
-            is_directory: false,
-            permissions: "rw".to_string(),
-        })
+        let mut drives = Vec::new();
+        for (name, path) in package_drives()? {
+            let Walk {
+                entries, truncated, ..
+            } = match walk_tree(&path) {
+                Ok(walk) => walk,
+                Err(e) => {
+                    error!("list_drives: skipping '{}': {}", path, e.message);
+                    continue;
+                }
+            };
+            drives.push(DriveInfo {
+                name,
+                entries: entries.len() as u64,
+                bytes: files_size(&entries).await,
+                path,
+                truncated,
+            });
+        }
+        Ok(drives)
     }
 
+    // Create a drive of this package and return its path; a drive that
+    // already exists is left as it is
+    #[local]
     #[http]
-    async fn read_file(&mut self, path: String) -> Result<Vec<u8>, String> {
-        info!("read_file called with path: {}", path);
-
-        let vfs_path = path.clone();
-
-        let file = vfs::open_file(&vfs_path, false, Some(5))
-            .map_err(|e| format!("Failed to open file: {}", e))?;
+    async fn create_new_drive(&mut self, name: String) -> Result<String, AppError> {
+        info!("create_new_drive called with name: {}", name);
 
-        file.read()
-            .map_err(|e| format!("Failed to read file: {}", e))
+        if name.is_empty()
+            || name.contains('/')
+            || name.contains('\0')
+            || name == "."
+            || name == ".."
+            || name.len() > MAX_NAME_LEN
+        {
+            return Err(AppError::invalid_input(format!(
+                "Invalid drive name: '{}'",
+                name
+            )));
+        }
+        create_drive(our().package_id(), &name, Some(5))
+            .map_err(|e| vfs_error(&format!("Failed to create drive '{}'", name), e))
     }
 
+    // Sizes and counts of what is below the directory `path`, for a
+    // properties view; walked no deeper or wider than search_files walks
+    #[local]
     #[http]
-    async fn update_file(&mut self, path: String, content: Vec<u8>) -> Result<FileInfo, String> {
-        info!("update_file called with path: {}", path);
-
-        let vfs_path = path.clone();
+    async fn get_directory_stats(&mut self, path: String) -> Result<DirStats, AppError> {
+        info!("get_directory_stats called with path: {}", path);
 
-        let file = vfs::open_file(&vfs_path, false, Some(5))
-            .map_err(|e| format!("Failed to open file: {}", e))?;
+        let path = checked_path(&path)?;
+        if !file_info(&path).await?.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is not a directory",
+                path
+            )));
+        }
+        directory_stats(&path).await
+    }
 
-        file.write(&content)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
+    // get_directory_stats for each drive of this package, by drive name. A
+    // drive that cannot be read at all is left out
+    #[local]
+    #[http]
+    async fn get_storage_summary(&mut self) -> Result<Vec<(String, DirStats)>, AppError> {
+        let mut summary = Vec::new();
+        for (name, path) in package_drives()? {
+            match directory_stats(&path).await {
+                Ok(stats) => summary.push((name, stats)),
+                Err(e) => error!("get_storage_summary: skipping '{}': {}", path, e.message),
+            }
+        }
+        Ok(summary)
+    }
 
-        let meta = file
-            .metadata()
-            .map_err(|e| format!("Failed to get metadata: {}", e))?;
+    // The total size of the files under `path`, a drive or any directory in
+    // one, walked no deeper or wider than search_files walks
+    #[local]
+    #[http]
+    async fn get_drive_usage(&mut self, path: String) -> Result<DriveUsage, AppError> {
+        info!("get_drive_usage called with path: {}", path);
 
-        Ok(FileInfo {
-            name: path.split('/').last().unwrap_or("").to_string(),
-            path,
-            size: meta.len,
-            created: 0,
-            modified: 0,
-            is_directory: false,
-            permissions: "rw".to_string(),
+        let path = checked_path(&path)?;
+        let info = file_info(&path).await?;
+        if !info.is_directory {
+            return Ok(DriveUsage {
+                bytes: info.size,
+                truncated: false,
+            });
+        }
+        let Walk {
+            entries, truncated, ..
+        } = walk_tree(&path)?;
+        Ok(DriveUsage {
+            bytes: files_size(&entries).await,
+            truncated,
         })
     }
 
+    // Find files and directories under `root` whose names match `query`: a
+    // case-insensitive substring, or a glob with `*` and `?`. Entries hidden
+    // from listings, and everything in hidden directories, are not found
+    #[local]
     #[http]
-    async fn delete_file(&mut self, path: String) -> Result<bool, String> {
-        info!("delete_file called with path: {}", path);
+    async fn search_files(
+        &mut self,
+        query: String,
+        root: String,
+        max_results: u32,
+    ) -> Result<SearchResults, AppError> {
+        info!(
+            "search_files called with query: {}, root: {}, max_results: {}",
+            query, root, max_results
+        );
 
-        let vfs_path = path.clone();
+        if query.is_empty() {
+            return Err(AppError::invalid_input("Search query is empty"));
+        }
+        if max_results == 0 {
+            return Err(AppError::invalid_input("max_results must be at least 1"));
+        }
+        let root = checked_path(&root)?;
 
-        vfs::remove_file(&vfs_path, Some(5))
-            .await
-            .map_err(|e| format!("Failed to delete file: {}", e))?;
+        let Walk {
+            entries,
+            mut truncated,
+            ..
+        } = walk_tree(&root)?;
+        let visibility = self.visibility();
+        let mut files = Vec::new();
+        for (entry_path, _) in entries {
+            let name = entry_path.split('/').last().unwrap_or("");
+            if !name_matches(&query, name) || !visibility.is_shown_below(&root, &entry_path) {
+                continue;
+            }
+            if files.len() >= max_results as usize {
+                truncated = true;
+                break;
+            }
+            match file_info(&entry_path).await {
+                Ok(info) => files.push(info),
+                Err(e) => error!("search_files: skipping '{}': {}", entry_path, e.message),
+            }
+        }
 
-        Ok(true)
+        Ok(SearchResults { files, truncated })
     }
 
+    // Find the lines containing `query`, ignoring case, in the files under
+    // `root` with one of `extensions` (or a text extension when empty).
+    // Binary files and files hidden from listings are skipped, and the hits
+    // are capped per file and overall
+    #[local]
     #[http]
-    async fn create_directory(&mut self, path: String) -> Result<FileInfo, String> {
-        info!("create_directory called with path: {}", path);
+    async fn search_contents(
+        &mut self,
+        query: String,
+        root: String,
+        extensions: Vec<String>,
+    ) -> Result<Vec<SearchHit>, AppError> {
+        info!(
+            "search_contents called with query: {}, root: {}, extensions: {:?}",
+            query, root, extensions
+        );
 
-        let vfs_path = path.clone();
+        if query.is_empty() {
+            return Err(AppError::invalid_input("Search query is empty"));
+        }
+        let root = checked_path(&root)?;
 
-        let _dir = vfs::open_dir(&vfs_path, true, Some(5))
-            .map_err(|e| format!("Failed to create directory: {}", e))?;
+        let Walk { entries, .. } = walk_tree(&root)?;
+        let visibility = self.visibility();
+        let mut hits = Vec::new();
+        for (entry_path, file_type) in entries {
+            let name = entry_path.split('/').last().unwrap_or("");
+            if file_type == FileType::Directory
+                || !wanted_extension(name, &extensions)
+                || !visibility.is_shown_below(&root, &entry_path)
+            {
+                continue;
+            }
+            let limit = MAX_HITS_PER_FILE.min(MAX_HITS - hits.len());
+            let (file, lines) = match matching_lines(&entry_path, &query, limit).await {
+                Ok(found) => found,
+                Err(e) => {
+                    error!("search_contents: skipping '{}': {}", entry_path, e.message);
+                    continue;
+                }
+            };
+            for (line, snippet) in lines {
+                hits.push(SearchHit {
+                    file: file.clone(),
+                    line,
+                    snippet,
+                });
+            }
+            if hits.len() >= MAX_HITS {
+                break;
+            }
+        }
 
-        Ok(FileInfo {
-            name: path.split('/').last().unwrap_or("").to_string(),
-            path,
-            size: 0,
-            created: 0,
-            modified: 0,
-            is_directory: true,
-            permissions: "rw".to_string(),
-        })
+        Ok(hits)
     }
 
+    // `path` and what is below it, `max_depth` levels deep, for a tree view.
+    // At most `max_entries` entries are listed, no more than
+    // MAX_TREE_ENTRIES, the closest first
+    #[local]
     #[http]
-    async fn delete_directory(&mut self, path: String) -> Result<bool, String> {
-        info!("delete_directory called with path: {}", path);
-
-        let vfs_path = path.clone();
-        let timeout = 5;
-
-        // Create a VFS request with RemoveDirAll action to handle non-empty directories
-        let request = vfs_request(&vfs_path, VfsAction::RemoveDirAll).expects_response(timeout);
-
-        // Send the request and await response
-        let response: Result<VfsResponse, _> = send(request).await;
+    async fn get_tree(
+        &mut self,
+        path: String,
+        max_depth: u32,
+        max_entries: u32,
+    ) -> Result<TreeNode, AppError> {
+        let path = checked_path(&path)?;
+        if max_depth == 0 {
+            return Err(AppError::invalid_input("max_depth must be at least 1"));
+        }
 
-        match response {
-            Ok(VfsResponse::Ok) => Ok(true),
-            Ok(VfsResponse::Err(e)) => Err(format!("Failed to delete directory: {:?}", e)),
-            Ok(_) => Err("Unexpected response from VFS".to_string()),
-            Err(e) => Err(format!("Failed to send VFS request: {}", e)),
+        let info = file_info(&path).await?;
+        if !info.is_directory {
+            return Ok(TreeNode {
+                info,
+                children: Vec::new(),
+                truncated: false,
+            });
         }
+        let max_entries = max_entries.min(MAX_TREE_ENTRIES) as usize;
+        let (children, truncated) =
+            tree_below(&path, max_depth, max_entries, self.visibility()).await?;
+        Ok(TreeNode {
+            info,
+            children,
+            truncated,
+        })
     }
 
+    // One page of a directory's entries in a stable order, with the total so
+    // the caller can page through the rest. `filter` is a glob such as
+    // `*.rs` the names of files must match, ignoring case unless
+    // `case_sensitive`; directories are listed whatever their names unless
+    // `files_only` leaves them out
+    #[local]
     #[http]
-    async fn upload_file(
+    async fn list_directory_paged(
         &mut self,
         path: String,
-        filename: String,
-        content: Vec<u8>,
-    ) -> Result<FileInfo, String> {
-        let full_path = format!("{}/{}", path, filename);
-        self.create_file(full_path, content).await
-    }
+        offset: u32,
+        limit: u32,
+        sort_by: SortBy,
+        ascending: bool,
+        directories_first: bool,
+        filter: Option<String>,
+        files_only: bool,
+        case_sensitive: bool,
+    ) -> Result<DirectoryPage, AppError> {
+        info!(
+            "list_directory_paged called with path: {}, offset: {}, limit: {}, sort_by: {:?}, filter: {:?}",
+            path, offset, limit, sort_by, filter
+        );
 
+        if limit == 0 || limit > MAX_PAGE_LIMIT {
+            return Err(AppError::invalid_input(format!(
+                "limit must be between 1 and {}",
+                MAX_PAGE_LIMIT
+            )));
+        }
+        let vfs_path = if path == "/" || path.is_empty() {
+            "/".to_string()
+        } else {
+            checked_path(&path)?
+        };
+
+        let mut entries = directory_entries(&vfs_path, true).await?;
+        let visibility = self.visibility();
+        entries.retain(|entry| {
+            if !visibility.is_shown(&entry.path) {
+                false
+            } else if entry.is_directory {
+                !files_only
+            } else {
+                filter
+                    .as_deref()
+                    .is_none_or(|filter| glob_match(filter, &entry.name, case_sensitive))
+            }
+        });
+        sort_entries(&mut entries, &sort_by, ascending, directories_first);
+
+        Ok(DirectoryPage {
+            total: entries.len() as u64,
+            entries: entries
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect(),
+        })
+    }
+
+    // Write a new file at `path`. `conflict` says what to do when something
+    // is already there and defaults to Fail; the FileInfo names the path
+    // actually written, which Rename may have changed
+    #[local]
     #[http]
-    async fn share_file(&mut self, path: String, auth: AuthScheme) -> Result<String, String> {
-        // Generate share ID from path hash
-        let share_id = format!("{:x}", md5::compute(&path));
+    async fn create_file(
+        &mut self,
+        path: String,
+        content: Vec<u8>,
+        conflict: Option<ConflictPolicy>,
+    ) -> Result<FileInfo, AppError> {
+        info!("create_file called with path: {}", path);
 
-        // Add to shared_files HashMap
-        self.shared_files.insert(path.clone(), auth);
+        let vfs_path = checked_path(&path)?;
+        let vfs_path =
+            resolve_conflict(&vfs_path, conflict.unwrap_or(ConflictPolicy::Fail)).await?;
+        let info = self.write_file(vfs_path, content).await?;
+        self.touch_recent(&info.path);
+        Ok(info)
+    }
+
+    #[local]
+    #[http]
+    async fn read_file(&mut self, path: String) -> Result<Vec<u8>, AppError> {
+        info!("read_file called with path: {}", path);
 
-        // Return share link with full path
-        Ok(format!("/{PROCESS_ID_LINK}/shared/{share_id}"))
+        let vfs_path = checked_path(&path)?;
+        let content = read_contents(&vfs_path).await?;
+        self.touch_recent(&vfs_path);
+        Ok(content)
     }
 
+    // Up to `length` bytes of a file from `offset`, so a large file can be
+    // read a window at a time. A window running past the end comes back
+    // short; an offset past the end is an error
+    #[local]
     #[http]
-    async fn unshare_file(&mut self, path: String) -> Result<bool, String> {
-        Ok(self.shared_files.remove(&path).is_some())
+    async fn read_file_range(
+        &mut self,
+        path: String,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>, AppError> {
+        info!(
+            "read_file_range called with path: {}, offset: {}, length: {}",
+            path, offset, length
+        );
+
+        if length > MAX_RANGE_BYTES {
+            return Err(AppError::invalid_input(format!(
+                "length {} is over the {} MB read_file_range returns at once",
+                length,
+                MAX_RANGE_BYTES / (1024 * 1024)
+            )));
+        }
+        let vfs_path = checked_path(&path)?;
+        let info = file_info(&vfs_path).await?;
+        if info.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is a directory",
+                vfs_path
+            )));
+        }
+        if offset > info.size {
+            return Err(AppError::invalid_input(format!(
+                "offset {} is past the end of '{}', which is {} bytes",
+                offset, vfs_path, info.size
+            )));
+        }
+        let length = length.min(info.size - offset);
+        if length == 0 {
+            return Ok(Vec::new());
+        }
+        read_range(&vfs_path, offset, length).await
     }
 
+    // The checksum of a file in lowercase hex, read in chunks so a large
+    // file is never held in memory whole
+    #[local]
     #[http]
-    async fn get_share_link(&mut self, path: String) -> Result<Option<String>, String> {
-        // Check if file is shared
-        if self.shared_files.contains_key(&path) {
-            let share_id = format!("{:x}", md5::compute(&path));
-            Ok(Some(format!("/{PROCESS_ID_LINK}/shared/{share_id}")))
-        } else {
-            Ok(None)
+    async fn get_checksum(
+        &mut self,
+        path: String,
+        algorithm: ChecksumAlgorithm,
+    ) -> Result<String, AppError> {
+        info!(
+            "get_checksum called with path: {}, algorithm: {:?}",
+            path, algorithm
+        );
+
+        let vfs_path = checked_path(&path)?;
+        let info = file_info(&vfs_path).await?;
+        if info.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is a directory; only files have checksums",
+                vfs_path
+            )));
         }
+        file_checksum(&vfs_path, info.size, &algorithm).await
     }
 
+    // The content type of a file, from its leading bytes where they carry a
+    // known signature and from its extension otherwise
+    #[local]
     #[http]
-    async fn serve_shared_file(&mut self) -> Result<Vec<u8>, String> {
-        // Use get_path() to handle routing
-        let request_path = get_path();
+    async fn get_mime_type(&mut self, path: String) -> Result<String, AppError> {
+        info!("get_mime_type called with path: {}", path);
 
-        // Extract the file path from the request
-        if let Some(request_path_str) = request_path {
-            if let Some(share_id) = request_path_str.strip_prefix("/shared/") {
-                // Find the original path from share_id
-                for (path, auth_scheme) in &self.shared_files {
-                    if format!("{:x}", md5::compute(path)) == share_id {
-                        match auth_scheme {
-                            AuthScheme::Public => {
-                                // Extract filename from path
-                                let filename = path.split('/').last().unwrap_or("download");
-
-                                // Set Content-Disposition header to preserve original filename
-                                add_response_header(
-                                    "Content-Disposition".to_string(),
-                                    format!("attachment; filename=\"{}\"", filename),
-                                );
+        let vfs_path = checked_path(&path)?;
+        let info = file_info(&vfs_path).await?;
+        if info.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is a directory; only files have a MIME type",
+                vfs_path
+            )));
+        }
+        Ok(sniff_mime(&vfs_path, info.size).await?.to_string())
+    }
 
-                                // Set appropriate Content-Type based on file extension
-                                let content_type = match filename.split('.').last() {
-                                    Some("txt") => "text/plain",
-                                    Some("html") | Some("htm") => "text/html",
-                                    Some("css") => "text/css",
-                                    Some("js") => "application/javascript",
-                                    Some("json") => "application/json",
-                                    Some("png") => "image/png",
-                                    Some("jpg") | Some("jpeg") => "image/jpeg",
-                                    Some("gif") => "image/gif",
-                                    Some("pdf") => "application/pdf",
-                                    Some("zip") => "application/zip",
-                                    _ => "application/octet-stream",
-                                };
-                                add_response_header(
-                                    "Content-Type".to_string(),
-                                    content_type.to_string(),
-                                );
+    // Groups of two or more files under `root` with the same content, each
+    // sorted by path. Only files sharing a size are hashed. Empty files are
+    // all alike, so they form one group with `include_empty` and are left
+    // out otherwise. The trash and kept versions are not searched
+    #[local]
+    #[http]
+    async fn find_duplicates(
+        &mut self,
+        root: String,
+        include_empty: bool,
+    ) -> Result<Vec<Vec<FileInfo>>, AppError> {
+        info!(
+            "find_duplicates called with root: {}, include_empty: {}",
+            root, include_empty
+        );
 
-                                // Read and return file content
-                                return self.read_file(path.clone()).await;
-                            }
-                            AuthScheme::Private => {
-                                return Err("Access denied: Private file".to_string());
-                            }
-                        }
-                    }
+        let root = checked_path(&root)?;
+        let Walk {
+            entries, truncated, ..
+        } = walk_tree(&root)?;
+        if truncated {
+            info!("find_duplicates: walk of '{}' was cut short", root);
+        }
+
+        let mut by_size: HashMap<u64, Vec<FileInfo>> = HashMap::new();
+        for (entry_path, file_type) in entries {
+            if file_type != FileType::File || is_internal(&entry_path) {
+                continue;
+            }
+            match file_info(&entry_path).await {
+                Ok(info) => by_size.entry(info.size).or_default().push(info),
+                Err(e) => error!("find_duplicates: skipping '{}': {}", entry_path, e.message),
+            }
+        }
+
+        let mut groups = Vec::new();
+        for (size, files) in by_size {
+            if files.len() < 2 {
+                continue;
+            }
+            if size == 0 {
+                if include_empty {
+                    groups.push(files);
                 }
-                Err("File not found or not shared".to_string())
-            } else {
-                Err("Invalid shared file path".to_string())
+                continue;
             }
-        } else {
-            Err("No request path provided".to_string())
+            let mut by_hash: HashMap<String, Vec<FileInfo>> = HashMap::new();
+            for info in files {
+                match file_checksum(&info.path, size, &ChecksumAlgorithm::Sha256).await {
+                    Ok(hash) => by_hash.entry(hash).or_default().push(info),
+                    Err(e) => error!("find_duplicates: skipping '{}': {}", info.path, e.message),
+                }
+            }
+            groups.extend(by_hash.into_values().filter(|group| group.len() >= 2));
         }
+
+        for group in &mut groups {
+            group.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        groups.sort_by(|a, b| a[0].path.cmp(&b[0].path));
+        Ok(groups)
     }
 
+    // Replace a file's content; with versioning on, the old content is kept
+    // first, and the oldest versions beyond max_versions are pruned
+    #[local]
     #[http]
-    async fn get_current_directory(&mut self) -> Result<String, String> {
-        info!("get_current_directory called, returning: {}", self.cwd);
-        Ok(self.cwd.clone())
+    async fn update_file(&mut self, path: String, content: Vec<u8>) -> Result<FileInfo, AppError> {
+        info!("update_file called with path: {}", path);
+
+        let vfs_path = checked_path(&path)?;
+
+        let file = vfs::open_file(&vfs_path, false, Some(5))
+            .map_err(|e| lookup_error("Failed to open file", e))?;
+
+        if self.versioning_enabled {
+            self.save_version(&vfs_path).await?;
+        }
+
+        file.write(&content)
+            .map_err(|e| vfs_error("Failed to write file", e))?;
+        drop_thumbnails(&vfs_path).await;
+
+        let meta = file
+            .metadata()
+            .map_err(|e| lookup_error("Failed to get metadata", e))?;
+
+        let info = FileInfo {
+            name: vfs_path.split('/').last().unwrap_or("").to_string(),
+            path: vfs_path,
+            size: meta.len,
+            created: 0,
+            modified: 0,
+            is_directory: false,
+            permissions: "rw".to_string(),
+        };
+        self.notify(ChangeKind::Modified, &info.path, None, Some(&info));
+        self.touch_recent(&info.path);
+        Ok(info)
     }
 
+    // Add `content` to the end of a file with one VFS append, so the bytes of
+    // one call stay together even when other appends arrive alongside it. A
+    // missing file is created only with `create_if_missing`
+    #[local]
     #[http]
-    async fn set_current_directory(&mut self, path: String) -> Result<String, String> {
-        self.cwd = path.clone();
-        Ok(path)
+    async fn append_to_file(
+        &mut self,
+        path: String,
+        content: Vec<u8>,
+        create_if_missing: bool,
+    ) -> Result<FileInfo, AppError> {
+        info!(
+            "append_to_file called with path: {}, {} bytes, create_if_missing: {}",
+            path,
+            content.len(),
+            create_if_missing
+        );
+
+        let vfs_path = checked_path(&path)?;
+        match file_info(&vfs_path).await {
+            Ok(info) if info.is_directory => {
+                return Err(AppError::invalid_input(format!(
+                    "'{}' is a directory",
+                    vfs_path
+                )))
+            }
+            Ok(_) => {}
+            Err(e) if e.code == app_error::NOT_FOUND && create_if_missing => {
+                require_parent(&vfs_path).await?;
+                return self.write_file(vfs_path, content).await;
+            }
+            Err(e) => return Err(e),
+        }
+
+        let mut file = vfs::open_file(&vfs_path, false, Some(5))
+            .map_err(|e| lookup_error("Failed to open file", e))?;
+        if !content.is_empty() {
+            file.append(&content)
+                .map_err(|e| vfs_error("Failed to append to file", e))?;
+        }
+        drop_thumbnails(&vfs_path).await;
+
+        let info = file_info(&vfs_path).await?;
+        self.notify(ChangeKind::Modified, &vfs_path, None, Some(&info));
+        Ok(info)
     }
 
+    // Cut a file down to its first `new_len` bytes. It cannot grow this way;
+    // with versioning on, the content cut off is kept like update_file keeps it
+    #[local]
     #[http]
-    async fn move_file(&mut self, source: String, destination: String) -> Result<FileInfo, String> {
-        // Read file content
-        let content = self.read_file(source.clone()).await?;
+    async fn truncate_file(&mut self, path: String, new_len: u64) -> Result<FileInfo, AppError> {
+        info!(
+            "truncate_file called with path: {}, new_len: {}",
+            path, new_len
+        );
 
-        // Create file at destination
-        let file_info = self.create_file(destination, content).await?;
+        let vfs_path = checked_path(&path)?;
+        let info = file_info(&vfs_path).await?;
+        if info.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is a directory",
+                vfs_path
+            )));
+        }
+        if new_len > info.size {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is {} bytes; truncate_file cannot make it {}",
+                vfs_path, info.size, new_len
+            )));
+        }
+        if new_len == info.size {
+            return Ok(info);
+        }
 
-        // Delete source file
-        self.delete_file(source).await?;
+        if self.versioning_enabled {
+            self.save_version(&vfs_path).await?;
+        }
+        let mut file = vfs::open_file(&vfs_path, false, Some(5))
+            .map_err(|e| lookup_error("Failed to open file", e))?;
+        file.set_len(new_len)
+            .map_err(|e| vfs_error("Failed to truncate file", e))?;
+        drop_thumbnails(&vfs_path).await;
 
-        Ok(file_info)
+        let info = file_info(&vfs_path).await?;
+        self.notify(ChangeKind::Modified, &vfs_path, None, Some(&info));
+        Ok(info)
     }
 
+    // A JPEG preview of a PNG, JPEG or GIF image, scaled down to fit in
+    // `max_dimension` on both sides. Thumbnails are cached by path, size and
+    // dimension; update_file, deletes and moves drop the cached ones
+    #[local]
     #[http]
-    async fn copy_file(&mut self, source: String, destination: String) -> Result<FileInfo, String> {
-        // Read file content
-        let content = self.read_file(source).await?;
+    async fn get_thumbnail(
+        &mut self,
+        path: String,
+        max_dimension: u32,
+    ) -> Result<Vec<u8>, AppError> {
+        info!(
+            "get_thumbnail called with path: {}, max_dimension: {}",
+            path, max_dimension
+        );
+
+        if max_dimension == 0 || max_dimension > MAX_THUMBNAIL_DIMENSION {
+            return Err(AppError::invalid_input(format!(
+                "max_dimension must be from 1 to {}, not {}",
+                MAX_THUMBNAIL_DIMENSION, max_dimension
+            )));
+        }
+        let vfs_path = checked_path(&path)?;
+        let info = file_info(&vfs_path).await?;
+        if info.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is a directory, not an image",
+                vfs_path
+            )));
+        }
+        if info.size > MAX_THUMBNAIL_SOURCE_BYTES {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is over {} MB, too big to make a thumbnail of",
+                vfs_path,
+                MAX_THUMBNAIL_SOURCE_BYTES / (1024 * 1024)
+            )));
+        }
+        // Checked before the whole file is read
+        let mime = sniff_mime(&vfs_path, info.size).await?;
+        if !matches!(mime, "image/png" | "image/jpeg" | "image/gif") {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is {}, not a PNG, JPEG or GIF image",
+                vfs_path, mime
+            )));
+        }
+
+        // The size guards against a cached thumbnail that outlived a change
+        // made around update_file, such as a new file at a moved file's path
+        let cached = format!(
+            "{}/{}-{}.jpg",
+            thumbnails_dir(&vfs_path),
+            info.size,
+            max_dimension
+        );
+        if let Ok(thumbnail) = read_contents(&cached).await {
+            return Ok(thumbnail);
+        }
+
+        let content = read_contents(&vfs_path).await?;
+        let thumbnail = make_thumbnail(&content, max_dimension)
+            .map_err(|e| AppError::invalid_input(format!("'{}': {}", vfs_path, e)))?;
 
-        // Create file at destination
-        self.create_file(destination, content).await
+        // A thumbnail that cannot be cached is still returned
+        let saved = match self.create_parents(&cached).await {
+            Ok(()) => self.write_file(cached, thumbnail.clone()).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = saved {
+            error!(
+                "get_thumbnail: not cached for '{}': {}",
+                vfs_path, e.message
+            );
+        }
+        Ok(thumbnail)
     }
-}
 
-// Helper function to list directory contents with 2 levels of depth
-async fn list_directory_contents(path: &str) -> Result<Vec<FileInfo>, String> {
-    debug!("list_directory_contents: path='{}'", path);
+    // Turn versioning in update_file on or off; versions already kept stay
+    // listed and restorable either way
+    #[local]
+    #[http]
+    async fn set_versioning_enabled(&mut self, enabled: bool) -> Result<bool, AppError> {
+        info!("set_versioning_enabled called with enabled: {}", enabled);
+        self.versioning_enabled = enabled;
+        Ok(enabled)
+    }
 
-    // Open directory
-    let dir = vfs::Directory {
-        path: path.to_string(),
-        timeout: 5,
-    };
+    // How many versions to keep per file, from 1 to MAX_MAX_VERSIONS. Files
+    // with more are pruned the next time they are updated
+    #[local]
+    #[http]
+    async fn set_max_versions(&mut self, count: u32) -> Result<u32, AppError> {
+        if count == 0 || count > MAX_MAX_VERSIONS {
+            return Err(AppError::invalid_input(format!(
+                "max_versions must be from 1 to {}, not {}",
+                MAX_MAX_VERSIONS, count
+            )));
+        }
+        self.max_versions = Some(count);
+        Ok(count)
+    }
 
-    // Read directory entries
-    let entries = dir
-        .read()
-        .map_err(|e| format!("Failed to read directory '{}': {}", path, e))?;
+    // Show or hide entries whose names start with `.` in listings, trees and
+    // searches
+    #[local]
+    #[http]
+    async fn set_show_hidden(&mut self, show_hidden: bool) -> Result<bool, AppError> {
+        self.show_hidden = show_hidden;
+        Ok(show_hidden)
+    }
+
+    #[local]
+    #[http]
+    async fn get_show_hidden(&mut self) -> Result<bool, AppError> {
+        Ok(self.show_hidden)
+    }
 
-    debug!("VFS returned {} entries for path '{}'", entries.len(), path);
+    // Show or hide the explorer's own directories, such as the trash. They
+    // are hidden entries too, so only appear while show_hidden is on as well
+    #[local]
+    #[http]
+    async fn set_show_system(&mut self, show_system: bool) -> Result<bool, AppError> {
+        self.show_system = show_system;
+        Ok(show_system)
+    }
 
-    let mut all_files = Vec::new();
+    // The kept versions of a file, newest first
+    #[local]
+    #[http]
+    async fn list_versions(&mut self, path: String) -> Result<Vec<VersionInfo>, AppError> {
+        let vfs_path = checked_path(&path)?;
+        file_versions(&vfs_path).await
+    }
+
+    // Put a kept version back as the file's content. With versioning on, the
+    // content it replaces becomes a version in turn, so a restore can be
+    // undone
+    #[local]
+    #[http]
+    async fn restore_version(
+        &mut self,
+        path: String,
+        timestamp: u64,
+    ) -> Result<FileInfo, AppError> {
+        info!(
+            "restore_version called with path: {}, timestamp: {}",
+            path, timestamp
+        );
+
+        let vfs_path = checked_path(&path)?;
+        let version_path = format!("{}/{}", versions_dir(&vfs_path), timestamp);
+        let content = match read_contents(&version_path).await {
+            Ok(content) => content,
+            Err(e) if e.code == app_error::NOT_FOUND => {
+                return Err(AppError::not_found(format!(
+                    "No version {} of '{}'",
+                    timestamp, vfs_path
+                )))
+            }
+            Err(e) => return Err(e),
+        };
+        self.update_file(vfs_path, content).await
+    }
 
-    // Convert to FileInfo - Level 1
-    for (i, entry) in entries.iter().enumerate() {
-        debug!(
-            "Entry[{}]: path='{}', file_type={:?}",
-            i, entry.path, entry.file_type
+    // Move a file to the trash, or with `permanent` delete it for good. Its
+    // tags go with it; a favorite of it is kept and listed as stale. A share
+    // of it is kept for a restore from the trash, and dropped with `permanent`
+    #[local]
+    #[http]
+    async fn delete_file(&mut self, path: String, permanent: bool) -> Result<bool, AppError> {
+        info!(
+            "delete_file called with path: {}, permanent: {}",
+            path, permanent
         );
 
-        // VFS already provides absolute paths in entry.path
-        let full_path = entry.path.clone();
+        let vfs_path = checked_path(&path)?;
+        if permanent {
+            remove_file(&vfs_path).await?;
+            self.drop_shares(&vfs_path);
+        } else {
+            self.move_to_trash(&vfs_path, false).await?;
+        }
+        drop_thumbnails(&vfs_path).await;
+        self.drop_tags(&vfs_path);
+        self.notify(ChangeKind::Deleted, &vfs_path, None, None);
+        Ok(true)
+    }
+
+    #[local]
+    #[http]
+    async fn create_directory(&mut self, path: String) -> Result<FileInfo, AppError> {
+        info!("create_directory called with path: {}", path);
 
-        // Extract filename from the path
-        let filename = entry.path.split('/').last().unwrap_or("").to_string();
+        let vfs_path = checked_path(&path)?;
 
-        debug!(
-            "Constructed: filename='{}', full_path='{}'",
-            filename, full_path
+        let _dir = vfs::open_dir(&vfs_path, true, Some(5))
+            .map_err(|e| vfs_error("Failed to create directory", e))?;
+
+        let info = FileInfo {
+            name: vfs_path.split('/').last().unwrap_or("").to_string(),
+            path: vfs_path,
+            size: 0,
+            created: 0,
+            modified: 0,
+            is_directory: true,
+            permissions: "rw".to_string(),
+        };
+        self.notify(ChangeKind::Created, &info.path, None, Some(&info));
+        Ok(info)
+    }
+
+    // Move a directory and everything in it to the trash, or with `permanent`
+    // delete it for good. Tags in it go with it; favorites in it are kept and
+    // listed as stale. Shares in it are kept for a restore from the trash, and
+    // dropped with `permanent`
+    #[local]
+    #[http]
+    async fn delete_directory(&mut self, path: String, permanent: bool) -> Result<bool, AppError> {
+        info!(
+            "delete_directory called with path: {}, permanent: {}",
+            path, permanent
         );
 
-        if entry.file_type == FileType::Directory {
-            // Get directory size
-            let sub_dir = vfs::Directory {
-                path: full_path.clone(),
-                timeout: 5,
-            };
+        let vfs_path = checked_path(&path)?;
+        if permanent {
+            remove_dir_all(&vfs_path).await?;
+            self.drop_shares(&vfs_path);
+        } else {
+            self.move_to_trash(&vfs_path, true).await?;
+        }
+        self.drop_tags(&vfs_path);
+        self.notify(ChangeKind::Deleted, &vfs_path, None, None);
+        Ok(true)
+    }
 
-            let dir_size = match sub_dir.read() {
-                Ok(contents) => {
-                    let count = contents.len() as u64;
-                    debug!("Directory '{}' has {} items", full_path, count);
-                    count
-                }
-                Err(e) => {
-                    error!("Failed to read subdirectory '{}': {}", full_path, e);
-                    0
-                }
-            };
+    // What is in the trash, most recently deleted first
+    #[local]
+    #[http]
+    async fn list_trash(&mut self) -> Result<Vec<TrashItem>, AppError> {
+        let mut items: Vec<TrashItem> = self.trash.values().cloned().collect();
+        items.sort_by(|a, b| {
+            b.deleted_at
+                .cmp(&a.deleted_at)
+                .then_with(|| a.original_path.cmp(&b.original_path))
+        });
+        Ok(items)
+    }
+
+    // Put a trashed entry back where it was deleted from, recreating missing
+    // parent directories. Something now at that path is only replaced with
+    // `force`. Shares of the path were left in place, so they work again
+    #[local]
+    #[http]
+    async fn restore_from_trash(
+        &mut self,
+        trash_id: String,
+        force: bool,
+    ) -> Result<FileInfo, AppError> {
+        info!(
+            "restore_from_trash called with trash_id: {}, force: {}",
+            trash_id, force
+        );
+
+        let Some(item) = self.trash.get(&trash_id).cloned() else {
+            return Err(AppError::not_found(format!("No trash item '{}'", trash_id)));
+        };
+        // Checked again, like a share's path, in case the saved state was
+        // edited: the id names a directory in the trash, and the original
+        // path's name the entry in it
+        let destination = checked_path(&item.original_path)?;
+        if destination != item.original_path || !is_random_id(&trash_id) {
+            return Err(AppError::invalid_input(format!(
+                "Trash item '{}' is not valid",
+                trash_id
+            )));
+        }
+        if let Ok(existing) = file_info(&destination).await {
+            if !force {
+                return Err(AppError::invalid_input(format!(
+                    "'{}' already exists; restore with force to replace it",
+                    destination
+                )));
+            }
+            if existing.is_directory {
+                remove_dir_all(&destination).await?;
+            } else {
+                remove_file(&destination).await?;
+            }
+        }
+        self.create_parents(&destination).await?;
+
+        self.relocate(&trashed_path(&item), item.is_directory, &destination)
+            .await?;
+        self.trash.remove(&trash_id);
+        remove_dir_all(&trash_entry_dir(&trash_id)).await?;
+        let info = file_info(&destination).await?;
+        self.notify(ChangeKind::Created, &destination, None, Some(&info));
+        Ok(info)
+    }
+
+    // Delete one trashed entry for good, and the shares it kept for a
+    // restore; false if there is no such item
+    #[local]
+    #[http]
+    async fn purge_trash_item(&mut self, trash_id: String) -> Result<bool, AppError> {
+        if !self.trash.contains_key(&trash_id) {
+            return Ok(false);
+        }
+        if !is_random_id(&trash_id) {
+            return Err(AppError::invalid_input(format!(
+                "Trash item '{}' is not valid",
+                trash_id
+            )));
+        }
+        remove_dir_all(&trash_entry_dir(&trash_id)).await?;
+        if let Some(item) = self.trash.remove(&trash_id) {
+            self.drop_purged_shares(&item.original_path).await;
+        }
+        Ok(true)
+    }
+
+    // Delete everything in the trash for good; returns how many items went
+    #[local]
+    #[http]
+    async fn empty_trash(&mut self) -> Result<u64, AppError> {
+        let ids: Vec<String> = self.trash.keys().cloned().collect();
+        for id in &ids {
+            self.purge_trash_item(id.clone()).await?;
+        }
+        Ok(ids.len() as u64)
+    }
+
+    // HTTP-only: the browser's upload form; other processes use create_file.
+    // `conflict` defaults to Rename, so a dropped file never replaces another
+    #[http]
+    async fn upload_file(
+        &mut self,
+        path: String,
+        filename: String,
+        content: Vec<u8>,
+        conflict: Option<ConflictPolicy>,
+    ) -> Result<FileInfo, AppError> {
+        // The filename must name a single entry inside `path`
+        if filename.is_empty() || filename.contains('/') || filename == "." || filename == ".." {
+            return Err(AppError::invalid_input(format!(
+                "Invalid filename: '{}'",
+                filename
+            )));
+        }
+        let full_path = checked_path(&format!("{}/{}", path, filename))?;
+        let full_path =
+            resolve_conflict(&full_path, conflict.unwrap_or(ConflictPolicy::Rename)).await?;
+        let info = self.write_file(full_path, content).await?;
+        self.touch_recent(&info.path);
+        Ok(info)
+    }
 
-            let file_info = FileInfo {
-                name: filename,
-                path: full_path.clone(),
-                size: dir_size,
-                created: 0,
-                modified: 0,
-                is_directory: true,
-                permissions: "rw".to_string(),
+    // Download `url` into the directory `destination_dir`. The file is named
+    // `filename`, or failing that by the response's Content-Disposition or the
+    // URL's last path segment; a file already there by that name is left alone.
+    // A download the server says is over MAX_DOWNLOAD_BYTES is refused before
+    // it is fetched; one that does not say is measured once it has arrived
+    #[local]
+    #[http]
+    async fn import_from_url(
+        &mut self,
+        url: String,
+        destination_dir: String,
+        filename: Option<String>,
+    ) -> Result<FileInfo, AppError> {
+        info!(
+            "import_from_url called with url: {}, destination_dir: {}",
+            url, destination_dir
+        );
+
+        let parsed = parse_download_url(&url).map_err(AppError::invalid_input)?;
+        let destination_dir = checked_path(&destination_dir)?;
+        if !file_info(&destination_dir).await?.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is not a directory",
+                destination_dir
+            )));
+        }
+        let filename =
+            match filename {
+                Some(name) => Some(usable_name(&name).ok_or_else(|| {
+                    AppError::invalid_input(format!("Invalid filename: '{}'", name))
+                })?),
+                None => None,
             };
 
-            all_files.push(file_info);
+        let too_large = || {
+            AppError::new(
+                DOWNLOAD_TOO_LARGE,
+                format!(
+                    "{} is over the {} MB import_from_url saves",
+                    url,
+                    MAX_DOWNLOAD_BYTES / (1024 * 1024)
+                ),
+            )
+        };
+        if announced_length(&parsed).is_some_and(|len| len > MAX_DOWNLOAD_BYTES) {
+            return Err(too_large());
+        }
 
-            // Load one level deep into directories
-            let sub_dir2 = vfs::Directory {
-                path: full_path.clone(),
-                timeout: 5,
+        let response = send_request_await_response(
+            Method::GET,
+            parsed.clone(),
+            None,
+            IMPORT_TIMEOUT_SECS,
+            Vec::new(),
+        )
+        .map_err(|e| client_error(&url, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let code = if status == StatusCode::NOT_FOUND {
+                app_error::NOT_FOUND
+            } else {
+                UPSTREAM_ERROR
             };
+            return Err(AppError::new(
+                code,
+                format!("GET {} answered {}", url, status),
+            ));
+        }
+        // The server may not have said, or said less than it sent
+        if response.body().len() as u64 > MAX_DOWNLOAD_BYTES {
+            return Err(too_large());
+        }
 
-            if let Ok(sub_entries) = sub_dir2.read() {
-                debug!(
-                    "Loading {} sub-entries from '{}'",
-                    sub_entries.len(),
-                    full_path
-                );
+        let disposition = response
+            .headers()
+            .get("content-disposition")
+            .and_then(|value| value.to_str().ok());
+        let Some(name) = filename.or_else(|| download_filename(&parsed, disposition)) else {
+            return Err(AppError::invalid_input(format!(
+                "Cannot tell what to call the file from {}; pass a filename",
+                url
+            )));
+        };
+        let path = checked_path(&format!("{}/{}", destination_dir, name))?;
+        let path = resolve_conflict(&path, ConflictPolicy::Fail).await?;
+        self.write_file(path, response.into_body()).await
+    }
 
-                for sub_entry in sub_entries {
-                    // VFS already provides absolute paths in sub_entry.path
-                    let sub_full_path = sub_entry.path.clone();
-                    let sub_filename = sub_entry.path.split('/').last().unwrap_or("").to_string();
+    // Share `path`, returning its link. With `max_downloads` the share is
+    // removed once it has been downloaded that many times, counting from when
+    // it was first shared, and its link then answers link_exhausted. Every
+    // response with bytes of the file in it is a download, a Range request for
+    // part of it too; only one that cannot be satisfied is not
+    #[local]
+    #[http]
+    async fn share_file(
+        &mut self,
+        path: String,
+        auth: AuthScheme,
+        max_downloads: Option<u32>,
+    ) -> Result<String, AppError> {
+        let path = checked_path(&path)?;
+        if max_downloads == Some(0) {
+            return Err(AppError::invalid_input(
+                "max_downloads must allow at least one download",
+            ));
+        }
 
-                    debug!(
-                        "Sub-entry: path='{}', filename='{}', file_type={:?}",
-                        sub_full_path, sub_filename, sub_entry.file_type
-                    );
+        // Sharing again changes who may fetch it and how often, but keeps the
+        // link
+        if let Some(share) = self.shared_files.get_mut(&path) {
+            share.auth = auth;
+            share.max_downloads = max_downloads;
+            let link = share_link(&share.id);
+            self.log_activity(Activity::Shared, &path, None);
+            return Ok(link);
+        }
 
-                    if sub_entry.file_type == FileType::Directory {
-                        all_files.push(FileInfo {
-                            name: sub_filename,
-                            path: sub_full_path,
-                            size: 0, // Don't load deeper
-                            created: 0,
-                            modified: 0,
-                            is_directory: true,
-                            permissions: "rw".to_string(),
-                        });
-                    } else {
-                        // For files, try to get metadata
-                        if let Ok(meta) = vfs::metadata(&sub_full_path, Some(5)).await {
-                            all_files.push(FileInfo {
-                                name: sub_filename,
-                                path: sub_full_path,
-                                size: meta.len,
-                                created: 0,
-                                modified: 0,
-                                is_directory: false,
-                                permissions: "rw".to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-        } else {
-            // For files, get metadata
-            let meta = vfs::metadata(&full_path, Some(5))
-                .await
-                .map_err(|e| format!("Failed to get metadata for '{}': {}", entry.path, e))?;
-
-            all_files.push(FileInfo {
-                name: filename,
-                path: full_path,
-                size: meta.len,
-                created: 0,
-                modified: 0,
-                is_directory: false,
-                permissions: "rw".to_string(),
-            });
+        let share_id = new_random_id();
+        self.share_paths.insert(share_id.clone(), path.clone());
+        self.log_activity(Activity::Shared, &path, None);
+        self.shared_files.insert(
+            path,
+            Share {
+                id: share_id.clone(),
+                auth,
+                created: now_ms(),
+                hits: 0,
+                max_downloads,
+            },
+        );
+        Ok(share_link(&share_id))
+    }
+
+    // Share a directory; its link downloads it as a zip archive
+    #[local]
+    #[http]
+    async fn share_directory(
+        &mut self,
+        path: String,
+        auth: AuthScheme,
+        max_downloads: Option<u32>,
+    ) -> Result<String, AppError> {
+        let path = checked_path(&path)?;
+        if !file_info(&path).await?.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is not a directory; use share_file",
+                path
+            )));
         }
+        self.share_file(path, auth, max_downloads).await
     }
 
-    debug!("Returning {} files total", all_files.len());
-    Ok(all_files)
+    // Unpack the zip archive at `archive_path` into `destination`, creating
+    // directories as needed. An archive with an entry that would land
+    // outside `destination` is refused before anything is written
+    #[local]
+    #[http]
+    async fn extract_archive(
+        &mut self,
+        archive_path: String,
+        destination: String,
+        overwrite: bool,
+    ) -> Result<ExtractReport, AppError> {
+        info!(
+            "extract_archive called with archive_path: {}, destination: {}, overwrite: {}",
+            archive_path, destination, overwrite
+        );
+
+        let archive_path = checked_path(&archive_path)?;
+        let destination = checked_path(&destination)?;
+        if file_info(&archive_path).await?.size > MAX_ARCHIVE_CONTENT_BYTES {
+            return Err(AppError::new(
+                ARCHIVE_TOO_LARGE,
+                format!("'{}' is too large to extract", archive_path),
+            ));
+        }
+        let bytes = read_contents(&archive_path).await?;
+        let entries = read_archive(&bytes).map_err(|e| {
+            AppError::invalid_input(format!("Cannot extract '{}': {}", archive_path, e))
+        })?;
+
+        require_parent(&destination).await?;
+        let mut report = ExtractReport {
+            created: Vec::new(),
+            skipped: Vec::new(),
+        };
+        // Directories known to exist, so each is looked up or made only once
+        let mut directories = HashSet::new();
+        self.ensure_directory(&destination, &mut directories, &mut report)
+            .await?;
+
+        for entry in entries {
+            let path = checked_path(&format!("{}/{}", destination, entry.path))?;
+            let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+            // An archive need not list the directories its files are in
+            let mut missing = Vec::new();
+            let mut ancestor = parent;
+            while ancestor.len() > destination.len() && !directories.contains(ancestor) {
+                missing.push(ancestor.to_string());
+                ancestor = ancestor.rsplit_once('/').map_or("", |(parent, _)| parent);
+            }
+            for dir in missing.iter().rev() {
+                self.ensure_directory(dir, &mut directories, &mut report)
+                    .await?;
+            }
+
+            if entry.is_directory {
+                self.ensure_directory(&path, &mut directories, &mut report)
+                    .await?;
+                continue;
+            }
+            match file_info(&path).await {
+                Ok(info) if info.is_directory => {
+                    return Err(AppError::invalid_input(format!(
+                        "Cannot extract '{}': a directory is in the way",
+                        path
+                    )))
+                }
+                Ok(_) if !overwrite => {
+                    report.skipped.push(path);
+                    continue;
+                }
+                _ => {}
+            }
+            let created = self.write_file(path, entry.content).await?;
+            report.created.push(created);
+        }
+
+        Ok(report)
+    }
+
+    // HTTP-only: the UI's download button; the archive is the response body.
+    // Zipping reports its progress as an operation cancel_operation can stop
+    #[http]
+    async fn download_directory_zip(&mut self, path: String) -> Result<Vec<u8>, AppError> {
+        info!("download_directory_zip called with path: {}", path);
+
+        let path = checked_path(&path)?;
+        let archive = self
+            .zip_directory(&path, Some("download_directory_zip"))
+            .await?;
+        add_zip_headers(&path);
+        Ok(archive)
+    }
+
+    // Ask a running operation to stop at its next chunk or file; its last
+    // progress event then says it was cancelled. False if no operation by
+    // that id is running
+    #[local]
+    #[http]
+    async fn cancel_operation(&mut self, operation_id: String) -> Result<bool, AppError> {
+        info!(
+            "cancel_operation called with operation_id: {}",
+            operation_id
+        );
+        Ok(self.operations.cancel(&operation_id))
+    }
+
+    #[local]
+    #[http]
+    async fn unshare_file(&mut self, path: String) -> Result<bool, AppError> {
+        let path = checked_path(&path)?;
+        match self.shared_files.remove(&path) {
+            Some(share) => {
+                self.share_paths.remove(&share.id);
+                self.log_activity(Activity::Unshared, &path, None);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // Give a shared path a new link; the old one stops working
+    #[local]
+    #[http]
+    async fn regenerate_share_link(&mut self, path: String) -> Result<String, AppError> {
+        let path = checked_path(&path)?;
+        let Some(share) = self.shared_files.get_mut(&path) else {
+            return Err(AppError::not_found(format!("'{}' is not shared", path)));
+        };
+
+        self.share_paths.remove(&share.id);
+        share.id = new_random_id();
+        let link = share_link(&share.id);
+        self.share_paths.insert(share.id.clone(), path.clone());
+        self.log_activity(Activity::Shared, &path, None);
+        Ok(link)
+    }
+
+    // Every shared path and who may fetch it, so the UI can mark shared
+    // entries after a reload
+    #[local]
+    #[http]
+    async fn get_all_shares(&mut self) -> Result<Vec<(String, AuthScheme)>, AppError> {
+        let mut shares: Vec<(String, AuthScheme)> = self
+            .shared_files
+            .iter()
+            .map(|(path, share)| (path.clone(), share.auth.clone()))
+            .collect();
+        shares.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(shares)
+    }
+
+    // Every share with its link, when it was made and how often it has been
+    // downloaded, by path
+    #[local]
+    #[http]
+    async fn list_shares(&mut self) -> Result<Vec<ShareInfo>, AppError> {
+        let mut shares: Vec<ShareInfo> = self
+            .shared_files
+            .iter()
+            .map(|(path, share)| share_info(path, share))
+            .collect();
+        shares.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(shares)
+    }
+
+    // The newest `limit` activity log entries, newest first. With
+    // `path_filter`, only those for that path or anything below it, a move
+    // counting for both its old and new path
+    #[local]
+    #[http]
+    async fn get_activity_log(
+        &mut self,
+        limit: u32,
+        path_filter: Option<String>,
+    ) -> Result<Vec<ActivityEntry>, AppError> {
+        let path_filter = path_filter.as_deref().map(checked_path).transpose()?;
+        let limit = (limit as usize).min(MAX_ACTIVITY_ENTRIES);
+        Ok(self
+            .activity_log
+            .iter()
+            .rev()
+            .filter(|entry| {
+                path_filter.as_deref().is_none_or(|dir| {
+                    is_at_or_below(&entry.path, dir)
+                        || entry
+                            .from
+                            .as_deref()
+                            .is_some_and(|from| is_at_or_below(from, dir))
+                })
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    // Empty the activity log, returning how many entries it held
+    #[local]
+    #[http]
+    async fn clear_activity_log(&mut self) -> Result<u64, AppError> {
+        let cleared = self.activity_log.len() as u64;
+        self.activity_log.clear();
+        Ok(cleared)
+    }
+
+    // The files last read, created, uploaded or updated, most recent first.
+    // Each is looked up again, and one no longer there is left out
+    #[local]
+    #[http]
+    async fn get_recent_files(&mut self) -> Result<Vec<FileInfo>, AppError> {
+        let mut recent = Vec::new();
+        for (path, _) in &self.recent_files {
+            if let Ok(info) = file_info(path).await {
+                recent.push(info);
+            }
+        }
+        Ok(recent)
+    }
+
+    // Forget the recent files, returning how many there were
+    #[local]
+    #[http]
+    async fn clear_recent_files(&mut self) -> Result<u64, AppError> {
+        let cleared = self.recent_files.len() as u64;
+        self.recent_files.clear();
+        Ok(cleared)
+    }
+
+    // Turn tracking of recent files on or off; turning it off also forgets
+    // the files tracked so far
+    #[local]
+    #[http]
+    async fn set_track_recent(&mut self, track_recent: bool) -> Result<bool, AppError> {
+        self.recent_opt_out = !track_recent;
+        if !track_recent {
+            self.recent_files.clear();
+        }
+        Ok(track_recent)
+    }
+
+    #[local]
+    #[http]
+    async fn get_track_recent(&mut self) -> Result<bool, AppError> {
+        Ok(!self.recent_opt_out)
+    }
+
+    // The share of `path`, with its link and how many downloads it has
+    // left, or None if it is not shared
+    #[local]
+    #[http]
+    async fn get_share_link(&mut self, path: String) -> Result<Option<ShareInfo>, AppError> {
+        let path = checked_path(&path)?;
+
+        // Check if file is shared
+        Ok(self
+            .shared_files
+            .get(&path)
+            .map(|share| share_info(&path, share)))
+    }
+
+    // HTTP-only: routed by the /shared/<id> URL it is requested on
+    #[http]
+    async fn serve_shared_file(&mut self) -> Result<Vec<u8>, AppError> {
+        // Use get_path() to handle routing
+        let request_path = get_path();
+
+        // Extract the file path from the request
+        if let Some(request_path_str) = request_path {
+            if let Some(share_id) = request_path_str.strip_prefix("/shared/") {
+                // Find the original path from share_id
+                let Some((path, auth_scheme)) = self.find_share(share_id) else {
+                    return Err(self.missing_share(share_id));
+                };
+                match auth_scheme {
+                    AuthScheme::Public => {
+                        // A shared directory downloads as one archive
+                        let info = file_info(&path).await?;
+                        if info.is_directory {
+                            self.take_download(share_id)?;
+                            let archive = self.zip_directory(&path, None).await?;
+                            add_zip_headers(&path);
+                            add_checksum_header(&archive);
+                            return Ok(archive);
+                        }
+
+                        // Every response with bytes of the file in it counts
+                        // as a download, taken before any of it is read, so a
+                        // limited link cannot be drained a part at a time; a
+                        // 416 sends none and leaves the limit alone, as long
+                        // as the share was not used up while file_info ran
+                        let len = info.size;
+                        let range = get_request_header("range")
+                            .map_or(ByteRange::Full, |range| parse_range(&range, len));
+                        if range != ByteRange::Unsatisfiable {
+                            self.take_download(share_id)?;
+                        } else if !self.share_paths.contains_key(share_id) {
+                            return Err(self.missing_share(share_id));
+                        }
+
+                        // Extract filename from path
+                        let filename = path.split('/').last().unwrap_or("download");
+
+                        // Set Content-Disposition header to preserve original filename
+                        add_response_header(
+                            "Content-Disposition".to_string(),
+                            format!("attachment; filename=\"{}\"", filename),
+                        );
+
+                        // Set Content-Type from the file's leading bytes,
+                        // or its extension when they are not recognised
+                        let content_type = sniff_mime(&path, info.size).await?;
+                        add_response_header("Content-Type".to_string(), content_type.to_string());
+                        add_response_header("Accept-Ranges".to_string(), "bytes".to_string());
+
+                        // A Range request gets just that part, so
+                        // media can be seeked and downloads resumed
+                        match range {
+                            ByteRange::Full => {}
+                            ByteRange::Partial { start, end } => {
+                                set_response_status(StatusCode::PARTIAL_CONTENT);
+                                add_response_header(
+                                    "Content-Range".to_string(),
+                                    format!("bytes {}-{}/{}", start, end, len),
+                                );
+                                return read_range(&path, start, end - start + 1).await;
+                            }
+                            ByteRange::Unsatisfiable => {
+                                set_response_status(StatusCode::RANGE_NOT_SATISFIABLE);
+                                add_response_header(
+                                    "Content-Range".to_string(),
+                                    format!("bytes */{}", len),
+                                );
+                                return Ok(Vec::new());
+                            }
+                        }
+
+                        // Read and return file content, with its checksum so
+                        // the download can be verified. A range above is sent
+                        // without one: the whole file would have to be read
+                        let content = read_contents(&path).await?;
+                        add_checksum_header(&content);
+                        Ok(content)
+                    }
+                    AuthScheme::Private(_) => {
+                        Err(AppError::not_permitted("Access denied: Private file"))
+                    }
+                }
+            } else {
+                Err(AppError::invalid_input("Invalid shared file path"))
+            }
+        } else {
+            Err(AppError::invalid_input("No request path provided"))
+        }
+    }
+
+    // Fetch a shared file from another process or node by its share id, or
+    // by the link share_file returned. A private share is only handed to the
+    // nodes it lists and to our own
+    #[local]
+    #[remote]
+    async fn fetch_shared_file(&mut self, share_id: String) -> Result<Vec<u8>, AppError> {
+        let requester = source();
+        let share_id = share_id.rsplit('/').next().unwrap_or("");
+        info!(
+            "fetch_shared_file called by {} for share: {}",
+            requester, share_id
+        );
+
+        let Some((path, auth)) = self.find_share(share_id) else {
+            return Err(self.missing_share(share_id));
+        };
+        let allowed = match auth {
+            AuthScheme::Public => true,
+            AuthScheme::Private(nodes) => {
+                requester.node == our().node || nodes.contains(&requester.node)
+            }
+        };
+        if !allowed {
+            return Err(AppError::not_permitted(format!(
+                "Access denied: not shared with {}",
+                requester.node
+            )));
+        }
+
+        self.take_download(share_id)?;
+        read_contents(&path).await
+    }
+
+    // HTTP-only: the working directory is UI state, not something other
+    // processes should depend on or change
+    #[http]
+    async fn get_current_directory(&mut self) -> Result<String, AppError> {
+        info!("get_current_directory called, returning: {}", self.cwd);
+        Ok(self.cwd.clone())
+    }
+
+    // HTTP-only, like get_current_directory. The path must be a directory;
+    // it is kept, and returned, normalized
+    #[http]
+    async fn set_current_directory(&mut self, path: String) -> Result<String, AppError> {
+        let path = checked_path(&path)?;
+        // The VFS root lists the drives, so the UI can stand in it too
+        if path != "/" && !file_info(&path).await?.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is not a directory",
+                path
+            )));
+        }
+        self.cwd = path.clone();
+        Ok(path)
+    }
+
+    // HTTP-only: a path the UI was given relative to the working directory,
+    // made absolute and normalized. It need not exist
+    #[http]
+    async fn resolve_path(&mut self, relative: String) -> Result<String, AppError> {
+        path::resolve_path(&self.cwd, &relative).map_err(AppError::invalid_input)
+    }
+
+    // Pin `path`, which must exist, under `label`, or under its name when
+    // `label` is empty. Pinning a favorite again only changes its label
+    #[local]
+    #[http]
+    async fn add_favorite(&mut self, path: String, label: String) -> Result<Favorite, AppError> {
+        info!("add_favorite called with path: {}, label: {}", path, label);
+
+        let path = checked_path(&path)?;
+        let info = file_info(&path).await?;
+        let label = match label.trim() {
+            "" => info.name,
+            label => label.to_string(),
+        };
+
+        if let Some(favorite) = self.favorites.iter_mut().find(|f| f.path == path) {
+            favorite.label = label.clone();
+        } else if self.favorites.len() >= MAX_FAVORITES {
+            return Err(AppError::invalid_input(format!(
+                "At most {} favorites can be kept; remove one first",
+                MAX_FAVORITES
+            )));
+        } else {
+            self.favorites.push(SavedFavorite {
+                path: path.clone(),
+                label: label.clone(),
+            });
+        }
+        Ok(Favorite {
+            path,
+            label,
+            exists: true,
+        })
+    }
+
+    // Unpin `path`; false if it was not a favorite
+    #[local]
+    #[http]
+    async fn remove_favorite(&mut self, path: String) -> Result<bool, AppError> {
+        let path = checked_path(&path)?;
+        let before = self.favorites.len();
+        self.favorites.retain(|favorite| favorite.path != path);
+        Ok(self.favorites.len() < before)
+    }
+
+    // The favorites in the order they were added, each checked for whether
+    // its path still exists
+    #[local]
+    #[http]
+    async fn list_favorites(&mut self) -> Result<Vec<Favorite>, AppError> {
+        let mut favorites = Vec::with_capacity(self.favorites.len());
+        for favorite in &self.favorites {
+            favorites.push(Favorite {
+                path: favorite.path.clone(),
+                label: favorite.label.clone(),
+                exists: file_info(&favorite.path).await.is_ok(),
+            });
+        }
+        Ok(favorites)
+    }
+
+    // Replace the tags of `path`, which must exist, and return them as kept:
+    // trimmed, lowercased, without duplicates and sorted. No tags untags it
+    #[local]
+    #[http]
+    async fn set_tags(&mut self, path: String, tags: Vec<String>) -> Result<Vec<String>, AppError> {
+        info!("set_tags called with path: {}, tags: {:?}", path, tags);
+
+        let path = checked_path(&path)?;
+        file_info(&path).await?;
+
+        let mut normalized = Vec::with_capacity(tags.len());
+        for tag in &tags {
+            let tag = normalize_tag(tag);
+            if tag.is_empty() {
+                continue;
+            }
+            if tag.chars().count() > MAX_TAG_LEN {
+                return Err(AppError::invalid_input(format!(
+                    "Tag '{}' is longer than {} characters",
+                    tag, MAX_TAG_LEN
+                )));
+            }
+            normalized.push(tag);
+        }
+        normalized.sort();
+        normalized.dedup();
+        if normalized.len() > MAX_TAGS_PER_PATH {
+            return Err(AppError::invalid_input(format!(
+                "At most {} tags can be set on one path",
+                MAX_TAGS_PER_PATH
+            )));
+        }
+
+        if normalized.is_empty() {
+            self.tags.remove(&path);
+        } else {
+            self.tags.insert(path, normalized.clone());
+        }
+        Ok(normalized)
+    }
+
+    // The tags of `path`; none if it has none
+    #[local]
+    #[http]
+    async fn get_tags(&mut self, path: String) -> Result<Vec<String>, AppError> {
+        let path = checked_path(&path)?;
+        Ok(self.tags.get(&path).cloned().unwrap_or_default())
+    }
+
+    // Everything tagged `tag`, sorted by path. Tags of paths that no longer
+    // exist, left by changes made around the explorer, are dropped
+    #[local]
+    #[http]
+    async fn list_by_tag(&mut self, tag: String) -> Result<Vec<FileInfo>, AppError> {
+        info!("list_by_tag called with tag: {}", tag);
+
+        let tag = normalize_tag(&tag);
+        let mut tagged: Vec<String> = self
+            .tags
+            .iter()
+            .filter(|(_, tags)| tags.contains(&tag))
+            .map(|(path, _)| path.clone())
+            .collect();
+        tagged.sort();
+
+        let mut entries = Vec::with_capacity(tagged.len());
+        for path in tagged {
+            match file_info(&path).await {
+                Ok(info) => entries.push(info),
+                Err(e) if e.code == app_error::NOT_FOUND => {
+                    debug!("list_by_tag: dropping the tags of missing '{}'", path);
+                    self.tags.remove(&path);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(entries)
+    }
+
+    #[local]
+    #[http]
+    async fn move_file(
+        &mut self,
+        source: String,
+        destination: String,
+    ) -> Result<FileInfo, AppError> {
+        let source = checked_path(&source)?;
+        let destination = checked_path(&destination)?;
+
+        // Moving a file onto itself leaves it where it is; going through the
+        // copy below would delete it
+        if source == destination {
+            return file_info(&source).await;
+        }
+        require_parent(&destination).await?;
+
+        let file_info = self.copy_contents(&source, &destination, None).await?;
+
+        // Delete source file; a move leaves nothing behind to restore
+        remove_file(&source).await?;
+
+        // A share follows the file to its new path, keeping its link, and so
+        // do its tags
+        self.move_shares(&source, &destination);
+        self.move_tags(&source, &destination);
+        drop_thumbnails(&source).await;
+
+        self.notify(
+            ChangeKind::Moved,
+            &destination,
+            Some(&source),
+            Some(&file_info),
+        );
+        Ok(file_info)
+    }
+
+    // Rename a file or directory in place with a single VFS rename, without
+    // reading it; shares under the old path follow it to the new one
+    #[local]
+    #[http]
+    async fn rename_file(&mut self, path: String, new_name: String) -> Result<FileInfo, AppError> {
+        info!(
+            "rename_file called with path: {}, new_name: {}",
+            path, new_name
+        );
+
+        // The new name must name a single entry next to the old one
+        if new_name.is_empty() || new_name.contains('/') || new_name == "." || new_name == ".." {
+            return Err(AppError::invalid_input(format!(
+                "Invalid name: '{}'",
+                new_name
+            )));
+        }
+        let vfs_path = checked_path(&path)?;
+        let parent = vfs_path.rsplit_once('/').map_or("", |(parent, _)| parent);
+        let new_path = checked_path(&format!("{}/{}", parent, new_name))?;
+        if new_path == vfs_path {
+            return file_info(&vfs_path).await;
+        }
+        if file_info(&new_path).await.is_ok() {
+            return Err(AppError::invalid_input(format!(
+                "'{}' already exists",
+                new_path
+            )));
+        }
+
+        vfs_rename(&vfs_path, &new_path).await?;
+
+        self.move_shares(&vfs_path, &new_path);
+        self.move_tags(&vfs_path, &new_path);
+        drop_thumbnails(&vfs_path).await;
+
+        let info = file_info(&new_path).await?;
+        self.notify(ChangeKind::Moved, &new_path, Some(&vfs_path), Some(&info));
+        Ok(info)
+    }
+
+    // Move a file or a directory anywhere. A rename is tried first; across
+    // drives, where the VFS refuses it, the source is copied and then
+    // deleted, and the outcome says which happened
+    #[local]
+    #[http]
+    async fn move_path(
+        &mut self,
+        source: String,
+        destination: String,
+    ) -> Result<MoveOutcome, AppError> {
+        info!(
+            "move_path called with source: {}, destination: {}",
+            source, destination
+        );
+
+        let source = checked_path(&source)?;
+        let destination = checked_path(&destination)?;
+        let source_info = file_info(&source).await?;
+        if source == destination {
+            return Ok(MoveOutcome {
+                info: source_info,
+                method: MoveMethod::Renamed,
+            });
+        }
+        if destination.starts_with(&format!("{}/", source)) {
+            return Err(AppError::invalid_input(format!(
+                "Cannot move '{}' into itself at '{}'",
+                source, destination
+            )));
+        }
+        require_parent(&destination).await?;
+        if file_info(&destination).await.is_ok() {
+            return Err(AppError::invalid_input(format!(
+                "'{}' already exists",
+                destination
+            )));
+        }
+
+        let method = self
+            .relocate(&source, source_info.is_directory, &destination)
+            .await?;
+
+        self.move_shares(&source, &destination);
+        self.move_tags(&source, &destination);
+        drop_thumbnails(&source).await;
+
+        let info = file_info(&destination).await?;
+        self.notify(ChangeKind::Moved, &destination, Some(&source), Some(&info));
+        Ok(MoveOutcome { info, method })
+    }
+
+    #[local]
+    #[http]
+    async fn copy_file(
+        &mut self,
+        source: String,
+        destination: String,
+    ) -> Result<FileInfo, AppError> {
+        let source = checked_path(&source)?;
+        let destination = checked_path(&destination)?;
+        require_parent(&destination).await?;
+
+        // A copy is not shared even if the source is
+        self.copy_contents(&source, &destination, None).await
+    }
+
+    // Copy a directory and everything under it; returns how many files were
+    // copied. Like copy_file, nothing copied is shared. The copy reports its
+    // progress as an operation that cancel_operation can stop; files copied
+    // before it stopped are left in place
+    #[local]
+    #[http]
+    async fn copy_directory(
+        &mut self,
+        source: String,
+        destination: String,
+    ) -> Result<u64, AppError> {
+        info!(
+            "copy_directory called with source: {}, destination: {}",
+            source, destination
+        );
+
+        let source = checked_path(&source)?;
+        let destination = checked_path(&destination)?;
+
+        // A copy inside its own source would walk into itself
+        if destination == source || destination.starts_with(&format!("{}/", source)) {
+            return Err(AppError::invalid_input(format!(
+                "Cannot copy '{}' into itself at '{}'",
+                source, destination
+            )));
+        }
+        if !file_info(&source).await?.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is not a directory; use copy_file",
+                source
+            )));
+        }
+        require_parent(&destination).await?;
+
+        // Sizes are only added up for a tree walk_tree can see all of
+        let total_bytes = match walk_tree(&source) {
+            Ok(walk) if !walk.truncated => files_size(&walk.entries).await,
+            _ => 0,
+        };
+        let operation = self.start_operation("copy_directory", &source, total_bytes);
+        let copied = self
+            .copy_tree(&source, &destination, Some(&operation))
+            .await;
+        self.finish_operation(&operation, &copied);
+        copied
+    }
+
+    // Copy a file or directory next to itself under the first free name in
+    // `report copy.pdf`, `report copy 2.pdf`, ... A directory is copied with
+    // everything under it; as with copy_file, nothing copied is shared
+    #[local]
+    #[http]
+    async fn duplicate_file(&mut self, path: String) -> Result<FileInfo, AppError> {
+        info!("duplicate_file called with path: {}", path);
+
+        let path = checked_path(&path)?;
+        // The package and drive segments name a drive, which has no directory
+        // to put a copy in
+        if path.trim_start_matches('/').split('/').count() < 3 {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is a drive; duplicate what is in it instead",
+                path
+            )));
+        }
+        let source = file_info(&path).await?;
+        let is_directory = source.is_directory;
+        let copy = first_free(&path, |name, n| copy_name(name, n, is_directory)).await?;
+
+        if !is_directory {
+            return self.copy_file(path, copy).await;
+        }
+        self.copy_tree(&path, &copy, None).await?;
+        let info = file_info(&copy).await?;
+        self.notify(ChangeKind::Created, &copy, None, Some(&info));
+        Ok(info)
+    }
+
+    // Delete, move or copy many paths in one request, for a multi-selection.
+    // Each path goes through the handler for one path; one that fails is
+    // reported in the result and the rest still run. A missing destination,
+    // or one that is not a directory, fails the whole batch up front
+    #[local]
+    #[http]
+    async fn batch_operation(
+        &mut self,
+        op: BatchOp,
+        paths: Vec<String>,
+        destination: Option<String>,
+    ) -> Result<BatchResult, AppError> {
+        info!(
+            "batch_operation called with op: {:?}, {} paths, destination: {:?}",
+            op,
+            paths.len(),
+            destination
+        );
+
+        if paths.len() > MAX_BATCH_PATHS {
+            return Err(AppError::invalid_input(format!(
+                "At most {} paths can go in one batch, not {}",
+                MAX_BATCH_PATHS,
+                paths.len()
+            )));
+        }
+        let destination = match (&op, destination) {
+            (BatchOp::Delete, _) => None,
+            (_, None) => {
+                return Err(AppError::invalid_input(format!(
+                    "{:?} needs a destination directory",
+                    op
+                )))
+            }
+            (_, Some(destination)) => {
+                let destination = checked_path(&destination)?;
+                if !file_info(&destination).await?.is_directory {
+                    return Err(AppError::invalid_input(format!(
+                        "'{}' is not a directory",
+                        destination
+                    )));
+                }
+                Some(destination)
+            }
+        };
+
+        let mut result = BatchResult {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        };
+        for path in paths {
+            match self.batch_item(&op, &path, destination.as_deref()).await {
+                Ok(()) => result.succeeded.push(path),
+                Err(error) => {
+                    debug!("batch_operation: '{}' failed: {}", path, error.message);
+                    result.failed.push(BatchFailure { path, error });
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    // Clients send `{"type": "subscribe_directory", "path": ...}` to be
+    // pushed a ChangeEvent for every change at or below `path`, and
+    // `unsubscribe_directory` to stop
+    #[ws]
+    fn websocket(&mut self, channel_id: u32, message_type: WsMessageType, blob: LazyLoadBlob) {
+        self.watchers
+            .handle_client_message(channel_id, message_type, &blob.bytes);
+    }
+}
+
+impl FileExplorerState {
+    // Write `content` to `path`, replacing any file already there. What
+    // create_file does once the conflict policy has picked the path, and what
+    // the explorer's own writers use
+    async fn write_file(&mut self, path: String, content: Vec<u8>) -> Result<FileInfo, AppError> {
+        let vfs_path = checked_path(&path)?;
+        debug!("VFS path: {}", vfs_path);
+
+        // Create file and write content; creating it already leaves it empty
+        let file = vfs::create_file(&vfs_path, Some(5))
+            .map_err(|e| vfs_error("Failed to create file", e))?;
+
+        if !content.is_empty() {
+            file.write(&content)
+                .map_err(|e| vfs_error("Failed to write file", e))?;
+        }
+
+        // Get metadata for response
+        let meta = file
+            .metadata()
+            .map_err(|e| lookup_error("Failed to get metadata", e))?;
+
+        let info = FileInfo {
+            name: vfs_path.split('/').last().unwrap_or("").to_string(),
+            path: vfs_path,
+            size: meta.len,
+            created: 0,
+            modified: 0,
+            is_directory: false,
+            permissions: "rw".to_string(),
+        };
+        self.notify(ChangeKind::Created, &info.path, None, Some(&info));
+        Ok(info)
+    }
+
+    // Copy the file `from` to `to`, replacing any file there, COPY_CHUNK_BYTES
+    // at a time, each chunk counting toward `operation` when there is one. A
+    // copy that fails or is cancelled part way removes what it had written
+    async fn copy_contents(
+        &mut self,
+        from: &str,
+        to: &str,
+        operation: Option<&str>,
+    ) -> Result<FileInfo, AppError> {
+        // Creating the destination would empty the source before any of it
+        // was read
+        if from == to {
+            return Err(AppError::invalid_input(format!(
+                "Cannot copy '{}' onto itself",
+                from
+            )));
+        }
+        let source = file_info(from).await?;
+        if source.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is a directory",
+                from
+            )));
+        }
+        if let Err(e) = self.copy_chunks(from, to, source.size, operation).await {
+            if let Err(cleanup) = remove_file(to).await {
+                debug!("Could not remove partial copy '{}': {}", to, cleanup);
+            }
+            return Err(e);
+        }
+
+        let info = file_info(to).await?;
+        self.notify(ChangeKind::Created, &info.path, None, Some(&info));
+        Ok(info)
+    }
+
+    // Write the first `len` bytes of `from` to a new file at `to`, one chunk
+    // of at most COPY_CHUNK_BYTES read and appended at a time
+    async fn copy_chunks(
+        &mut self,
+        from: &str,
+        to: &str,
+        len: u64,
+        operation: Option<&str>,
+    ) -> Result<(), AppError> {
+        let mut source = vfs::open_file(from, false, Some(5))
+            .map_err(|e| lookup_error("Failed to open file", e))?;
+        let mut destination =
+            vfs::create_file(to, Some(5)).map_err(|e| vfs_error("Failed to create file", e))?;
+
+        let mut offset = 0;
+        while offset < len {
+            let chunk = read_chunk(&mut source, from, offset, len)?;
+            destination.append(&chunk).map_err(|e| {
+                vfs_error(&format!("Failed to write '{}' at offset {}", to, offset), e)
+            })?;
+            offset += chunk.len() as u64;
+            self.advance_operation(operation, chunk.len() as u64, from)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Register a long-running operation and tell progress subscribers it has
+    // started; the id is how they and cancel_operation refer to it
+    fn start_operation(&mut self, operation: &str, path: &str, total_bytes: u64) -> String {
+        let id = new_random_id();
+        let event = self
+            .operations
+            .start(id.clone(), operation, path, total_bytes, now_ms());
+        self.watchers.notify_progress(&event);
+        id
+    }
+
+    // Count `bytes` more of `operation` done, telling progress subscribers
+    // every PROGRESS_INTERVAL_MS. VFS calls block the process, so that is also
+    // when a running operation waits a moment to let other requests in,
+    // cancel_operation among them. Fails with CANCELLED once it was cancelled
+    async fn advance_operation(
+        &mut self,
+        operation: Option<&str>,
+        bytes: u64,
+        path: &str,
+    ) -> Result<(), AppError> {
+        let Some(id) = operation else {
+            return Ok(());
+        };
+        if let Some(event) = self.operations.advance(id, bytes, path, now_ms()) {
+            self.watchers.notify_progress(&event);
+            let _ = sleep(0).await;
+        }
+        if self.operations.is_cancelled(id) {
+            return Err(AppError::new(
+                CANCELLED,
+                format!("Operation {} was cancelled", id),
+            ));
+        }
+        Ok(())
+    }
+
+    // Drop a finished operation and send its last event, saying how it ended
+    fn finish_operation<T>(&mut self, operation: &str, result: &Result<T, AppError>) {
+        let status = match result {
+            Ok(_) => ProgressStatus::Finished,
+            Err(e) if e.code == CANCELLED => ProgressStatus::Cancelled,
+            Err(_) => ProgressStatus::Failed,
+        };
+        if let Some(event) = self.operations.finish(operation, status) {
+            self.watchers.notify_progress(&event);
+        }
+    }
+
+    // One path of batch_operation; `destination` is set for Move and Copy
+    async fn batch_item(
+        &mut self,
+        op: &BatchOp,
+        path: &str,
+        destination: Option<&str>,
+    ) -> Result<(), AppError> {
+        let path = checked_path(path)?;
+        let info = file_info(&path).await?;
+        let target = destination.map(|dir| format!("{}/{}", dir, info.name));
+        match (op, target) {
+            (BatchOp::Delete, _) if info.is_directory => {
+                self.delete_directory(path, false).await?;
+            }
+            (BatchOp::Delete, _) => {
+                self.delete_file(path, false).await?;
+            }
+            (BatchOp::Move, Some(target)) => {
+                self.move_path(path, target).await?;
+            }
+            (BatchOp::Copy, Some(target)) if target == path => {
+                return Err(AppError::invalid_input(format!(
+                    "'{}' is already in the destination",
+                    path
+                )));
+            }
+            (BatchOp::Copy, Some(target)) if info.is_directory => {
+                self.copy_directory(path, target).await?;
+            }
+            (BatchOp::Copy, Some(target)) => {
+                self.copy_file(path, target).await?;
+            }
+            (_, None) => {
+                return Err(AppError::invalid_input(format!(
+                    "{:?} needs a destination directory",
+                    op
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    // Tell WebSocket watchers about a change and add it to the activity log.
+    // Changes inside the explorer's own directories, such as the trash, are
+    // not reported
+    fn notify(
+        &mut self,
+        kind: ChangeKind,
+        path: &str,
+        from: Option<&str>,
+        info: Option<&FileInfo>,
+    ) {
+        if is_internal(path) {
+            return;
+        }
+        let from = from.filter(|from| !is_internal(from));
+        let operation = match kind {
+            ChangeKind::Created => Activity::Created,
+            ChangeKind::Deleted => Activity::Deleted,
+            ChangeKind::Modified => Activity::Modified,
+            ChangeKind::Moved => Activity::Moved,
+        };
+        self.log_activity(operation, path, from);
+        self.watchers.notify(&ChangeEvent {
+            kind,
+            path: path.to_string(),
+            from: from.map(str::to_string),
+            info: info.cloned(),
+        });
+    }
+
+    fn visibility(&self) -> Visibility {
+        Visibility {
+            show_hidden: self.show_hidden,
+            show_system: self.show_system,
+        }
+    }
+
+    // Record what the current request did to `path`, dropping the oldest
+    // entry once the log is full
+    fn log_activity(&mut self, operation: Activity, path: &str, from: Option<&str>) {
+        self.activity_log.push_back(ActivityEntry {
+            timestamp: now_ms(),
+            operation,
+            path: path.to_string(),
+            from: from.map(str::to_string),
+            source: request_source(),
+        });
+        while self.activity_log.len() > MAX_ACTIVITY_ENTRIES {
+            self.activity_log.pop_front();
+        }
+    }
+
+    // Move `path` to the front of the recent files, unless they are not
+    // tracked, dropping the least recent once there are MAX_RECENT_FILES
+    fn touch_recent(&mut self, path: &str) {
+        if self.recent_opt_out {
+            return;
+        }
+        self.recent_files.retain(|(recent, _)| recent != path);
+        self.recent_files.push_front((path.to_string(), now_ms()));
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    // Move `source` to `destination`, which must not exist yet: one VFS
+    // rename within a drive, otherwise a copy and then a permanent delete of
+    // the source. Only a rename refused because it crosses drives falls back
+    // to copying; any other failure is the move's error as it stands
+    async fn relocate(
+        &mut self,
+        source: &str,
+        is_directory: bool,
+        destination: &str,
+    ) -> Result<MoveMethod, AppError> {
+        match vfs_rename(source, destination).await {
+            Ok(()) => Ok(MoveMethod::Renamed),
+            Err(e) if same_drive(source, destination) => Err(e),
+            Err(e) => {
+                debug!(
+                    "rename across drives refused ({}), copying instead",
+                    e.message
+                );
+                // The source is only deleted once all of it has been copied
+                if is_directory {
+                    self.copy_tree(source, destination, None).await?;
+                    remove_dir_all(source).await?;
+                } else {
+                    self.copy_contents(source, destination, None).await?;
+                    remove_file(source).await?;
+                }
+                Ok(MoveMethod::Copied)
+            }
+        }
+    }
+
+    // Move `path` into its own directory in the trash and remember where it
+    // came from. Shares of it stay keyed by its old path, so the link works
+    // again if it is restored
+    async fn move_to_trash(&mut self, path: &str, is_directory: bool) -> Result<(), AppError> {
+        let info = file_info(path).await?;
+        if info.is_directory != is_directory {
+            return Err(AppError::invalid_input(if info.is_directory {
+                format!("'{}' is a directory; use delete_directory", path)
+            } else {
+                format!("'{}' is a file; use delete_file", path)
+            }));
+        }
+        let trash = trash_root();
+        if path == trash || path.starts_with(&format!("{}/", trash)) {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is already in the trash; purge it instead",
+                path
+            )));
+        }
+        if trash.starts_with(&format!("{}/", path)) {
+            return Err(AppError::invalid_input(format!(
+                "'{}' holds the trash; delete it permanently",
+                path
+            )));
+        }
+
+        let item = TrashItem {
+            id: new_random_id(),
+            original_path: path.to_string(),
+            is_directory,
+            deleted_at: now_ms(),
+        };
+        let trashed = trashed_path(&item);
+        self.create_parents(&trashed).await?;
+        self.relocate(path, is_directory, &trashed).await?;
+        info!("Moved '{}' to the trash as {}", path, item.id);
+        self.trash.insert(item.id.clone(), item);
+        Ok(())
+    }
+
+    // Keep the current content of `path` as a version, then prune its oldest
+    // versions down to max_versions
+    async fn save_version(&mut self, path: &str) -> Result<(), AppError> {
+        let dir = versions_dir(path);
+        // Two updates in the same millisecond each keep their version
+        let mut timestamp = now_ms();
+        while file_info(&format!("{}/{}", dir, timestamp)).await.is_ok() {
+            timestamp += 1;
+        }
+        let version_path = format!("{}/{}", dir, timestamp);
+        self.create_parents(&version_path).await?;
+        self.copy_contents(path, &version_path, None).await?;
+
+        let keep = self.max_versions.unwrap_or(DEFAULT_MAX_VERSIONS) as usize;
+        for old in file_versions(path).await?.iter().skip(keep) {
+            remove_file(&format!("{}/{}", dir, old.timestamp)).await?;
+        }
+        Ok(())
+    }
+
+    // Create whichever directories above `path` are missing, below its drive
+    async fn create_parents(&mut self, path: &str) -> Result<(), AppError> {
+        let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+        // The package and drive segments name the drive itself
+        for depth in 3..segments.len() {
+            let parent = format!("/{}", segments[..depth].join("/"));
+            match file_info(&parent).await {
+                Ok(info) if info.is_directory => {}
+                Ok(_) => {
+                    return Err(AppError::invalid_input(format!(
+                        "'{}' is a file, not a directory",
+                        parent
+                    )))
+                }
+                Err(e) if e.code == app_error::NOT_FOUND => {
+                    self.create_directory(parent).await?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    // Re-key shares of `from`, and of anything under it when it is a
+    // directory, to the same place under `to`; their links stay the same
+    fn move_shares(&mut self, from: &str, to: &str) {
+        let prefix = format!("{}/", from);
+        let moved: Vec<String> = self
+            .shared_files
+            .keys()
+            .filter(|path| path.as_str() == from || path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for path in moved {
+            if let Some(share) = self.shared_files.remove(&path) {
+                let new_path = format!("{}{}", to, &path[from.len()..]);
+                self.share_paths.insert(share.id.clone(), new_path.clone());
+                self.shared_files.insert(new_path, share);
+            }
+        }
+    }
+
+    // Re-key the tags of `from`, and of anything under it, to the same place
+    // under `to`
+    fn move_tags(&mut self, from: &str, to: &str) {
+        let prefix = format!("{}/", from);
+        let moved: Vec<String> = self
+            .tags
+            .keys()
+            .filter(|path| path.as_str() == from || path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for path in moved {
+            if let Some(tags) = self.tags.remove(&path) {
+                self.tags
+                    .insert(format!("{}{}", to, &path[from.len()..]), tags);
+            }
+        }
+    }
+
+    // Forget the tags of `path` and of anything under it
+    fn drop_tags(&mut self, path: &str) {
+        let prefix = format!("{}/", path);
+        self.tags
+            .retain(|tagged, _| tagged != path && !tagged.starts_with(&prefix));
+    }
+
+    // Forget the shares of `path` and of anything under it; their links stop
+    // resolving
+    fn drop_shares(&mut self, path: &str) {
+        let prefix = format!("{}/", path);
+        let share_paths = &mut self.share_paths;
+        self.shared_files.retain(|shared, share| {
+            let dropped = shared == path || shared.starts_with(&prefix);
+            if dropped {
+                share_paths.remove(&share.id);
+            }
+            !dropped
+        });
+    }
+
+    // Forget the shares a purged trash item kept at `original_path` and under
+    // it, except those something can still use: a path another trash item
+    // would restore, or one where a file is again
+    async fn drop_purged_shares(&mut self, original_path: &str) {
+        let prefix = format!("{}/", original_path);
+        let kept: Vec<String> = self
+            .shared_files
+            .keys()
+            .filter(|path| path.as_str() == original_path || path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for path in kept {
+            let restorable = self.trash.values().any(|item| {
+                path == item.original_path || path.starts_with(&format!("{}/", item.original_path))
+            });
+            if !restorable && file_info(&path).await.is_err() {
+                self.drop_shares(&path);
+            }
+        }
+    }
+
+    // Rebuild share_paths, which is not saved, from shared_files
+    fn index_shares(&mut self) {
+        self.share_paths = self
+            .shared_files
+            .iter()
+            .map(|(path, share)| (share.id.clone(), path.clone()))
+            .collect();
+    }
+
+    // The path and access of the share with link id `share_id`. share_file
+    // only keeps checked paths, so one that is not came from a saved state
+    // edited by hand, and is not served
+    fn find_share(&self, share_id: &str) -> Option<(String, AuthScheme)> {
+        let path = self.share_paths.get(share_id)?;
+        let share = self.shared_files.get(path)?;
+        if checked_path(path).ok().as_ref() != Some(path) {
+            error!(
+                "Refusing share {} of unnormalized path '{}'",
+                share_id, path
+            );
+            return None;
+        }
+        Some((path.clone(), share.auth.clone()))
+    }
+
+    // Why there is no share with link id `share_id`: its last download was
+    // taken, or it never was or is no longer shared
+    fn missing_share(&self, share_id: &str) -> AppError {
+        if self.exhausted_shares.iter().any(|id| id == share_id) {
+            AppError::new(
+                LINK_EXHAUSTED,
+                "Link exhausted: it has been downloaded as many times as it allows",
+            )
+        } else {
+            AppError::not_found("File not found or not shared")
+        }
+    }
+
+    // Count a download of the share with link id `share_id`, before any of
+    // it is read, so requests racing for a limited link can never be served
+    // more than it allows. The download that reaches the limit removes the
+    // share and leaves its id behind, so a request after it is answered
+    // link_exhausted
+    fn take_download(&mut self, share_id: &str) -> Result<(), AppError> {
+        // The share may have been removed while this request was awaiting
+        // the VFS, by the download that used up its limit
+        let Some(path) = self.share_paths.get(share_id).cloned() else {
+            return Err(self.missing_share(share_id));
+        };
+        let Some(share) = self.shared_files.get_mut(&path) else {
+            return Err(self.missing_share(share_id));
+        };
+        share.hits += 1;
+        if share
+            .max_downloads
+            .is_some_and(|max| share.hits >= u64::from(max))
+        {
+            self.shared_files.remove(&path);
+            self.share_paths.remove(share_id);
+            self.exhausted_shares.push_back(share_id.to_string());
+            if self.exhausted_shares.len() > MAX_EXHAUSTED_SHARES {
+                self.exhausted_shares.pop_front();
+            }
+        }
+        self.log_activity(Activity::Downloaded, &path, None);
+        Ok(())
+    }
+
+    // Make sure `path` is a directory, creating it if it is missing; one that
+    // is created goes into the extraction report
+    async fn ensure_directory(
+        &mut self,
+        path: &str,
+        directories: &mut HashSet<String>,
+        report: &mut ExtractReport,
+    ) -> Result<(), AppError> {
+        if directories.contains(path) {
+            return Ok(());
+        }
+        match file_info(path).await {
+            Ok(info) if info.is_directory => {}
+            Ok(_) => {
+                return Err(AppError::invalid_input(format!(
+                    "Cannot extract into '{}': it is a file",
+                    path
+                )))
+            }
+            Err(_) => {
+                let created = self.create_directory(path.to_string()).await?;
+                report.created.push(created);
+            }
+        }
+        directories.insert(path.to_string());
+        Ok(())
+    }
+
+    // Zip the directory `root` with everything in it under one top-level
+    // folder named after it. The sizes are added up before anything is read,
+    // so a directory over MAX_ARCHIVE_CONTENT_BYTES is refused without
+    // reading it. With `operation_name`, the zipping reports its progress as
+    // an operation that cancel_operation can stop
+    async fn zip_directory(
+        &mut self,
+        root: &str,
+        operation_name: Option<&str>,
+    ) -> Result<Vec<u8>, AppError> {
+        if !file_info(root).await?.is_directory {
+            return Err(AppError::invalid_input(format!(
+                "'{}' is not a directory",
+                root
+            )));
+        }
+        let Walk {
+            entries, truncated, ..
+        } = walk_tree(root)?;
+        if truncated {
+            return Err(AppError::new(
+                ARCHIVE_TOO_LARGE,
+                format!(
+                    "'{}' has more than {} entries or is deeper than {} levels",
+                    root, MAX_SEARCH_VISITED, MAX_SEARCH_DEPTH
+                ),
+            ));
+        }
+
+        let mut files = Vec::new();
+        let mut total = 0;
+        for (entry_path, file_type) in &entries {
+            if *file_type == FileType::Directory {
+                continue;
+            }
+            let size = file_info(entry_path).await?.size;
+            total += size;
+            if total > MAX_ARCHIVE_CONTENT_BYTES {
+                return Err(AppError::new(
+                    ARCHIVE_TOO_LARGE,
+                    format!(
+                        "'{}' holds more than {} MB, the most one archive may",
+                        root,
+                        MAX_ARCHIVE_CONTENT_BYTES / (1024 * 1024)
+                    ),
+                ));
+            }
+            files.push((entry_path, size));
+        }
+
+        let operation = operation_name.map(|name| self.start_operation(name, root, total));
+        let archive = self
+            .build_archive(root, &entries, &files, operation.as_deref())
+            .await;
+        if let Some(operation) = &operation {
+            self.finish_operation(operation, &archive);
+        }
+        archive
+    }
+
+    // The archive zip_directory makes of `entries` under `root`, reading
+    // `files`, the files among them with their sizes, one at a time and
+    // COPY_CHUNK_BYTES at a time as copy_chunks does
+    async fn build_archive(
+        &mut self,
+        root: &str,
+        entries: &[(String, FileType)],
+        files: &[(&String, u64)],
+        operation: Option<&str>,
+    ) -> Result<Vec<u8>, AppError> {
+        let top = root.split('/').last().unwrap_or("download");
+        let name_in_archive = |entry_path: &str| format!("{}{}", top, &entry_path[root.len()..]);
+        let mut builder = ArchiveBuilder::new();
+        builder.add_directory(top).map_err(AppError::internal)?;
+        for (entry_path, file_type) in entries {
+            if *file_type == FileType::Directory {
+                builder
+                    .add_directory(&name_in_archive(entry_path))
+                    .map_err(AppError::internal)?;
+            }
+        }
+        for (entry_path, len) in files {
+            let name = name_in_archive(entry_path);
+            builder.start_file(&name).map_err(AppError::internal)?;
+            let mut file = vfs::open_file(entry_path, false, Some(5))
+                .map_err(|e| lookup_error("Failed to open file", e))?;
+            let mut offset = 0;
+            while offset < *len {
+                let chunk = read_chunk(&mut file, entry_path, offset, *len)?;
+                builder.write(&name, &chunk).map_err(AppError::internal)?;
+                offset += chunk.len() as u64;
+                self.advance_operation(operation, chunk.len() as u64, entry_path)
+                    .await?;
+            }
+        }
+        builder.finish().map_err(AppError::internal)
+    }
+
+    // Recreate the directory `from` at `to` level by level, copying files as
+    // they are found; an error names the file or directory it stopped at.
+    // Progress counts toward `operation` when there is one
+    async fn copy_tree(
+        &mut self,
+        from: &str,
+        to: &str,
+        operation: Option<&str>,
+    ) -> Result<u64, AppError> {
+        let mut pending = vec![(from.to_string(), to.to_string())];
+        let mut copied = 0;
+
+        while let Some((from_dir, to_dir)) = pending.pop() {
+            vfs::open_dir(&to_dir, true, Some(5)).map_err(|e| {
+                vfs_error(
+                    &format!(
+                        "Failed to create directory '{}' after {} files",
+                        to_dir, copied
+                    ),
+                    e,
+                )
+            })?;
+
+            let dir = vfs::Directory {
+                path: from_dir.clone(),
+                timeout: 5,
+            };
+            let entries = dir
+                .read()
+                .map_err(|e| vfs_error(&format!("Failed to read directory '{}'", from_dir), e))?;
+
+            for entry in entries {
+                let name = entry.path.split('/').last().unwrap_or("");
+                let from_path = format!("{}/{}", from_dir, name);
+                let to_path = format!("{}/{}", to_dir, name);
+
+                if entry.file_type == FileType::Directory {
+                    pending.push((from_path, to_path));
+                    continue;
+                }
+
+                if let Err(mut e) = self.copy_contents(&from_path, &to_path, operation).await {
+                    e.message = format!(
+                        "Failed to copy '{}' after {} files: {}",
+                        from_path, copied, e.message
+                    );
+                    return Err(e);
+                }
+                copied += 1;
+            }
+        }
+
+        Ok(copied)
+    }
+}
+
+// The drives of this package as (name, path), by name
+fn package_drives() -> Result<Vec<(String, String)>, AppError> {
+    let package = format!("{}/", our().package_id());
+    let root = vfs::Directory {
+        path: "/".to_string(),
+        timeout: 5,
+    };
+    let listing = root
+        .read()
+        .map_err(|e| vfs_error("Failed to list drives", e))?;
+
+    let mut drives: Vec<(String, String)> = listing
+        .into_iter()
+        .filter_map(|entry| {
+            let name = entry
+                .path
+                .trim_start_matches('/')
+                .strip_prefix(&package)?
+                .to_string();
+            let path = format!("/{}{}", package, name);
+            Some((name, path))
+        })
+        .collect();
+    drives.sort();
+    Ok(drives)
+}
+
+// What walk_tree found below its root
+struct Walk {
+    // Every entry, breadth first so the closest come first, with its type
+    entries: Vec<(String, FileType)>,
+    // Whether MAX_SEARCH_DEPTH or MAX_SEARCH_VISITED cut the walk short
+    truncated: bool,
+    // Directories below the root that could not be read, and so were skipped
+    unreadable: u64,
+}
+
+// Every entry under `root` with its type. The walk stops at MAX_SEARCH_DEPTH
+// and MAX_SEARCH_VISITED; directories below `root` that cannot be read are
+// skipped
+fn walk_tree(root: &str) -> Result<Walk, AppError> {
+    let mut entries = Vec::new();
+    let mut truncated = false;
+    let mut unreadable = 0;
+    let mut pending = VecDeque::from([(root.to_string(), 0)]);
+    while let Some((dir_path, depth)) = pending.pop_front() {
+        let dir = vfs::Directory {
+            path: dir_path.clone(),
+            timeout: 5,
+        };
+        let listing = match dir.read() {
+            Ok(listing) => listing,
+            Err(e) if dir_path == root => {
+                return Err(vfs_error(
+                    &format!("Failed to read directory '{}'", root),
+                    e,
+                ))
+            }
+            Err(e) => {
+                error!("walk_tree: skipping '{}': {}", dir_path, e);
+                unreadable += 1;
+                continue;
+            }
+        };
+
+        for entry in listing {
+            if entries.len() >= MAX_SEARCH_VISITED {
+                return Ok(Walk {
+                    entries,
+                    truncated: true,
+                    unreadable,
+                });
+            }
+            let name = entry.path.split('/').last().unwrap_or("");
+            let entry_path = format!("{}/{}", dir_path, name);
+            if entry.file_type == FileType::Directory {
+                if depth + 1 < MAX_SEARCH_DEPTH {
+                    pending.push_back((entry_path.clone(), depth + 1));
+                } else {
+                    truncated = true;
+                }
+            }
+            entries.push((entry_path, entry.file_type));
+        }
+    }
+    Ok(Walk {
+        entries,
+        truncated,
+        unreadable,
+    })
+}
+
+// Add up what is below the directory `root`, walked as walk_tree walks it.
+// Directories that cannot be read, files whose metadata cannot be, and
+// entries that are neither files nor directories count as errors
+async fn directory_stats(root: &str) -> Result<DirStats, AppError> {
+    let Walk {
+        entries,
+        truncated,
+        unreadable,
+    } = walk_tree(root)?;
+    let mut stats = DirStats {
+        total_bytes: 0,
+        file_count: 0,
+        directory_count: 0,
+        largest_file: None,
+        truncated,
+        errors: unreadable,
+    };
+    for (path, file_type) in entries {
+        match file_type {
+            FileType::Directory => stats.directory_count += 1,
+            FileType::File => match file_info(&path).await {
+                Ok(info) => stats.add_file(info),
+                Err(e) => {
+                    error!("directory_stats: skipping '{}': {}", path, e.message);
+                    stats.errors += 1;
+                }
+            },
+            _ => stats.errors += 1,
+        }
+    }
+    Ok(stats)
+}
+
+// The total size of the files among walk_tree's `entries`; a file whose
+// metadata cannot be read counts for nothing
+async fn files_size(entries: &[(String, FileType)]) -> u64 {
+    let mut bytes = 0;
+    for (path, file_type) in entries {
+        if *file_type != FileType::File {
+            continue;
+        }
+        match vfs::metadata(path, Some(5)).await {
+            Ok(meta) => bytes += meta.len,
+            Err(e) => error!("Failed to get metadata for '{}': {}", path, e),
+        }
+    }
+    bytes
+}
+
+// The file at `vfs_path` and up to `limit` of its lines containing `query`,
+// read a chunk at a time; none for a binary file
+async fn matching_lines(
+    vfs_path: &str,
+    query: &str,
+    limit: usize,
+) -> Result<(FileInfo, Vec<(u32, String)>), AppError> {
+    let info = file_info(vfs_path).await?;
+    let mut file = vfs::open_file(vfs_path, false, Some(5))
+        .map_err(|e| lookup_error("Failed to open file", e))?;
+
+    let mut matcher = LineMatcher::new(query, limit);
+    let mut offset = 0;
+    while offset < info.size && !matcher.is_full() {
+        let chunk_len = (info.size - offset).min(CONTENT_CHUNK_BYTES) as usize;
+        let mut chunk = vec![0u8; chunk_len];
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_at(&mut chunk))
+            .map_err(|e| vfs_error(&format!("Failed to read at offset {}", offset), e))?;
+        if offset == 0 && looks_binary(&chunk) {
+            return Ok((info, Vec::new()));
+        }
+        matcher.feed(&chunk);
+        offset += chunk_len as u64;
+    }
+    Ok((info, matcher.finish()))
+}
+
+// The checksum of the `size` bytes of the file at `vfs_path`, read a chunk at
+// a time
+async fn file_checksum(
+    vfs_path: &str,
+    size: u64,
+    algorithm: &ChecksumAlgorithm,
+) -> Result<String, AppError> {
+    let mut file = vfs::open_file(vfs_path, false, Some(5))
+        .map_err(|e| lookup_error("Failed to open file", e))?;
+
+    let mut checksum = Checksum::new(algorithm);
+    let mut offset = 0;
+    while offset < size {
+        let chunk_len = (size - offset).min(CONTENT_CHUNK_BYTES) as usize;
+        let mut chunk = vec![0u8; chunk_len];
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_at(&mut chunk))
+            .map_err(|e| vfs_error(&format!("Failed to read at offset {}", offset), e))?;
+        checksum.update(&chunk);
+        offset += chunk_len as u64;
+    }
+    Ok(checksum.finish())
+}
+
+// `len` bytes of the file at `vfs_path` from `start`, without reading the
+// rest; fewer if the file ends first. Shared by read_file_range, Range
+// requests for shared files and MIME sniffing
+async fn read_range(vfs_path: &str, start: u64, len: u64) -> Result<Vec<u8>, AppError> {
+    let mut file = vfs::open_file(vfs_path, false, Some(5))
+        .map_err(|e| lookup_error("Failed to open file", e))?;
+    let mut buffer = vec![0u8; len as usize];
+    let read = file
+        .seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_at(&mut buffer))
+        .map_err(|e| {
+            vfs_error(
+                &format!("Failed to read '{}' at offset {}", vfs_path, start),
+                e,
+            )
+        })?;
+    buffer.truncate(read);
+    Ok(buffer)
+}
+
+// The content type of the `size`-byte file at `vfs_path`, reading only as
+// much of it as detect_mime looks at
+async fn sniff_mime(vfs_path: &str, size: u64) -> Result<&'static str, AppError> {
+    let head = if size == 0 {
+        Vec::new()
+    } else {
+        read_range(vfs_path, 0, size.min(MIME_SNIFF_BYTES)).await?
+    };
+    let name = vfs_path.rsplit('/').next().unwrap_or_default();
+    Ok(detect_mime(&head, name))
+}
+
+// Headers for a response that is the zip archive of the directory `path`
+fn add_zip_headers(path: &str) {
+    let name = path.split('/').last().filter(|name| !name.is_empty());
+    add_response_header(
+        "Content-Disposition".to_string(),
+        format!(
+            "attachment; filename=\"{}.zip\"",
+            name.unwrap_or("download")
+        ),
+    );
+    add_response_header("Content-Type".to_string(), "application/zip".to_string());
+}
+
+// X-Checksum-SHA256 for a shared download, so clients can check it arrived
+// intact
+fn add_checksum_header(body: &[u8]) {
+    add_response_header("X-Checksum-SHA256".to_string(), sha256_hex(body));
+}
+
+// A random 128-bit id, for share links and trash items. A link id says
+// nothing about the path, and sharing a path again after unsharing it gives a
+// new one
+fn new_random_id() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+// Whether `id` could have come from new_random_id, and so is safe to put in
+// a path
+fn is_random_id(id: &str) -> bool {
+    id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// Who sent the request being handled: "http" for the UI and HTTP API,
+// otherwise the asking process's address
+fn request_source() -> String {
+    let source = source();
+    if source.process.to_string() == "http-server:distro:sys" {
+        "http".to_string()
+    } else {
+        source.to_string()
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// A directory the explorer keeps for itself on the home drive
+fn home_dir(name: &str) -> String {
+    format!("/{}/home/{}", our().package_id(), name)
+}
+
+// The directory holding every file's kept versions
+fn versions_root() -> String {
+    home_dir(VERSIONS_DIR)
+}
+
+// The directory holding the kept versions of `path`
+fn versions_dir(path: &str) -> String {
+    format!(
+        "{}/{}",
+        versions_root(),
+        hex::encode(Sha256::digest(path.as_bytes()))
+    )
+}
+
+// The kept versions of `path`, newest first; none if it never had any
+async fn file_versions(path: &str) -> Result<Vec<VersionInfo>, AppError> {
+    let entries = match directory_entries(&versions_dir(path), false).await {
+        Ok(entries) => entries,
+        Err(e) if e.code == app_error::NOT_FOUND => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut versions: Vec<VersionInfo> = entries
+        .into_iter()
+        .filter(|entry| !entry.is_directory)
+        .filter_map(|entry| {
+            Some(VersionInfo {
+                timestamp: entry.name.parse().ok()?,
+                size: entry.size,
+            })
+        })
+        .collect();
+    versions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    Ok(versions)
+}
+
+// A tag as set_tags keeps it, so "Work" and " work" are the same tag
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+// The trash directory
+fn trash_root() -> String {
+    home_dir(TRASH_DIR)
+}
+
+// The directories the explorer keeps for itself: the trash, the kept versions
+// and the thumbnail cache
+fn system_dirs() -> [String; 3] {
+    [trash_root(), versions_root(), home_dir(THUMBNAILS_DIR)]
+}
+
+// Whether `path` is inside the trash, the kept versions or the thumbnail cache
+fn is_internal(path: &str) -> bool {
+    system_dirs()
+        .iter()
+        .any(|dir| path.starts_with(&format!("{}/", dir)))
+}
+
+// Which entries listings, trees and searches show
+#[derive(Debug, Clone, Copy)]
+struct Visibility {
+    show_hidden: bool,
+    show_system: bool,
+}
+
+impl Visibility {
+    // Whether the entry at `path` is shown: one whose name starts with `.`
+    // only with show_hidden, and one of system_dirs with show_system too
+    fn is_shown(self, path: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or("");
+        if !name.starts_with('.') {
+            return true;
+        }
+        // VFS listings give paths without their leading `/`
+        let path = path.trim_start_matches('/');
+        let is_system = system_dirs()
+            .iter()
+            .any(|dir| dir.trim_start_matches('/') == path);
+        self.show_hidden && (self.show_system || !is_system)
+    }
+
+    // Whether `path`, somewhere below `root`, is shown, and so is every
+    // directory between them
+    fn is_shown_below(self, root: &str, path: &str) -> bool {
+        let mut current = path;
+        while current.len() > root.len() {
+            if !self.is_shown(current) {
+                return false;
+            }
+            match current.rsplit_once('/') {
+                Some((parent, _)) => current = parent,
+                None => break,
+            }
+        }
+        true
+    }
+}
+
+// The directory holding the cached thumbnails of `path`
+fn thumbnails_dir(path: &str) -> String {
+    format!(
+        "{}/{}",
+        home_dir(THUMBNAILS_DIR),
+        hex::encode(Sha256::digest(path.as_bytes()))
+    )
+}
+
+// Forget the cached thumbnails of `path` after it changed or went away. A
+// failure is only logged: the size in a cached thumbnail's name still keeps
+// most stale ones from being served
+async fn drop_thumbnails(path: &str) {
+    match remove_dir_all(&thumbnails_dir(path)).await {
+        Ok(()) => debug!("Dropped the thumbnails of '{}'", path),
+        Err(e) if e.code == app_error::NOT_FOUND => {}
+        Err(e) => error!("Failed to drop the thumbnails of '{}': {}", path, e.message),
+    }
+}
+
+// The directory a trash item is kept in
+fn trash_entry_dir(trash_id: &str) -> String {
+    format!("{}/{}", trash_root(), trash_id)
+}
+
+// Where a trash item's file or directory is kept, under its own name
+fn trashed_path(item: &TrashItem) -> String {
+    let name = item.original_path.rsplit('/').next().unwrap_or_default();
+    format!("{}/{}", trash_entry_dir(&item.id), name)
+}
+
+fn share_link(share_id: &str) -> String {
+    format!("/{PROCESS_ID_LINK}/shared/{share_id}")
+}
+
+fn share_info(path: &str, share: &Share) -> ShareInfo {
+    ShareInfo {
+        path: path.to_string(),
+        id: share.id.clone(),
+        link: share_link(&share.id),
+        auth: share.auth.clone(),
+        created: share.created,
+        hits: share.hits,
+        max_downloads: share.max_downloads,
+        downloads_remaining: share
+            .max_downloads
+            .map(|max| u64::from(max).saturating_sub(share.hits) as u32),
+    }
+}
+
+// Delete a file for good
+async fn remove_file(vfs_path: &str) -> Result<(), AppError> {
+    vfs::remove_file(vfs_path, Some(5))
+        .await
+        .map_err(|e| vfs_error("Failed to delete file", e))
+}
+
+// Delete a directory and everything in it for good
+async fn remove_dir_all(vfs_path: &str) -> Result<(), AppError> {
+    let timeout = 5;
+
+    // Create a VFS request with RemoveDirAll action to handle non-empty directories
+    let request = vfs_request(vfs_path, VfsAction::RemoveDirAll).expects_response(timeout);
+
+    // Send the request and await response
+    let response: Result<VfsResponse, _> = send(request).await;
+
+    match response {
+        Ok(VfsResponse::Ok) => Ok(()),
+        Ok(VfsResponse::Err(e)) => Err(vfs_error("Failed to delete directory", e)),
+        Ok(_) => Err(AppError::internal("Unexpected response from VFS")),
+        Err(e) => Err(send_error("Failed to send VFS request", e)),
+    }
+}
+
+// Map a GET that http-client could not carry out. A request it refused is a
+// bad URL; one it sent that failed is the far side's fault and may well work
+// later
+fn client_error(url: &str, e: HttpClientError) -> AppError {
+    match e {
+        HttpClientError::BadUrl { .. }
+        | HttpClientError::BadMethod { .. }
+        | HttpClientError::BadVersion { .. }
+        | HttpClientError::BuildRequestFailed(_) => {
+            AppError::invalid_input(format!("GET {} failed: {}", url, e))
+        }
+        HttpClientError::ExecuteRequestFailed(_) => AppError {
+            retryable: true,
+            ..AppError::new(UPSTREAM_ERROR, format!("GET {} failed: {}", url, e))
+        },
+        _ => AppError::internal(format!("GET {} failed: {}", url, e)),
+    }
+}
+
+// The length the server gives for `url` in answer to a HEAD request, so an
+// import can be refused before any of its body is fetched. None when it
+// does not say, or does not answer HEAD; the GET then goes ahead
+fn announced_length(url: &url::Url) -> Option<u64> {
+    let response = send_request_await_response(
+        Method::HEAD,
+        url.clone(),
+        None,
+        IMPORT_TIMEOUT_SECS,
+        Vec::new(),
+    )
+    .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response
+        .headers()
+        .get("content-length")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// Rename `from` to `to` with a single VFS request; the VFS only renames within
+// a drive
+async fn vfs_rename(from: &str, to: &str) -> Result<(), AppError> {
+    let request = vfs_request(
+        from,
+        VfsAction::Rename {
+            new_path: to.to_string(),
+        },
+    )
+    .expects_response(5);
+    match send::<VfsResponse>(request).await {
+        Ok(VfsResponse::Ok) => Ok(()),
+        Ok(VfsResponse::Err(e)) => Err(vfs_error(&format!("Failed to rename '{}'", from), e)),
+        Ok(_) => Err(AppError::internal("Unexpected response from VFS")),
+        Err(e) => Err(send_error("Failed to send VFS request", e)),
+    }
+}
+
+// Helper function to list directory contents with 2 levels of depth: each
+// entry by name, each directory followed by its own entries
+async fn list_directory_contents(
+    path: &str,
+    visibility: Visibility,
+) -> Result<Vec<FileInfo>, AppError> {
+    debug!("list_directory_contents: path='{}'", path);
+
+    let (tree, _) = tree_below(path, 2, usize::MAX, visibility).await?;
+    let mut all_files = Vec::new();
+    for node in tree {
+        all_files.push(node.info);
+        all_files.extend(node.children.into_iter().map(|child| child.info));
+    }
+
+    debug!("Returning {} files total", all_files.len());
+    Ok(all_files)
+}
+
+// What is below `path` as a tree, `max_depth` levels deep, each level by name;
+// level 1 is what is directly in `path`. The walk goes breadth first, so when
+// it stops at `max_entries` the closest entries are the ones listed. A
+// listed directory's size is the number of entries in it, or 0 if it was not
+// read. Entries `visibility` hides are left out. Says whether either limit
+// left something out; directories below `path` that cannot be read are left
+// empty
+async fn tree_below(
+    path: &str,
+    max_depth: u32,
+    max_entries: usize,
+    visibility: Visibility,
+) -> Result<(Vec<TreeNode>, bool), AppError> {
+    // Every entry found, in walk order, with the index of its directory's
+    // node; None for those directly in `path`
+    let mut found: Vec<(TreeNode, Option<usize>)> = Vec::new();
+    let mut truncated = false;
+    // VFS has no links, but a directory seen twice is never walked twice
+    let mut visited = HashSet::from([path.to_string()]);
+    let mut pending = VecDeque::from([(path.to_string(), None, 1)]);
+
+    while let Some((dir_path, parent, depth)) = pending.pop_front() {
+        let mut entries = match directory_entries(&dir_path, false).await {
+            Ok(entries) => entries,
+            Err(e) if parent.is_none() => return Err(e),
+            Err(e) => {
+                error!("Failed to read subdirectory '{}': {}", dir_path, e.message);
+                continue;
+            }
+        };
+        entries.retain(|entry| visibility.is_shown(&entry.path));
+        sort_entries(&mut entries, &SortBy::Name, true, false);
+        if let Some(parent) = parent {
+            found[parent].0.info.size = entries.len() as u64;
+        }
+
+        for info in entries {
+            if found.len() >= max_entries {
+                // This directory and every one still waiting lose entries
+                for parent in std::iter::once(parent).chain(pending.iter().map(|p| p.1)) {
+                    match parent {
+                        Some(parent) => found[parent].0.truncated = true,
+                        None => truncated = true,
+                    }
+                }
+                pending.clear();
+                break;
+            }
+            let mut node_truncated = false;
+            if info.is_directory {
+                if depth >= max_depth {
+                    node_truncated = true;
+                } else if visited.insert(info.path.clone()) {
+                    pending.push_back((info.path.clone(), Some(found.len()), depth + 1));
+                }
+            }
+            found.push((
+                TreeNode {
+                    info,
+                    children: Vec::new(),
+                    truncated: node_truncated,
+                },
+                parent,
+            ));
+        }
+    }
+
+    // Every node comes after its directory's, so taking them from the end
+    // hands each directory its children complete, in reverse
+    let mut tree = Vec::new();
+    while let Some((mut node, parent)) = found.pop() {
+        node.children.reverse();
+        match parent {
+            Some(parent) => {
+                let directory = &mut found[parent].0;
+                directory.truncated |= node.truncated;
+                directory.children.push(node);
+            }
+            None => {
+                truncated |= node.truncated;
+                tree.push(node);
+            }
+        }
+    }
+    tree.reverse();
+    Ok((tree, truncated))
+}
+
+// The entries directly in `path`, in VFS order. A directory's size is the
+// number of entries in it when `count_children` is set, else 0; a file whose
+// metadata cannot be read is left out
+async fn directory_entries(path: &str, count_children: bool) -> Result<Vec<FileInfo>, AppError> {
+    let dir = vfs::Directory {
+        path: path.to_string(),
+        timeout: 5,
+    };
+    let listing = dir
+        .read()
+        .map_err(|e| vfs_error(&format!("Failed to read directory '{}'", path), e))?;
+    debug!("VFS returned {} entries for path '{}'", listing.len(), path);
+
+    let mut entries = Vec::new();
+    for entry in listing {
+        // VFS already provides absolute paths in entry.path
+        let name = entry.path.split('/').last().unwrap_or("").to_string();
+        let size = if entry.file_type == FileType::Directory {
+            if !count_children {
+                0
+            } else {
+                let sub_dir = vfs::Directory {
+                    path: entry.path.clone(),
+                    timeout: 5,
+                };
+                match sub_dir.read() {
+                    Ok(contents) => contents.len() as u64,
+                    Err(e) => {
+                        error!("Failed to read subdirectory '{}': {}", entry.path, e);
+                        0
+                    }
+                }
+            }
+        } else {
+            match vfs::metadata(&entry.path, Some(5)).await {
+                Ok(meta) => meta.len,
+                Err(e) => {
+                    error!("Failed to get metadata for '{}': {}", entry.path, e);
+                    continue;
+                }
+            }
+        };
+
+        entries.push(FileInfo {
+            name,
+            path: entry.path,
+            size,
+            created: 0,
+            modified: 0,
+            is_directory: entry.file_type == FileType::Directory,
+            permissions: "rw".to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+// Sort directory entries for a listing. Ties, and every entry under Modified
+// while the VFS reports no times, fall back to the name so a page boundary
+// never moves between requests
+fn sort_entries(
+    entries: &mut [FileInfo],
+    sort_by: &SortBy,
+    ascending: bool,
+    directories_first: bool,
+) {
+    let extension = |info: &FileInfo| match info.name.rsplit_once('.') {
+        Some((stem, extension)) if !info.is_directory && !stem.is_empty() => {
+            extension.to_lowercase()
+        }
+        _ => String::new(),
+    };
+    entries.sort_by(|a, b| {
+        let order = match sort_by {
+            SortBy::Name => std::cmp::Ordering::Equal,
+            SortBy::Size => a.size.cmp(&b.size),
+            SortBy::Modified => a.modified.cmp(&b.modified),
+            SortBy::Type => b
+                .is_directory
+                .cmp(&a.is_directory)
+                .then_with(|| extension(a).cmp(&extension(b))),
+        }
+        .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        .then_with(|| a.name.cmp(&b.name));
+        let order = if ascending { order } else { order.reverse() };
+        if directories_first {
+            b.is_directory.cmp(&a.is_directory).then(order)
+        } else {
+            order
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: &str = "/file-explorer:sys/home/kept.txt";
+
+    const SHARE_ID: &str = "00112233445566778899aabbccddeeff";
+
+    const EXHAUSTED_ID: &str = "ffeeddccbbaa99887766554433221100";
+
+    // What a restart does to the state: the copy saved after the last change
+    // is loaded back, and init rebuilds what is not saved
+    fn restart(state: &FileExplorerState) -> FileExplorerState {
+        load(&serde_json::to_vec(state).unwrap())
+    }
+
+    fn load(saved: &[u8]) -> FileExplorerState {
+        let mut state: FileExplorerState = serde_json::from_slice(saved).unwrap();
+        state.index_shares();
+        state
+    }
+
+    fn saved_fields(state: &FileExplorerState) -> serde_json::Value {
+        serde_json::to_value(state).unwrap()
+    }
+
+    fn populated() -> FileExplorerState {
+        let mut state = FileExplorerState {
+            cwd: "/file-explorer:sys/home/projects".to_string(),
+            versioning_enabled: true,
+            max_versions: Some(3),
+            show_hidden: true,
+            recent_opt_out: true,
+            ..FileExplorerState::default()
+        };
+        state.shared_files.insert(
+            PATH.to_string(),
+            Share {
+                id: SHARE_ID.to_string(),
+                auth: AuthScheme::Private(vec!["peer.os".to_string()]),
+                created: 1_700_000_000_000,
+                hits: 2,
+                max_downloads: Some(5),
+            },
+        );
+        state.index_shares();
+        state.exhausted_shares.push_back(EXHAUSTED_ID.to_string());
+        state.favorites.push(SavedFavorite {
+            path: PATH.to_string(),
+            label: "Kept".to_string(),
+        });
+        state
+            .tags
+            .insert(PATH.to_string(), vec!["work".to_string()]);
+        state.trash.insert(
+            "t1".to_string(),
+            TrashItem {
+                id: "t1".to_string(),
+                original_path: "/file-explorer:sys/home/gone.txt".to_string(),
+                is_directory: false,
+                deleted_at: 1_700_000_000_001,
+            },
+        );
+        state.activity_log.push_back(ActivityEntry {
+            timestamp: 1_700_000_000_002,
+            operation: Activity::Shared,
+            path: PATH.to_string(),
+            from: None,
+            source: "http".to_string(),
+        });
+        state
+            .recent_files
+            .push_back((PATH.to_string(), 1_700_000_000_003));
+        state
+    }
+
+    #[test]
+    fn saved_state_survives_a_restart() {
+        let before = populated();
+        let after = restart(&before);
+        assert_eq!(saved_fields(&before), saved_fields(&after));
+    }
+
+    #[test]
+    fn share_links_resolve_after_a_restart() {
+        let after = restart(&populated());
+        assert_eq!(after.share_paths.get(SHARE_ID), Some(&PATH.to_string()));
+        assert_eq!(after.share_paths.len(), 1);
+    }
+
+    #[test]
+    fn state_saved_before_anything_was_loads_empty() {
+        let state = load(b"{}");
+        assert_eq!(
+            saved_fields(&state),
+            saved_fields(&FileExplorerState::default())
+        );
+        assert!(state.share_paths.is_empty());
+    }
+
+    #[test]
+    fn dropping_a_directory_drops_the_shares_under_it() {
+        let mut state = populated();
+        state.drop_shares("/file-explorer:sys/home/kept");
+        assert!(state.shared_files.contains_key(PATH));
+
+        state.drop_shares("/file-explorer:sys/home");
+        assert!(state.shared_files.is_empty());
+        assert!(state.share_paths.is_empty());
+        assert!(state.find_share(SHARE_ID).is_none());
+    }
+
+    #[test]
+    fn exhausted_links_stay_exhausted_after_a_restart() {
+        let after = restart(&populated());
+        assert_eq!(after.missing_share(EXHAUSTED_ID).code, LINK_EXHAUSTED);
+        assert_eq!(after.missing_share("unknown").code, app_error::NOT_FOUND);
+    }
+
+    #[test]
+    fn shares_saved_by_an_older_build_load_unlimited() {
+        let saved = serde_json::json!({
+            "shared_files": { PATH: { "id": SHARE_ID, "auth": "Public" } },
+            "cwd": "/file-explorer:sys/home",
+        });
+        let state = load(saved.to_string().as_bytes());
+
+        let share = &state.shared_files[PATH];
+        assert_eq!(
+            (share.created, share.hits, share.max_downloads),
+            (0, 0, None)
+        );
+        assert!(matches!(share.auth, AuthScheme::Public));
+        assert_eq!(state.share_paths.get(SHARE_ID), Some(&PATH.to_string()));
+        assert_eq!(state.cwd, "/file-explorer:sys/home");
+    }
 }
@@ -0,0 +1,70 @@
+// Content types for served and previewed files, from their leading bytes or,
+// failing that, their name. Kept free of process_lib like path.rs.
+
+/// Leading bytes detect_mime looks at; the longest signature it knows (WEBP
+/// and WAV, tagged at offset 8) ends at 12
+pub const MIME_SNIFF_BYTES: u64 = 16;
+
+/// The content type of a file that starts with `head` and is named
+/// `filename`. A known magic number wins over the extension, so a PNG named
+/// `.txt` is still served as a PNG; a file with neither a known signature nor
+/// a known extension is `application/octet-stream`
+pub fn detect_mime(head: &[u8], filename: &str) -> &'static str {
+    sniff(head).unwrap_or_else(|| by_extension(filename))
+}
+
+fn sniff(head: &[u8]) -> Option<&'static str> {
+    let riff_tagged = |tag: &[u8]| head.starts_with(b"RIFF") && head.get(8..12) == Some(tag);
+    let mime = if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        "image/gif"
+    } else if riff_tagged(b"WEBP") {
+        "image/webp"
+    } else if riff_tagged(b"WAVE") {
+        "audio/wav"
+    } else if head.starts_with(b"%PDF-") {
+        "application/pdf"
+    } else if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+        "application/zip"
+    } else if head.starts_with(&[0x1F, 0x8B]) {
+        "application/gzip"
+    } else if head.starts_with(b"ID3") || is_mpeg_audio_frame(head) {
+        "audio/mpeg"
+    } else {
+        return None;
+    };
+    Some(mime)
+}
+
+// An MPEG audio frame header without an ID3 tag: eleven set sync bits, then
+// a layer other than the reserved 00
+fn is_mpeg_audio_frame(head: &[u8]) -> bool {
+    matches!(head, [0xFF, second, ..] if second & 0xE0 == 0xE0 && second & 0x06 != 0)
+}
+
+fn by_extension(filename: &str) -> &'static str {
+    let extension = match filename.rsplit_once('.') {
+        Some((_, extension)) => extension.to_lowercase(),
+        None => return "application/octet-stream",
+    };
+    match extension.as_str() {
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" => "application/javascript",
+        "json" => "application/json",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,99 @@
+// Path normalization shared by every handler that takes a VFS path.
+// Kept free of process_lib so the explorer tester can compile it directly.
+
+/// Longest single file or directory name the explorer will pass to VFS
+pub const MAX_NAME_LEN: usize = 255;
+
+/// Longest whole path the explorer will pass to VFS
+pub const MAX_PATH_LEN: usize = 4096;
+
+/// Segments that make up a drive root: `package:publisher/drive`
+const DRIVE_DEPTH: usize = 2;
+
+/// Normalize a VFS path to `/package:publisher/drive/...` form.
+///
+/// Empty and `.` segments are dropped (so `//`, trailing slashes and `./`
+/// collapse). A `..` segment is refused wherever it appears, even one that
+/// would stay inside the drive: handlers never need it, and a path that
+/// cannot name a parent cannot climb out of anything. The result is
+/// idempotent: normalizing it again returns it unchanged. `/` on its own
+/// stays `/` so the VFS root can still be listed.
+pub fn normalize_path(path: &str) -> Result<String, String> {
+    if path.contains('\0') {
+        return Err("Invalid path: contains a NUL byte".to_string());
+    }
+    if path.len() > MAX_PATH_LEN {
+        return Err(format!("Invalid path: longer than {MAX_PATH_LEN} bytes"));
+    }
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                return Err(format!("Invalid path: '{path}' has a '..' segment"));
+            }
+            name if name.len() > MAX_NAME_LEN => {
+                return Err(format!(
+                    "Invalid path: name longer than {MAX_NAME_LEN} bytes"
+                ));
+            }
+            name => segments.push(name),
+        }
+    }
+
+    Ok(format!("/{}", segments.join("/")))
+}
+
+/// Refuse a normalized `path` on a drive of any package but `package`
+/// (`name:publisher`). `/` itself only lists drives, so it passes.
+pub fn check_package(path: &str, package: &str) -> Result<(), String> {
+    let first = path.trim_start_matches('/').split('/').next().unwrap_or("");
+    if first.is_empty() || first == package {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid path: '{path}' is outside the {package} drives"
+        ))
+    }
+}
+
+/// Whether the normalized paths `a` and `b` are on the same drive, the
+/// only place a VFS rename can move anything.
+pub fn same_drive(a: &str, b: &str) -> bool {
+    let drive = |path| {
+        str::trim_start_matches(path, '/')
+            .split('/')
+            .take(DRIVE_DEPTH)
+    };
+    drive(a).eq(drive(b))
+}
+
+/// Resolve `relative` against the directory `cwd`, then normalize it.
+///
+/// A `relative` starting with `/` is already absolute. Unlike
+/// normalize_path, `..` here steps up to the parent, since this is how the
+/// UI navigates; it may not climb out of the drive, so `..` from a drive
+/// root is an error rather than the package or VFS root.
+pub fn resolve_path(cwd: &str, relative: &str) -> Result<String, String> {
+    let joined = if relative.starts_with('/') {
+        relative.to_string()
+    } else {
+        format!("{}/{}", cwd.trim_end_matches('/'), relative)
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.len() <= DRIVE_DEPTH {
+                    return Err(format!("Invalid path: '{joined}' escapes its drive"));
+                }
+                segments.pop();
+            }
+            name => segments.push(name),
+        }
+    }
+    normalize_path(&format!("/{}", segments.join("/")))
+}
@@ -0,0 +1,125 @@
+// Bookkeeping for long-running operations: how far each has got and whether
+// cancel_operation asked it to stop. Kept free of process_lib like path.rs;
+// the explorer pushes the events this hands back.
+
+use std::collections::HashMap;
+
+/// Least time between two progress events of one running operation
+pub const PROGRESS_INTERVAL_MS: u64 = 250;
+
+/// Where an operation is: running until its last event says how it ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressStatus {
+    Running,
+    Finished,
+    Cancelled,
+    Failed,
+}
+
+/// One progress event as sent to clients, `{"type": "progress", ...}`.
+/// `operation` names the handler and `path` what it was asked to work on;
+/// `total_bytes` is 0 when it was not known up front, and `current_path` is
+/// the file last worked on
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "progress")]
+pub struct ProgressEvent {
+    pub operation_id: String,
+    pub operation: String,
+    pub path: String,
+    pub completed_bytes: u64,
+    pub total_bytes: u64,
+    pub current_path: Option<String>,
+    pub status: ProgressStatus,
+}
+
+#[derive(Debug)]
+struct Running {
+    event: ProgressEvent,
+    cancelled: bool,
+    last_event_ms: u64,
+}
+
+/// The operations running now, by id. A finished one is dropped as its last
+/// event is handed back
+#[derive(Default, Debug)]
+pub struct Operations {
+    running: HashMap<String, Running>,
+}
+
+impl Operations {
+    /// Register operation `id` and return its first event
+    pub fn start(
+        &mut self,
+        id: String,
+        operation: &str,
+        path: &str,
+        total_bytes: u64,
+        now_ms: u64,
+    ) -> ProgressEvent {
+        let event = ProgressEvent {
+            operation_id: id.clone(),
+            operation: operation.to_string(),
+            path: path.to_string(),
+            completed_bytes: 0,
+            total_bytes,
+            current_path: None,
+            status: ProgressStatus::Running,
+        };
+        self.running.insert(
+            id,
+            Running {
+                event: event.clone(),
+                cancelled: false,
+                last_event_ms: now_ms,
+            },
+        );
+        event
+    }
+
+    /// Count `bytes` more of operation `id` done, the last of them from
+    /// `path`. Returns an event when PROGRESS_INTERVAL_MS has passed since
+    /// the last one
+    pub fn advance(
+        &mut self,
+        id: &str,
+        bytes: u64,
+        path: &str,
+        now_ms: u64,
+    ) -> Option<ProgressEvent> {
+        let running = self.running.get_mut(id)?;
+        running.event.completed_bytes += bytes;
+        running.event.current_path = Some(path.to_string());
+        if now_ms < running.last_event_ms + PROGRESS_INTERVAL_MS {
+            return None;
+        }
+        running.last_event_ms = now_ms;
+        Some(running.event.clone())
+    }
+
+    /// Ask operation `id` to stop the next time it checks; false if no
+    /// operation by that id is running
+    pub fn cancel(&mut self, id: &str) -> bool {
+        match self.running.get_mut(id) {
+            Some(running) => {
+                running.cancelled = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether operation `id` was asked to stop
+    pub fn is_cancelled(&self, id: &str) -> bool {
+        self.running
+            .get(id)
+            .is_some_and(|running| running.cancelled)
+    }
+
+    /// Drop operation `id`, returning its last event with how it ended
+    pub fn finish(&mut self, id: &str, status: ProgressStatus) -> Option<ProgressEvent> {
+        let mut event = self.running.remove(id)?.event;
+        event.status = status;
+        Some(event)
+    }
+}
@@ -0,0 +1,69 @@
+// HTTP Range headers for serve_shared_file. Kept free of process_lib like
+// path.rs.
+
+/// What a request's Range header asks of a file of a known length
+#[derive(Debug, PartialEq)]
+pub enum ByteRange {
+    /// No usable range: send the whole file, as without the header
+    Full,
+    /// Bytes `start..=end`, both within the file
+    Partial { start: u64, end: u64 },
+    /// A range that starts past the end of the file
+    Unsatisfiable,
+}
+
+/// Parse a `Range` header for a file of `len` bytes.
+///
+/// Takes one range in any of its forms: `bytes=start-end`, the open-ended
+/// `bytes=start-` and the suffix `bytes=-count`; an end past the file is cut
+/// to the last byte. A header that is malformed, names another unit or asks
+/// for several ranges is ignored, which RFC 9110 allows, and the whole file
+/// is sent.
+pub fn parse_range(header: &str, len: u64) -> ByteRange {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+    let parse = |n: &str| n.trim().parse::<u64>().ok();
+
+    match (start.trim().is_empty(), end.trim().is_empty()) {
+        // bytes=-count: the last `count` bytes
+        (true, false) => match parse(end) {
+            Some(0) => ByteRange::Unsatisfiable,
+            Some(_) if len == 0 => ByteRange::Unsatisfiable,
+            Some(count) => ByteRange::Partial {
+                start: len.saturating_sub(count),
+                end: len - 1,
+            },
+            None => ByteRange::Full,
+        },
+        // bytes=start- and bytes=start-end
+        (false, open_ended) => {
+            let Some(start) = parse(start) else {
+                return ByteRange::Full;
+            };
+            let end = if open_ended {
+                u64::MAX
+            } else {
+                match parse(end) {
+                    Some(end) if end >= start => end,
+                    _ => return ByteRange::Full,
+                }
+            };
+            if start >= len {
+                ByteRange::Unsatisfiable
+            } else {
+                ByteRange::Partial {
+                    start,
+                    end: end.min(len - 1),
+                }
+            }
+        }
+        (true, true) => ByteRange::Full,
+    }
+}
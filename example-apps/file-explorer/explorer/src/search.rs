@@ -0,0 +1,265 @@
+// Matching and limits for the explorer's name and content searches.
+// Kept free of process_lib like path.rs.
+
+/// Deepest directory below the search root a search descends into
+pub const MAX_SEARCH_DEPTH: usize = 32;
+
+/// Most entries one search looks at before giving up, so a search over a
+/// large tree answers instead of running until the request times out
+pub const MAX_SEARCH_VISITED: usize = 20_000;
+
+/// Whether the entry name `name` matches the search `query`, ignoring case.
+///
+/// A query with `*` (any run of characters) or `?` (any one character) is a
+/// glob that must match the whole name; any other query matches names that
+/// contain it.
+pub fn name_matches(query: &str, name: &str) -> bool {
+    let query = query.to_lowercase();
+    let name = name.to_lowercase();
+    if query.contains(['*', '?']) {
+        let pattern: Vec<GlobToken> = query
+            .chars()
+            .map(|c| match c {
+                '*' => GlobToken::Star,
+                '?' => GlobToken::AnyOne,
+                c => GlobToken::Literal(c),
+            })
+            .collect();
+        let name: Vec<char> = name.chars().collect();
+        glob_matches(&pattern, &name)
+    } else {
+        name.contains(&query)
+    }
+}
+
+/// Whether the entry name `name` matches the listing filter `pattern`, a
+/// glob that must match the whole name: `*` is any run of characters, `?`
+/// any one character, and `[abc]`, `[a-z]` or `[!abc]` one character in or
+/// not in a set. Case is ignored unless `case_sensitive`; a `[` that is never
+/// closed is an ordinary character.
+pub fn glob_match(pattern: &str, name: &str, case_sensitive: bool) -> bool {
+    let (pattern, name) = if case_sensitive {
+        (pattern.to_string(), name.to_string())
+    } else {
+        (pattern.to_lowercase(), name.to_lowercase())
+    };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    glob_matches(&parse_glob(&pattern), &name)
+}
+
+// One piece of a glob
+#[derive(Debug, PartialEq)]
+enum GlobToken {
+    // `*`
+    Star,
+    // `?`
+    AnyOne,
+    // `[...]`: one character in the inclusive ranges, or with `negated` one
+    // not in them
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Literal(char),
+}
+
+impl GlobToken {
+    // Whether this token, other than a star, matches the one character `c`
+    fn matches(&self, c: char) -> bool {
+        match self {
+            GlobToken::Star => false,
+            GlobToken::AnyOne => true,
+            GlobToken::Class { negated, ranges } => {
+                ranges.iter().any(|&(low, high)| low <= c && c <= high) != *negated
+            }
+            GlobToken::Literal(literal) => *literal == c,
+        }
+    }
+}
+
+fn parse_glob(pattern: &[char]) -> Vec<GlobToken> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < pattern.len() {
+        let token = match pattern[i] {
+            '*' => GlobToken::Star,
+            '?' => GlobToken::AnyOne,
+            '[' => match parse_class(&pattern[i + 1..]) {
+                Some((class, used)) => {
+                    i += used;
+                    class
+                }
+                None => GlobToken::Literal('['),
+            },
+            c => GlobToken::Literal(c),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    tokens
+}
+
+// The class whose body starts `body`, just past its `[`, and how many
+// characters of `body` it took, its closing `]` included. A `]` first in the
+// set is one of its characters, as in shell globs. None if it is never closed
+fn parse_class(body: &[char]) -> Option<(GlobToken, usize)> {
+    let negated = matches!(body.first(), Some('!' | '^'));
+    let start = usize::from(negated);
+    let mut ranges = Vec::new();
+    let mut i = start;
+    loop {
+        let &c = body.get(i)?;
+        if c == ']' && i > start {
+            return Some((GlobToken::Class { negated, ranges }, i + 1));
+        }
+        match (body.get(i + 1), body.get(i + 2)) {
+            (Some('-'), Some(&high)) if high != ']' => {
+                ranges.push((c, high));
+                i += 3;
+            }
+            _ => {
+                ranges.push((c, c));
+                i += 1;
+            }
+        }
+    }
+}
+
+// Greedy glob match that backtracks only to the last `*`, so it stays linear
+// in practice however many stars the pattern has
+fn glob_matches(pattern: &[GlobToken], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(GlobToken::Star) => {
+                last_star = Some((p, n));
+                p += 1;
+            }
+            Some(token) if token.matches(name[n]) => {
+                p += 1;
+                n += 1;
+            }
+            _ => match last_star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    last_star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|token| *token == GlobToken::Star)
+}
+
+/// Most matching lines content search reports from one file
+pub const MAX_HITS_PER_FILE: usize = 20;
+
+/// Most matching lines one content search reports
+pub const MAX_HITS: usize = 500;
+
+/// A file with a NUL byte in this many leading bytes is binary and skipped
+pub const BINARY_SNIFF_BYTES: usize = 1024;
+
+/// Longest line content search looks at; the rest of a longer line is skipped
+pub const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// Longest snippet of a matching line, in characters
+pub const SNIPPET_CHARS: usize = 200;
+
+/// Extensions content search opens when the caller names none
+pub const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "json", "toml", "yaml", "yml", "csv", "log", "xml", "html", "css", "js", "ts",
+    "tsx", "rs", "py", "sh", "wit",
+];
+
+/// Whether content search should open a file named `name`. Extensions are
+/// compared without case and with or without a leading dot; an empty list
+/// means TEXT_EXTENSIONS.
+pub fn wanted_extension(name: &str, extensions: &[String]) -> bool {
+    let Some((_, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    let extension = extension.to_lowercase();
+    if extensions.is_empty() {
+        TEXT_EXTENSIONS.contains(&extension.as_str())
+    } else {
+        extensions
+            .iter()
+            .any(|wanted| wanted.trim_start_matches('.').to_lowercase() == extension)
+    }
+}
+
+pub fn looks_binary(head: &[u8]) -> bool {
+    head.iter().take(BINARY_SNIFF_BYTES).any(|&b| b == 0)
+}
+
+/// Finds the lines of one file that contain a query, ignoring case, from the
+/// file's bytes fed in chunks of any size
+pub struct LineMatcher {
+    query: String,
+    limit: usize,
+    line: u32,
+    pending: Vec<u8>,
+    // Set while the rest of an over-long line is being skipped
+    skipping: bool,
+    hits: Vec<(u32, String)>,
+}
+
+impl LineMatcher {
+    pub fn new(query: &str, limit: usize) -> Self {
+        LineMatcher {
+            query: query.to_lowercase(),
+            limit,
+            line: 0,
+            pending: Vec::new(),
+            skipping: false,
+            hits: Vec::new(),
+        }
+    }
+
+    /// Whether `limit` lines have matched, so there is no point reading on
+    pub fn is_full(&self) -> bool {
+        self.hits.len() >= self.limit
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            if self.is_full() {
+                return;
+            }
+            if byte == b'\n' {
+                if !self.skipping {
+                    self.end_line();
+                }
+                self.skipping = false;
+            } else if !self.skipping {
+                self.pending.push(byte);
+                if self.pending.len() >= MAX_LINE_BYTES {
+                    self.end_line();
+                    self.skipping = true;
+                }
+            }
+        }
+    }
+
+    /// The matching lines, numbered from 1, with a snippet of each
+    pub fn finish(mut self) -> Vec<(u32, String)> {
+        if !self.pending.is_empty() && !self.is_full() {
+            self.end_line();
+        }
+        self.hits
+    }
+
+    fn end_line(&mut self) {
+        self.line += 1;
+        let line = String::from_utf8_lossy(&self.pending);
+        if line.to_lowercase().contains(&self.query) {
+            let snippet = line.trim().chars().take(SNIPPET_CHARS).collect();
+            self.hits.push((self.line, snippet));
+        }
+        self.pending.clear();
+    }
+}
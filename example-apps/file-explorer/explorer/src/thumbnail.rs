@@ -0,0 +1,45 @@
+// Downscaled JPEG previews of PNG, JPEG and GIF images, for get_thumbnail.
+// Kept free of process_lib like path.rs.
+
+use image::codecs::jpeg::JpegEncoder;
+use image::ImageFormat;
+
+/// Largest thumbnail side get_thumbnail makes
+pub const MAX_THUMBNAIL_DIMENSION: u32 = 1024;
+
+/// Largest image file get_thumbnail decodes; the whole file is read and
+/// decoded in memory
+pub const MAX_THUMBNAIL_SOURCE_BYTES: u64 = 20 * 1024 * 1024;
+
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// A JPEG of the image `bytes`, scaled down to fit in `max_dimension` on both
+/// sides, keeping its aspect ratio; a smaller image keeps its size. Anything
+/// but a PNG, JPEG or GIF, and an image that fails to decode, is an error,
+/// never a panic
+pub fn make_thumbnail(bytes: &[u8], max_dimension: u32) -> Result<Vec<u8>, String> {
+    let format = image::guess_format(bytes)
+        .ok()
+        .filter(|format| {
+            matches!(
+                format,
+                ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif
+            )
+        })
+        .ok_or("Not a PNG, JPEG or GIF image")?;
+    let image = image::load_from_memory_with_format(bytes, format)
+        .map_err(|e| format!("Cannot decode the {:?} image: {}", format, e))?;
+
+    let image = if image.width() > max_dimension || image.height() > max_dimension {
+        image.thumbnail(max_dimension, max_dimension)
+    } else {
+        image
+    };
+
+    // JPEG has no alpha channel
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, THUMBNAIL_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(|e| format!("Cannot encode the thumbnail: {}", e))?;
+    Ok(out)
+}
@@ -0,0 +1,173 @@
+// Directory change notifications and operation progress pushed to WebSocket
+// clients.
+
+use std::collections::{HashMap, HashSet};
+
+use hyperware_process_lib::http::server::{send_ws_push, WsMessageType};
+use hyperware_process_lib::logging::debug;
+use hyperware_process_lib::LazyLoadBlob;
+
+use crate::progress::ProgressEvent;
+use crate::{checked_path, FileInfo};
+
+// Most channels watching at once
+const MAX_CHANNELS: usize = 64;
+
+// Most directories one channel may watch
+const MAX_WATCHED_PER_CHANNEL: usize = 32;
+
+// What happened to the entry at a change event's path
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Deleted,
+    Modified,
+    Moved,
+}
+
+// One change as sent to clients. `from` is the old path of a moved entry;
+// `info` is absent for a deletion
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ChangeEvent {
+    #[serde(rename = "type")]
+    pub kind: ChangeKind,
+    pub path: String,
+    pub from: Option<String>,
+    pub info: Option<FileInfo>,
+}
+
+// What clients send: `{"type": "subscribe_directory", "path": ...}` to be told
+// about changes at or below `path`, and `unsubscribe_directory` to stop;
+// `subscribe_progress` to be sent the progress of long-running operations,
+// and `unsubscribe_progress` to stop
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    SubscribeDirectory { path: String },
+    UnsubscribeDirectory { path: String },
+    SubscribeProgress,
+    UnsubscribeProgress,
+}
+
+// Watched directories, by WebSocket channel, and the channels following
+// operation progress
+#[derive(Default, Debug)]
+pub struct Watchers {
+    channels: HashMap<u32, HashSet<String>>,
+    progress: HashSet<u32>,
+}
+
+impl Watchers {
+    // Handle a frame from a client; closing the channel drops everything it
+    // watched
+    pub fn handle_client_message(
+        &mut self,
+        channel_id: u32,
+        message_type: WsMessageType,
+        bytes: &[u8],
+    ) {
+        if message_type == WsMessageType::Close {
+            self.channels.remove(&channel_id);
+            self.progress.remove(&channel_id);
+            return;
+        }
+
+        let message = match serde_json::from_slice::<ClientMessage>(bytes) {
+            Ok(message) => message,
+            Err(e) => {
+                debug!("Ignoring WebSocket message on {}: {}", channel_id, e);
+                return;
+            }
+        };
+        match message {
+            ClientMessage::SubscribeDirectory { path } => {
+                let Ok(path) = checked_path(&path) else {
+                    return;
+                };
+                if self.channels.len() >= MAX_CHANNELS && !self.channels.contains_key(&channel_id) {
+                    return;
+                }
+                let watched = self.channels.entry(channel_id).or_default();
+                if watched.len() < MAX_WATCHED_PER_CHANNEL {
+                    watched.insert(path);
+                }
+            }
+            ClientMessage::UnsubscribeDirectory { path } => {
+                let Ok(path) = checked_path(&path) else {
+                    return;
+                };
+                if let Some(watched) = self.channels.get_mut(&channel_id) {
+                    watched.remove(&path);
+                    if watched.is_empty() {
+                        self.channels.remove(&channel_id);
+                    }
+                }
+            }
+            ClientMessage::SubscribeProgress => {
+                if self.progress.len() < MAX_CHANNELS {
+                    self.progress.insert(channel_id);
+                }
+            }
+            ClientMessage::UnsubscribeProgress => {
+                self.progress.remove(&channel_id);
+            }
+        }
+    }
+
+    // Push `event` to every channel watching a directory that holds its path,
+    // or for a move its old path, at any depth
+    pub fn notify(&self, event: &ChangeEvent) {
+        if self.channels.is_empty() {
+            return;
+        }
+        let Ok(bytes) = serde_json::to_vec(event) else {
+            return;
+        };
+
+        for (channel_id, watched) in &self.channels {
+            let wanted = watched.iter().any(|dir| {
+                is_at_or_below(&event.path, dir)
+                    || event
+                        .from
+                        .as_deref()
+                        .is_some_and(|from| is_at_or_below(from, dir))
+            });
+            if wanted {
+                push(*channel_id, bytes.clone());
+            }
+        }
+    }
+
+    // Push `event` to every channel following operation progress
+    pub fn notify_progress(&self, event: &ProgressEvent) {
+        if self.progress.is_empty() {
+            return;
+        }
+        let Ok(bytes) = serde_json::to_vec(event) else {
+            return;
+        };
+        for channel_id in &self.progress {
+            push(*channel_id, bytes.clone());
+        }
+    }
+}
+
+fn push(channel_id: u32, bytes: Vec<u8>) {
+    send_ws_push(
+        channel_id,
+        WsMessageType::Text,
+        LazyLoadBlob {
+            mime: Some("application/json".to_string()),
+            bytes,
+        },
+    );
+}
+
+// Whether `path` is `dir` or inside it, at any depth
+pub fn is_at_or_below(path: &str, dir: &str) -> bool {
+    path == dir
+        || path
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/') || dir.ends_with('/'))
+}
@@ -0,0 +1,85 @@
+use caller_utils::file_explorer::{
+    create_file_local_rpc, delete_file_local_rpc, list_directory_local_rpc, read_file_local_rpc,
+    AppError,
+};
+use hyperprocess_macro::hyperprocess;
+use hyperware_process_lib::hyperapp::SaveOptions;
+use hyperware_process_lib::Address;
+
+// A small consumer of file-explorer's generated caller_utils, so the explorer
+// tester can check that the typed RPC works end to end
+
+fn make_explorer_sys() -> Address {
+    Address::new("our", ("explorer", "file-explorer", "sys"))
+}
+
+// Fold a typed call's transport error and the explorer's own error into one
+// message naming the step
+fn flatten<T, E: std::fmt::Debug>(
+    step: &str,
+    result: Result<Result<T, AppError>, E>,
+) -> Result<T, String> {
+    match result {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(format!("{step}: {}: {}", e.code, e.message)),
+        Err(e) => Err(format!("{step}: {e:?}")),
+    }
+}
+
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+struct ExplorerClientState {}
+
+#[hyperprocess(
+    name = "explorer-client",
+    ui = None,
+    endpoints = vec![],
+    save_config = SaveOptions::Never,
+    wit_world = "explorer-client-sys-v0",
+)]
+impl ExplorerClientState {
+    #[init]
+    async fn init(&mut self) {}
+
+    // Write `content` to `path` through the typed RPC, check the explorer
+    // lists it, read it back, then delete it; returns what was read
+    #[local]
+    async fn round_trip(&mut self, path: String, content: Vec<u8>) -> Result<Vec<u8>, String> {
+        let explorer = make_explorer_sys();
+
+        let info = flatten(
+            "create_file",
+            create_file_local_rpc(&explorer, path, content, None).await,
+        )?;
+
+        let parent = info
+            .path
+            .rsplit_once('/')
+            .map(|(dir, _)| dir)
+            .unwrap_or("/");
+        let listing = flatten(
+            "list_directory",
+            list_directory_local_rpc(&explorer, parent.to_string()).await,
+        )?;
+        if !listing
+            .iter()
+            .any(|entry| entry.path.trim_start_matches('/') == info.path.trim_start_matches('/'))
+        {
+            return Err(format!(
+                "{} missing from list_directory {}",
+                info.path, parent
+            ));
+        }
+
+        let read = flatten(
+            "read_file",
+            read_file_local_rpc(&explorer, info.path.clone()).await,
+        )?;
+
+        flatten(
+            "delete_file",
+            delete_file_local_rpc(&explorer, info.path, true).await,
+        )?;
+
+        Ok(read)
+    }
+}
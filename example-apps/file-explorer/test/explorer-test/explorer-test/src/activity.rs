@@ -0,0 +1,103 @@
+//! the activity log records file and share operations newest first, with who
+//!  asked for them; it can be filtered by path, limited and cleared
+
+use hyperware_process_lib::{our, Address};
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, step, TestError};
+
+const ACTIVITY_DIR: &str = "/file-explorer:sys/home/explorer-activity";
+
+const ACTIVITY_BYTES: &[u8] = b"explorer-test: logged\n";
+
+/// the explorer's ActivityEntry
+#[derive(Debug, serde::Deserialize)]
+struct ActivityEntry {
+    timestamp: u64,
+    operation: String,
+    path: String,
+    from: Option<String>,
+    source: String,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [ACTIVITY_DIR, true] }));
+    let _: u64 = expect_ok(explorer, json!({ "ClearActivityLog": null }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": ACTIVITY_DIR }));
+
+    let path = format!("{ACTIVITY_DIR}/logged.txt");
+    let moved = format!("{ACTIVITY_DIR}/moved.txt");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, ACTIVITY_BYTES, null] }),
+    );
+    let link: String = expect_ok(explorer, json!({ "ShareFile": [path, "Public", null] }));
+    let _: Vec<u8> = expect_ok(explorer, json!({ "FetchSharedFile": link }));
+    let _: bool = expect_ok(explorer, json!({ "UnshareFile": path }));
+    let _: FileInfo = expect_ok(explorer, json!({ "MoveFile": [path, moved] }));
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [moved, true] }));
+
+    step!("newest first", {
+        let log = activity_log(explorer, 100, Some(ACTIVITY_DIR));
+        // a move copies the file into place before removing the original
+        let operations: Vec<&str> = log.iter().map(|e| e.operation.as_str()).collect();
+        assert_eq_ctx!(
+            vec![
+                "Deleted",
+                "Moved",
+                "Created",
+                "Unshared",
+                "Downloaded",
+                "Shared",
+                "Created",
+                "Created"
+            ],
+            operations,
+            "operations in get_activity_log"
+        )?;
+        assert_eq_ctx!(moved, log[1].path, "path of the move")?;
+        assert_eq_ctx!(Some(path.clone()), log[1].from.clone(), "from of the move")?;
+        assert_eq_ctx!(ACTIVITY_DIR, log[7].path, "path of the directory")?;
+        assert_eq_ctx!(
+            true,
+            log.windows(2).all(|w| w[0].timestamp >= w[1].timestamp),
+            "timestamps newest first"
+        )?;
+        let us = our().to_string();
+        assert_eq_ctx!(
+            true,
+            log.iter().all(|e| e.source == us),
+            "source of every entry"
+        )
+    })?;
+    step!("filtered to a path", {
+        let log = activity_log(explorer, 100, Some(&path));
+        assert_eq_ctx!(5, log.len(), "entries for the file before it moved")?;
+        assert_eq_ctx!(
+            true,
+            log.iter()
+                .all(|e| e.path == path || e.from.as_ref() == Some(&path)),
+            "paths of the filtered entries"
+        )
+    })?;
+    step!("limited", {
+        let log = activity_log(explorer, 2, Some(ACTIVITY_DIR));
+        let operations: Vec<&str> = log.iter().map(|e| e.operation.as_str()).collect();
+        assert_eq_ctx!(vec!["Deleted", "Moved"], operations, "newest two entries")
+    })?;
+    step!("cleared", {
+        let cleared: u64 = expect_ok(explorer, json!({ "ClearActivityLog": null }));
+        assert_eq_ctx!(8, cleared, "entries clear_activity_log removed")?;
+        let log = activity_log(explorer, 100, None);
+        assert_eq_ctx!(0, log.len(), "entries after clear_activity_log")
+    })?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [ACTIVITY_DIR, true] }));
+    Ok(())
+}
+
+/// the newest `limit` entries get_activity_log returns for `path_filter`
+fn activity_log(explorer: &Address, limit: u32, path_filter: Option<&str>) -> Vec<ActivityEntry> {
+    expect_ok(explorer, json!({ "GetActivityLog": [limit, path_filter] }))
+}
@@ -0,0 +1,77 @@
+//! append_to_file adds to the end and creates a missing file only when asked;
+//!  truncate_file shortens but never grows; both report the new size
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const APPEND_DIR: &str = "/file-explorer:sys/home/explorer-append";
+
+const LINES: &[&[u8]] = &[b"first line\n", b"second line\n", b"third line\n"];
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [APPEND_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": APPEND_DIR }));
+
+    let log = format!("{APPEND_DIR}/app.log");
+    step!("append to a missing file", append_missing(explorer, &log))?;
+    step!("append lines", append_lines(explorer, &log))?;
+    step!("truncate", truncate(explorer, &log))?;
+    step!(
+        "append to a directory",
+        expect_err_code(
+            call::<FileInfo>(
+                explorer,
+                json!({ "AppendToFile": [APPEND_DIR, b"x", true] })
+            ),
+            "invalid_input",
+        )
+    )?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [APPEND_DIR, true] }));
+    Ok(())
+}
+
+/// refused without create_if_missing, created with it
+fn append_missing(explorer: &Address, log: &str) -> Result<(), TestError> {
+    expect_err_code(
+        call::<FileInfo>(explorer, json!({ "AppendToFile": [log, LINES[0], false] })),
+        "not_found",
+    )?;
+    let created: FileInfo = expect_ok(explorer, json!({ "AppendToFile": [log, LINES[0], true] }));
+    assert_eq_ctx!(
+        LINES[0].len() as u64,
+        created.size,
+        "size of the created file"
+    )
+}
+
+fn append_lines(explorer: &Address, log: &str) -> Result<(), TestError> {
+    let mut expected = LINES[0].to_vec();
+    for line in &LINES[1..] {
+        let appended: FileInfo = expect_ok(explorer, json!({ "AppendToFile": [log, line, false] }));
+        expected.extend_from_slice(line);
+        assert_eq_ctx!(expected.len() as u64, appended.size, "size after an append")?;
+    }
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": log }));
+    assert_eq_ctx!(expected, content, "content after the appends")
+}
+
+fn truncate(explorer: &Address, log: &str) -> Result<(), TestError> {
+    let whole: u64 = LINES.iter().map(|line| line.len() as u64).sum();
+    expect_err_code(
+        call::<FileInfo>(explorer, json!({ "TruncateFile": [log, whole + 1] })),
+        "invalid_input",
+    )?;
+
+    let kept = LINES[0].len() as u64;
+    let truncated: FileInfo = expect_ok(explorer, json!({ "TruncateFile": [log, kept] }));
+    assert_eq_ctx!(kept, truncated.size, "size after truncate_file")?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": log }));
+    assert_eq_ctx!(LINES[0], content, "content after truncate_file")?;
+
+    let emptied: FileInfo = expect_ok(explorer, json!({ "TruncateFile": [log, 0] }));
+    assert_eq_ctx!(0, emptied.size, "size after truncating to nothing")
+}
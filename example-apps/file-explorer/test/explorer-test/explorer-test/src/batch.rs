@@ -0,0 +1,157 @@
+//! batch_operation: a bad path among good ones fails alone, moves and copies
+//!  land under the destination, and a missing or non-directory destination
+//!  fails the whole batch
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const BATCH_DIR: &str = "/file-explorer:sys/home/explorer-batch";
+
+const BATCH_BYTES: &[u8] = b"explorer-test: one of many\n";
+
+/// the explorer's BatchFailure, with just the error code
+#[derive(Debug, serde::Deserialize)]
+struct BatchFailure {
+    path: String,
+    error: ErrorCode,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ErrorCode {
+    code: String,
+}
+
+/// the explorer's BatchResult
+#[derive(Debug, serde::Deserialize)]
+struct BatchResult {
+    succeeded: Vec<String>,
+    failed: Vec<BatchFailure>,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [BATCH_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": BATCH_DIR }));
+
+    step!("copy then move", copy_and_move(explorer))?;
+    step!("delete past a bad path", delete(explorer))?;
+    step!(
+        "move without a destination",
+        expect_err_code(
+            call::<BatchResult>(
+                explorer,
+                json!({ "BatchOperation": ["Move", [BATCH_DIR], null] })
+            ),
+            "invalid_input",
+        )
+    )?;
+    step!("a file as the destination", file_destination(explorer))?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [BATCH_DIR, true] }));
+    Ok(())
+}
+
+/// `names` created as files in `dir`, returning their paths
+fn make_files(explorer: &Address, dir: &str, names: &[&str]) -> Result<Vec<String>, TestError> {
+    let mut paths = Vec::new();
+    for name in names {
+        let path = format!("{dir}/{name}");
+        let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, BATCH_BYTES, null] }));
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// a file and a directory are copied, then moved, into `target`; a missing
+///  path fails without stopping them
+fn copy_and_move(explorer: &Address) -> Result<(), TestError> {
+    let source = format!("{BATCH_DIR}/source");
+    let target = format!("{BATCH_DIR}/target");
+    let inner = format!("{source}/dir");
+    for dir in [&source, &target, &inner] {
+        let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    }
+    let mut paths = make_files(explorer, &source, &["a.txt"])?;
+    make_files(explorer, &inner, &["nested.txt"])?;
+    paths.push(inner.clone());
+    let missing = format!("{source}/missing.txt");
+    paths.insert(1, missing.clone());
+
+    let copied: BatchResult = expect_ok(
+        explorer,
+        json!({ "BatchOperation": ["Copy", paths, target] }),
+    );
+    assert_eq_ctx!(
+        vec![paths[0].clone(), inner.clone()],
+        copied.succeeded,
+        "copied paths"
+    )?;
+    assert_eq_ctx!(1, copied.failed.len(), "copies that failed")?;
+    assert_eq_ctx!(missing, copied.failed[0].path, "path of the failed copy")?;
+    assert_eq_ctx!(
+        "not_found",
+        copied.failed[0].error.code,
+        "code of the failed copy"
+    )?;
+    let content: Vec<u8> = expect_ok(
+        explorer,
+        json!({ "ReadFile": format!("{target}/dir/nested.txt") }),
+    );
+    assert_eq_ctx!(BATCH_BYTES, content, "content copied in a directory")?;
+
+    // The copies are in the way of the move
+    let moved: BatchResult = expect_ok(
+        explorer,
+        json!({ "BatchOperation": ["Move", paths, target] }),
+    );
+    assert_eq_ctx!(0, moved.succeeded.len(), "moves onto the copies")?;
+    assert_eq_ctx!(3, moved.failed.len(), "moves that failed")?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [target, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": target }));
+    let moved: BatchResult = expect_ok(
+        explorer,
+        json!({ "BatchOperation": ["Move", paths, target] }),
+    );
+    assert_eq_ctx!(2, moved.succeeded.len(), "paths moved")?;
+    let left: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": source }));
+    let left: Vec<String> = left
+        .into_iter()
+        .map(|entry| entry.path)
+        .filter(|path| *path != source)
+        .collect();
+    assert_eq_ctx!(0, left.len(), "entries left behind by the move")
+}
+
+/// deleted paths go to the trash; one that does not exist fails alone
+fn delete(explorer: &Address) -> Result<(), TestError> {
+    let mut paths = make_files(explorer, BATCH_DIR, &["one.txt", "two.txt"])?;
+    paths.push(format!("{BATCH_DIR}/missing.txt"));
+
+    let deleted: BatchResult = expect_ok(
+        explorer,
+        json!({ "BatchOperation": ["Delete", paths, null] }),
+    );
+    assert_eq_ctx!(paths[..2].to_vec(), deleted.succeeded, "deleted paths")?;
+    assert_eq_ctx!(1, deleted.failed.len(), "deletes that failed")?;
+    for path in &paths[..2] {
+        expect_err_code(
+            call::<Vec<u8>>(explorer, json!({ "ReadFile": path })),
+            "not_found",
+        )?;
+    }
+    Ok(())
+}
+
+fn file_destination(explorer: &Address) -> Result<(), TestError> {
+    let paths = make_files(explorer, BATCH_DIR, &["not-a-dir.txt", "copied.txt"])?;
+    expect_err_code(
+        call::<BatchResult>(
+            explorer,
+            json!({ "BatchOperation": ["Copy", [paths[1]], paths[0]] }),
+        ),
+        "invalid_input",
+    )
+}
@@ -0,0 +1,64 @@
+//! get_checksum against known SHA-256 and MD5 vectors, and its refusal of a
+//!  directory
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const CHECKSUM_DIR: &str = "/file-explorer:sys/home/explorer-checksum";
+
+/// (file name, content, SHA-256, MD5)
+const VECTORS: &[(&str, &[u8], &str, &str)] = &[
+    (
+        "empty.txt",
+        b"",
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        "d41d8cd98f00b204e9800998ecf8427e",
+    ),
+    (
+        "hello.txt",
+        b"hello world",
+        "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+        "5eb63bbbe01eeed093cb22bb8f5acdc3",
+    ),
+];
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [CHECKSUM_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": CHECKSUM_DIR }));
+
+    for &(name, content, sha256, md5) in VECTORS {
+        step!(
+            format!("checksums of {name}"),
+            known_vector(explorer, name, content, sha256, md5)
+        )?;
+    }
+    step!(
+        "checksum of a directory",
+        expect_err_code(
+            call::<String>(explorer, json!({ "GetChecksum": [CHECKSUM_DIR, "Sha256"] }),),
+            "invalid_input",
+        )
+    )?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [CHECKSUM_DIR, true] }));
+    Ok(())
+}
+
+fn known_vector(
+    explorer: &Address,
+    name: &str,
+    content: &[u8],
+    sha256: &str,
+    md5: &str,
+) -> Result<(), TestError> {
+    let path = format!("{CHECKSUM_DIR}/{name}");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, content, null] }));
+
+    let actual: String = expect_ok(explorer, json!({ "GetChecksum": [path, "Sha256"] }));
+    assert_eq_ctx!(sha256, actual, format!("SHA-256 of {name}"))?;
+    let actual: String = expect_ok(explorer, json!({ "GetChecksum": [path, "Md5"] }));
+    assert_eq_ctx!(md5, actual, format!("MD5 of {name}"))
+}
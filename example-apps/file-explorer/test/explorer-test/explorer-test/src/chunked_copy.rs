@@ -0,0 +1,118 @@
+//! a file larger than the explorer's 4 MB copy chunk survives copy_file and
+//!  move_file byte for byte, including across the chunk boundary, and so does
+//!  its entry in a zip of its directory, which is read in the same chunks; the
+//!  explorer's memory use itself is not measured from here
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+use std::io::{Cursor, Read};
+
+use crate::shared_http::fetch_with;
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const CHUNKED_DIR: &str = "/file-explorer:sys/home/explorer-chunked";
+
+/// the explorer's COPY_CHUNK_BYTES
+const COPY_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+/// a chunk and a bit, so the copy takes two chunks
+const FILE_BYTES: u64 = COPY_CHUNK_BYTES + 1000;
+
+/// the source is written with appends of this many bytes
+const APPEND_BYTES: u64 = 1024 * 1024;
+
+/// byte `i` of the file; 251 is prime, so no chunk lines up with the pattern
+fn byte_at(i: u64) -> u8 {
+    (i % 251) as u8
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [CHUNKED_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": CHUNKED_DIR }));
+
+    let source = format!("{CHUNKED_DIR}/large.bin");
+    step!("write the source", write_source(explorer, &source))?;
+    let checksum: String = expect_ok(explorer, json!({ "GetChecksum": [source, "Sha256"] }));
+
+    let copy = format!("{CHUNKED_DIR}/copy.bin");
+    let copied: FileInfo = expect_ok(explorer, json!({ "CopyFile": [source, copy] }));
+    step!("copy_file", same_bytes(explorer, &copied, &checksum))?;
+
+    let moved_to = format!("{CHUNKED_DIR}/moved.bin");
+    let moved: FileInfo = expect_ok(explorer, json!({ "MoveFile": [copy, moved_to] }));
+    step!("move_file", same_bytes(explorer, &moved, &checksum))?;
+    step!(
+        "moved file's old path",
+        expect_err_code(
+            call::<Vec<u8>>(explorer, json!({ "ReadFile": copy })),
+            "not_found",
+        )
+    )?;
+    step!("zipped directory", zipped(explorer))?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [CHUNKED_DIR, true] }));
+    Ok(())
+}
+
+fn write_source(explorer: &Address, source: &str) -> Result<(), TestError> {
+    let mut written = 0;
+    let mut size = 0;
+    while written < FILE_BYTES {
+        let part: Vec<u8> = (written..(written + APPEND_BYTES).min(FILE_BYTES))
+            .map(byte_at)
+            .collect();
+        let appended: FileInfo =
+            expect_ok(explorer, json!({ "AppendToFile": [source, part, true] }));
+        written += part.len() as u64;
+        size = appended.size;
+    }
+    assert_eq_ctx!(FILE_BYTES, size, "size of the source")
+}
+
+/// same size and checksum as the source, and the bytes either side of the
+///  chunk boundary where they should be
+fn same_bytes(explorer: &Address, info: &FileInfo, checksum: &str) -> Result<(), TestError> {
+    assert_eq_ctx!(FILE_BYTES, info.size, "size of the copy")?;
+    let actual: String = expect_ok(explorer, json!({ "GetChecksum": [info.path, "Sha256"] }));
+    assert_eq_ctx!(checksum, actual, "checksum of the copy")?;
+
+    let start = COPY_CHUNK_BYTES - 8;
+    let window: Vec<u8> = expect_ok(explorer, json!({ "ReadFileRange": [info.path, start, 16] }));
+    let expected: Vec<u8> = (start..start + 16).map(byte_at).collect();
+    assert_eq_ctx!(expected, window, "bytes across the chunk boundary")
+}
+
+/// the directory, holding the source and the moved copy, zipped through its
+///  share link: both entries unzip to the file's bytes
+fn zipped(explorer: &Address) -> Result<(), String> {
+    let link: String = expect_ok(
+        explorer,
+        json!({ "ShareDirectory": [CHUNKED_DIR, "Public", null] }),
+    );
+    let fetched = fetch_with(&link, None)?;
+    let _: bool = expect_ok(explorer, json!({ "UnshareFile": CHUNKED_DIR }));
+    if fetched.status != 200 {
+        return Err(format!("zipping {CHUNKED_DIR}: status {}", fetched.status));
+    }
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(fetched.body))
+        .map_err(|e| format!("{CHUNKED_DIR} did not zip to an archive: {e}"))?;
+    let expected: Vec<u8> = (0..FILE_BYTES).map(byte_at).collect();
+    for name in ["explorer-chunked/large.bin", "explorer-chunked/moved.bin"] {
+        let mut entry = archive
+            .by_name(name)
+            .map_err(|e| format!("the archive has no {name}: {e}"))?;
+        let mut unzipped = Vec::new();
+        entry
+            .read_to_end(&mut unzipped)
+            .map_err(|e| format!("failed to unzip {name}: {e}"))?;
+        if unzipped != expected {
+            return Err(format!(
+                "{name} unzipped to {} bytes that differ from the file's {FILE_BYTES}",
+                unzipped.len()
+            ));
+        }
+    }
+    Ok(())
+}
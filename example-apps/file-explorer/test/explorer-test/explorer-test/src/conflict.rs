@@ -0,0 +1,91 @@
+//! create_file's conflict policy: Fail, the default, refuses a taken path,
+//!  Overwrite replaces the file there but never a directory, and Rename
+//!  writes to the first free `name (n).ext`
+
+use hyperware_process_lib::Address;
+use serde_json::{json, Value};
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const CONFLICT_DIR: &str = "/file-explorer:sys/home/explorer-conflict";
+
+const FIRST_BYTES: &[u8] = b"first\n";
+
+const SECOND_BYTES: &[u8] = b"second\n";
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [CONFLICT_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": CONFLICT_DIR }));
+
+    let path = format!("{CONFLICT_DIR}/notes.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, FIRST_BYTES, null] }));
+    step!("fail by default", refused(explorer, &path, Value::Null))?;
+    step!("fail", refused(explorer, &path, json!("Fail")))?;
+    step!(
+        "rename",
+        rename(
+            explorer,
+            &path,
+            &[
+                format!("{CONFLICT_DIR}/notes (1).txt"),
+                format!("{CONFLICT_DIR}/notes (2).txt"),
+            ]
+        )
+    )?;
+    step!("overwrite", overwrite(explorer, &path))?;
+    let dotfile = format!("{CONFLICT_DIR}/.env");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [dotfile, FIRST_BYTES, null] }),
+    );
+    step!(
+        "rename a dotfile",
+        rename(explorer, &dotfile, &[format!("{CONFLICT_DIR}/.env (1)")])
+    )?;
+    let dir = format!("{CONFLICT_DIR}/folder");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    step!(
+        "overwrite a directory",
+        refused(explorer, &dir, json!("Overwrite"))
+    )?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [CONFLICT_DIR, true] }));
+    Ok(())
+}
+
+/// the file already at `path` is left as it was
+fn refused(explorer: &Address, path: &str, conflict: Value) -> Result<(), TestError> {
+    expect_err_code(
+        call::<FileInfo>(
+            explorer,
+            json!({ "CreateFile": [path, SECOND_BYTES, conflict] }),
+        ),
+        "invalid_input",
+    )
+}
+
+fn overwrite(explorer: &Address, path: &str) -> Result<(), TestError> {
+    let written: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, SECOND_BYTES, "Overwrite"] }),
+    );
+    assert_eq_ctx!(path, written.path, "path of the overwritten file")?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(SECOND_BYTES, content, "content after Overwrite")
+}
+
+/// each write to the taken `path` lands on the next of `expected`
+fn rename(explorer: &Address, path: &str, expected: &[String]) -> Result<(), TestError> {
+    for next in expected {
+        let written: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [path, SECOND_BYTES, "Rename"] }),
+        );
+        assert_eq_ctx!(next, &written.path, "path chosen by Rename")?;
+        let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": next }));
+        assert_eq_ctx!(SECOND_BYTES, content, "content written by Rename")?;
+    }
+    let kept: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(FIRST_BYTES, kept, "content left at the taken path")
+}
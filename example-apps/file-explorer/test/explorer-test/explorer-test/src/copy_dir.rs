@@ -0,0 +1,87 @@
+//! copy_directory: an empty directory, a tree three levels deep, and a copy
+//!  into the directory being copied; cancel_operation with nothing running
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const COPY_DIR: &str = "/file-explorer:sys/home/explorer-copy-dir";
+
+/// one file at each level of the tree, named after its depth
+const TREE: &[(&str, &[u8])] = &[
+    ("one.txt", b"level one\n"),
+    ("a/two.txt", b"level two\n"),
+    ("a/b/three.txt", b"level three\n"),
+];
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [COPY_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": COPY_DIR }));
+
+    step!("empty directory", copy_empty(explorer))?;
+    step!("three-level tree", copy_tree(explorer))?;
+    step!("copy into itself", copy_into_itself(explorer))?;
+    step!("cancel an unknown operation", cancel_unknown(explorer))?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [COPY_DIR, true] }));
+    Ok(())
+}
+
+/// nothing to copy, but the destination directory is made
+fn copy_empty(explorer: &Address) -> Result<(), TestError> {
+    let from = format!("{COPY_DIR}/empty");
+    let to = format!("{COPY_DIR}/empty-copy");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": from }));
+
+    let copied: u64 = expect_ok(explorer, json!({ "CopyDirectory": [from, to] }));
+    assert_eq_ctx!(0, copied, "files copied from an empty directory")?;
+    let listing: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": to }));
+    assert_eq_ctx!(0, listing.len(), "entries in the copy")
+}
+
+/// every file arrives at the same place under the destination
+fn copy_tree(explorer: &Address) -> Result<(), TestError> {
+    let from = format!("{COPY_DIR}/tree");
+    let to = format!("{COPY_DIR}/tree-copy");
+    for dir in [from.clone(), format!("{from}/a"), format!("{from}/a/b")] {
+        let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    }
+    for (file, content) in TREE {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [format!("{from}/{file}"), content, null] }),
+        );
+    }
+
+    let copied: u64 = expect_ok(explorer, json!({ "CopyDirectory": [from, to] }));
+    assert_eq_ctx!(TREE.len() as u64, copied, "files copied from the tree")?;
+    for (file, content) in TREE {
+        let read: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": format!("{to}/{file}") }));
+        assert_eq_ctx!(*content, read, format!("content of {file} in the copy"))?;
+    }
+    Ok(())
+}
+
+/// refused before anything is written, whether the destination is the
+///  source or somewhere under it
+fn copy_into_itself(explorer: &Address) -> Result<(), TestError> {
+    let from = format!("{COPY_DIR}/loop");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": from }));
+
+    for to in [from.clone(), format!("{from}/inner")] {
+        expect_err_code(
+            call::<u64>(explorer, json!({ "CopyDirectory": [from, to] })),
+            "invalid_input",
+        )?;
+    }
+    let listing: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": from }));
+    assert_eq_ctx!(0, listing.len(), "entries left in the source")
+}
+
+/// nothing is running by that id, so there is nothing to cancel
+fn cancel_unknown(explorer: &Address) -> Result<(), TestError> {
+    let cancelled: bool = expect_ok(explorer, json!({ "CancelOperation": "no-such-operation" }));
+    assert_eq_ctx!(false, cancelled, "cancelling an operation not running")
+}
@@ -0,0 +1,173 @@
+//! drives: create_new_drive validates names and returns an existing drive's
+//!  path again, list_drives lists it with its usage, get_drive_usage adds
+//!  up the files under a path, and get_directory_stats and
+//!  get_storage_summary count them
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+/// a drive only this test uses; drives cannot be removed, so it is left
+///  behind empty
+const DRIVE_NAME: &str = "explorer-test-drive";
+
+const DRIVE_PATH: &str = "/file-explorer:sys/explorer-test-drive";
+
+/// (file name, size) of the files made in the test drive
+const FILES: &[(&str, usize)] = &[("small.bin", 10), ("large.bin", 4096), ("empty.bin", 0)];
+
+/// the explorer's DriveInfo
+#[derive(Debug, serde::Deserialize)]
+struct DriveInfo {
+    name: String,
+    path: String,
+    entries: u64,
+    bytes: u64,
+    truncated: bool,
+}
+
+/// the explorer's DriveUsage
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct DriveUsage {
+    bytes: u64,
+    truncated: bool,
+}
+
+/// the explorer's DirStats
+#[derive(Debug, serde::Deserialize)]
+struct DirStats {
+    total_bytes: u64,
+    file_count: u64,
+    directory_count: u64,
+    largest_file: Option<FileInfo>,
+    truncated: bool,
+    errors: u64,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    step!("create a drive", create(explorer))?;
+    for name in ["", "a/b", ".."] {
+        step!(
+            format!("drive name {name:?}"),
+            expect_err_code(
+                call::<String>(explorer, json!({ "CreateNewDrive": name })),
+                "invalid_input",
+            )
+        )?;
+    }
+
+    let dir = format!("{DRIVE_PATH}/data");
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [dir, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    for &(name, size) in FILES {
+        let path = format!("{dir}/{name}");
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [path, vec![7u8; size], null] }),
+        );
+    }
+
+    step!("usage of a directory", usage(explorer, &dir))?;
+    step!("listed drives", listed(explorer))?;
+    step!("stats of a directory", {
+        let stats: DirStats = expect_ok(explorer, json!({ "GetDirectoryStats": dir }));
+        expect_stats(&stats, 0)
+    })?;
+    step!(
+        "stats of a file",
+        expect_err_code(
+            call::<DirStats>(
+                explorer,
+                json!({ "GetDirectoryStats": format!("{dir}/{}", FILES[0].0) }),
+            ),
+            "invalid_input",
+        )
+    )?;
+    step!("storage summary", summarized(explorer))?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [dir, true] }));
+    Ok(())
+}
+
+/// creating the drive twice gives the same path
+fn create(explorer: &Address) -> Result<(), TestError> {
+    let path: String = expect_ok(explorer, json!({ "CreateNewDrive": DRIVE_NAME }));
+    assert_eq_ctx!(DRIVE_PATH, path, "path of the new drive")?;
+    let again: String = expect_ok(explorer, json!({ "CreateNewDrive": DRIVE_NAME }));
+    assert_eq_ctx!(DRIVE_PATH, again, "path of a drive created again")
+}
+
+fn usage(explorer: &Address, dir: &str) -> Result<(), TestError> {
+    let total: usize = FILES.iter().map(|&(_, size)| size).sum();
+    let usage: DriveUsage = expect_ok(explorer, json!({ "GetDriveUsage": dir }));
+    let expected = DriveUsage {
+        bytes: total as u64,
+        truncated: false,
+    };
+    assert_eq_ctx!(expected, usage, "usage of the test directory")?;
+
+    let file = format!("{dir}/{}", FILES[1].0);
+    let usage: DriveUsage = expect_ok(explorer, json!({ "GetDriveUsage": file }));
+    assert_eq_ctx!(FILES[1].1 as u64, usage.bytes, "usage of one file")
+}
+
+/// the test drive holds just the directory and its files
+fn listed(explorer: &Address) -> Result<(), TestError> {
+    let drives: Vec<DriveInfo> = expect_ok(explorer, json!({ "ListDrives": null }));
+    if !drives.iter().any(|drive| drive.name == "home") {
+        return Err(TestError::Assertion {
+            error: format!("list_drives does not list home: {drives:?}"),
+        });
+    }
+    let Some(drive) = drives.iter().find(|drive| drive.name == DRIVE_NAME) else {
+        return Err(TestError::Assertion {
+            error: format!("list_drives does not list {DRIVE_NAME}: {drives:?}"),
+        });
+    };
+    let total: usize = FILES.iter().map(|&(_, size)| size).sum();
+    assert_eq_ctx!(DRIVE_PATH, drive.path, "path of the listed drive")?;
+    assert_eq_ctx!(
+        (FILES.len() + 1) as u64,
+        drive.entries,
+        "entries in the listed drive"
+    )?;
+    assert_eq_ctx!(total as u64, drive.bytes, "bytes in the listed drive")?;
+    assert_eq_ctx!(false, drive.truncated, "truncated for the listed drive")
+}
+
+/// get_storage_summary counts the test drive's one directory and its files
+fn summarized(explorer: &Address) -> Result<(), TestError> {
+    let summary: Vec<(String, DirStats)> =
+        expect_ok(explorer, json!({ "GetStorageSummary": null }));
+    let Some((_, stats)) = summary.iter().find(|(name, _)| name == DRIVE_NAME) else {
+        return Err(TestError::Assertion {
+            error: format!("get_storage_summary does not list {DRIVE_NAME}"),
+        });
+    };
+    expect_stats(stats, 1)
+}
+
+/// `stats` counts FILES and `directories` directories, with the largest of
+///  FILES as the largest file
+fn expect_stats(stats: &DirStats, directories: u64) -> Result<(), TestError> {
+    let total: usize = FILES.iter().map(|&(_, size)| size).sum();
+    let largest = FILES
+        .iter()
+        .max_by_key(|&&(_, size)| size)
+        .map(|&(name, _)| name);
+    assert_eq_ctx!(total as u64, stats.total_bytes, "total_bytes")?;
+    assert_eq_ctx!(FILES.len() as u64, stats.file_count, "file_count")?;
+    assert_eq_ctx!(directories, stats.directory_count, "directory_count")?;
+    assert_eq_ctx!(
+        largest,
+        stats.largest_file.as_ref().map(|info| info.name.as_str()),
+        "largest_file"
+    )?;
+    assert_eq_ctx!(
+        (false, 0),
+        (stats.truncated, stats.errors),
+        "truncated and errors"
+    )
+}
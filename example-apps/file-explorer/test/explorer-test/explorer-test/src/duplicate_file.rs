@@ -0,0 +1,95 @@
+//! duplicate_file copies a file or directory beside itself as `name copy`,
+//!  then `name copy 2`, keeping the extension; a share of the original does
+//!  not follow the copy, and a drive cannot be duplicated
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, share_link_of, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const DUPLICATE_DIR: &str = "/file-explorer:sys/home/explorer-duplicate";
+
+const REPORT_BYTES: &[u8] = b"quarterly report\n";
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(
+        explorer,
+        json!({ "DeleteDirectory": [DUPLICATE_DIR, true] }),
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": DUPLICATE_DIR }));
+
+    step!("duplicate a file", duplicate_file(explorer))?;
+    step!("duplicate a directory", duplicate_directory(explorer))?;
+    step!(
+        "duplicate a drive",
+        expect_err_code(
+            call::<FileInfo>(
+                explorer,
+                json!({ "DuplicateFile": "/file-explorer:sys/home" })
+            ),
+            "invalid_input",
+        )
+    )?;
+
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteDirectory": [DUPLICATE_DIR, true] }),
+    );
+    Ok(())
+}
+
+fn duplicate_file(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{DUPLICATE_DIR}/report.pdf");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, REPORT_BYTES, null] }),
+    );
+    let _: String = expect_ok(explorer, json!({ "ShareFile": [path, "Public", null] }));
+
+    for expected in ["report copy.pdf", "report copy 2.pdf"] {
+        let copy: FileInfo = expect_ok(explorer, json!({ "DuplicateFile": path }));
+        assert_eq_ctx!(
+            format!("{DUPLICATE_DIR}/{expected}"),
+            copy.path,
+            "path of the duplicate"
+        )?;
+        let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": copy.path }));
+        assert_eq_ctx!(REPORT_BYTES, content, "content of the duplicate")?;
+        let link = share_link_of(explorer, &copy.path);
+        assert_eq_ctx!(None, link, "share link of the duplicate")?;
+    }
+
+    let _: bool = expect_ok(explorer, json!({ "UnshareFile": path }));
+    Ok(())
+}
+
+/// the whole tree is copied, and the directory's dotted name kept whole
+fn duplicate_directory(explorer: &Address) -> Result<(), TestError> {
+    let dir = format!("{DUPLICATE_DIR}/release.v1");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateDirectory": format!("{dir}/nested") }),
+    );
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [format!("{dir}/nested/notes.txt"), REPORT_BYTES, null] }),
+    );
+
+    let copy: FileInfo = expect_ok(explorer, json!({ "DuplicateFile": dir }));
+    assert_eq_ctx!(
+        (format!("{DUPLICATE_DIR}/release.v1 copy"), true),
+        (copy.path.clone(), copy.is_directory),
+        "path of the duplicated directory"
+    )?;
+    let content: Vec<u8> = expect_ok(
+        explorer,
+        json!({ "ReadFile": format!("{}/nested/notes.txt", copy.path) }),
+    );
+    assert_eq_ctx!(
+        REPORT_BYTES,
+        content,
+        "file inside the duplicated directory"
+    )
+}
@@ -0,0 +1,89 @@
+//! find_duplicates: same-content files grouped across subdirectories, a
+//!  same-size file with other content left out, and empty files grouped only
+//!  when asked
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, step, TestError};
+
+const DUPLICATES_DIR: &str = "/file-explorer:sys/home/explorer-duplicates";
+
+/// files relative to DUPLICATES_DIR; `other.txt` is the size of the
+///  duplicates but not their content
+const FILES: &[(&str, &[u8])] = &[
+    ("a.txt", b"same bytes\n"),
+    ("b.txt", b"same bytes\n"),
+    ("sub/c.txt", b"same bytes\n"),
+    ("other.txt", b"some bytes\n"),
+    ("empty-1.txt", b""),
+    ("sub/empty-2.txt", b""),
+];
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(
+        explorer,
+        json!({ "DeleteDirectory": [DUPLICATES_DIR, true] }),
+    );
+    for dir in [DUPLICATES_DIR.to_string(), format!("{DUPLICATES_DIR}/sub")] {
+        let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    }
+    for (file, content) in FILES {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [format!("{DUPLICATES_DIR}/{file}"), content, null] }),
+        );
+    }
+
+    step!(
+        "without empty files",
+        expect_groups(explorer, false, &[&["a.txt", "b.txt", "sub/c.txt"]])
+    )?;
+    step!(
+        "with empty files",
+        expect_groups(
+            explorer,
+            true,
+            &[
+                &["a.txt", "b.txt", "sub/c.txt"],
+                &["empty-1.txt", "sub/empty-2.txt"],
+            ],
+        )
+    )?;
+
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteDirectory": [DUPLICATES_DIR, true] }),
+    );
+    Ok(())
+}
+
+fn expect_groups(
+    explorer: &Address,
+    include_empty: bool,
+    expected: &[&[&str]],
+) -> Result<(), TestError> {
+    let groups: Vec<Vec<FileInfo>> = expect_ok(
+        explorer,
+        json!({ "FindDuplicates": [DUPLICATES_DIR, include_empty] }),
+    );
+    let groups: Vec<Vec<String>> = groups
+        .iter()
+        .map(|group| group.iter().map(|info| info.path.clone()).collect())
+        .collect();
+    let expected: Vec<Vec<String>> = expected
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .map(|file| format!("{DUPLICATES_DIR}/{file}"))
+                .collect()
+        })
+        .collect();
+    assert_eq_ctx!(
+        expected,
+        groups,
+        format!("duplicate groups with include_empty {include_empty}")
+    )
+}
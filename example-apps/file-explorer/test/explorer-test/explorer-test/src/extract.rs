@@ -0,0 +1,149 @@
+//! extract_archive: nested and empty directories, files that already exist
+//!  with and without overwrite, and an archive with a `..` entry
+
+use std::io::{Cursor, Write};
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const EXTRACT_DIR: &str = "/file-explorer:sys/home/explorer-extract";
+
+/// the files of the archive; the directories they are in are not listed in it
+const FILES: &[(&str, &[u8])] = &[("top.txt", b"top\n"), ("a/b/c/deep.txt", b"deep\n")];
+
+/// listed in the archive with nothing in it
+const EMPTY_DIR: &str = "a/empty/";
+
+/// the explorer's ExtractReport
+#[derive(Debug, serde::Deserialize)]
+struct ExtractReport {
+    created: Vec<FileInfo>,
+    skipped: Vec<String>,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [EXTRACT_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": EXTRACT_DIR }));
+
+    step!("nested and empty directories", extract_tree(explorer))?;
+    step!("existing files", extract_again(explorer))?;
+    step!("entry outside the destination", extract_traversal(explorer))?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [EXTRACT_DIR, true] }));
+    Ok(())
+}
+
+/// a zip of `files`, plus `directories` as directory entries
+fn zip_of(files: &[(&str, &[u8])], directories: &[&str]) -> Result<Vec<u8>, TestError> {
+    let zip_error = |e: &dyn std::fmt::Display| TestError::Assertion {
+        error: format!("failed to build the test archive: {e}"),
+    };
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    for dir in directories {
+        writer
+            .add_directory(*dir, options)
+            .map_err(|e| zip_error(&e))?;
+    }
+    for (name, content) in files {
+        writer
+            .start_file(*name, options)
+            .map_err(|e| zip_error(&e))?;
+        writer.write_all(content).map_err(|e| zip_error(&e))?;
+    }
+    let archive = writer.finish().map_err(|e| zip_error(&e))?;
+    Ok(archive.into_inner())
+}
+
+fn upload(explorer: &Address, name: &str, archive: &[u8]) -> String {
+    let path = format!("{EXTRACT_DIR}/{name}");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, archive, null] }));
+    path
+}
+
+/// every file arrives, in directories made for it, and the empty directory
+///  is made too
+fn extract_tree(explorer: &Address) -> Result<(), TestError> {
+    let archive = upload(explorer, "tree.zip", &zip_of(FILES, &[EMPTY_DIR])?);
+    let out = format!("{EXTRACT_DIR}/out");
+
+    let report: ExtractReport =
+        expect_ok(explorer, json!({ "ExtractArchive": [archive, out, false] }));
+    assert_eq_ctx!(0, report.skipped.len(), "entries skipped")?;
+    let mut created: Vec<(String, bool)> = report
+        .created
+        .iter()
+        .map(|info| (info.path[out.len()..].to_string(), info.is_directory))
+        .collect();
+    created.sort();
+    let expected: Vec<(String, bool)> = [
+        ("", true),
+        ("/a", true),
+        ("/a/b", true),
+        ("/a/b/c", true),
+        ("/a/b/c/deep.txt", false),
+        ("/a/empty", true),
+        ("/top.txt", false),
+    ]
+    .iter()
+    .map(|(path, is_directory)| (path.to_string(), *is_directory))
+    .collect();
+    assert_eq_ctx!(expected, created, "paths created under the destination")?;
+
+    for (name, content) in FILES {
+        let read: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": format!("{out}/{name}") }));
+        assert_eq_ctx!(*content, read, format!("content of {name}"))?;
+    }
+    Ok(())
+}
+
+/// without overwrite the files already there are skipped and reported, with
+///  it they are replaced
+fn extract_again(explorer: &Address) -> Result<(), TestError> {
+    let changed: &[(&str, &[u8])] = &[("top.txt", b"changed\n")];
+    let archive = upload(explorer, "changed.zip", &zip_of(changed, &[])?);
+    let out = format!("{EXTRACT_DIR}/out");
+    let top = format!("{out}/top.txt");
+
+    let report: ExtractReport =
+        expect_ok(explorer, json!({ "ExtractArchive": [archive, out, false] }));
+    assert_eq_ctx!(
+        (0, vec![top.clone()]),
+        (report.created.len(), report.skipped),
+        "created and skipped without overwrite"
+    )?;
+    let read: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": top }));
+    assert_eq_ctx!(b"top\n", read, "the skipped file is unchanged")?;
+
+    let report: ExtractReport =
+        expect_ok(explorer, json!({ "ExtractArchive": [archive, out, true] }));
+    assert_eq_ctx!(
+        (1, 0),
+        (report.created.len(), report.skipped.len()),
+        "created and skipped with overwrite"
+    )?;
+    let read: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": top }));
+    assert_eq_ctx!(b"changed\n", read, "the overwritten file")
+}
+
+/// refused as a whole: not even the harmless entry before it is written
+fn extract_traversal(explorer: &Address) -> Result<(), TestError> {
+    let entries: &[(&str, &[u8])] = &[
+        ("harmless.txt", b"harmless\n"),
+        ("../../etc/passwd", b"root::0:0::/:/bin/sh\n"),
+    ];
+    let archive = upload(explorer, "evil.zip", &zip_of(entries, &[])?);
+    let out = format!("{EXTRACT_DIR}/evil-out");
+
+    expect_err_code(
+        call::<ExtractReport>(explorer, json!({ "ExtractArchive": [archive, out, true] })),
+        "invalid_input",
+    )?;
+    let exists = call::<Vec<FileInfo>>(explorer, json!({ "ListDirectory": out })).is_ok();
+    assert_eq_ctx!(false, exists, "the destination after the refused archive")
+}
@@ -0,0 +1,99 @@
+//! favorites: add_favorite labels by name when no label is given and relabels
+//!  rather than duplicating, a deleted target stays listed as stale, and
+//!  remove_favorite unpins
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const FAVORITES_DIR: &str = "/file-explorer:sys/home/explorer-favorites";
+
+/// the explorer's Favorite
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct Favorite {
+    path: String,
+    label: String,
+    exists: bool,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(
+        explorer,
+        json!({ "DeleteDirectory": [FAVORITES_DIR, true] }),
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": FAVORITES_DIR }));
+
+    step!("add and relabel", add_and_relabel(explorer))?;
+    step!("a deleted favorite is stale", stale(explorer))?;
+    step!(
+        "favorite of a missing path",
+        expect_err_code(
+            call::<Favorite>(
+                explorer,
+                json!({ "AddFavorite": [format!("{FAVORITES_DIR}/missing"), ""] }),
+            ),
+            "not_found",
+        )
+    )?;
+
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteDirectory": [FAVORITES_DIR, true] }),
+    );
+    Ok(())
+}
+
+/// the listed favorite of `path`, if there is one
+fn favorite_of(explorer: &Address, path: &str) -> Result<Option<Favorite>, TestError> {
+    let favorites: Vec<Favorite> = expect_ok(explorer, json!({ "ListFavorites": null }));
+    Ok(favorites.into_iter().find(|favorite| favorite.path == path))
+}
+
+fn add_and_relabel(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{FAVORITES_DIR}/deep");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": path }));
+
+    let added: Favorite = expect_ok(explorer, json!({ "AddFavorite": [path, ""] }));
+    let expected = Favorite {
+        path: path.clone(),
+        label: "deep".to_string(),
+        exists: true,
+    };
+    assert_eq_ctx!(expected, added, "favorite added without a label")?;
+
+    let _: Favorite = expect_ok(explorer, json!({ "AddFavorite": [path, "Projects"] }));
+    let favorites: Vec<Favorite> = expect_ok(explorer, json!({ "ListFavorites": null }));
+    let matching: Vec<&Favorite> = favorites.iter().filter(|f| f.path == path).collect();
+    assert_eq_ctx!(1, matching.len(), "favorites of a path pinned twice")?;
+    assert_eq_ctx!("Projects", matching[0].label, "label after pinning again")?;
+
+    let removed: bool = expect_ok(explorer, json!({ "RemoveFavorite": path }));
+    assert_eq_ctx!(true, removed, "remove_favorite of a favorite")?;
+    let removed: bool = expect_ok(explorer, json!({ "RemoveFavorite": path }));
+    assert_eq_ctx!(false, removed, "remove_favorite of a removed favorite")?;
+    assert_eq_ctx!(
+        None,
+        favorite_of(explorer, &path)?,
+        "favorite after removal"
+    )
+}
+
+/// deleting the target keeps the favorite; restoring it revives it
+fn stale(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{FAVORITES_DIR}/pinned.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, b"pinned\n", null] }));
+    let _: Favorite = expect_ok(explorer, json!({ "AddFavorite": [path, "Pinned"] }));
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [path, true] }));
+    let favorite = favorite_of(explorer, &path)?.map(|favorite| favorite.exists);
+    assert_eq_ctx!(Some(false), favorite, "favorite of a deleted file")?;
+
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, b"pinned\n", null] }));
+    let favorite = favorite_of(explorer, &path)?.map(|favorite| favorite.exists);
+    assert_eq_ctx!(Some(true), favorite, "favorite of a recreated file")?;
+
+    let _: bool = expect_ok(explorer, json!({ "RemoveFavorite": path }));
+    Ok(())
+}
@@ -0,0 +1,177 @@
+//! randomized paths through the explorer's normalize_path, then through
+//!  create_file / list_directory / delete_file where the normalized form is
+//!  valid
+//!
+//! the seed is fixed at build time: `EXPLORER_FUZZ_SEED=1234 kit t ...`
+//!  reproduces a failing run, whose seed is in the failure message
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::path::{normalize_path, MAX_NAME_LEN, MAX_PATH_LEN};
+use crate::{call, expect_ok, FileInfo};
+
+const DEFAULT_SEED: u64 = 0x5eed_f11e_e0e0_0001;
+
+const CASES: usize = 300;
+
+/// at most this many cases also go through the explorer itself
+const END_TO_END_CASES: usize = 40;
+
+const DRIVE_ROOT: &str = "/file-explorer:sys/home";
+
+const FUZZ_DIR: &str = "/file-explorer:sys/home/explorer-fuzz";
+
+const NAMES: &[&str] = &[
+    "a",
+    "file.txt",
+    "with space",
+    "-dash",
+    ".hidden",
+    "..dots",
+    "dots..",
+    "ünïcödé",
+    "日本語.md",
+    "emoji-🎵",
+];
+
+const SEPARATORS: &[&str] = &["/", "//", "/./", "///"];
+
+/// xorshift64*: small, deterministic, and good enough for picking cases
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+pub fn seed() -> u64 {
+    option_env!("EXPLORER_FUZZ_SEED")
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(DEFAULT_SEED)
+        .max(1)
+}
+
+fn segment(rng: &mut Rng) -> String {
+    match rng.below(10) {
+        0 => ".".to_string(),
+        1 | 2 => "..".to_string(),
+        3 => "".to_string(),
+        // around the name length limit, and sometimes far past it
+        4 => "n".repeat(MAX_NAME_LEN - 2 + rng.below(5)),
+        5 if rng.below(4) == 0 => "L".repeat(MAX_PATH_LEN),
+        _ => rng.pick(NAMES).to_string(),
+    }
+}
+
+fn random_path(rng: &mut Rng) -> String {
+    let mut path = FUZZ_DIR.to_string();
+    for _ in 0..1 + rng.below(5) {
+        path.push_str(rng.pick(SEPARATORS));
+        path.push_str(&segment(rng));
+    }
+    if rng.below(4) == 0 {
+        path.push('/');
+    }
+    path
+}
+
+/// the invariants every normalized path must satisfy
+fn check_normalized(path: &str, normalized: &str) -> Result<(), String> {
+    match normalize_path(normalized) {
+        Ok(again) if again == normalized => {}
+        other => return Err(format!("not idempotent: {normalized:?} -> {other:?}")),
+    }
+    if normalized != DRIVE_ROOT && !normalized.starts_with(&format!("{DRIVE_ROOT}/")) {
+        return Err(format!("{path:?} escaped the drive as {normalized:?}"));
+    }
+    if normalized
+        .split('/')
+        .skip(1)
+        .any(|s| s.is_empty() || s == "." || s == ".." || s.len() > MAX_NAME_LEN)
+    {
+        return Err(format!(
+            "{normalized:?} still has an empty, dot, or overlong segment"
+        ));
+    }
+    Ok(())
+}
+
+/// a rejected path must have had a reason to be rejected
+fn check_rejected(path: &str, error: &str) -> Result<(), String> {
+    let has_reason =
+        path.len() > MAX_PATH_LEN || path.split('/').any(|s| s == ".." || s.len() > MAX_NAME_LEN);
+    if has_reason {
+        Ok(())
+    } else {
+        Err(format!("{path:?} rejected for no reason: {error}"))
+    }
+}
+
+fn listed_path(info: &FileInfo) -> String {
+    normalize_path(&format!("/{}", info.path.trim_start_matches('/'))).unwrap_or_default()
+}
+
+/// create the file through the raw path and find it under the normalized one
+fn check_end_to_end(explorer: &Address, path: &str, normalized: &str) -> Result<(), String> {
+    let created: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, b"fuzz", null] }));
+    if created.path != normalized {
+        return Err(format!(
+            "create_file {path:?} reported {:?}, expected {normalized:?}",
+            created.path
+        ));
+    }
+    let listing: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": FUZZ_DIR }));
+    if !listing.iter().any(|info| listed_path(info) == normalized) {
+        return Err(format!("{normalized:?} missing from listing {listing:?}"));
+    }
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [path, true] }));
+    Ok(())
+}
+
+pub fn run(explorer: &Address) -> Result<(), String> {
+    let seed = seed();
+    let mut rng = Rng(seed);
+
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [FUZZ_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": FUZZ_DIR }));
+    // names such as .hidden must still be listed
+    let _: bool = expect_ok(explorer, json!({ "SetShowHidden": true }));
+
+    let mut end_to_end = 0;
+    for case in 0..CASES {
+        let path = random_path(&mut rng);
+        let checked = match normalize_path(&path) {
+            Ok(normalized) => check_normalized(&path, &normalized).and_then(|()| {
+                let in_fuzz_dir = normalized
+                    .strip_prefix(FUZZ_DIR)
+                    .and_then(|rest| rest.strip_prefix('/'))
+                    .is_some_and(|name| !name.contains('/'));
+                if in_fuzz_dir && end_to_end < END_TO_END_CASES {
+                    end_to_end += 1;
+                    check_end_to_end(explorer, &path, &normalized)
+                } else {
+                    Ok(())
+                }
+            }),
+            Err(e) => check_rejected(&path, &e),
+        };
+        checked.map_err(|e| format!("seed {seed} case {case}: {e}"))?;
+    }
+
+    let _: bool = expect_ok(explorer, json!({ "SetShowHidden": false }));
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [FUZZ_DIR, true] }));
+    Ok(())
+}
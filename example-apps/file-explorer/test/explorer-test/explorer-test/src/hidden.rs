@@ -0,0 +1,97 @@
+//! entries whose names start with `.` are left out of listings, trees and
+//!  searches until set_show_hidden shows them; the trash stays hidden until
+//!  set_show_system shows it too
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, step, TestError};
+
+const DRIVE_ROOT: &str = "/file-explorer:sys/home";
+
+const HIDDEN_DIR: &str = "/file-explorer:sys/home/explorer-hidden";
+
+/// the explorer's TreeNode, as far as names go
+#[derive(Debug, serde::Deserialize)]
+struct TreeNode {
+    info: FileInfo,
+    children: Vec<TreeNode>,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [HIDDEN_DIR, true] }));
+    for dir in ["", "/.config"] {
+        let dir = format!("{HIDDEN_DIR}{dir}");
+        let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    }
+    for file in [".env", ".config/settings", "visible.txt", "trashed.txt"] {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [format!("{HIDDEN_DIR}/{file}"), b"hidden", null] }),
+        );
+    }
+    // puts the trash directory in the home drive
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteFile": [format!("{HIDDEN_DIR}/trashed.txt"), false] }),
+    );
+
+    step!("off by default", {
+        let shown: bool = expect_ok(explorer, json!({ "GetShowHidden": null }));
+        assert_eq_ctx!(false, shown, "get_show_hidden")
+    })?;
+    step!("hidden", {
+        assert_eq_ctx!(vec!["visible.txt"], listed(explorer), "list_directory")?;
+        assert_eq_ctx!(vec!["visible.txt"], tree_names(explorer), "get_tree")
+    })?;
+
+    let _: bool = expect_ok(explorer, json!({ "SetShowHidden": true }));
+    step!("shown", {
+        let expected = vec![".config", "settings", ".env", "visible.txt"];
+        assert_eq_ctx!(expected, listed(explorer), "list_directory")?;
+        assert_eq_ctx!(
+            vec![".config", ".env", "visible.txt"],
+            tree_names(explorer),
+            "get_tree"
+        )
+    })?;
+    step!("the trash still hidden", {
+        assert_eq_ctx!(false, trash_listed(explorer), "trash in the home drive")
+    })?;
+    let _: bool = expect_ok(explorer, json!({ "SetShowSystem": true }));
+    step!("the trash shown", {
+        assert_eq_ctx!(true, trash_listed(explorer), "trash in the home drive")
+    })?;
+
+    let _: bool = expect_ok(explorer, json!({ "SetShowSystem": false }));
+    let _: bool = expect_ok(explorer, json!({ "SetShowHidden": false }));
+    let _: u64 = expect_ok(explorer, json!({ "EmptyTrash": null }));
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [HIDDEN_DIR, true] }));
+    Ok(())
+}
+
+/// the names list_directory gives for HIDDEN_DIR, two levels deep
+fn listed(explorer: &Address) -> Vec<String> {
+    let listing: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": HIDDEN_DIR }));
+    listing.into_iter().map(|info| info.name).collect()
+}
+
+/// the names of what get_tree finds directly in HIDDEN_DIR
+fn tree_names(explorer: &Address) -> Vec<String> {
+    let tree: TreeNode = expect_ok(explorer, json!({ "GetTree": [HIDDEN_DIR, 1, 100] }));
+    tree.children
+        .into_iter()
+        .map(|child| child.info.name)
+        .collect()
+}
+
+/// whether the home drive's listing has the trash directory in it
+fn trash_listed(explorer: &Address) -> bool {
+    let listing: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": DRIVE_ROOT }));
+    // listings give paths without their leading `/`
+    let trash = format!("{}/.trash", DRIVE_ROOT.trim_start_matches('/'));
+    listing
+        .iter()
+        .any(|info| info.path.trim_start_matches('/') == trash)
+}
@@ -0,0 +1,108 @@
+//! import_from_url fetches a public share link off the node's own HTTP
+//!  server; the file is named by the response's Content-Disposition unless a
+//!  name is given, and an existing file, a non-http URL and a missing page
+//!  are refused
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::shared_http::NODE_URL;
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const IMPORT_DIR: &str = "/file-explorer:sys/home/explorer-import";
+
+const SOURCE_BYTES: &[u8] = b"explorer-test: imported over HTTP\n";
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [IMPORT_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": IMPORT_DIR }));
+
+    let source = format!("{IMPORT_DIR}/source.txt");
+    let into = format!("{IMPORT_DIR}/imported");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [source, SOURCE_BYTES, null] }),
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": into }));
+    let link: String = expect_ok(explorer, json!({ "ShareFile": [source, "Public", null] }));
+    let url = format!("{NODE_URL}{link}");
+
+    step!("import", import(explorer, &url, &into, None, "source.txt"))?;
+    step!(
+        "import under a given name",
+        import(explorer, &url, &into, Some("renamed.txt"), "renamed.txt")
+    )?;
+    step!(
+        "import over an existing file",
+        refused(explorer, &url, &into, None, "invalid_input")
+    )?;
+    step!(
+        "import under a name with a slash",
+        refused(
+            explorer,
+            &url,
+            &into,
+            Some("../escape.txt"),
+            "invalid_input"
+        )
+    )?;
+    step!(
+        "import into a file",
+        refused(explorer, &url, &source, Some("x.txt"), "invalid_input")
+    )?;
+    step!(
+        "import a non-http URL",
+        refused(
+            explorer,
+            "ftp://localhost/source.txt",
+            &into,
+            None,
+            "invalid_input"
+        )
+    )?;
+    step!(
+        "import a missing page",
+        refused(
+            explorer,
+            &format!("{NODE_URL}/explorer-test-no-such-page"),
+            &into,
+            Some("missing.txt"),
+            "not_found",
+        )
+    )?;
+
+    let _: bool = expect_ok(explorer, json!({ "UnshareFile": source }));
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [IMPORT_DIR, true] }));
+    Ok(())
+}
+
+fn import(
+    explorer: &Address,
+    url: &str,
+    into: &str,
+    filename: Option<&str>,
+    expected_name: &str,
+) -> Result<(), TestError> {
+    let imported: FileInfo = expect_ok(explorer, json!({ "ImportFromUrl": [url, into, filename] }));
+    assert_eq_ctx!(
+        format!("{into}/{expected_name}"),
+        imported.path,
+        "path of the imported file"
+    )?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": imported.path }));
+    assert_eq_ctx!(SOURCE_BYTES, content, "content of the imported file")
+}
+
+fn refused(
+    explorer: &Address,
+    url: &str,
+    into: &str,
+    filename: Option<&str>,
+    code: &str,
+) -> Result<(), TestError> {
+    expect_err_code(
+        call::<FileInfo>(explorer, json!({ "ImportFromUrl": [url, into, filename] })),
+        code,
+    )
+}
@@ -0,0 +1,333 @@
+use crate::hyperware::process::tester::{
+    FailResponse, Request as TesterRequest, Response as TesterResponse, RunRequest,
+};
+use hyperware_process_lib::{
+    await_message, call_init, print_to_terminal, Address, ProcessId, Response,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+mod activity;
+mod append;
+mod batch;
+mod checksum;
+mod chunked_copy;
+mod conflict;
+mod copy_dir;
+mod drives;
+mod duplicate_file;
+mod duplicates;
+mod extract;
+mod favorites;
+mod fuzz;
+mod hidden;
+mod import;
+mod mime;
+mod move_copy;
+mod paging;
+#[path = "../../../../explorer/src/path.rs"]
+mod path;
+mod range_read;
+mod recent;
+mod resolve;
+mod search;
+mod share_limits;
+mod share_list;
+mod shared_http;
+mod tags;
+mod thumbnails;
+mod trash;
+mod traversal;
+mod tree;
+mod versions;
+use tester_lib::{
+    assert_eq_ctx, call_json, call_ok, expect_err_code, fail, step, CodedError, TestError,
+};
+
+wit_bindgen::generate!({
+    path: "../target/wit",
+    world: "explorer-test-sys-v0",
+    generate_unused_types: true,
+    additional_derives: [PartialEq, serde::Deserialize, serde::Serialize, process_macros::SerdeJsonInto],
+});
+
+const TIMEOUT_SECS: u64 = 15;
+
+/// extra attempts for a request that times out
+const RETRIES: u32 = 2;
+
+/// everything the test creates lives under here, so one delete cleans it up
+const SCRATCH_DIR: &str = "/file-explorer:sys/home/explorer-test";
+
+const FILE_BYTES: &[u8] = b"explorer-test: known bytes\n\x00\x01\x02\xff";
+
+/// the fields of the explorer's FileInfo this test checks
+#[derive(Debug, serde::Deserialize)]
+struct FileInfo {
+    name: String,
+    path: String,
+    size: u64,
+    is_directory: bool,
+}
+
+/// the explorer's ShareInfo, as list_shares and get_share_link report it
+#[derive(Debug, serde::Deserialize)]
+struct ShareInfo {
+    path: String,
+    id: String,
+    link: String,
+    auth: Value,
+    created: u64,
+    hits: u64,
+    max_downloads: Option<u32>,
+    downloads_remaining: Option<u32>,
+}
+
+call_init!(init);
+fn init(our: Address) {
+    print_to_terminal(0, "begin");
+
+    loop {
+        handle_message(&our);
+    }
+}
+
+fn handle_message(our: &Address) {
+    let message = await_message()
+        .unwrap_or_else(|e| fail_with(format!("failed to receive tester message: {e:?}")));
+
+    if !message.is_request() {
+        fail_with("expected tester request message");
+    }
+
+    let source = message.source();
+    if our.node != source.node {
+        fail_with(format!("rejecting foreign message from {:?}", source));
+    }
+
+    let TesterRequest::Run(RunRequest {
+        input_node_names: node_names,
+        ..
+    }) = message
+        .body()
+        .try_into()
+        .unwrap_or_else(|e| fail_with(format!("failed to decode tester run request: {e:?}")));
+
+    print_to_terminal(0, "explorer_test: start");
+
+    if our.node != node_names[0] {
+        Response::new()
+            .body(TesterResponse::Run(Ok(())))
+            .send()
+            .unwrap_or_else(|e| fail_with(format!("failed to send tester ack: {e:?}")));
+        return;
+    }
+
+    let explorer = Address {
+        node: our.node.clone(),
+        process: ProcessId::new(Some("explorer"), "file-explorer", "sys"),
+    };
+
+    tester_lib::reset_steps();
+    run(our, &explorer).unwrap_or_else(|e| fail_with(e.to_string()));
+    print_to_terminal(0, &format!("explorer_test: {}", tester_lib::step_summary()));
+
+    Response::new()
+        .body(TesterResponse::Run(Ok(())))
+        .send()
+        .unwrap_or_else(|e| fail_with(format!("failed to send tester success: {e:?}")));
+}
+
+fn run(our: &Address, explorer: &Address) -> Result<(), TestError> {
+    // a previous run that failed part way may have left the directory behind
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [SCRATCH_DIR, true] }));
+
+    let file_path = format!("{SCRATCH_DIR}/hello.bin");
+    let moved_path = format!("{SCRATCH_DIR}/moved.bin");
+    step!("create directory", create_scratch(explorer))?;
+    step!("create file", create_file(explorer, &file_path))?;
+    step!("list directory", list_scratch(explorer))?;
+    step!("read file", read_back(explorer, &file_path))?;
+    step!("move file", move_file(explorer, &file_path, &moved_path))?;
+    step!(
+        "share and unshare",
+        share_and_unshare(explorer, &moved_path)
+    )?;
+    step!("read a missing file", {
+        let missing = format!("{SCRATCH_DIR}/does-not-exist.bin");
+        expect_err_code(
+            call::<Vec<u8>>(explorer, json!({ "ReadFile": missing })),
+            "not_found",
+        )
+    })?;
+    step!("delete directory", delete_scratch(explorer))?;
+    step!("typed round trip", typed_round_trip(our, explorer))?;
+    step!("move and copy edge cases", move_copy::run(explorer))?;
+    step!("create_file conflicts", conflict::run(explorer))?;
+    step!("copy a directory", copy_dir::run(explorer))?;
+    step!("chunked copy", chunked_copy::run(explorer))?;
+    step!("duplicate in place", duplicate_file::run(explorer))?;
+    step!("search files", search::run(explorer))?;
+    step!("paged listing", paging::run(explorer))?;
+    step!("directory tree", tree::run(explorer))?;
+    step!("hidden entries", hidden::run(explorer))?;
+    step!("extract an archive", extract::run(explorer))?;
+    step!("path fuzz", fuzz::run(explorer))?;
+    step!("resolve relative paths", resolve::run())?;
+    step!("path traversal", traversal::run(explorer))?;
+    step!("shared file over HTTP", shared_http::run(explorer))?;
+    step!("import from a URL", import::run(explorer))?;
+    step!("share listing", share_list::run(explorer))?;
+    step!("share download limits", share_limits::run(explorer))?;
+    step!("trash and restore", trash::run(explorer))?;
+    step!("file versions", versions::run(explorer))?;
+    step!("checksums", checksum::run(explorer))?;
+    step!("duplicate files", duplicates::run(explorer))?;
+    step!("thumbnails", thumbnails::run(explorer))?;
+    step!("MIME types", mime::run(explorer))?;
+    step!("favorites", favorites::run(explorer))?;
+    step!("tags", tags::run(explorer))?;
+    step!("batch operations", batch::run(explorer))?;
+    step!("drives", drives::run(explorer))?;
+    step!("append and truncate", append::run(explorer))?;
+    step!("ranged reads", range_read::run(explorer))?;
+    step!("activity log", activity::run(explorer))?;
+    step!("recent files", recent::run(explorer))?;
+    // last: reloading a saved state drops every share made before it
+    Ok(())
+}
+
+fn create_scratch(explorer: &Address) -> Result<(), TestError> {
+    let created: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": SCRATCH_DIR }));
+    assert_eq_ctx!(
+        (true, "explorer-test"),
+        (created.is_directory, created.name.as_str()),
+        "create_directory is_directory and name"
+    )
+}
+
+fn create_file(explorer: &Address, file_path: &str) -> Result<(), TestError> {
+    let created: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [file_path, FILE_BYTES, null] }),
+    );
+    assert_eq_ctx!(
+        (false, FILE_BYTES.len() as u64),
+        (created.is_directory, created.size),
+        "create_file is_directory and size"
+    )
+}
+
+fn list_scratch(explorer: &Address) -> Result<(), TestError> {
+    let listing: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": SCRATCH_DIR }));
+    let [info] = listing.as_slice() else {
+        return Err(TestError::Assertion {
+            error: format!("expected only hello.bin, got {listing:?}"),
+        });
+    };
+    assert_eq_ctx!(
+        ("hello.bin", FILE_BYTES.len() as u64, false),
+        (info.name.as_str(), info.size, info.is_directory),
+        "listed name, size and is_directory"
+    )?;
+    if !info.path.ends_with("explorer-test/hello.bin") {
+        return Err(TestError::Assertion {
+            error: format!(
+                "listed path {:?} is not under the scratch directory",
+                info.path
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn read_back(explorer: &Address, path: &str) -> Result<(), TestError> {
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(FILE_BYTES, content, format!("content of {path}"))
+}
+
+fn move_file(explorer: &Address, from: &str, to: &str) -> Result<(), TestError> {
+    let moved: FileInfo = expect_ok(explorer, json!({ "MoveFile": [from, to] }));
+    assert_eq_ctx!(
+        (to, FILE_BYTES.len() as u64),
+        (moved.path.as_str(), moved.size),
+        "move_file path and size"
+    )?;
+    step!(
+        "read the moved source",
+        expect_err_code(
+            call::<Vec<u8>>(explorer, json!({ "ReadFile": from })),
+            "not_found",
+        )
+    )?;
+    step!("read the moved file", read_back(explorer, to))
+}
+
+fn share_and_unshare(explorer: &Address, path: &str) -> Result<(), TestError> {
+    let link: String = expect_ok(explorer, json!({ "ShareFile": [path, "Public", null] }));
+    if !link.starts_with("/explorer:file-explorer:sys/shared/") {
+        return Err(TestError::Assertion {
+            error: format!("share_file returned {link:?}"),
+        });
+    }
+    let fetched = share_link_of(explorer, path);
+    assert_eq_ctx!(Some(link.as_str()), fetched.as_deref(), "get_share_link")?;
+
+    let unshared: bool = expect_ok(explorer, json!({ "UnshareFile": path }));
+    assert_eq_ctx!(true, unshared, "unshare_file found the share")?;
+    let fetched = share_link_of(explorer, path);
+    assert_eq_ctx!(None, fetched, "get_share_link after unshare")
+}
+
+fn delete_scratch(explorer: &Address) -> Result<(), TestError> {
+    let deleted: bool = expect_ok(explorer, json!({ "DeleteDirectory": [SCRATCH_DIR, true] }));
+    assert_eq_ctx!(true, deleted, "delete_directory")?;
+    if call::<Vec<FileInfo>>(explorer, json!({ "ListDirectory": SCRATCH_DIR })).is_ok() {
+        return Err(TestError::Assertion {
+            error: "list_directory still succeeds after delete_directory".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// the same round trip through the generated caller_utils
+fn typed_round_trip(our: &Address, explorer: &Address) -> Result<(), TestError> {
+    let client = Address {
+        node: our.node.clone(),
+        process: ProcessId::new(Some("explorer-client"), "explorer-client", "sys"),
+    };
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": SCRATCH_DIR }));
+    let typed_path = format!("{SCRATCH_DIR}/typed.bin");
+    let content: Vec<u8> = expect_ok(&client, json!({ "RoundTrip": [typed_path, FILE_BYTES] }));
+    assert_eq_ctx!(FILE_BYTES, content, "content after the typed round trip")?;
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [SCRATCH_DIR, true] }));
+    Ok(())
+}
+
+/// send one explorer request and decode its `Result`; transport and decoding
+///  problems fail the test, an `Err` from the explorer is returned
+fn call<T: DeserializeOwned>(address: &Address, body: Value) -> Result<T, CodedError> {
+    call_json(address, &body, TIMEOUT_SECS, RETRIES).unwrap_or_else(|e| fail_with(e.to_string()))
+}
+
+fn expect_ok<T: DeserializeOwned>(address: &Address, body: Value) -> T {
+    call_ok::<_, T, CodedError>(address, &body, TIMEOUT_SECS, RETRIES)
+        .unwrap_or_else(|e| fail_with(e.to_string()))
+}
+
+/// the link get_share_link reports for `path`, if it is shared
+fn share_link_of(explorer: &Address, path: &str) -> Option<String> {
+    let share: Option<ShareInfo> = expect_ok(explorer, json!({ "GetShareLink": path }));
+    share.map(|share| share.link)
+}
+
+/// give up, naming the step we were in and listing the ones that passed
+fn fail_with(message: impl Into<String>) -> ! {
+    let message = tester_lib::in_current_step(message.into());
+    let log = format!(
+        "explorer_test: error: {message}\n{}",
+        tester_lib::step_summary()
+    );
+    print_to_terminal(0, log.as_str());
+    fail!(message);
+}
@@ -0,0 +1,76 @@
+//! get_mime_type: each known magic number wins over a misleading or missing
+//!  extension, the extension decides when no signature matches, and a
+//!  directory is refused
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const MIME_DIR: &str = "/file-explorer:sys/home/explorer-mime";
+
+/// (file name, leading bytes, expected type); the names of the signed files
+///  point elsewhere or nowhere
+const SIGNED: &[(&str, &[u8], &str)] = &[
+    ("png.txt", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", "image/png"),
+    ("jpeg", &[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10], "image/jpeg"),
+    ("gif87.bin", b"GIF87a\x01\0\x01\0", "image/gif"),
+    ("gif89.json", b"GIF89a\x01\0\x01\0", "image/gif"),
+    ("pdf.html", b"%PDF-1.7\n", "application/pdf"),
+    ("zip", b"PK\x03\x04\x14\0\0\0", "application/zip"),
+    ("gzip.txt", &[0x1F, 0x8B, 0x08, 0, 0, 0], "application/gzip"),
+    ("wav.png", b"RIFF\x24\0\0\0WAVEfmt ", "audio/wav"),
+    ("webp.gif", b"RIFF\x24\0\0\0WEBPVP8 ", "image/webp"),
+    ("id3.bin", b"ID3\x04\0\0\0\0\0\0", "audio/mpeg"),
+    ("frame", &[0xFF, 0xFB, 0x90, 0x64, 0], "audio/mpeg"),
+];
+
+/// (file name, content, expected type) with no known signature
+const UNSIGNED: &[(&str, &[u8], &str)] = &[
+    ("notes.txt", b"plain text\n", "text/plain"),
+    ("page.HTML", b"<p>hi</p>\n", "text/html"),
+    ("data.json", b"{}\n", "application/json"),
+    ("empty.pdf", b"", "application/pdf"),
+    (
+        "riff.bin",
+        b"RIFF\x24\0\0\0AVI LIST",
+        "application/octet-stream",
+    ),
+    ("no-extension", b"plain text\n", "application/octet-stream"),
+];
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [MIME_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": MIME_DIR }));
+
+    for &(name, content, expected) in SIGNED.iter().chain(UNSIGNED) {
+        step!(
+            format!("type of {name}"),
+            mime_of(explorer, name, content, expected)
+        )?;
+    }
+    step!(
+        "type of a directory",
+        expect_err_code(
+            call::<String>(explorer, json!({ "GetMimeType": MIME_DIR })),
+            "invalid_input",
+        )
+    )?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [MIME_DIR, true] }));
+    Ok(())
+}
+
+fn mime_of(
+    explorer: &Address,
+    name: &str,
+    content: &[u8],
+    expected: &str,
+) -> Result<(), TestError> {
+    let path = format!("{MIME_DIR}/{name}");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, content, null] }));
+
+    let actual: String = expect_ok(explorer, json!({ "GetMimeType": path }));
+    assert_eq_ctx!(expected, actual, format!("MIME type of {name}"))
+}
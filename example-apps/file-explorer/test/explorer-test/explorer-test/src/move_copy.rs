@@ -0,0 +1,262 @@
+//! the corners of move_file, copy_file, rename_file and move_path: moving or
+//!  copying a file onto itself, a destination whose directory is missing, moving a
+//!  shared file, copying an empty file, renaming files and directories in
+//!  place, moving a directory with a share inside it, and moving a file to
+//!  another drive, which copies it since the VFS only renames within a drive
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::path::same_drive;
+use crate::{call, expect_ok, share_link_of, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const EDGE_DIR: &str = "/file-explorer:sys/home/explorer-move-copy";
+
+const BYTES: &[u8] = b"explorer-test: moved and copied\n";
+
+/// a second drive, for a move that cannot be a rename
+const OTHER_DRIVE: &str = "explorer-test-move-drive";
+
+/// the explorer's MoveOutcome
+#[derive(Debug, serde::Deserialize)]
+struct MoveOutcome {
+    info: FileInfo,
+    method: MoveMethod,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+enum MoveMethod {
+    Renamed,
+    Copied,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [EDGE_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": EDGE_DIR }));
+
+    step!("move onto itself", move_onto_itself(explorer))?;
+    step!("copy onto itself", copy_onto_itself(explorer))?;
+    step!("missing parent", missing_parent(explorer))?;
+    step!("move a shared file", move_shared(explorer))?;
+    step!("copy an empty file", copy_empty(explorer))?;
+    step!("rename", rename(explorer))?;
+    step!("move a directory", move_directory(explorer))?;
+    step!("move to another drive", move_across_drives(explorer))?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [EDGE_DIR, true] }));
+    Ok(())
+}
+
+/// a no-op that succeeds and leaves the file as it was
+fn move_onto_itself(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{EDGE_DIR}/self.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, BYTES, null] }));
+
+    let moved: FileInfo = expect_ok(explorer, json!({ "MoveFile": [path, path] }));
+    assert_eq_ctx!(
+        (path.as_str(), BYTES.len() as u64, false),
+        (moved.path.as_str(), moved.size, moved.is_directory),
+        "move_file path, size and is_directory"
+    )?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(BYTES, content, "content after moving onto itself")
+}
+
+/// refused, however the path is spelled, and the file is left as it was
+fn copy_onto_itself(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{EDGE_DIR}/self-copy.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, BYTES, null] }));
+
+    for destination in [path.clone(), format!("{EDGE_DIR}/./self-copy.txt")] {
+        expect_err_code(
+            call::<FileInfo>(explorer, json!({ "CopyFile": [path, destination] })),
+            "invalid_input",
+        )?;
+    }
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(BYTES, content, "content after copying onto itself")
+}
+
+/// refused with parent_not_found by both, and the source is left alone
+fn missing_parent(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{EDGE_DIR}/orphan.txt");
+    let nowhere = format!("{EDGE_DIR}/no-such-dir/orphan.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, BYTES, null] }));
+
+    step!(
+        "copy_file",
+        expect_err_code(
+            call::<FileInfo>(explorer, json!({ "CopyFile": [path, nowhere] })),
+            "parent_not_found",
+        )
+    )?;
+    step!(
+        "move_file",
+        expect_err_code(
+            call::<FileInfo>(explorer, json!({ "MoveFile": [path, nowhere] })),
+            "parent_not_found",
+        )
+    )?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(BYTES, content, "source after the refused move")
+}
+
+/// the share moves to the new path and keeps its link
+fn move_shared(explorer: &Address) -> Result<(), TestError> {
+    let from = format!("{EDGE_DIR}/shared.txt");
+    let to = format!("{EDGE_DIR}/shared-moved.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [from, BYTES, null] }));
+    let old_link: String = expect_ok(explorer, json!({ "ShareFile": [from, "Public", null] }));
+
+    let _: FileInfo = expect_ok(explorer, json!({ "MoveFile": [from, to] }));
+
+    let old = share_link_of(explorer, &from);
+    assert_eq_ctx!(None, old, "share link of the old path")?;
+    let new = share_link_of(explorer, &to);
+    assert_eq_ctx!(Some(old_link), new, "share link of the new path")?;
+
+    let unshared: bool = expect_ok(explorer, json!({ "UnshareFile": to }));
+    assert_eq_ctx!(true, unshared, "unshare_file of the new path")
+}
+
+/// the copy exists, is empty, and is described as an empty file
+fn copy_empty(explorer: &Address) -> Result<(), TestError> {
+    let from = format!("{EDGE_DIR}/empty.bin");
+    let to = format!("{EDGE_DIR}/empty-copy.bin");
+    let created: FileInfo = expect_ok(explorer, json!({ "CreateFile": [from, b"", null] }));
+    assert_eq_ctx!(0, created.size, "size of the created empty file")?;
+
+    let copied: FileInfo = expect_ok(explorer, json!({ "CopyFile": [from, to] }));
+    assert_eq_ctx!(
+        ("empty-copy.bin", 0, false),
+        (copied.name.as_str(), copied.size, copied.is_directory),
+        "copy_file name, size and is_directory"
+    )?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": to }));
+    assert_eq_ctx!(0, content.len(), "bytes read from the copy")?;
+
+    let listing: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": EDGE_DIR }));
+    let mut listed: Vec<(&str, u64)> = listing
+        .iter()
+        .filter(|info| info.name.starts_with("empty"))
+        .map(|info| (info.name.as_str(), info.size))
+        .collect();
+    listed.sort();
+    assert_eq_ctx!(
+        vec![("empty-copy.bin", 0), ("empty.bin", 0)],
+        listed,
+        "empty files listed"
+    )
+}
+
+/// a rename keeps the content and the share, renames a directory with what is
+///  in it, and refuses names that are not a single entry
+fn rename(explorer: &Address) -> Result<(), TestError> {
+    let from = format!("{EDGE_DIR}/before.txt");
+    let to = format!("{EDGE_DIR}/after.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [from, BYTES, null] }));
+    let _: String = expect_ok(explorer, json!({ "ShareFile": [from, "Public", null] }));
+
+    let renamed: FileInfo = expect_ok(explorer, json!({ "RenameFile": [from, "after.txt"] }));
+    assert_eq_ctx!(
+        (to.as_str(), BYTES.len() as u64),
+        (renamed.path.as_str(), renamed.size),
+        "rename_file path and size"
+    )?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": to }));
+    assert_eq_ctx!(BYTES, content, "content after the rename")?;
+    let old = share_link_of(explorer, &from);
+    assert_eq_ctx!(None, old, "share link of the old name")?;
+    let new = share_link_of(explorer, &to);
+    assert_eq_ctx!(true, new.is_some(), "the renamed file is still shared")?;
+    let _: bool = expect_ok(explorer, json!({ "UnshareFile": to }));
+
+    for bad in ["", "a/b", ".", ".."] {
+        expect_err_code(
+            call::<FileInfo>(explorer, json!({ "RenameFile": [to, bad] })),
+            "invalid_input",
+        )?;
+    }
+
+    let dir = format!("{EDGE_DIR}/dir-before");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [format!("{dir}/inner.txt"), BYTES, null] }),
+    );
+    let renamed: FileInfo = expect_ok(explorer, json!({ "RenameFile": [dir, "dir-after"] }));
+    assert_eq_ctx!(true, renamed.is_directory, "a renamed directory")?;
+    let content: Vec<u8> = expect_ok(
+        explorer,
+        json!({ "ReadFile": format!("{EDGE_DIR}/dir-after/inner.txt") }),
+    );
+    assert_eq_ctx!(BYTES, content, "a file inside the renamed directory")
+}
+
+/// within one drive the move is a rename; what was inside comes along, and
+///  so does a share of it
+fn move_directory(explorer: &Address) -> Result<(), TestError> {
+    let from = format!("{EDGE_DIR}/moving");
+    let to = format!("{EDGE_DIR}/moved-dir");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": from }));
+    let inner = format!("{from}/inner.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [inner, BYTES, null] }));
+    let _: String = expect_ok(explorer, json!({ "ShareFile": [inner, "Public", null] }));
+
+    expect_err_code(
+        call::<MoveOutcome>(
+            explorer,
+            json!({ "MovePath": [from, format!("{from}/sub")] }),
+        ),
+        "invalid_input",
+    )?;
+
+    let moved: MoveOutcome = expect_ok(explorer, json!({ "MovePath": [from, to] }));
+    assert_eq_ctx!(
+        (to.as_str(), true, MoveMethod::Renamed),
+        (
+            moved.info.path.as_str(),
+            moved.info.is_directory,
+            moved.method
+        ),
+        "move_path path, is_directory and method"
+    )?;
+    let moved_inner = format!("{to}/inner.txt");
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": moved_inner }));
+    assert_eq_ctx!(BYTES, content, "the file inside the moved directory")?;
+    let old = share_link_of(explorer, &inner);
+    assert_eq_ctx!(None, old, "share link under the old directory")?;
+    let unshared: bool = expect_ok(explorer, json!({ "UnshareFile": moved_inner }));
+    assert_eq_ctx!(true, unshared, "unshare_file under the new directory")
+}
+
+/// a move between drives is copied and the source deleted; one within the
+///  drive stays a rename
+fn move_across_drives(explorer: &Address) -> Result<(), TestError> {
+    let drive: String = expect_ok(explorer, json!({ "CreateNewDrive": OTHER_DRIVE }));
+    let from = format!("{EDGE_DIR}/travelling.txt");
+    let to = format!("{drive}/travelling.txt");
+    assert_eq_ctx!(
+        (true, false),
+        (same_drive(&from, EDGE_DIR), same_drive(&from, &to)),
+        "same_drive within and across drives"
+    )?;
+    let _ = call::<bool>(explorer, json!({ "DeleteFile": [to, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [from, BYTES, null] }));
+
+    let moved: MoveOutcome = expect_ok(explorer, json!({ "MovePath": [from, to] }));
+    assert_eq_ctx!(
+        (to.as_str(), MoveMethod::Copied),
+        (moved.info.path.as_str(), moved.method),
+        "move_path path and method across drives"
+    )?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": to }));
+    assert_eq_ctx!(BYTES, content, "the file on the other drive")?;
+    expect_err_code(
+        call::<Vec<u8>>(explorer, json!({ "ReadFile": from })),
+        "not_found",
+    )?;
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [to, true] }));
+    Ok(())
+}
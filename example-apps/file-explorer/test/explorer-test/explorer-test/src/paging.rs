@@ -0,0 +1,183 @@
+//! list_directory_paged: pages that add up to the whole directory, each sort
+//!  order in both directions, directories first, name filters, and a bad
+//!  limit
+
+use hyperware_process_lib::Address;
+use serde_json::{json, Value};
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const PAGING_DIR: &str = "/file-explorer:sys/home/explorer-paging";
+
+/// files of distinct sizes, created in an order that is none of the sorts
+const FILES: &[(&str, usize)] = &[("b.txt", 3), ("d.md", 1), ("a.md", 4), ("c.bin", 2)];
+
+const SUBDIR: &str = "z-dir";
+
+/// the explorer's DirectoryPage
+#[derive(Debug, serde::Deserialize)]
+struct DirectoryPage {
+    entries: Vec<FileInfo>,
+    total: u64,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [PAGING_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": PAGING_DIR }));
+    for (name, size) in FILES {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [format!("{PAGING_DIR}/{name}"), vec![b'x'; *size], null] }),
+        );
+    }
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateDirectory": format!("{PAGING_DIR}/{SUBDIR}") }),
+    );
+
+    step!("pages", pages(explorer))?;
+    step!(
+        "by name",
+        expect_order(
+            explorer,
+            ("Name", true, false),
+            &["a.md", "b.txt", "c.bin", "d.md", SUBDIR]
+        )
+    )?;
+    step!(
+        "by size, descending",
+        expect_order(
+            explorer,
+            ("Size", false, false),
+            &["a.md", "b.txt", "c.bin", "d.md", SUBDIR]
+        )
+    )?;
+    step!(
+        "by type",
+        expect_order(
+            explorer,
+            ("Type", true, false),
+            &[SUBDIR, "c.bin", "a.md", "d.md", "b.txt"]
+        )
+    )?;
+    step!(
+        "directories first, by name descending",
+        expect_order(
+            explorer,
+            ("Name", false, true),
+            &[SUBDIR, "d.md", "c.bin", "b.txt", "a.md"]
+        )
+    )?;
+    step!(
+        "filtered, ignoring case",
+        expect_filtered(explorer, ("*.MD", false, false), &["a.md", "d.md", SUBDIR])
+    )?;
+    step!(
+        "filtered, files only",
+        expect_filtered(
+            explorer,
+            ("[a-c]*", true, false),
+            &["a.md", "b.txt", "c.bin"]
+        )
+    )?;
+    step!(
+        "filtered, case sensitive",
+        expect_filtered(explorer, ("*.MD", true, true), &[])
+    )?;
+    step!("a filtered page", {
+        let page: DirectoryPage = expect_ok(
+            explorer,
+            json!({
+                "ListDirectoryPaged": [PAGING_DIR, 1, 1, "Name", true, false, "*.md", true, false]
+            }),
+        );
+        let names: Vec<&str> = page.entries.iter().map(|info| info.name.as_str()).collect();
+        assert_eq_ctx!(2, page.total, "total of the filtered entries")?;
+        assert_eq_ctx!(vec!["d.md"], names, "second filtered page")
+    })?;
+    step!(
+        "zero limit",
+        expect_err_code(
+            call::<DirectoryPage>(explorer, page_request(0, 0, ("Name", true, false))),
+            "invalid_input",
+        )
+    )?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [PAGING_DIR, true] }));
+    Ok(())
+}
+
+/// pages of two cover every entry once, and a page past the end is empty
+fn pages(explorer: &Address) -> Result<(), TestError> {
+    let order = ("Name", true, false);
+    let mut names = Vec::new();
+    for offset in [0, 2, 4] {
+        let page: DirectoryPage = expect_ok(explorer, page_request(offset, 2, order));
+        assert_eq_ctx!(
+            FILES.len() as u64 + 1,
+            page.total,
+            format!("total at offset {offset}")
+        )?;
+        names.extend(page.entries.into_iter().map(|info| info.name));
+    }
+    assert_eq_ctx!(
+        vec!["a.md", "b.txt", "c.bin", "d.md", SUBDIR],
+        names,
+        "names across the pages"
+    )?;
+
+    let past: DirectoryPage = expect_ok(explorer, page_request(10, 2, order));
+    assert_eq_ctx!(0, past.entries.len(), "entries past the end")
+}
+
+/// one page holding the whole directory lists it in `expected` order
+fn expect_order(
+    explorer: &Address,
+    order: (&str, bool, bool),
+    expected: &[&str],
+) -> Result<(), TestError> {
+    let page: DirectoryPage = expect_ok(explorer, page_request(0, 100, order));
+    let names: Vec<&str> = page.entries.iter().map(|info| info.name.as_str()).collect();
+    assert_eq_ctx!(
+        expected,
+        names.as_slice(),
+        format!("names sorted by {order:?}")
+    )
+}
+
+/// the whole directory by name, with (filter, files_only, case_sensitive),
+///  lists `expected`
+fn expect_filtered(
+    explorer: &Address,
+    (filter, files_only, case_sensitive): (&str, bool, bool),
+    expected: &[&str],
+) -> Result<(), TestError> {
+    let page: DirectoryPage = expect_ok(
+        explorer,
+        json!({
+            "ListDirectoryPaged": [
+                PAGING_DIR, 0, 100, "Name", true, false, filter, files_only, case_sensitive
+            ]
+        }),
+    );
+    let names: Vec<&str> = page.entries.iter().map(|info| info.name.as_str()).collect();
+    assert_eq_ctx!(
+        expected,
+        names.as_slice(),
+        format!("names filtered by {filter:?}")
+    )
+}
+
+/// (sort_by, ascending, directories_first), unfiltered
+fn page_request(
+    offset: u32,
+    limit: u32,
+    (sort_by, ascending, directories_first): (&str, bool, bool),
+) -> Value {
+    json!({
+        "ListDirectoryPaged": [
+            PAGING_DIR, offset, limit, sort_by, ascending, directories_first, null, false, false
+        ]
+    })
+}
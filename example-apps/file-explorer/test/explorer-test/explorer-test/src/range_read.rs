@@ -0,0 +1,63 @@
+//! read_file_range returns the bytes of a window, short at the end of the
+//! file, and refuses windows that start past the end or are too long
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const RANGE_DIR: &str = "/file-explorer:sys/home/explorer-range";
+
+const CONTENT: &[u8] = b"0123456789abcdefghij";
+
+// One byte over the cap read_file_range serves at once
+const OVER_CAP: u64 = 4 * 1024 * 1024 + 1;
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [RANGE_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": RANGE_DIR }));
+
+    let file = format!("{RANGE_DIR}/digits.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [file, CONTENT, null] }));
+
+    step!(
+        "middle window",
+        read(explorer, &file, 5, 5, &CONTENT[5..10])
+    )?;
+    step!(
+        "window past the end",
+        read(explorer, &file, 15, 100, &CONTENT[15..])
+    )?;
+    step!(
+        "offset at the end",
+        read(explorer, &file, CONTENT.len() as u64, 10, b"")
+    )?;
+    step!(
+        "offset past the end",
+        refused(explorer, &file, CONTENT.len() as u64 + 1, 1)
+    )?;
+    step!("length over the cap", refused(explorer, &file, 0, OVER_CAP))?;
+    step!("a directory", refused(explorer, RANGE_DIR, 0, 1))?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [RANGE_DIR, true] }));
+    Ok(())
+}
+
+fn read(
+    explorer: &Address,
+    file: &str,
+    offset: u64,
+    length: u64,
+    expected: &[u8],
+) -> Result<(), TestError> {
+    let bytes: Vec<u8> = expect_ok(explorer, json!({ "ReadFileRange": [file, offset, length] }));
+    assert_eq_ctx!(expected, bytes, "bytes of the window")
+}
+
+fn refused(explorer: &Address, path: &str, offset: u64, length: u64) -> Result<(), TestError> {
+    expect_err_code(
+        call::<Vec<u8>>(explorer, json!({ "ReadFileRange": [path, offset, length] })),
+        "invalid_input",
+    )
+}
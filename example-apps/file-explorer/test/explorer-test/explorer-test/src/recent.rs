@@ -0,0 +1,101 @@
+//! recent files: reads and writes through the file handlers, most recent
+//!  first and each once; deleted files left out, clearing, and opting out
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, step, TestError};
+
+const RECENT_DIR: &str = "/file-explorer:sys/home/explorer-recent";
+
+const RECENT_BYTES: &[u8] = b"explorer-test: recent\n";
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [RECENT_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": RECENT_DIR }));
+    let _: bool = expect_ok(explorer, json!({ "SetTrackRecent": true }));
+    let _: u64 = expect_ok(explorer, json!({ "ClearRecentFiles": null }));
+
+    let first = format!("{RECENT_DIR}/first.txt");
+    let second = format!("{RECENT_DIR}/second.txt");
+    for path in [&first, &second] {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [path, RECENT_BYTES, null] }),
+        );
+    }
+
+    step!("read moves a file to the front", {
+        let _: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": first }));
+        assert_eq_ctx!(
+            vec![first.clone(), second.clone()],
+            recent_paths(explorer),
+            "recent files after reading the first"
+        )
+    })?;
+    step!("update moves a file to the front", {
+        let _: FileInfo = expect_ok(explorer, json!({ "UpdateFile": [second, RECENT_BYTES] }));
+        assert_eq_ctx!(
+            vec![second.clone(), first.clone()],
+            recent_paths(explorer),
+            "recent files after updating the second"
+        )
+    })?;
+    step!("deleted files left out", {
+        let _: bool = expect_ok(explorer, json!({ "DeleteFile": [first, true] }));
+        assert_eq_ctx!(
+            vec![second.clone()],
+            recent_paths(explorer),
+            "recent files after deleting the first"
+        )
+    })?;
+    step!("clear", {
+        // the deleted file is still remembered until cleared
+        let cleared: u64 = expect_ok(explorer, json!({ "ClearRecentFiles": null }));
+        assert_eq_ctx!(2, cleared, "recent files cleared")?;
+        assert_eq_ctx!(
+            0,
+            recent_paths(explorer).len(),
+            "recent files after clearing"
+        )
+    })?;
+    step!("opted out", opted_out(explorer, &second))?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [RECENT_DIR, true] }));
+    Ok(())
+}
+
+/// nothing is tracked, and what was tracked is forgotten, until tracking is
+///  turned back on
+fn opted_out(explorer: &Address, path: &str) -> Result<(), TestError> {
+    let _: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    let _: bool = expect_ok(explorer, json!({ "SetTrackRecent": false }));
+    let tracking: bool = expect_ok(explorer, json!({ "GetTrackRecent": null }));
+    assert_eq_ctx!(false, tracking, "tracking after opting out")?;
+    assert_eq_ctx!(
+        0,
+        recent_paths(explorer).len(),
+        "recent files on opting out"
+    )?;
+
+    let _: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(
+        0,
+        recent_paths(explorer).len(),
+        "recent files while opted out"
+    )?;
+
+    let _: bool = expect_ok(explorer, json!({ "SetTrackRecent": true }));
+    let _: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(
+        vec![path.to_string()],
+        recent_paths(explorer),
+        "recent files once tracking again"
+    )
+}
+
+fn recent_paths(explorer: &Address) -> Vec<String> {
+    let recent: Vec<FileInfo> = expect_ok(explorer, json!({ "GetRecentFiles": null }));
+    recent.into_iter().map(|info| info.path).collect()
+}
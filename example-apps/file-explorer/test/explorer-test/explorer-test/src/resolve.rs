@@ -0,0 +1,49 @@
+//! the explorer's resolve_path, compiled in directly like normalize_path for
+//!  the path fuzz: relative paths against a working directory, absolute
+//!  paths as they are, and `..` refused above the drive
+
+use crate::path::resolve_path;
+use tester_lib::{assert_eq_ctx, step, TestError};
+
+const CWD: &str = "/file-explorer:sys/home/projects";
+
+/// (relative, resolved against CWD); None where it must be refused
+const CASES: &[(&str, Option<&str>)] = &[
+    (
+        "notes.txt",
+        Some("/file-explorer:sys/home/projects/notes.txt"),
+    ),
+    ("./a/./b/", Some("/file-explorer:sys/home/projects/a/b")),
+    ("", Some("/file-explorer:sys/home/projects")),
+    ("a//b/../c", Some("/file-explorer:sys/home/projects/a/c")),
+    ("..", Some("/file-explorer:sys/home")),
+    ("../other/x", Some("/file-explorer:sys/home/other/x")),
+    (
+        "/file-explorer:sys/home/abs",
+        Some("/file-explorer:sys/home/abs"),
+    ),
+    ("../..", None),
+    ("../../../etc", None),
+    ("/file-explorer:sys/home/../..", None),
+];
+
+pub fn run() -> Result<(), TestError> {
+    for &(relative, expected) in CASES {
+        step!(format!("resolve {relative:?}"), {
+            let resolved = resolve_path(CWD, relative).ok();
+            assert_eq_ctx!(
+                expected.map(str::to_string),
+                resolved,
+                format!("{relative:?} against {CWD}")
+            )
+        })?;
+    }
+    step!("resolve against a trailing slash", {
+        let resolved = resolve_path(&format!("{CWD}/"), "x").ok();
+        assert_eq_ctx!(
+            Some(format!("{CWD}/x")),
+            resolved,
+            "x against a working directory ending in /"
+        )
+    })
+}
@@ -0,0 +1,172 @@
+//! search_files: substring and glob matches at any depth, hidden files only
+//!  while shown, and a search cut short by max_results; search_contents: line numbers, the
+//!  extension filter, binary files, and the per-file cap
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const SEARCH_DIR: &str = "/file-explorer:sys/home/explorer-search";
+
+/// files the searches look for, in directories up to three levels down
+const FILES: &[&str] = &[
+    "Report.TXT",
+    "a/report-draft.txt",
+    "a/b/notes.md",
+    "a/b/c/old-report.txt",
+    "a/.state",
+];
+
+/// how many hits search_contents reports from one file at most
+const MAX_HITS_PER_FILE: usize = 20;
+
+/// the explorer's SearchResults
+#[derive(Debug, serde::Deserialize)]
+struct SearchResults {
+    files: Vec<FileInfo>,
+    truncated: bool,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [SEARCH_DIR, true] }));
+    for dir in ["", "/a", "/a/b", "/a/b/c"] {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateDirectory": format!("{SEARCH_DIR}{dir}") }),
+        );
+    }
+    for file in FILES {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [format!("{SEARCH_DIR}/{file}"), b"searched\n", null] }),
+        );
+    }
+
+    step!(
+        "substring",
+        expect_found(
+            explorer,
+            "REPORT",
+            &["Report.TXT", "a/b/c/old-report.txt", "a/report-draft.txt"],
+        )
+    )?;
+    step!("glob", expect_found(explorer, "*.md", &["a/b/notes.md"]))?;
+    step!("hidden", expect_found(explorer, ".state", &[]))?;
+    step!("hidden, shown", {
+        let _: bool = expect_ok(explorer, json!({ "SetShowHidden": true }));
+        let found = expect_found(explorer, ".state", &["a/.state"]);
+        let _: bool = expect_ok(explorer, json!({ "SetShowHidden": false }));
+        found
+    })?;
+    step!("max_results", {
+        search(explorer, "report", 1).and_then(|(found, truncated)| {
+            assert_eq_ctx!((1, true), (found.len(), truncated), "one result, truncated")
+        })
+    })?;
+    step!(
+        "empty query",
+        expect_err_code(
+            call::<SearchResults>(explorer, json!({ "SearchFiles": ["", SEARCH_DIR, 10] })),
+            "invalid_input",
+        )
+    )?;
+
+    step!("contents", search_contents(explorer))?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [SEARCH_DIR, true] }));
+    Ok(())
+}
+
+/// the explorer's SearchHit
+#[derive(Debug, serde::Deserialize)]
+struct SearchHit {
+    file: FileInfo,
+    line: u32,
+    snippet: String,
+}
+
+fn search_contents(explorer: &Address) -> Result<(), TestError> {
+    let dir = format!("{SEARCH_DIR}/contents");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    let many: String = (0..MAX_HITS_PER_FILE + 10).map(|_| "needle\n").collect();
+    let files: &[(&str, &[u8])] = &[
+        ("lines.txt", b"hay\n  a NEEDLE here  \nhay\nneedle\n"),
+        ("binary.txt", b"needle\0\x01\x02"),
+        ("table.csv", b"needle,1\n"),
+        ("many.log", many.as_bytes()),
+    ];
+    for (name, content) in files {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [format!("{dir}/{name}"), content, null] }),
+        );
+    }
+
+    let hits: Vec<SearchHit> = expect_ok(
+        explorer,
+        json!({ "SearchContents": ["needle", dir, ["txt"]] }),
+    );
+    let found: Vec<(&str, u32, &str)> = hits
+        .iter()
+        .map(|hit| (hit.file.name.as_str(), hit.line, hit.snippet.as_str()))
+        .collect();
+    assert_eq_ctx!(
+        vec![
+            ("lines.txt", 2, "a NEEDLE here"),
+            ("lines.txt", 4, "needle")
+        ],
+        found,
+        "hits in .txt files, the binary one skipped"
+    )?;
+
+    let hits: Vec<SearchHit> = expect_ok(
+        explorer,
+        json!({ "SearchContents": ["needle", dir, [".LOG", "csv"]] }),
+    );
+    let count = |name: &str| hits.iter().filter(|hit| hit.file.name == name).count();
+    assert_eq_ctx!(
+        (1, MAX_HITS_PER_FILE, 0),
+        (count("table.csv"), count("many.log"), count("lines.txt")),
+        "hits per file for the csv and log extensions"
+    )
+}
+
+/// a search with room for every result finds exactly `expected`
+fn expect_found(explorer: &Address, query: &str, expected: &[&str]) -> Result<(), TestError> {
+    let (found, truncated) = search(explorer, query, 100)?;
+    let expected: Vec<String> = expected.iter().map(|path| path.to_string()).collect();
+    assert_eq_ctx!(
+        (expected, false),
+        (found, truncated),
+        format!("paths found for `{query}` and whether truncated")
+    )
+}
+
+/// the paths found below SEARCH_DIR, sorted, and whether the search was cut
+///  short
+fn search(
+    explorer: &Address,
+    query: &str,
+    max_results: u32,
+) -> Result<(Vec<String>, bool), TestError> {
+    let results: SearchResults = expect_ok(
+        explorer,
+        json!({ "SearchFiles": [query, SEARCH_DIR, max_results] }),
+    );
+    let prefix = format!("{SEARCH_DIR}/");
+    let mut found = Vec::new();
+    for info in results.files {
+        match info.path.strip_prefix(&prefix) {
+            Some(relative) => found.push(relative.to_string()),
+            None => {
+                return Err(TestError::Assertion {
+                    error: format!("search found {} outside {SEARCH_DIR}", info.path),
+                })
+            }
+        }
+    }
+    found.sort();
+    Ok((found, results.truncated))
+}
@@ -0,0 +1,130 @@
+//! a share with max_downloads serves that many downloads, counting down in
+//!  get_share_link, then answers link_exhausted and is gone; over HTTP a
+//!  Range for part of the file counts like the whole of it, and only a 416
+//!  does not; a limit of zero is refused
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::shared_http::{expect_refused, fetch_with};
+use crate::{call, expect_ok, share_link_of, FileInfo, ShareInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const SHARE_LIMITS_DIR: &str = "/file-explorer:sys/home/explorer-share-limits";
+
+const HANDOFF_BYTES: &[u8] = b"explorer-test: one-time handoff\n";
+
+const MAX_DOWNLOADS: u32 = 2;
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(
+        explorer,
+        json!({ "DeleteDirectory": [SHARE_LIMITS_DIR, true] }),
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": SHARE_LIMITS_DIR }));
+
+    let path = format!("{SHARE_LIMITS_DIR}/handoff.txt");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, HANDOFF_BYTES, null] }),
+    );
+    step!(
+        "a limit of zero",
+        expect_err_code(
+            call::<String>(explorer, json!({ "ShareFile": [path, "Public", 0] })),
+            "invalid_input",
+        )
+    )?;
+    step!("an unlimited share", {
+        let _: String = expect_ok(explorer, json!({ "ShareFile": [path, "Public", null] }));
+        limit(explorer, &path, None, None)
+    })?;
+
+    let link: String = expect_ok(
+        explorer,
+        json!({ "ShareFile": [path, "Public", MAX_DOWNLOADS] }),
+    );
+    step!(
+        "a limited share",
+        limit(explorer, &path, Some(MAX_DOWNLOADS), Some(MAX_DOWNLOADS))
+    )?;
+    step!("downloads up to the limit", {
+        for left in (1..MAX_DOWNLOADS).rev() {
+            let content: Vec<u8> = expect_ok(explorer, json!({ "FetchSharedFile": link }));
+            assert_eq_ctx!(HANDOFF_BYTES, content, "content of a download")?;
+            limit(explorer, &path, Some(MAX_DOWNLOADS), Some(left))?;
+        }
+        Ok::<(), TestError>(())
+    })?;
+    step!("the last download", {
+        let content: Vec<u8> = expect_ok(explorer, json!({ "FetchSharedFile": link }));
+        assert_eq_ctx!(HANDOFF_BYTES, content, "content of the last download")?;
+        assert_eq_ctx!(
+            None,
+            share_link_of(explorer, &path),
+            "share link after the last download"
+        )
+    })?;
+    step!(
+        "a download past the limit",
+        expect_err_code(
+            call::<Vec<u8>>(explorer, json!({ "FetchSharedFile": link })),
+            "link_exhausted",
+        )
+    )?;
+    step!("Range requests against the limit", ranges(explorer, &path))?;
+
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteDirectory": [SHARE_LIMITS_DIR, true] }),
+    );
+    Ok(())
+}
+
+/// the limit get_share_link reports for `path`, and the downloads it has left
+fn limit(
+    explorer: &Address,
+    path: &str,
+    max: Option<u32>,
+    left: Option<u32>,
+) -> Result<(), TestError> {
+    let share: Option<ShareInfo> = expect_ok(explorer, json!({ "GetShareLink": path }));
+    let Some(share) = share else {
+        return Err(TestError::Assertion {
+            error: format!("{path} is not shared"),
+        });
+    };
+    assert_eq_ctx!(
+        (max, left),
+        (share.max_downloads, share.downloads_remaining),
+        "max_downloads and downloads remaining"
+    )
+}
+
+/// a share of two downloads survives a 416, then is used up by two Ranges
+///  for a part of the file each, and refuses a third
+fn ranges(explorer: &Address, path: &str) -> Result<(), String> {
+    let link: String = expect_ok(explorer, json!({ "ShareFile": [path, "Public", 2] }));
+    let len = HANDOFF_BYTES.len();
+    let past_end = format!("bytes={len}-");
+    let fetched = fetch_with(&link, Some(&past_end))?;
+    if fetched.status != 416 {
+        return Err(format!("Range {past_end}: expected 416, got {fetched:?}"));
+    }
+    limit(explorer, path, Some(2), Some(2)).map_err(|e| format!("after a 416: {e}"))?;
+
+    let first = fetch_with(&link, Some("bytes=0-4"))?;
+    if first.status != 206 || first.body != HANDOFF_BYTES[..5] {
+        return Err(format!("Range bytes=0-4: expected 206, got {first:?}"));
+    }
+    limit(explorer, path, Some(2), Some(1)).map_err(|e| format!("after a Range: {e}"))?;
+
+    let rest = fetch_with(&link, Some("bytes=5-"))?;
+    if rest.status != 206 || rest.body != HANDOFF_BYTES[5..] {
+        return Err(format!("Range bytes=5-: expected 206, got {rest:?}"));
+    }
+    if let Some(left) = share_link_of(explorer, path) {
+        return Err(format!("share link after the last Range: {left}"));
+    }
+    expect_refused(&link, &HANDOFF_BYTES[..5], "link_exhausted")
+}
@@ -0,0 +1,63 @@
+//! list_shares reports each share's link, access and creation time, and
+//!  counts every download of it; an unshared path drops out of the list
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo, ShareInfo};
+use tester_lib::{assert_eq_ctx, step, TestError};
+
+const SHARE_LIST_DIR: &str = "/file-explorer:sys/home/explorer-share-list";
+
+const SHARED_BYTES: &[u8] = b"explorer-test: counted\n";
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(
+        explorer,
+        json!({ "DeleteDirectory": [SHARE_LIST_DIR, true] }),
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": SHARE_LIST_DIR }));
+
+    let path = format!("{SHARE_LIST_DIR}/counted.txt");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, SHARED_BYTES, null] }),
+    );
+    let link: String = expect_ok(explorer, json!({ "ShareFile": [path, "Public", null] }));
+
+    step!("a new share", listed(explorer, &path, &link, 0))?;
+    for _ in 0..2 {
+        let _: Vec<u8> = expect_ok(explorer, json!({ "FetchSharedFile": link }));
+    }
+    step!("after two downloads", listed(explorer, &path, &link, 2))?;
+
+    let _: bool = expect_ok(explorer, json!({ "UnshareFile": path }));
+    step!("after unshare_file", {
+        let shares: Vec<ShareInfo> = expect_ok(explorer, json!({ "ListShares": null }));
+        assert_eq_ctx!(
+            false,
+            shares.iter().any(|share| share.path == path),
+            "unshared path in list_shares"
+        )
+    })?;
+
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteDirectory": [SHARE_LIST_DIR, true] }),
+    );
+    Ok(())
+}
+
+fn listed(explorer: &Address, path: &str, link: &str, hits: u64) -> Result<(), TestError> {
+    let shares: Vec<ShareInfo> = expect_ok(explorer, json!({ "ListShares": null }));
+    let Some(share) = shares.into_iter().find(|share| share.path == path) else {
+        return Err(TestError::Assertion {
+            error: format!("{path} missing from list_shares"),
+        });
+    };
+    assert_eq_ctx!(link, share.link, "link in list_shares")?;
+    assert_eq_ctx!(true, link.ends_with(&share.id), "id in list_shares")?;
+    assert_eq_ctx!(json!("Public"), share.auth, "auth in list_shares")?;
+    assert_eq_ctx!(true, share.created > 0, "created in list_shares")?;
+    assert_eq_ctx!(hits, share.hits, "hits in list_shares")
+}
@@ -0,0 +1,290 @@
+//! the unauthenticated /shared/<id> endpoint, fetched over the node's real
+//!  HTTP server through http-client:distro:sys, with no credentials; a shared
+//!  directory comes back as a zip archive, and a Range header gets part of a
+//!  file
+
+use hyperware_process_lib::http::client::send_request_await_response;
+use hyperware_process_lib::http::Method;
+use hyperware_process_lib::Address;
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::CodedError;
+
+/// the node the tester runs on, as set in tests.toml
+pub const NODE_URL: &str = "http://localhost:8080";
+
+const HTTP_TIMEOUT_SECS: u64 = 10;
+
+const SHARE_DIR: &str = "/file-explorer:sys/home/explorer-share";
+
+const PUBLIC_BYTES: &[u8] = b"explorer-test: served without credentials\n";
+
+const PRIVATE_BYTES: &[u8] = b"explorer-test: never served\n";
+
+/// a node a private share lists; it need not exist
+const OTHER_NODE: &str = "explorer-test-peer.os";
+
+/// the shared directory's files, and an empty directory, relative to it
+const ZIPPED_FILES: &[(&str, &[u8])] =
+    &[("top.txt", b"top\n"), ("nested/deeper/leaf.txt", b"leaf\n")];
+
+const ZIPPED_EMPTY_DIR: &str = "empty";
+
+/// what an anonymous GET of a share link came back with
+#[derive(Debug)]
+pub struct Fetched {
+    pub status: u16,
+    content_type: Option<String>,
+    disposition: Option<String>,
+    accept_ranges: Option<String>,
+    content_range: Option<String>,
+    checksum: Option<String>,
+    pub body: Vec<u8>,
+}
+
+fn fetch(link: &str) -> Result<Fetched, String> {
+    fetch_with(link, None)
+}
+
+/// a GET with a `Range` header when `range` is set
+pub fn fetch_with(link: &str, range: Option<&str>) -> Result<Fetched, String> {
+    let url = url::Url::parse(&format!("{NODE_URL}{link}"))
+        .map_err(|e| format!("bad share link {link:?}: {e}"))?;
+    let headers = range.map(|range| HashMap::from([("Range".to_string(), range.to_string())]));
+    let response =
+        send_request_await_response(Method::GET, url, headers, HTTP_TIMEOUT_SECS, Vec::new())
+            .map_err(|e| format!("GET {link} failed: {e:?}"))?;
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    Ok(Fetched {
+        status: response.status().as_u16(),
+        content_type: header("content-type"),
+        disposition: header("content-disposition"),
+        accept_ranges: header("accept-ranges"),
+        content_range: header("content-range"),
+        checksum: header("x-checksum-sha256"),
+        body: response.body().clone(),
+    })
+}
+
+/// the link must not hand out `content`: either an HTTP error status, or the
+///  handler's `{"Err": ...}` carrying `code`
+pub fn expect_refused(link: &str, content: &[u8], code: &str) -> Result<(), String> {
+    let fetched = fetch(link)?;
+    if fetched.body.windows(content.len()).any(|w| w == content) {
+        return Err(format!("{link} served the file: {fetched:?}"));
+    }
+    if !(200..300).contains(&fetched.status) {
+        return Ok(());
+    }
+    match serde_json::from_slice::<Result<serde_json::Value, CodedError>>(&fetched.body) {
+        Ok(Err(e)) if e.code == code => Ok(()),
+        Ok(Err(e)) => Err(format!("{link}: expected error {code}, got {e}")),
+        _ => Err(format!(
+            "{link} answered {} without an error: {fetched:?}",
+            fetched.status
+        )),
+    }
+}
+
+pub fn run(explorer: &Address) -> Result<(), String> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [SHARE_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": SHARE_DIR }));
+
+    let public_path = format!("{SHARE_DIR}/public.txt");
+    let private_path = format!("{SHARE_DIR}/private.bin");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [public_path, PUBLIC_BYTES, null] }),
+    );
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [private_path, PRIVATE_BYTES, null] }),
+    );
+
+    let public_link: String = expect_ok(
+        explorer,
+        json!({ "ShareFile": [public_path, "Public", null] }),
+    );
+    let fetched = fetch(&public_link)?;
+    if fetched.status != 200 || fetched.body != PUBLIC_BYTES {
+        return Err(format!("public share {public_link}: {fetched:?}"));
+    }
+    if fetched.content_type.as_deref() != Some("text/plain") {
+        return Err(format!(
+            "public share Content-Type: expected text/plain, got {:?}",
+            fetched.content_type
+        ));
+    }
+    if fetched.disposition.as_deref() != Some("attachment; filename=\"public.txt\"") {
+        return Err(format!(
+            "public share Content-Disposition: got {:?}",
+            fetched.disposition
+        ));
+    }
+
+    let checksum: String = expect_ok(explorer, json!({ "GetChecksum": [public_path, "Sha256"] }));
+    if fetched.checksum.as_deref() != Some(checksum.as_str()) {
+        return Err(format!(
+            "public share X-Checksum-SHA256: expected {checksum}, got {:?}",
+            fetched.checksum
+        ));
+    }
+
+    ranges(&public_link)?;
+    rotated_links(explorer, &public_path, &public_link)?;
+
+    let private_link: String = expect_ok(
+        explorer,
+        json!({ "ShareFile": [private_path, { "Private": [OTHER_NODE] }, null] }),
+    );
+    expect_refused(&private_link, PRIVATE_BYTES, "not_permitted")?;
+    // over fetch_shared_file our own node is always let in, by link or id
+    let share_id = private_link.rsplit('/').next().unwrap_or_default();
+    for share in [private_link.as_str(), share_id] {
+        let fetched: Vec<u8> = expect_ok(explorer, json!({ "FetchSharedFile": share }));
+        if fetched != PRIVATE_BYTES {
+            return Err(format!("fetch_shared_file {share}: got {fetched:?}"));
+        }
+    }
+
+    let unshared: bool = expect_ok(explorer, json!({ "UnshareFile": public_path }));
+    if !unshared {
+        return Err("unshare_file did not find the public share".to_string());
+    }
+    expect_refused(&public_link, PUBLIC_BYTES, "not_found")?;
+
+    let _: bool = expect_ok(explorer, json!({ "UnshareFile": private_path }));
+    shared_directory(explorer)?;
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [SHARE_DIR, true] }));
+    Ok(())
+}
+
+/// each form of Range gets its slice with a 206 and Content-Range; one past
+///  the end gets a 416
+fn ranges(link: &str) -> Result<(), String> {
+    let len = PUBLIC_BYTES.len();
+    let whole = fetch(link)?;
+    if whole.accept_ranges.as_deref() != Some("bytes") {
+        return Err(format!(
+            "public share Accept-Ranges: got {:?}",
+            whole.accept_ranges
+        ));
+    }
+
+    let cases = [
+        ("bytes=0-4", 0, 4),
+        ("bytes=10-", 10, len - 1),
+        ("bytes=-5", len - 5, len - 1),
+        ("bytes=20-100000", 20, len - 1),
+    ];
+    for (range, start, end) in cases {
+        let fetched = fetch_with(link, Some(range))?;
+        let content_range = format!("bytes {start}-{end}/{len}");
+        if fetched.status != 206
+            || fetched.content_range.as_deref() != Some(content_range.as_str())
+            || fetched.body != PUBLIC_BYTES[start..=end]
+        {
+            return Err(format!(
+                "Range {range}: expected 206, {content_range} and bytes {start}..={end}, got {fetched:?}"
+            ));
+        }
+    }
+
+    let past_end = format!("bytes={len}-");
+    let fetched = fetch_with(link, Some(&past_end))?;
+    let content_range = format!("bytes */{len}");
+    if fetched.status != 416 || fetched.content_range.as_deref() != Some(content_range.as_str()) {
+        return Err(format!(
+            "Range {past_end}: expected 416 and {content_range}, got {fetched:?}"
+        ));
+    }
+    Ok(())
+}
+
+/// links are random ids: a regenerated link replaces the old one, and
+///  sharing again after unsharing gives a link never handed out before
+fn rotated_links(explorer: &Address, path: &str, link: &str) -> Result<(), String> {
+    let id = link.rsplit('/').next().unwrap_or_default();
+    if id.len() != 32 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("share id {id:?} is not 128 random bits in hex"));
+    }
+
+    let rotated: String = expect_ok(explorer, json!({ "RegenerateShareLink": path }));
+    if rotated == link {
+        return Err("regenerate_share_link kept the old link".to_string());
+    }
+    expect_refused(link, PUBLIC_BYTES, "not_found")?;
+    let fetched = fetch(&rotated)?;
+    if fetched.body != PUBLIC_BYTES {
+        return Err(format!("regenerated link {rotated}: {fetched:?}"));
+    }
+
+    let _: bool = expect_ok(explorer, json!({ "UnshareFile": path }));
+    let reshared: String = expect_ok(explorer, json!({ "ShareFile": [path, "Public", null] }));
+    if reshared == link || reshared == rotated {
+        return Err(format!("sharing again handed out an old link: {reshared}"));
+    }
+    Ok(())
+}
+
+/// the link of a shared directory downloads a zip of it, empty directories
+///  included, under a folder named after it
+fn shared_directory(explorer: &Address) -> Result<(), String> {
+    let dir = format!("{SHARE_DIR}/zipped");
+    for sub in ["", "/nested", "/nested/deeper", "/empty"] {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateDirectory": format!("{dir}{sub}") }),
+        );
+    }
+    for (name, content) in ZIPPED_FILES {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [format!("{dir}/{name}"), content, null] }),
+        );
+    }
+
+    let link: String = expect_ok(explorer, json!({ "ShareDirectory": [dir, "Public", null] }));
+    let fetched = fetch(&link)?;
+    if fetched.status != 200
+        || fetched.content_type.as_deref() != Some("application/zip")
+        || fetched.disposition.as_deref() != Some("attachment; filename=\"zipped.zip\"")
+    {
+        return Err(format!(
+            "shared directory {link}: status {}, Content-Type {:?}, Content-Disposition {:?}",
+            fetched.status, fetched.content_type, fetched.disposition
+        ));
+    }
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(fetched.body))
+        .map_err(|e| format!("shared directory {link} is not a zip archive: {e}"))?;
+    let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+    let empty_dir = format!("zipped/{ZIPPED_EMPTY_DIR}/");
+    if !names.contains(&empty_dir) {
+        return Err(format!("the archive has no {empty_dir}: {names:?}"));
+    }
+    for (name, content) in ZIPPED_FILES {
+        let mut entry = archive
+            .by_name(&format!("zipped/{name}"))
+            .map_err(|e| format!("the archive has no zipped/{name}: {e}; has {names:?}"))?;
+        let mut unzipped = Vec::new();
+        entry
+            .read_to_end(&mut unzipped)
+            .map_err(|e| format!("failed to unzip zipped/{name}: {e}"))?;
+        if unzipped != *content {
+            return Err(format!("zipped/{name} unzipped to {unzipped:?}"));
+        }
+    }
+
+    let _: bool = expect_ok(explorer, json!({ "UnshareFile": dir }));
+    Ok(())
+}
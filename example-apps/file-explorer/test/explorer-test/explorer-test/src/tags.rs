@@ -0,0 +1,121 @@
+//! tags: set_tags normalizes case, whitespace and duplicates, list_by_tag
+//!  finds tagged paths, tags follow renames and moves, and deletes drop them
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const TAGS_DIR: &str = "/file-explorer:sys/home/explorer-tags";
+
+/// a tag no other test uses, so list_by_tag sees only this test's files
+const TAG: &str = "explorer-test-work";
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [TAGS_DIR, true] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": TAGS_DIR }));
+
+    step!("tags are normalized", normalized(explorer))?;
+    step!("tags follow moves", moved(explorer))?;
+    step!("deletes drop tags", deleted(explorer))?;
+    step!(
+        "tags of a missing path",
+        expect_err_code(
+            call::<Vec<String>>(
+                explorer,
+                json!({ "SetTags": [format!("{TAGS_DIR}/missing"), [TAG]] }),
+            ),
+            "not_found",
+        )
+    )?;
+    step!(
+        "a tag too long",
+        expect_err_code(
+            call::<Vec<String>>(explorer, json!({ "SetTags": [TAGS_DIR, ["x".repeat(65)]] })),
+            "invalid_input",
+        )
+    )?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [TAGS_DIR, true] }));
+    Ok(())
+}
+
+/// paths list_by_tag returns for `tag`
+fn tagged(explorer: &Address, tag: &str) -> Result<Vec<String>, TestError> {
+    let entries: Vec<FileInfo> = expect_ok(explorer, json!({ "ListByTag": tag }));
+    Ok(entries.into_iter().map(|entry| entry.path).collect())
+}
+
+fn normalized(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{TAGS_DIR}/report.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, b"report\n", null] }));
+
+    let upper = TAG.to_uppercase();
+    let set: Vec<String> = expect_ok(
+        explorer,
+        json!({ "SetTags": [path, [format!(" {upper} "), TAG, "Urgent", ""]] }),
+    );
+    let expected = vec![TAG.to_string(), "urgent".to_string()];
+    assert_eq_ctx!(expected, set, "tags kept by set_tags")?;
+    let got: Vec<String> = expect_ok(explorer, json!({ "GetTags": path }));
+    assert_eq_ctx!(expected, got, "get_tags")?;
+    assert_eq_ctx!(vec![path.clone()], tagged(explorer, &upper)?, "list_by_tag")?;
+
+    let cleared: Vec<String> = expect_ok(explorer, json!({ "SetTags": [path, []] }));
+    assert_eq_ctx!(0, cleared.len(), "tags after clearing")?;
+    assert_eq_ctx!(
+        0,
+        tagged(explorer, TAG)?.len(),
+        "list_by_tag after clearing"
+    )
+}
+
+/// a renamed file and a file in a moved directory keep their tags
+fn moved(explorer: &Address) -> Result<(), TestError> {
+    let dir = format!("{TAGS_DIR}/before");
+    let inner = format!("{dir}/inner.txt");
+    let file = format!("{TAGS_DIR}/old-name.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [inner, b"inner\n", null] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [file, b"file\n", null] }));
+    let _: Vec<String> = expect_ok(explorer, json!({ "SetTags": [inner, [TAG]] }));
+    let _: Vec<String> = expect_ok(explorer, json!({ "SetTags": [file, [TAG]] }));
+
+    let _: FileInfo = expect_ok(explorer, json!({ "RenameFile": [file, "new-name.txt"] }));
+    let moved_dir = format!("{TAGS_DIR}/after");
+    let _: serde_json::Value = expect_ok(explorer, json!({ "MovePath": [dir, moved_dir] }));
+
+    let expected = vec![
+        format!("{moved_dir}/inner.txt"),
+        format!("{TAGS_DIR}/new-name.txt"),
+    ];
+    assert_eq_ctx!(
+        expected,
+        tagged(explorer, TAG)?,
+        "tagged paths after moving"
+    )?;
+    let old: Vec<String> = expect_ok(explorer, json!({ "GetTags": file }));
+    assert_eq_ctx!(0, old.len(), "tags left at the old path")?;
+
+    let _: Vec<String> = expect_ok(explorer, json!({ "SetTags": [expected[0], []] }));
+    let _: Vec<String> = expect_ok(explorer, json!({ "SetTags": [expected[1], []] }));
+    Ok(())
+}
+
+/// trashing a file drops its tags, and restoring it does not bring them back
+fn deleted(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{TAGS_DIR}/doomed.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, b"doomed\n", null] }));
+    let _: Vec<String> = expect_ok(explorer, json!({ "SetTags": [path, [TAG]] }));
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [path, false] }));
+    assert_eq_ctx!(
+        0,
+        tagged(explorer, TAG)?.len(),
+        "list_by_tag after the delete"
+    )?;
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, b"doomed\n", null] }));
+    let tags: Vec<String> = expect_ok(explorer, json!({ "GetTags": path }));
+    assert_eq_ctx!(0, tags.len(), "tags of a file made where one was deleted")
+}
@@ -0,0 +1,100 @@
+//! get_thumbnail: a PNG scaled to a JPEG keeping its aspect ratio, a fresh
+//!  thumbnail after update_file replaces the image, and errors for a text
+//!  file and a corrupt PNG
+
+use std::io::Cursor;
+
+use hyperware_process_lib::Address;
+use image::{ImageFormat, RgbImage};
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const THUMBNAIL_DIR: &str = "/file-explorer:sys/home/explorer-thumbnails";
+
+const MAX_DIMENSION: u32 = 50;
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(
+        explorer,
+        json!({ "DeleteDirectory": [THUMBNAIL_DIR, true] }),
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": THUMBNAIL_DIR }));
+
+    step!("wide image", wide_then_tall(explorer))?;
+    step!(
+        "text file",
+        not_an_image(explorer, "notes.txt", b"not pixels\n")
+    )?;
+    step!(
+        "corrupt PNG",
+        not_an_image(explorer, "broken.png", b"\x89PNG\r\n\x1a\nnot really")
+    )?;
+
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteDirectory": [THUMBNAIL_DIR, true] }),
+    );
+    Ok(())
+}
+
+/// a PNG of a plain image `width` by `height`
+fn png_of(width: u32, height: u32) -> Result<Vec<u8>, TestError> {
+    let mut out = Cursor::new(Vec::new());
+    RgbImage::from_pixel(width, height, image::Rgb([40, 120, 200]))
+        .write_to(&mut out, ImageFormat::Png)
+        .map_err(|e| TestError::Assertion {
+            error: format!("failed to build the test image: {e}"),
+        })?;
+    Ok(out.into_inner())
+}
+
+/// the format and size of the thumbnail of `path`
+fn thumbnail_of(explorer: &Address, path: &str) -> Result<(ImageFormat, u32, u32), TestError> {
+    let thumbnail: Vec<u8> = expect_ok(explorer, json!({ "GetThumbnail": [path, MAX_DIMENSION] }));
+    let format = image::guess_format(&thumbnail).map_err(|e| TestError::Assertion {
+        error: format!("thumbnail of {path} is not an image: {e}"),
+    })?;
+    let decoded = image::load_from_memory(&thumbnail).map_err(|e| TestError::Assertion {
+        error: format!("thumbnail of {path} does not decode: {e}"),
+    })?;
+    Ok((format, decoded.width(), decoded.height()))
+}
+
+/// 200x100 fits in 50x25; after the file becomes 100x200 the thumbnail is
+///  made again, 25x50, rather than served from the cache
+fn wide_then_tall(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{THUMBNAIL_DIR}/picture.png");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, png_of(200, 100)?, null] }),
+    );
+    assert_eq_ctx!(
+        (ImageFormat::Jpeg, 50, 25),
+        thumbnail_of(explorer, &path)?,
+        "format and size of the wide thumbnail"
+    )?;
+    // twice, so the second comes from the cache
+    assert_eq_ctx!(
+        (ImageFormat::Jpeg, 50, 25),
+        thumbnail_of(explorer, &path)?,
+        "format and size of the cached thumbnail"
+    )?;
+
+    let _: FileInfo = expect_ok(explorer, json!({ "UpdateFile": [path, png_of(100, 200)?] }));
+    assert_eq_ctx!(
+        (ImageFormat::Jpeg, 25, 50),
+        thumbnail_of(explorer, &path)?,
+        "format and size of the thumbnail after update_file"
+    )
+}
+
+fn not_an_image(explorer: &Address, name: &str, content: &[u8]) -> Result<(), TestError> {
+    let path = format!("{THUMBNAIL_DIR}/{name}");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, content, null] }));
+    expect_err_code(
+        call::<Vec<u8>>(explorer, json!({ "GetThumbnail": [path, MAX_DIMENSION] })),
+        "invalid_input",
+    )
+}
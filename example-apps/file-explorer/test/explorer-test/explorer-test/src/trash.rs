@@ -0,0 +1,199 @@
+//! delete without `permanent` moves to the trash: restore puts an entry back,
+//!  recreating its parent and replacing what took its place only with force;
+//!  purge_trash_item and empty_trash delete for good, dropping a share the
+//!  trash kept
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, share_link_of, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const TRASH_TEST_DIR: &str = "/file-explorer:sys/home/explorer-trash";
+
+const TRASHED_BYTES: &[u8] = b"explorer-test: deleted, then restored\n";
+
+const REPLACEMENT_BYTES: &[u8] = b"explorer-test: made after the delete\n";
+
+/// the fields of the explorer's TrashItem this test checks
+#[derive(Debug, serde::Deserialize)]
+struct TrashItem {
+    id: String,
+    original_path: String,
+    is_directory: bool,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(
+        explorer,
+        json!({ "DeleteDirectory": [TRASH_TEST_DIR, true] }),
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": TRASH_TEST_DIR }));
+
+    step!("delete and restore a file", delete_and_restore(explorer))?;
+    step!("restore into a missing parent", restore_parent(explorer))?;
+    step!("restore over a new file", restore_conflict(explorer))?;
+    step!("purge one item", purge(explorer))?;
+    step!("empty the trash", empty(explorer))?;
+    step!("shares of deleted files", shares(explorer))?;
+
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteDirectory": [TRASH_TEST_DIR, true] }),
+    );
+    Ok(())
+}
+
+/// the newest trash item deleted from `path`
+fn trashed(explorer: &Address, path: &str) -> Result<TrashItem, TestError> {
+    let items: Vec<TrashItem> = expect_ok(explorer, json!({ "ListTrash": null }));
+    items
+        .into_iter()
+        .find(|item| item.original_path == path)
+        .ok_or_else(|| TestError::Assertion {
+            error: format!("list_trash has nothing deleted from {path}"),
+        })
+}
+
+fn delete_and_restore(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{TRASH_TEST_DIR}/kept.txt");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, TRASHED_BYTES, null] }),
+    );
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [path, false] }));
+    expect_err_code(
+        call::<Vec<u8>>(explorer, json!({ "ReadFile": path })),
+        "not_found",
+    )?;
+    let item = trashed(explorer, &path)?;
+    assert_eq_ctx!(false, item.is_directory, "trash item is_directory")?;
+
+    let restored: FileInfo = expect_ok(explorer, json!({ "RestoreFromTrash": [item.id, false] }));
+    assert_eq_ctx!(path, restored.path, "restored path")?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(TRASHED_BYTES, content, "restored content")?;
+    expect_err_code(
+        call::<FileInfo>(explorer, json!({ "RestoreFromTrash": [item.id, false] })),
+        "not_found",
+    )
+}
+
+/// a file whose directory was deleted for good comes back with the directory
+fn restore_parent(explorer: &Address) -> Result<(), TestError> {
+    let dir = format!("{TRASH_TEST_DIR}/gone");
+    let path = format!("{dir}/nested.txt");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, TRASHED_BYTES, null] }),
+    );
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [path, false] }));
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [dir, true] }));
+    let item = trashed(explorer, &path)?;
+
+    let _: FileInfo = expect_ok(explorer, json!({ "RestoreFromTrash": [item.id, false] }));
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(TRASHED_BYTES, content, "content restored into a new parent")
+}
+
+/// a file made where the trashed one was is kept unless restore is forced
+fn restore_conflict(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{TRASH_TEST_DIR}/replaced.txt");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, TRASHED_BYTES, null] }),
+    );
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [path, false] }));
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, REPLACEMENT_BYTES, null] }),
+    );
+    let item = trashed(explorer, &path)?;
+
+    expect_err_code(
+        call::<FileInfo>(explorer, json!({ "RestoreFromTrash": [item.id, false] })),
+        "invalid_input",
+    )?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(
+        REPLACEMENT_BYTES,
+        content,
+        "content after a refused restore"
+    )?;
+
+    let _: FileInfo = expect_ok(explorer, json!({ "RestoreFromTrash": [item.id, true] }));
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(TRASHED_BYTES, content, "content after a forced restore")
+}
+
+fn purge(explorer: &Address) -> Result<(), TestError> {
+    let dir = format!("{TRASH_TEST_DIR}/purged");
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [dir, false] }));
+    let item = trashed(explorer, &dir)?;
+    assert_eq_ctx!(true, item.is_directory, "trash item is_directory")?;
+
+    let purged: bool = expect_ok(explorer, json!({ "PurgeTrashItem": item.id }));
+    assert_eq_ctx!(true, purged, "purge_trash_item found the item")?;
+    let purged: bool = expect_ok(explorer, json!({ "PurgeTrashItem": item.id }));
+    assert_eq_ctx!(false, purged, "purge_trash_item of a purged item")?;
+    expect_err_code(
+        call::<FileInfo>(explorer, json!({ "RestoreFromTrash": [item.id, false] })),
+        "not_found",
+    )
+}
+
+fn empty(explorer: &Address) -> Result<(), TestError> {
+    for name in ["one.txt", "two.txt"] {
+        let path = format!("{TRASH_TEST_DIR}/{name}");
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [path, TRASHED_BYTES, null] }),
+        );
+        let _: bool = expect_ok(explorer, json!({ "DeleteFile": [path, false] }));
+    }
+
+    let emptied: u64 = expect_ok(explorer, json!({ "EmptyTrash": null }));
+    if emptied < 2 {
+        return Err(TestError::Assertion {
+            error: format!("empty_trash purged {emptied} items, expected at least 2"),
+        });
+    }
+    let items: Vec<TrashItem> = expect_ok(explorer, json!({ "ListTrash": null }));
+    assert_eq_ctx!(0, items.len(), "items left after empty_trash")
+}
+
+/// a share outlives a delete to the trash, and goes with a purge or a
+///  permanent delete
+fn shares(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{TRASH_TEST_DIR}/shared.txt");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, TRASHED_BYTES, null] }),
+    );
+    let link: String = expect_ok(explorer, json!({ "ShareFile": [path, "Public", null] }));
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [path, false] }));
+    let fetched = share_link_of(explorer, &path);
+    assert_eq_ctx!(
+        Some(link.as_str()),
+        fetched.as_deref(),
+        "share link while trashed"
+    )?;
+    let item = trashed(explorer, &path)?;
+    let _: bool = expect_ok(explorer, json!({ "PurgeTrashItem": item.id }));
+    let fetched = share_link_of(explorer, &path);
+    assert_eq_ctx!(None, fetched, "share link after purge_trash_item")?;
+
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [path, TRASHED_BYTES, null] }),
+    );
+    let _: String = expect_ok(explorer, json!({ "ShareFile": [path, "Public", null] }));
+    let _: bool = expect_ok(explorer, json!({ "DeleteFile": [path, true] }));
+    let fetched = share_link_of(explorer, &path);
+    assert_eq_ctx!(None, fetched, "share link after a permanent delete")
+}
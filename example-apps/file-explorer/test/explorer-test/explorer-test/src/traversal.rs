@@ -0,0 +1,140 @@
+//! paths with a `..` segment, or on another package's drive, are refused by
+//!  every kind of handler before they reach VFS, and by the explorer's
+//!  check_package compiled in directly; a percent-encoded `..` is only a
+//!  name, and a NUL byte is refused
+
+use hyperware_process_lib::Address;
+use serde_json::{json, Value};
+
+use crate::path::check_package;
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const TRAVERSAL_DIR: &str = "/file-explorer:sys/home/explorer-traversal";
+
+/// the package whose drives the explorer may touch
+const PACKAGE: &str = "file-explorer:sys";
+
+/// paths whose `..` climbs out of the home drive, into another package's
+///  drive or above every drive
+const ESCAPES: &[&str] = &[
+    "/file-explorer:sys/home/../../other-package:sys/secrets/key",
+    "/file-explorer:sys/home/explorer-traversal/../../../etc",
+    "/file-explorer:sys/home/..",
+    "/file-explorer:sys/..",
+    "/..",
+];
+
+/// absolute paths, without any `..`, on drives the explorer does not own
+const OTHER_PACKAGES: &[&str] = &[
+    "/other-package:sys/secrets/key",
+    "/other-package:sys",
+    "/file-explorer:sysx/home/key",
+];
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(
+        explorer,
+        json!({ "DeleteDirectory": [TRAVERSAL_DIR, true] }),
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": TRAVERSAL_DIR }));
+    let inside = format!("{TRAVERSAL_DIR}/inside.txt");
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [inside, b"inside\n", null] }),
+    );
+
+    for escape in ESCAPES {
+        step!(
+            format!("escape {escape}"),
+            refused_everywhere(explorer, escape, &inside)
+        )?;
+    }
+    for other in OTHER_PACKAGES {
+        step!(format!("another package {other}"), {
+            assert_eq_ctx!(
+                true,
+                check_package(other, PACKAGE).is_err(),
+                format!("check_package refuses {other}")
+            )?;
+            refused_everywhere(explorer, other, &inside)
+        })?;
+    }
+    step!("the explorer's own drives", {
+        for own in ["/", "/file-explorer:sys", TRAVERSAL_DIR] {
+            assert_eq_ctx!(
+                Ok(()),
+                check_package(own, PACKAGE),
+                format!("check_package on {own}")
+            )?;
+        }
+        Ok::<(), TestError>(())
+    })?;
+    step!(
+        "a '..' that stays inside the drive",
+        refused_everywhere(
+            explorer,
+            &format!("{TRAVERSAL_DIR}/../explorer-traversal/inside.txt"),
+            &inside
+        )
+    )?;
+    step!("percent-encoded dots", percent_encoded(explorer))?;
+    step!(
+        "a NUL byte",
+        refused_everywhere(explorer, &format!("{TRAVERSAL_DIR}/a\0b"), &inside)
+    )?;
+
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteDirectory": [TRAVERSAL_DIR, true] }),
+    );
+    Ok(())
+}
+
+/// read, write, delete, share, move, copy and list all refuse `path`
+fn refused_everywhere(explorer: &Address, path: &str, inside: &str) -> Result<(), TestError> {
+    let requests = [
+        json!({ "ReadFile": path }),
+        json!({ "CreateFile": [path, b"x", null] }),
+        json!({ "UpdateFile": [path, b"x"] }),
+        json!({ "DeleteFile": [path, true] }),
+        json!({ "DeleteDirectory": [path, true] }),
+        json!({ "ShareFile": [path, "Public", null] }),
+        json!({ "MovePath": [inside, path] }),
+        json!({ "MovePath": [path, inside] }),
+        json!({ "CopyFile": [path, format!("{TRAVERSAL_DIR}/copy.txt")] }),
+        json!({ "CopyFile": [inside, path] }),
+        json!({ "ListDirectory": path }),
+    ];
+    for request in requests {
+        expect_err_code(call::<Value>(explorer, request.clone()), "invalid_input").map_err(
+            |e| TestError::Assertion {
+                error: format!("{request}: {e}"),
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// `%2e%2e` is not decoded: it names nothing outside, and can name a file
+///  inside like any other name
+fn percent_encoded(explorer: &Address) -> Result<(), TestError> {
+    let outside = format!("{TRAVERSAL_DIR}/%2e%2e/%2e%2e/%2e%2e/etc");
+    expect_err_code(
+        call::<Vec<u8>>(explorer, json!({ "ReadFile": outside })),
+        "not_found",
+    )?;
+
+    let literal = format!("{TRAVERSAL_DIR}/%2e%2e");
+    let created: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [literal, b"dots\n", null] }),
+    );
+    assert_eq_ctx!(literal, created.path, "path of a file named %2e%2e")?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": literal }));
+    assert_eq_ctx!(
+        b"dots\n".to_vec(),
+        content,
+        "content of a file named %2e%2e"
+    )
+}
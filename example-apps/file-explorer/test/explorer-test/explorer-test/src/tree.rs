@@ -0,0 +1,93 @@
+//! get_tree lists a directory level by level down to a depth, stops at an
+//!  entry limit, and flags what either limit left out; list_directory is the
+//!  same walk two levels deep
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const TREE_DIR: &str = "/file-explorer:sys/home/explorer-tree";
+
+/// the explorer's TreeNode
+#[derive(Debug, serde::Deserialize)]
+struct TreeNode {
+    info: FileInfo,
+    children: Vec<TreeNode>,
+    truncated: bool,
+}
+
+impl TreeNode {
+    fn names(&self) -> Vec<&str> {
+        self.children.iter().map(|c| c.info.name.as_str()).collect()
+    }
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(explorer, json!({ "DeleteDirectory": [TREE_DIR, true] }));
+    for dir in ["", "/a", "/a/b"] {
+        let dir = format!("{TREE_DIR}{dir}");
+        let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": dir }));
+    }
+    for file in ["a/b/c.txt", "a/x.txt", "z.txt"] {
+        let _: FileInfo = expect_ok(
+            explorer,
+            json!({ "CreateFile": [format!("{TREE_DIR}/{file}"), b"tree", null] }),
+        );
+    }
+
+    step!("one level", {
+        let tree = get_tree(explorer, TREE_DIR, 1, 100);
+        assert_eq_ctx!(vec!["a", "z.txt"], tree.names(), "entries one level deep")?;
+        assert_eq_ctx!(true, tree.children[0].children.is_empty(), "a not read")?;
+        assert_eq_ctx!(true, tree.children[0].truncated, "a truncated")?;
+        assert_eq_ctx!(false, tree.children[1].truncated, "z.txt truncated")?;
+        assert_eq_ctx!(true, tree.truncated, "tree truncated")
+    })?;
+    step!("every level", {
+        let tree = get_tree(explorer, TREE_DIR, 3, 100);
+        let a = &tree.children[0];
+        assert_eq_ctx!(vec!["b", "x.txt"], a.names(), "entries of a")?;
+        assert_eq_ctx!(2, a.info.size, "size of a")?;
+        assert_eq_ctx!(vec!["c.txt"], a.children[0].names(), "entries of a/b")?;
+        assert_eq_ctx!(false, tree.truncated, "tree truncated")
+    })?;
+    step!("entry limit", {
+        let tree = get_tree(explorer, TREE_DIR, 3, 2);
+        assert_eq_ctx!(vec!["a", "z.txt"], tree.names(), "the closest entries")?;
+        assert_eq_ctx!(true, tree.children[0].children.is_empty(), "entries of a")?;
+        assert_eq_ctx!(true, tree.children[0].truncated, "a truncated")?;
+        assert_eq_ctx!(true, tree.truncated, "tree truncated")
+    })?;
+    step!("a file", {
+        let path = format!("{TREE_DIR}/z.txt");
+        let tree = get_tree(explorer, &path, 3, 100);
+        assert_eq_ctx!(path, tree.info.path, "path of the file")?;
+        assert_eq_ctx!(0, tree.children.len(), "children of a file")?;
+        assert_eq_ctx!(false, tree.truncated, "file truncated")
+    })?;
+    step!(
+        "no depth",
+        expect_err_code(
+            call::<serde_json::Value>(explorer, json!({ "GetTree": [TREE_DIR, 0, 100] })),
+            "invalid_input",
+        )
+    )?;
+    step!("list_directory two levels deep", {
+        let listed: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": TREE_DIR }));
+        let names: Vec<&str> = listed.iter().map(|f| f.name.as_str()).collect();
+        assert_eq_ctx!(vec!["a", "b", "x.txt", "z.txt"], names, "list_directory")
+    })?;
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": [TREE_DIR, true] }));
+    Ok(())
+}
+
+/// the tree get_tree returns for `path`
+fn get_tree(explorer: &Address, path: &str, max_depth: u32, max_entries: u32) -> TreeNode {
+    expect_ok(
+        explorer,
+        json!({ "GetTree": [path, max_depth, max_entries] }),
+    )
+}
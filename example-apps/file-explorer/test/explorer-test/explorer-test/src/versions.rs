@@ -0,0 +1,100 @@
+//! update_file versioning: nothing is kept while it is off; once on, each
+//!  update keeps the content it replaces, pruned to max_versions, and
+//!  restore_version puts one back
+
+use hyperware_process_lib::Address;
+use serde_json::json;
+
+use crate::{call, expect_ok, FileInfo};
+use tester_lib::{assert_eq_ctx, expect_err_code, step, TestError};
+
+const VERSIONS_TEST_DIR: &str = "/file-explorer:sys/home/explorer-versions";
+
+/// successive contents of the versioned file, each a different length so the
+///  listed sizes tell them apart
+const CONTENTS: &[&[u8]] = &[b"v0\n", b"v1 .\n", b"v2 ..\n", b"v3 ...\n"];
+
+/// the explorer's VersionInfo
+#[derive(Debug, serde::Deserialize)]
+struct VersionInfo {
+    timestamp: u64,
+    size: u64,
+}
+
+pub fn run(explorer: &Address) -> Result<(), TestError> {
+    let _ = call::<bool>(
+        explorer,
+        json!({ "DeleteDirectory": [VERSIONS_TEST_DIR, true] }),
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": VERSIONS_TEST_DIR }));
+
+    step!("versioning off", unversioned(explorer))?;
+    step!("versions pruned", versioned(explorer))?;
+    step!(
+        "max_versions of 0",
+        expect_err_code(
+            call::<u32>(explorer, json!({ "SetMaxVersions": 0 })),
+            "invalid_input",
+        )
+    )?;
+
+    let _: bool = expect_ok(explorer, json!({ "SetVersioningEnabled": false }));
+    let _: bool = expect_ok(
+        explorer,
+        json!({ "DeleteDirectory": [VERSIONS_TEST_DIR, true] }),
+    );
+    Ok(())
+}
+
+fn unversioned(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{VERSIONS_TEST_DIR}/plain.txt");
+    let _: bool = expect_ok(explorer, json!({ "SetVersioningEnabled": false }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, CONTENTS[0], null] }));
+    let _: FileInfo = expect_ok(explorer, json!({ "UpdateFile": [path, CONTENTS[1]] }));
+
+    let versions: Vec<VersionInfo> = expect_ok(explorer, json!({ "ListVersions": path }));
+    assert_eq_ctx!(0, versions.len(), "versions kept with versioning off")
+}
+
+/// three updates with room for two versions keep the two latest contents
+///  replaced; restoring the newest keeps the content it replaces in turn
+fn versioned(explorer: &Address) -> Result<(), TestError> {
+    let path = format!("{VERSIONS_TEST_DIR}/versioned.txt");
+    let _: bool = expect_ok(explorer, json!({ "SetVersioningEnabled": true }));
+    let _: u32 = expect_ok(explorer, json!({ "SetMaxVersions": 2 }));
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateFile": [path, CONTENTS[0], null] }));
+    for content in &CONTENTS[1..] {
+        let _: FileInfo = expect_ok(explorer, json!({ "UpdateFile": [path, content] }));
+    }
+
+    let versions: Vec<VersionInfo> = expect_ok(explorer, json!({ "ListVersions": path }));
+    let sizes: Vec<u64> = versions.iter().map(|version| version.size).collect();
+    assert_eq_ctx!(
+        vec![CONTENTS[2].len() as u64, CONTENTS[1].len() as u64],
+        sizes,
+        "sizes of the kept versions, newest first"
+    )?;
+
+    let newest = versions[0].timestamp;
+    let restored: FileInfo = expect_ok(explorer, json!({ "RestoreVersion": [path, newest] }));
+    assert_eq_ctx!(
+        CONTENTS[2].len() as u64,
+        restored.size,
+        "size after restore_version"
+    )?;
+    let content: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": path }));
+    assert_eq_ctx!(CONTENTS[2], content, "content after restore_version")?;
+
+    let versions: Vec<VersionInfo> = expect_ok(explorer, json!({ "ListVersions": path }));
+    let sizes: Vec<u64> = versions.iter().map(|version| version.size).collect();
+    assert_eq_ctx!(
+        vec![CONTENTS[3].len() as u64, CONTENTS[2].len() as u64],
+        sizes,
+        "versions after the restore"
+    )?;
+
+    expect_err_code(
+        call::<FileInfo>(explorer, json!({ "RestoreVersion": [path, 1u64] })),
+        "not_found",
+    )
+}
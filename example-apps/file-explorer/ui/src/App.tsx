@@ -3,6 +3,7 @@ import HyperwareClientApi from "@hyperware-ai/client-api";
 import "./App.css";
 import FileExplorer from "./components/FileExplorer/FileExplorer";
 import { ThemeProvider } from "./contexts/ThemeContext";
+import useFileExplorerStore from "./store/fileExplorer";
 
 const BASE_URL = import.meta.env.BASE_URL;
 if (window.our) window.our.process = BASE_URL?.replace("/", "");
@@ -25,21 +26,25 @@ function App() {
         uri: WEBSOCKET_URL,
         nodeId: window.our.node,
         processId: window.our.process,
-        onOpen: (_event, _api) => {
+        onOpen: (_event, api) => {
           console.log("Connected to Hyperware");
+          // Subscriptions can only be sent once the socket is open
+          setApi(api);
         },
         onMessage: (json, _api) => {
           console.log('WEBSOCKET MESSAGE', json)
           try {
             const data = JSON.parse(json);
             console.log("WebSocket received message", data);
+            // A change event for a directory FileExplorer subscribed to
+            if (data.type && data.path) {
+              useFileExplorerStore.getState().noteChange();
+            }
           } catch (error) {
             console.error("Error parsing WebSocket message", error);
           }
         },
       });
-
-      setApi(api);
     } else {
       setNodeConnected(false);
     }
@@ -57,7 +62,7 @@ function App() {
             </h4>
           </div>
         )}
-        {nodeConnected && <FileExplorer />}
+        {nodeConnected && <FileExplorer api={api} />}
       </div>
     </ThemeProvider>
   );
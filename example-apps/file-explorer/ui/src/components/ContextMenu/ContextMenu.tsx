@@ -92,6 +92,16 @@ const ContextMenu: React.FC<ContextMenuProps> = ({ position, file, onClose, onSh
     }
   };
 
+  // The copy shows up through the directory's change events
+  const handleDuplicate = async () => {
+    try {
+      await FileExplorer.duplicate_file(file.path);
+      onClose();
+    } catch (err) {
+      console.error('Failed to duplicate:', err);
+    }
+  };
+
 
   return (
     <div
@@ -105,6 +115,9 @@ const ContextMenu: React.FC<ContextMenuProps> = ({ position, file, onClose, onSh
       <button onClick={() => { /* TODO */ onClose(); }}>
         ✂️ Cut
       </button>
+      <button onClick={handleDuplicate}>
+        🗐 Duplicate
+      </button>
       <button onClick={() => { /* TODO */ onClose(); }}>
         📄 Rename
       </button>
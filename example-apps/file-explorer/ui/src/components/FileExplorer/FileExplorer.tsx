@@ -1,20 +1,29 @@
 import React, { useEffect, useState } from 'react';
+import HyperwareClientApi from '@hyperware-ai/client-api';
 import useFileExplorerStore from '../../store/fileExplorer';
 import { FileExplorer as FileExplorerAPI } from '../../lib/api';
+import { shareUrl } from '../../lib/shareUrl';
 import FileList from './FileList';
 import Breadcrumb from './Breadcrumb';
 import Toolbar from './Toolbar';
 import UploadZone from '../Upload/UploadZone';
 import './FileExplorer.css';
 
-const FileExplorer: React.FC = () => {
+interface FileExplorerProps {
+  // Open WebSocket to the explorer, once connected
+  api?: HyperwareClientApi;
+}
+
+const FileExplorer: React.FC<FileExplorerProps> = ({ api }) => {
   const {
     currentPath,
     files,
     selectedFiles,
     loading,
     error,
+    changeCount,
     setCurrentPath,
+    addSharedLink,
     setFiles,
     setLoading,
     setError,
@@ -94,7 +103,20 @@ const FileExplorer: React.FC = () => {
       }
     };
     
+    // Shares outlive a reload of the page, and of the node; mark them again
+    const loadShares = async () => {
+      try {
+        const shares = await FileExplorerAPI.list_shares();
+        for (const share of shares) {
+          addSharedLink(share.path, shareUrl(share.link));
+        }
+      } catch (err) {
+        console.error('Failed to load shares:', err);
+      }
+    };
+
     initializeDirectory();
+    loadShares();
   }, []);
 
   // Load directory whenever path changes
@@ -104,6 +126,23 @@ const FileExplorer: React.FC = () => {
     }
   }, [currentPath]);
 
+  // Watch the open directory so changes made elsewhere, such as in another
+  // tab, show up here
+  useEffect(() => {
+    if (!api || !currentPath) return;
+    api.send({ data: { type: 'subscribe_directory', path: currentPath } });
+    return () => {
+      api.send({ data: { type: 'unsubscribe_directory', path: currentPath } });
+    };
+  }, [api, currentPath]);
+
+  // Reload when the explorer reports a change under the open directory
+  useEffect(() => {
+    if (changeCount > 0 && currentPath) {
+      loadDirectory(currentPath);
+    }
+  }, [changeCount]);
+
   const handleNavigate = (path: string) => {
     clearSelection();
     setCurrentPath(path);
@@ -137,7 +176,7 @@ const FileExplorer: React.FC = () => {
 
     try {
       // Create an empty file
-      await FileExplorerAPI.create_file(newPath, []);
+      await FileExplorerAPI.create_file(newPath, [], null);
       await loadDirectory(currentPath);
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to create file');
@@ -147,19 +186,24 @@ const FileExplorer: React.FC = () => {
   const handleDelete = async () => {
     if (selectedFiles.length === 0) return;
     
-    if (!confirm(`Delete ${selectedFiles.length} item(s)?`)) return;
+    if (!confirm(`Move ${selectedFiles.length} item(s) to the trash?`)) return;
 
     try {
-      for (const path of selectedFiles) {
-        const file = files.find(f => f.path === path);
-        if (file?.is_directory) {
-          await FileExplorerAPI.delete_directory(path);
-        } else {
-          await FileExplorerAPI.delete_file(path);
-        }
-      }
+      // One request for the whole selection; items that fail are reported
+      // without stopping the rest
+      const result = await FileExplorerAPI.batch_operation(
+        FileExplorerAPI.BatchOp.Delete,
+        selectedFiles,
+        null
+      );
       clearSelection();
       await loadDirectory(currentPath);
+      if (result.failed.length > 0) {
+        const failures = result.failed
+          .map(failure => `${failure.path}: ${failure.error.message}`)
+          .join('\n');
+        setError(`Failed to delete ${result.failed.length} item(s):\n${failures}`);
+      }
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to delete items');
     }
@@ -5,6 +5,10 @@ import ContextMenu from '../ContextMenu/ContextMenu';
 import ShareDialog from '../ShareDialog/ShareDialog';
 import './FileItem.css';
 
+// Images the backend can make thumbnails of, and the size asked for
+const THUMBNAIL_EXTENSIONS = ['png', 'jpg', 'jpeg', 'gif'];
+const THUMBNAIL_SIZE = 96;
+
 interface FileItemProps {
   file: FileExplorer.FileInfo & { children?: FileExplorer.FileInfo[] };
   viewMode: 'list' | 'grid';
@@ -25,6 +29,7 @@ const FileItem: React.FC<FileItemProps> = ({ file, viewMode, onNavigate, depth =
   const isShared = !file.is_directory && isFileShared(file.path);
 
   const [isExpanded, setIsExpanded] = useState(false);
+  const [thumbnailUrl, setThumbnailUrl] = useState<string | null>(null);
   const [childrenLoaded, setChildrenLoaded] = useState(false);
   const [loadedChildren, setLoadedChildren] = useState<(FileExplorer.FileInfo & { children?: FileExplorer.FileInfo[] })[]>([]);
 
@@ -170,6 +175,29 @@ const FileItem: React.FC<FileItemProps> = ({ file, viewMode, onNavigate, depth =
     touchStartPos.current = null;
   };
 
+  // Show images by their thumbnail in the grid; a file the backend cannot
+  // make one of keeps its icon
+  useEffect(() => {
+    const ext = file.name.split('.').pop()?.toLowerCase() ?? '';
+    if (viewMode !== 'grid' || file.is_directory || !THUMBNAIL_EXTENSIONS.includes(ext)) {
+      return;
+    }
+    let url: string | null = null;
+    let cancelled = false;
+    FileExplorer.get_thumbnail(file.path, THUMBNAIL_SIZE)
+      .then((bytes) => {
+        if (cancelled) return;
+        url = URL.createObjectURL(new Blob([new Uint8Array(bytes)], { type: 'image/jpeg' }));
+        setThumbnailUrl(url);
+      })
+      .catch((err) => console.debug(`No thumbnail for ${file.path}:`, err));
+    return () => {
+      cancelled = true;
+      if (url) URL.revokeObjectURL(url);
+      setThumbnailUrl(null);
+    };
+  }, [file.path, file.size, file.is_directory, viewMode]);
+
   // Clean up timer on unmount
   useEffect(() => {
     return () => {
@@ -180,13 +208,13 @@ const FileItem: React.FC<FileItemProps> = ({ file, viewMode, onNavigate, depth =
   }, []);
 
   const handleDelete = async () => {
-    if (!confirm(`Delete ${file.name}?`)) return;
+    if (!confirm(`Move ${file.name} to the trash?`)) return;
 
     try {
       if (file.is_directory) {
-        await FileExplorer.delete_directory(file.path);
+        await FileExplorer.delete_directory(file.path, false);
       } else {
-        await FileExplorer.delete_file(file.path);
+        await FileExplorer.delete_file(file.path, false);
       }
       // Call the parent's onDelete callback to refresh the list
       if (onDelete) {
@@ -247,7 +275,11 @@ const FileItem: React.FC<FileItemProps> = ({ file, viewMode, onNavigate, depth =
           className={`file-icon ${file.is_directory && viewMode === 'list' ? 'clickable-folder' : ''}`}
           onClick={file.is_directory && viewMode === 'list' ? handleExpandToggle : undefined}
         >
-          {getFileIcon()}
+          {thumbnailUrl ? (
+            <img className="file-thumbnail" src={thumbnailUrl} alt={file.name} />
+          ) : (
+            getFileIcon()
+          )}
         </span>
         <span className="file-name">{file.name}</span>
         {isShared && (
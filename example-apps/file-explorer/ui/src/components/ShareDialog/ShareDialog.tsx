@@ -1,5 +1,6 @@
 import React, { useState, useEffect, useRef } from 'react';
 import { FileExplorer } from '../../lib/api';
+import { shareUrl } from '../../lib/shareUrl';
 import useFileExplorerStore from '../../store/fileExplorer';
 import QRCode from 'qrcode';
 import './ShareDialog.css';
@@ -70,20 +71,8 @@ const ShareDialog: React.FC<ShareDialogProps> = ({ file, onClose }) => {
   const handleShare = async () => {
     setLoading(true);
     try {
-      const link = await FileExplorer.share_file(file.path, authScheme);
-
-      // Remove first element of origin (e.g., http://foo.bar.com -> http://bar.com)
-      let origin = window.location.origin;
-      const urlParts = new URL(origin);
-      const hostParts = urlParts.hostname.split('.');
-      if (hostParts.length > 2) {
-        // Remove the first subdomain
-        hostParts.shift();
-        urlParts.hostname = hostParts.join('.');
-        origin = urlParts.toString().replace(/\/$/, ''); // Remove trailing slash
-      }
-
-      const fullLink = `${origin}${link}`;
+      const link = await FileExplorer.share_file(file.path, authScheme, null);
+      const fullLink = shareUrl(link);
       setShareLink(fullLink);
       addSharedLink(file.path, fullLink);
 
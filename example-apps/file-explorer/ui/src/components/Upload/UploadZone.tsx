@@ -154,7 +154,7 @@ const UploadZone: React.FC<UploadZoneProps> = ({ currentPath, onUploadComplete,
       // Simulate upload progress
       updateUploadProgress(fileId, 50);
       
-      await FileExplorer.upload_file(uploadPath, fileName, contentArray);
+      await FileExplorer.upload_file(uploadPath, fileName, contentArray, null);
       
       updateUploadProgress(fileId, 100);
       onUploadComplete();
@@ -0,0 +1,15 @@
+// Turn a share link from the backend into a full URL. Shares are served from
+// the node's main origin, not the app's secure subdomain, so the first
+// subdomain is dropped (e.g., http://foo.bar.com -> http://bar.com)
+export const shareUrl = (link: string): string => {
+  let origin = window.location.origin;
+  const urlParts = new URL(origin);
+  const hostParts = urlParts.hostname.split('.');
+  if (hostParts.length > 2) {
+    // Remove the first subdomain
+    hostParts.shift();
+    urlParts.hostname = hostParts.join('.');
+    origin = urlParts.toString().replace(/\/$/, ''); // Remove trailing slash
+  }
+  return `${origin}${link}`;
+};
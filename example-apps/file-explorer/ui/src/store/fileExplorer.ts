@@ -10,6 +10,8 @@ interface FileExplorerStore {
   sharedLinks: Map<string, string>;
   loading: boolean;
   error: string | null;
+  // Bumped for each change event the explorer pushes over the WebSocket
+  changeCount: number;
   
   // Actions
   setCurrentPath: (path: string) => void;
@@ -26,6 +28,7 @@ interface FileExplorerStore {
   isFileShared: (path: string) => boolean;
   setLoading: (loading: boolean) => void;
   setError: (error: string | null) => void;
+  noteChange: () => void;
 }
 
 const useFileExplorerStore = create<FileExplorerStore>((set, get) => ({
@@ -37,6 +40,7 @@ const useFileExplorerStore = create<FileExplorerStore>((set, get) => ({
   sharedLinks: new Map(),
   loading: false,
   error: null,
+  changeCount: 0,
 
   setCurrentPath: (path) => set({ currentPath: path }),
   
@@ -105,6 +109,8 @@ const useFileExplorerStore = create<FileExplorerStore>((set, get) => ({
   setLoading: (loading) => set({ loading }),
   
   setError: (error) => set({ error }),
+
+  noteChange: () => set((state) => ({ changeCount: state.changeCount + 1 })),
 }));
 
 export default useFileExplorerStore;
\ No newline at end of file
@@ -0,0 +1,26 @@
+use caller_utils::sign::SignError;
+
+/// attempts made before a transient failure is reported
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// wait before the first retry; doubled for each one after
+pub const RETRY_BACKOFF_MS: u64 = 250;
+
+/// the answer to "this blob claims to be from node X: is it?"
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AssertionResult {
+    pub asserted_node: String,
+    /// the signature was made by the id app on `asserted_node`
+    pub verified: bool,
+    /// the node name is well-formed and known to the network; when false,
+    ///  `verified` is false too
+    pub resolved: bool,
+    /// total time spent, retries included
+    pub latency_ms: u64,
+    pub attempts: u32,
+}
+
+/// failures worth another try: the net or the sign process may just be slow
+pub fn is_transient(e: &SignError) -> bool {
+    matches!(e, SignError::NetTimeout | SignError::UnexpectedNetResponse)
+}
@@ -0,0 +1,264 @@
+use canonical_json::to_canonical;
+use serde_json::{json, Value};
+
+use crate::challenge::push_field;
+use crate::error::IdError;
+
+/// domain tag for signed attestations
+pub const ATTESTATION_TAG: &[u8] = b"hyperware-id-attestation-v1";
+
+pub const ATTESTATION_VERSION: u32 = 1;
+
+const MAX_LABEL_BYTES: usize = 128;
+const MAX_PAYLOAD_BYTES: usize = 16 * 1024;
+
+/// longest lifetime an attestation may be issued with: one year
+pub const MAX_ATTESTATION_TTL_SECONDS: u64 = 365 * 24 * 60 * 60;
+
+/// a statement about `payload` made by `node` for one domain and purpose
+///
+/// what is signed is the canonical JSON of
+///  `{v, domain, purpose, node, issued_at, expires_at, payload}` (times in
+///  ms); `payload` is kept here as canonical JSON text
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Attestation {
+    pub v: u32,
+    pub domain: String,
+    pub purpose: String,
+    pub node: String,
+    pub issued_at_ms: u64,
+    /// None means the attestation does not expire
+    pub expires_at_ms: Option<u64>,
+    pub payload: String,
+    pub signature: Vec<u8>,
+}
+
+impl Attestation {
+    /// an unsigned attestation; `payload` is any JSON text
+    pub fn new(
+        domain: String,
+        purpose: String,
+        node: String,
+        payload: &str,
+        ttl_seconds: Option<u64>,
+        now_ms: u64,
+    ) -> Result<Self, IdError> {
+        check_label("domain", &domain)?;
+        check_label("purpose", &purpose)?;
+        if payload.len() > MAX_PAYLOAD_BYTES {
+            return Err(IdError::InvalidInput(format!(
+                "payload is longer than {MAX_PAYLOAD_BYTES} bytes"
+            )));
+        }
+        let expires_at_ms = match ttl_seconds {
+            Some(ttl) if ttl == 0 || ttl > MAX_ATTESTATION_TTL_SECONDS => {
+                return Err(IdError::InvalidInput(format!(
+                    "ttl_seconds must be between 1 and {MAX_ATTESTATION_TTL_SECONDS}"
+                )));
+            }
+            Some(ttl) => Some(now_ms + ttl * 1000),
+            None => None,
+        };
+
+        Ok(Attestation {
+            v: ATTESTATION_VERSION,
+            domain,
+            purpose,
+            node,
+            issued_at_ms: now_ms,
+            expires_at_ms,
+            payload: to_canonical(&parse_payload(payload)?)?,
+            signature: Vec::new(),
+        })
+    }
+}
+
+/// bytes signed for an attestation: [tag, canonical JSON], each length-prefixed
+pub fn attestation_statement(attestation: &Attestation) -> Result<Vec<u8>, IdError> {
+    if attestation.v != ATTESTATION_VERSION {
+        return Err(IdError::UnsupportedAttestationVersion(attestation.v));
+    }
+    let canonical = to_canonical(&json!({
+        "v": attestation.v,
+        "domain": attestation.domain,
+        "purpose": attestation.purpose,
+        "node": attestation.node,
+        "issued_at": attestation.issued_at_ms,
+        "expires_at": attestation.expires_at_ms,
+        "payload": parse_payload(&attestation.payload)?,
+    }))?;
+
+    let mut bytes = Vec::with_capacity(ATTESTATION_TAG.len() + canonical.len() + 8);
+    push_field(&mut bytes, ATTESTATION_TAG);
+    push_field(&mut bytes, canonical.as_bytes());
+    Ok(bytes)
+}
+
+/// domain and purpose must match exactly, byte for byte, and the attestation
+///  must not have expired; done once the signature is known to be good
+pub fn check_attestation(
+    attestation: &Attestation,
+    expected_domain: &str,
+    expected_purpose: &str,
+    now_ms: u64,
+) -> Result<(), IdError> {
+    if attestation.domain != expected_domain {
+        return Err(IdError::DomainMismatch);
+    }
+    if attestation.purpose != expected_purpose {
+        return Err(IdError::PurposeMismatch);
+    }
+    if attestation
+        .expires_at_ms
+        .is_some_and(|expires| now_ms >= expires)
+    {
+        return Err(IdError::AttestationExpired);
+    }
+    Ok(())
+}
+
+/// 1 to 128 printable ASCII characters, no spaces
+fn check_label(name: &str, label: &str) -> Result<(), IdError> {
+    if label.is_empty()
+        || label.len() > MAX_LABEL_BYTES
+        || !label.bytes().all(|c| c.is_ascii_graphic())
+    {
+        return Err(IdError::InvalidInput(format!(
+            "{name} must be 1 to {MAX_LABEL_BYTES} printable ASCII characters"
+        )));
+    }
+    Ok(())
+}
+
+fn parse_payload(payload: &str) -> Result<Value, IdError> {
+    serde_json::from_str(payload)
+        .map_err(|e| IdError::InvalidInput(format!("invalid JSON payload: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW_MS: u64 = 1000;
+
+    fn fixture() -> Attestation {
+        Attestation::new(
+            "example.com".to_string(),
+            "login".to_string(),
+            "alice.os".to_string(),
+            r#"{ "b": [true, null], "a": 1 }"#,
+            Some(60),
+            NOW_MS,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn payload_is_kept_as_canonical_json() {
+        let attestation = fixture();
+        assert_eq!(attestation.payload, r#"{"a":1,"b":[true,null]}"#);
+        assert_eq!(attestation.expires_at_ms, Some(61_000));
+    }
+
+    #[test]
+    fn statement_bytes_are_fixed() {
+        let canonical = concat!(
+            r#"{"domain":"example.com","expires_at":61000,"issued_at":1000,"#,
+            r#""node":"alice.os","payload":{"a":1,"b":[true,null]},"purpose":"login","v":1}"#
+        );
+        let expected = [
+            &27u32.to_be_bytes()[..],
+            b"hyperware-id-attestation-v1",
+            &136u32.to_be_bytes()[..],
+            canonical.as_bytes(),
+        ]
+        .concat();
+        assert_eq!(attestation_statement(&fixture()), Ok(expected));
+    }
+
+    #[test]
+    fn statement_covers_every_field_but_the_signature() {
+        let statement = attestation_statement(&fixture()).unwrap();
+        let mut signed = fixture();
+        signed.signature = vec![1, 2, 3];
+        assert_eq!(attestation_statement(&signed), Ok(statement.clone()));
+
+        let mut other = fixture();
+        other.purpose = "logout".to_string();
+        assert_ne!(attestation_statement(&other), Ok(statement));
+    }
+
+    #[test]
+    fn unknown_versions_are_refused() {
+        let mut attestation = fixture();
+        attestation.v = 2;
+        assert_eq!(
+            attestation_statement(&attestation),
+            Err(IdError::UnsupportedAttestationVersion(2))
+        );
+    }
+
+    #[test]
+    fn domain_and_purpose_must_match_exactly() {
+        let attestation = fixture();
+        assert_eq!(
+            check_attestation(&attestation, "example.com", "login", NOW_MS),
+            Ok(())
+        );
+        assert_eq!(
+            check_attestation(&attestation, "Example.com", "login", NOW_MS),
+            Err(IdError::DomainMismatch)
+        );
+        assert_eq!(
+            check_attestation(&attestation, "example.com", "login ", NOW_MS),
+            Err(IdError::PurposeMismatch)
+        );
+    }
+
+    #[test]
+    fn expiry_is_checked_to_the_millisecond() {
+        let attestation = fixture();
+        assert_eq!(
+            check_attestation(&attestation, "example.com", "login", 60_999),
+            Ok(())
+        );
+        assert_eq!(
+            check_attestation(&attestation, "example.com", "login", 61_000),
+            Err(IdError::AttestationExpired)
+        );
+
+        let mut lasting = fixture();
+        lasting.expires_at_ms = None;
+        assert_eq!(
+            check_attestation(&lasting, "example.com", "login", u64::MAX),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn labels_and_ttls_are_validated() {
+        let new = |domain: &str, ttl_seconds| {
+            Attestation::new(
+                domain.to_string(),
+                "login".to_string(),
+                "alice.os".to_string(),
+                "{}",
+                ttl_seconds,
+                NOW_MS,
+            )
+        };
+        assert!(matches!(new("", None), Err(IdError::InvalidInput(_))));
+        assert!(matches!(
+            new("has space", None),
+            Err(IdError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            new("example.com", Some(0)),
+            Err(IdError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            new("example.com", Some(MAX_ATTESTATION_TTL_SECONDS + 1)),
+            Err(IdError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+use sha2::{Digest, Sha256};
+
+use crate::error::IdError;
+
+/// most entries kept; the oldest are dropped first
+const MAX_AUDIT_ENTRIES: usize = 5000;
+
+/// most entries returned by one get_audit call
+pub const MAX_AUDIT_PAGE: u32 = 500;
+
+/// one operation requested through the id app
+///
+/// only a hash of the message or token is kept, never its contents
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IdAuditEntry {
+    pub timestamp_ms: u64,
+    /// handler name, e.g. `sign` or `verify_token`
+    pub operation: String,
+    /// `http`, `local:<process>`, or `remote:<node>`
+    pub caller: String,
+    pub payload_sha256: String,
+    /// `ok`, `valid`, `invalid`, or an error code
+    pub outcome: String,
+}
+
+/// one page of entries, newest first
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IdAuditPage {
+    pub entries: Vec<IdAuditEntry>,
+    /// entries matching the filter, across all pages
+    pub total: u64,
+}
+
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+pub struct IdAuditLog {
+    entries: VecDeque<IdAuditEntry>,
+}
+
+impl IdAuditLog {
+    pub fn push(&mut self, entry: IdAuditEntry) {
+        if self.entries.len() >= MAX_AUDIT_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn page(&self, offset: u64, limit: u32, operation: Option<&str>) -> IdAuditPage {
+        let matching = || {
+            self.entries
+                .iter()
+                .rev()
+                .filter(move |e| operation.map_or(true, |op| e.operation == op))
+        };
+        IdAuditPage {
+            entries: matching()
+                .skip(offset as usize)
+                .take(limit.min(MAX_AUDIT_PAGE) as usize)
+                .cloned()
+                .collect(),
+            total: matching().count() as u64,
+        }
+    }
+
+    /// every entry as CSV, oldest first
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("timestamp_ms,operation,caller,payload_sha256,outcome\n");
+        for e in &self.entries {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                e.timestamp_ms,
+                csv_field(&e.operation),
+                csv_field(&e.caller),
+                e.payload_sha256,
+                csv_field(&e.outcome),
+            ));
+        }
+        csv
+    }
+}
+
+/// outcome string for a finished operation
+pub fn outcome<T>(result: &Result<T, IdError>) -> String {
+    match result {
+        Ok(_) => "ok".to_string(),
+        // only the code, never the detail
+        Err(e) => e.code().to_string(),
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// quote fields that could break the row; callers and outcomes may contain
+///  commas or quotes
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use crate::token::TokenClaims;
+
+/// how long a verified token is reused unless configured otherwise
+const DEFAULT_TTL_SECONDS: u64 = 60;
+
+/// negative results are kept for this fraction of the TTL
+const NEGATIVE_TTL_DIVISOR: u64 = 4;
+
+const DEFAULT_MAX_ENTRIES: u32 = 1024;
+
+/// upper bound on the configurable size
+pub const MAX_CACHE_ENTRIES: u32 = 10_000;
+
+/// tokens this close to expiry are always verified afresh
+const DEFAULT_EXPIRY_WINDOW_SECONDS: u64 = 30;
+
+/// counters since the process started or the cache was last configured
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: u64,
+}
+
+#[derive(Debug)]
+struct CachedToken {
+    claims: TokenClaims,
+    signature_ok: bool,
+    cached_until_ms: u64,
+    /// recency counter for least-recently-used eviction
+    last_used: u64,
+}
+
+/// recent signature checks for verify_token, by token hash
+///
+/// only the signature outcome is reused: audience, expiry, and revocation are
+///  checked on every call. the settings persist with IdState; the results
+///  and counters do not
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TokenCache {
+    enabled: bool,
+    ttl_seconds: u64,
+    max_entries: u32,
+    #[serde(default = "default_expiry_window")]
+    expiry_window_seconds: u64,
+    #[serde(skip)]
+    entries: HashMap<String, CachedToken>,
+    #[serde(skip)]
+    uses: u64,
+    #[serde(skip)]
+    stats: CacheStats,
+}
+
+fn default_expiry_window() -> u64 {
+    DEFAULT_EXPIRY_WINDOW_SECONDS
+}
+
+impl Default for TokenCache {
+    fn default() -> Self {
+        TokenCache {
+            enabled: true,
+            ttl_seconds: DEFAULT_TTL_SECONDS,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            expiry_window_seconds: DEFAULT_EXPIRY_WINDOW_SECONDS,
+            entries: HashMap::new(),
+            uses: 0,
+            stats: CacheStats::default(),
+        }
+    }
+}
+
+impl TokenCache {
+    /// change the settings; this drops cached results and resets the counters
+    pub fn configure(&mut self, enabled: bool, ttl_seconds: u64, max_entries: u32) {
+        self.enabled = enabled;
+        self.ttl_seconds = ttl_seconds;
+        self.max_entries = max_entries.min(MAX_CACHE_ENTRIES);
+        self.entries.clear();
+        self.stats = CacheStats::default();
+    }
+
+    pub fn set_expiry_window(&mut self, seconds: u64) {
+        self.expiry_window_seconds = seconds;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len() as u64,
+            ..self.stats.clone()
+        }
+    }
+
+    /// the claims and signature outcome for a token hash, if still fresh
+    pub fn get(&mut self, key: &str, now_ms: u64) -> Option<(TokenClaims, bool)> {
+        if !self.enabled {
+            return None;
+        }
+        let window_ms = self.expiry_window_seconds.saturating_mul(1000);
+        let usable = self.entries.get(key).map(|cached| {
+            now_ms < cached.cached_until_ms
+                && now_ms.saturating_add(window_ms) < cached.claims.expires_at_ms
+        });
+        match usable {
+            Some(true) => {}
+            Some(false) => {
+                self.entries.remove(key);
+                self.stats.misses += 1;
+                return None;
+            }
+            None => {
+                self.stats.misses += 1;
+                return None;
+            }
+        }
+
+        self.stats.hits += 1;
+        self.uses += 1;
+        let cached = self.entries.get_mut(key)?;
+        cached.last_used = self.uses;
+        Some((cached.claims.clone(), cached.signature_ok))
+    }
+
+    pub fn insert(&mut self, key: String, claims: &TokenClaims, signature_ok: bool, now_ms: u64) {
+        if !self.enabled || self.max_entries == 0 {
+            return;
+        }
+        let window_ms = self.expiry_window_seconds.saturating_mul(1000);
+        if now_ms.saturating_add(window_ms) >= claims.expires_at_ms {
+            return;
+        }
+
+        let mut ttl_ms = self.ttl_seconds.saturating_mul(1000);
+        if !signature_ok {
+            ttl_ms /= NEGATIVE_TTL_DIVISOR;
+        }
+
+        self.entries.retain(|_, c| now_ms < c.cached_until_ms);
+        while self.entries.len() >= self.max_entries as usize {
+            let Some(lru) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, c)| c.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.entries.remove(&lru);
+            self.stats.evictions += 1;
+        }
+
+        self.uses += 1;
+        self.entries.insert(
+            key,
+            CachedToken {
+                claims: claims.clone(),
+                signature_ok,
+                cached_until_ms: now_ms.saturating_add(ttl_ms),
+                last_used: self.uses,
+            },
+        );
+    }
+
+    /// forget every cached result for `token_id`
+    pub fn invalidate(&mut self, token_id: &str) {
+        self.entries.retain(|_, c| c.claims.token_id != token_id);
+    }
+}
@@ -0,0 +1,43 @@
+/// tokens this node has issued, by state
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TokenCounts {
+    pub active: u64,
+    pub expired: u64,
+    pub revoked: u64,
+}
+
+/// everything the id app can say about our node, for the UI
+///
+/// fields that need the sign process or net are None when those could not be
+///  reached; `errors` says why
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IdentityCard {
+    pub node_name: String,
+    pub public_key_hex: Option<String>,
+    pub key_algorithm: Option<String>,
+    pub tokens: TokenCounts,
+    pub app_version: String,
+    pub errors: Vec<String>,
+}
+
+/// the subset of IdentityCard that is safe to show anyone
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PublicIdentityCard {
+    pub node_name: String,
+    pub public_key_hex: Option<String>,
+    pub key_algorithm: Option<String>,
+    pub app_version: String,
+    pub errors: Vec<String>,
+}
+
+impl From<IdentityCard> for PublicIdentityCard {
+    fn from(card: IdentityCard) -> Self {
+        PublicIdentityCard {
+            node_name: card.node_name,
+            public_key_hex: card.public_key_hex,
+            key_algorithm: card.key_algorithm,
+            app_version: card.app_version,
+            errors: card.errors,
+        }
+    }
+}
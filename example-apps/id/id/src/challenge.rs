@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use crate::error::IdError;
+
+/// domain tag for signed challenge statements
+pub const CHALLENGE_TAG: &[u8] = b"hyperware-id-challenge-v1";
+
+/// every domain tag of ours starts with this; no caller-supplied message may
+///  begin with such a tag, or its signature would pass for one of our
+///  statements
+pub const RESERVED_TAG_PREFIX: &[u8] = b"hyperware-id-";
+
+/// longest lifetime a challenge may be created with
+pub const MAX_CHALLENGE_TTL_SECONDS: u64 = 600;
+
+/// expired or consumed challenges are remembered this long past expiry so
+///  late responses get a precise error instead of "unknown"
+const CHALLENGE_GRACE_MS: u64 = 10 * 60 * 1000;
+
+/// most challenges held at once
+const MAX_CHALLENGES: usize = 10_000;
+
+/// a login challenge handed to the party that must prove its identity
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Challenge {
+    pub challenge_id: String,
+    /// who the proof is for, e.g. `https://example.com`
+    pub audience: String,
+    /// random 128-bit hex nonce
+    pub nonce: String,
+    pub issued_at_ms: u64,
+    pub expires_at_ms: u64,
+}
+
+/// a challenge signed by `node`'s id process
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SignedChallenge {
+    pub challenge: Challenge,
+    pub node: String,
+    pub signature: Vec<u8>,
+}
+
+/// the result of a successful verify_challenge_response
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VerifiedIdentity {
+    pub node: String,
+    pub audience: String,
+    pub verified_at_ms: u64,
+}
+
+#[derive(Debug)]
+struct StoredChallenge {
+    challenge: Challenge,
+    consumed: bool,
+}
+
+/// challenges we created, until well after they expire
+#[derive(Default, Debug)]
+pub struct Challenges {
+    challenges: HashMap<String, StoredChallenge>,
+}
+
+impl Challenges {
+    pub fn create(
+        &mut self,
+        audience: String,
+        ttl_seconds: u64,
+        now_ms: u64,
+    ) -> Result<Challenge, IdError> {
+        if audience.is_empty() {
+            return Err(IdError::InvalidInput(
+                "audience must not be empty".to_string(),
+            ));
+        }
+        if ttl_seconds == 0 || ttl_seconds > MAX_CHALLENGE_TTL_SECONDS {
+            return Err(IdError::InvalidInput(format!(
+                "ttl_seconds must be between 1 and {MAX_CHALLENGE_TTL_SECONDS}"
+            )));
+        }
+
+        self.sweep(now_ms);
+        if self.challenges.len() >= MAX_CHALLENGES {
+            return Err(IdError::TooManyChallenges);
+        }
+
+        let challenge = Challenge {
+            challenge_id: random_hex(),
+            audience,
+            nonce: random_hex(),
+            issued_at_ms: now_ms,
+            expires_at_ms: now_ms + ttl_seconds * 1000,
+        };
+        self.challenges.insert(
+            challenge.challenge_id.clone(),
+            StoredChallenge {
+                challenge: challenge.clone(),
+                consumed: false,
+            },
+        );
+        Ok(challenge)
+    }
+
+    /// a challenge that can still be answered
+    pub fn open(&self, challenge_id: &str, now_ms: u64) -> Result<Challenge, IdError> {
+        let stored = self
+            .challenges
+            .get(challenge_id)
+            .ok_or_else(|| IdError::NotFound("unknown challenge".to_string()))?;
+        check_usable(stored, now_ms)?;
+        Ok(stored.challenge.clone())
+    }
+
+    /// check that `challenge` is exactly one we issued and is still usable,
+    ///  without consuming it
+    pub fn check(&self, challenge: &Challenge, now_ms: u64) -> Result<(), IdError> {
+        let stored = self
+            .challenges
+            .get(&challenge.challenge_id)
+            .ok_or_else(|| IdError::NotFound("unknown challenge".to_string()))?;
+        if stored.challenge != *challenge {
+            return Err(IdError::ChallengeMismatch);
+        }
+        check_usable(stored, now_ms)
+    }
+
+    /// mark a challenge used; a second successful response is rejected as reused
+    pub fn consume(&mut self, challenge_id: &str) {
+        if let Some(stored) = self.challenges.get_mut(challenge_id) {
+            stored.consumed = true;
+        }
+    }
+
+    fn sweep(&mut self, now_ms: u64) {
+        self.challenges.retain(|_, stored| {
+            now_ms
+                < stored
+                    .challenge
+                    .expires_at_ms
+                    .saturating_add(CHALLENGE_GRACE_MS)
+        });
+    }
+}
+
+fn check_usable(stored: &StoredChallenge, now_ms: u64) -> Result<(), IdError> {
+    if stored.consumed {
+        return Err(IdError::ChallengeReused);
+    }
+    if now_ms >= stored.challenge.expires_at_ms {
+        return Err(IdError::ChallengeExpired);
+    }
+    Ok(())
+}
+
+/// bytes signed for a challenge: [tag, audience, nonce, node, issued_at_ms (u64 BE)],
+///  each length-prefixed (u32 big-endian)
+pub fn challenge_statement(challenge: &Challenge, node: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    push_field(&mut bytes, CHALLENGE_TAG);
+    push_field(&mut bytes, challenge.audience.as_bytes());
+    push_field(&mut bytes, challenge.nonce.as_bytes());
+    push_field(&mut bytes, node.as_bytes());
+    push_field(&mut bytes, &challenge.issued_at_ms.to_be_bytes());
+    bytes
+}
+
+pub fn push_field(bytes: &mut Vec<u8>, field: &[u8]) {
+    bytes.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(field);
+}
+
+/// the fields push_field wrote, or None if `bytes` is not exactly a run of
+///  length-prefixed fields
+pub fn split_fields(mut bytes: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut fields = Vec::new();
+    while !bytes.is_empty() {
+        let (len, rest) = bytes.split_first_chunk::<4>()?;
+        let len = u32::from_be_bytes(*len) as usize;
+        if rest.len() < len {
+            return None;
+        }
+        let (field, rest) = rest.split_at(len);
+        fields.push(field);
+        bytes = rest;
+    }
+    Some(fields)
+}
+
+/// the tag of one of our statements, if `message` starts with a field that
+///  looks like one
+pub fn reserved_tag(message: &[u8]) -> Option<&[u8]> {
+    let (len, rest) = message.split_first_chunk::<4>()?;
+    let tag = rest.get(..u32::from_be_bytes(*len) as usize)?;
+    tag.starts_with(RESERVED_TAG_PREFIX).then_some(tag)
+}
+
+pub fn random_hex() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUDIENCE: &str = "https://example.com";
+
+    fn issue(challenges: &mut Challenges, now_ms: u64) -> Challenge {
+        challenges.create(AUDIENCE.to_string(), 60, now_ms).unwrap()
+    }
+
+    #[test]
+    fn an_answered_challenge_is_reused_not_expired() {
+        let mut challenges = Challenges::default();
+        let challenge = issue(&mut challenges, 0);
+        assert_eq!(challenges.check(&challenge, 1000), Ok(()));
+        assert_eq!(
+            challenges.open(&challenge.challenge_id, 1000),
+            Ok(challenge.clone())
+        );
+
+        challenges.consume(&challenge.challenge_id);
+        assert_eq!(
+            challenges.check(&challenge, 1000),
+            Err(IdError::ChallengeReused)
+        );
+        // reuse wins over expiry, so a replay is reported as one
+        assert_eq!(
+            challenges.check(&challenge, 60_000),
+            Err(IdError::ChallengeReused)
+        );
+    }
+
+    #[test]
+    fn an_unanswered_challenge_expires_at_its_deadline() {
+        let mut challenges = Challenges::default();
+        let challenge = issue(&mut challenges, 0);
+        assert_eq!(challenges.check(&challenge, 59_999), Ok(()));
+        assert_eq!(
+            challenges.check(&challenge, 60_000),
+            Err(IdError::ChallengeExpired)
+        );
+        assert_eq!(
+            challenges.open(&challenge.challenge_id, 60_000),
+            Err(IdError::ChallengeExpired)
+        );
+    }
+
+    #[test]
+    fn challenges_are_forgotten_after_the_grace_period() {
+        let mut challenges = Challenges::default();
+        let challenge = issue(&mut challenges, 0);
+        // creating another sweeps the first once its grace period is over
+        issue(&mut challenges, 60_000 + CHALLENGE_GRACE_MS);
+        assert_eq!(
+            challenges.check(&challenge, 60_000 + CHALLENGE_GRACE_MS),
+            Err(IdError::NotFound("unknown challenge".to_string()))
+        );
+    }
+
+    #[test]
+    fn an_altered_challenge_is_a_mismatch() {
+        let mut challenges = Challenges::default();
+        let mut challenge = issue(&mut challenges, 0);
+        challenge.audience = "https://evil.example".to_string();
+        assert_eq!(
+            challenges.check(&challenge, 1000),
+            Err(IdError::ChallengeMismatch)
+        );
+    }
+
+    #[test]
+    fn create_checks_audience_and_ttl() {
+        let mut challenges = Challenges::default();
+        for (audience, ttl_seconds) in [
+            ("", 60),
+            (AUDIENCE, 0),
+            (AUDIENCE, MAX_CHALLENGE_TTL_SECONDS + 1),
+        ] {
+            assert!(matches!(
+                challenges.create(audience.to_string(), ttl_seconds, 0),
+                Err(IdError::InvalidInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn split_fields_undoes_push_field() {
+        let challenge = Challenges::default()
+            .create(AUDIENCE.to_string(), 60, 5)
+            .unwrap();
+        let statement = challenge_statement(&challenge, "alice.os");
+        let fields = split_fields(&statement).unwrap();
+        assert_eq!(fields[0], CHALLENGE_TAG);
+        assert_eq!(fields[1], AUDIENCE.as_bytes());
+        assert_eq!(fields[3], b"alice.os");
+        assert_eq!(fields[4], 5u64.to_be_bytes());
+        assert_eq!(reserved_tag(&statement), Some(CHALLENGE_TAG));
+
+        // a length that runs past the end is not a field
+        assert_eq!(split_fields(&statement[..statement.len() - 1]), None);
+        assert_eq!(reserved_tag(b"hyperware-id-token-v1"), None);
+    }
+}
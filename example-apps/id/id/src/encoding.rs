@@ -0,0 +1,156 @@
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD};
+use base64::engine::DecodePaddingMode;
+use base64::{DecodeError, Engine};
+
+use crate::error::IdError;
+
+/// standard alphabet, with or without trailing `=` padding
+const BASE64_ANY_PADDING: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// decode `input` as `base64` (standard alphabet, padding optional) or `hex`
+///  (either case); errors say where decoding failed
+pub fn decode(input: &str, encoding: &str) -> Result<Vec<u8>, IdError> {
+    match encoding {
+        "base64" => BASE64_ANY_PADDING.decode(input).map_err(|e| {
+            IdError::InvalidInput(match e {
+                DecodeError::InvalidByte(offset, _) | DecodeError::InvalidLastSymbol(offset, _) => {
+                    format!("invalid base64 at offset {offset}")
+                }
+                DecodeError::InvalidLength(_) => "invalid base64 length".to_string(),
+                DecodeError::InvalidPadding => "invalid base64 padding".to_string(),
+            })
+        }),
+        "hex" => hex::decode(input).map_err(|e| {
+            IdError::InvalidInput(match e {
+                hex::FromHexError::InvalidHexCharacter { index, .. } => {
+                    format!("invalid hex at offset {index}")
+                }
+                hex::FromHexError::OddLength => "odd hex length".to_string(),
+                hex::FromHexError::InvalidStringLength => "invalid hex length".to_string(),
+            })
+        }),
+        other => Err(IdError::InvalidInput(format!(
+            "unknown encoding {other:?}, expected \"base64\" or \"hex\""
+        ))),
+    }
+}
+
+/// signatures are always returned as padded standard base64
+pub fn encode_signature(signature: &[u8]) -> String {
+    STANDARD.encode(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_input_decodes_to_nothing() {
+        assert_eq!(decode("", "base64"), Ok(Vec::new()));
+        assert_eq!(decode("", "hex"), Ok(Vec::new()));
+        assert_eq!(encode_signature(&[]), "");
+    }
+
+    #[test]
+    fn base64_padding_is_optional() {
+        for (padded, unpadded, bytes) in [
+            ("YQ==", "YQ", &b"a"[..]),
+            ("YWI=", "YWI", &b"ab"[..]),
+            ("YWJj", "YWJj", &b"abc"[..]),
+        ] {
+            assert_eq!(decode(padded, "base64").as_deref(), Ok(bytes), "{padded}");
+            assert_eq!(
+                decode(unpadded, "base64").as_deref(),
+                Ok(bytes),
+                "{unpadded}"
+            );
+        }
+    }
+
+    #[test]
+    fn base64_padding_may_be_partial_but_not_excess() {
+        assert_eq!(decode("YQ=", "base64"), Ok(b"a".to_vec()));
+        assert_eq!(
+            decode("YWJj=", "base64"),
+            Err(IdError::InvalidInput(
+                "invalid base64 at offset 4".to_string()
+            ))
+        );
+        assert_eq!(
+            decode("YQ===", "base64"),
+            Err(IdError::InvalidInput(
+                "invalid base64 at offset 2".to_string()
+            ))
+        );
+        assert_eq!(
+            decode("Y", "base64"),
+            Err(IdError::InvalidInput("invalid base64 length".to_string()))
+        );
+    }
+
+    #[test]
+    fn invalid_base64_names_the_offset() {
+        assert_eq!(
+            decode("YW*j", "base64"),
+            Err(IdError::InvalidInput(
+                "invalid base64 at offset 2".to_string()
+            ))
+        );
+        // url-safe characters are not in the standard alphabet
+        assert_eq!(
+            decode("_-8", "base64"),
+            Err(IdError::InvalidInput(
+                "invalid base64 at offset 0".to_string()
+            ))
+        );
+        // trailing bits that would be dropped are refused, not ignored
+        assert_eq!(
+            decode("YR==", "base64"),
+            Err(IdError::InvalidInput(
+                "invalid base64 at offset 1".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn hex_accepts_either_case() {
+        let bytes = vec![0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(decode("deadbeef", "hex"), Ok(bytes.clone()));
+        assert_eq!(decode("DEADBEEF", "hex"), Ok(bytes.clone()));
+        assert_eq!(decode("DeAdBeEf", "hex"), Ok(bytes));
+    }
+
+    #[test]
+    fn invalid_hex_names_the_offset() {
+        assert_eq!(
+            decode("dead-eef", "hex"),
+            Err(IdError::InvalidInput("invalid hex at offset 4".to_string()))
+        );
+        assert_eq!(
+            decode("abc", "hex"),
+            Err(IdError::InvalidInput("odd hex length".to_string()))
+        );
+    }
+
+    #[test]
+    fn unknown_encodings_are_rejected() {
+        assert_eq!(
+            decode("YQ==", "base32"),
+            Err(IdError::InvalidInput(
+                "unknown encoding \"base32\", expected \"base64\" or \"hex\"".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn signatures_encode_as_padded_standard_base64() {
+        assert_eq!(encode_signature(&[0xfb, 0xff]), "+/8=");
+        assert_eq!(
+            decode(&encode_signature(&[0xfb, 0xff]), "base64"),
+            Ok(vec![0xfb, 0xff])
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::fmt;
+
+use app_error::AppError;
+use caller_utils::sign::SignError;
+use canonical_json::CanonicalError;
+
+/// errors returned by the id app's helpers; handlers hand them back as
+///  AppError
+///
+/// callers branch on code(), which is stable across releases; the detail
+///  strings carried by some variants are for humans only
+#[derive(Clone, Debug, PartialEq)]
+pub enum IdError {
+    /// the sign process answered with an error of its own
+    Sign(SignError),
+    /// the sign process could not be reached at all
+    SignUnreachable(String),
+    /// our signing delegate's id app answered with an error of its own
+    Delegate(AppError),
+    /// our signing delegate's id app could not be reached; we never fall back
+    ///  to signing locally, since the result would carry the wrong key
+    DelegateUnreachable {
+        node: String,
+        detail: String,
+    },
+    InvalidInput(String),
+    NotFound(String),
+    /// the caller is not allowed to do this; the detail says which rule
+    ///  refused it
+    NotPermitted(String),
+    /// the caller made too many requests; retry after this many milliseconds
+    RateLimited(u64),
+    /// the node name is not shaped like one, e.g. `alice.os`
+    InvalidNodeName(String),
+    /// the node name is well-formed but unknown to the network
+    UnknownNode(String),
+    /// a token, challenge response, proof, or attestation was not signed by
+    ///  the node it names
+    InvalidSignature,
+    TokenMalformed,
+    TokenNotYetValid,
+    TokenExpired,
+    TokenRevoked,
+    /// a good token issued for some other audience
+    AudienceMismatch,
+    TooManyChallenges,
+    /// the response carries a challenge that differs from the one we issued
+    ChallengeMismatch,
+    ChallengeExpired,
+    ChallengeReused,
+    TooManyProofs,
+    ProofMalformed,
+    ProofNotYetValid,
+    ProofExpired,
+    ProofReused,
+    UnsupportedAttestationVersion(u32),
+    DomainMismatch,
+    PurposeMismatch,
+    AttestationExpired,
+    /// the sidecar next to a file is not an envelope
+    SidecarMalformed(String),
+    /// a sidecar could not be read or written
+    Vfs(String),
+    Internal(String),
+}
+
+impl IdError {
+    pub fn code(&self) -> &str {
+        match self {
+            IdError::Sign(e) => sign_code(e),
+            IdError::SignUnreachable(_) => "sign_unreachable",
+            IdError::Delegate(e) => &e.code,
+            IdError::DelegateUnreachable { .. } => "delegate_unreachable",
+            IdError::InvalidInput(_) => app_error::INVALID_INPUT,
+            IdError::NotFound(_) => app_error::NOT_FOUND,
+            IdError::NotPermitted(_) => app_error::NOT_PERMITTED,
+            IdError::RateLimited(_) => app_error::RATE_LIMITED,
+            IdError::InvalidNodeName(_) => "invalid_node_name",
+            IdError::UnknownNode(_) => "unknown_node",
+            IdError::InvalidSignature => "invalid_signature",
+            IdError::TokenMalformed => "token_malformed",
+            IdError::TokenNotYetValid => "token_not_yet_valid",
+            IdError::TokenExpired => "token_expired",
+            IdError::TokenRevoked => "token_revoked",
+            IdError::AudienceMismatch => "audience_mismatch",
+            IdError::TooManyChallenges => "too_many_challenges",
+            IdError::ChallengeMismatch => "challenge_mismatch",
+            IdError::ChallengeExpired => "challenge_expired",
+            IdError::ChallengeReused => "challenge_reused",
+            IdError::TooManyProofs => "too_many_proofs",
+            IdError::ProofMalformed => "proof_malformed",
+            IdError::ProofNotYetValid => "proof_not_yet_valid",
+            IdError::ProofExpired => "proof_expired",
+            IdError::ProofReused => "proof_reused",
+            IdError::UnsupportedAttestationVersion(_) => "unsupported_attestation_version",
+            IdError::DomainMismatch => "domain_mismatch",
+            IdError::PurposeMismatch => "purpose_mismatch",
+            IdError::AttestationExpired => "attestation_expired",
+            IdError::SidecarMalformed(_) => "sidecar_malformed",
+            IdError::Vfs(_) => "vfs",
+            IdError::Internal(_) => app_error::INTERNAL,
+        }
+    }
+
+    /// what a person reading the error needs beyond the code, if anything
+    fn detail(&self) -> Option<String> {
+        match self {
+            IdError::Sign(e) => sign_detail(e),
+            IdError::Delegate(e) => Some(e.message.clone()),
+            IdError::DelegateUnreachable { node, detail } => Some(format!("{node}: {detail}")),
+            IdError::SignUnreachable(detail)
+            | IdError::InvalidInput(detail)
+            | IdError::NotFound(detail)
+            | IdError::NotPermitted(detail)
+            | IdError::SidecarMalformed(detail)
+            | IdError::Vfs(detail)
+            | IdError::Internal(detail) => Some(detail.clone()),
+            IdError::InvalidNodeName(node) | IdError::UnknownNode(node) => Some(node.clone()),
+            IdError::RateLimited(retry_after_ms) => {
+                Some(format!("retry after {retry_after_ms} ms"))
+            }
+            IdError::UnsupportedAttestationVersion(version) => Some(version.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// a network timeout takes the shared `timeout` code so it comes back
+///  retryable; the rest keep the sign process's own codes
+fn sign_code(e: &SignError) -> &'static str {
+    match e {
+        SignError::NetTimeout => app_error::TIMEOUT,
+        SignError::NoSignatureBlob => "no_signature_blob",
+        SignError::UnexpectedNetResponse => "unexpected_net_response",
+        SignError::InvalidNodeName => "invalid_node_name",
+        SignError::NotPermitted | SignError::RawSigningNotPermitted => app_error::NOT_PERMITTED,
+        SignError::RateLimited(_) => app_error::RATE_LIMITED,
+        SignError::EnvelopeFormat => "envelope_format",
+        SignError::UnsupportedEnvelopeVersion(_) => "unsupported_envelope_version",
+        SignError::InvalidInput(_) => app_error::INVALID_INPUT,
+        SignError::Vfs(_) => "vfs",
+        SignError::Internal(_) => app_error::INTERNAL,
+    }
+}
+
+fn sign_detail(e: &SignError) -> Option<String> {
+    match e {
+        SignError::NetTimeout => Some("sign did not hear back from the network".to_string()),
+        SignError::RawSigningNotPermitted => Some("raw signing not permitted".to_string()),
+        SignError::RateLimited(retry_after_ms) => Some(format!("retry after {retry_after_ms} ms")),
+        SignError::UnsupportedEnvelopeVersion(version) => Some(version.to_string()),
+        SignError::InvalidInput(detail) | SignError::Vfs(detail) | SignError::Internal(detail) => {
+            Some(detail.clone())
+        }
+        _ => None,
+    }
+}
+
+/// `code` or `code: detail`, as written to event summaries
+impl fmt::Display for IdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.detail() {
+            Some(detail) => write!(f, "{}: {detail}", self.code()),
+            None => write!(f, "{}", self.code()),
+        }
+    }
+}
+
+impl std::error::Error for IdError {}
+
+/// canonical JSON fails only on input that cannot be signed as JSON
+impl From<CanonicalError> for IdError {
+    fn from(e: CanonicalError) -> Self {
+        IdError::InvalidInput(e.to_string())
+    }
+}
+
+/// the shared shape every handler answers with; an error from our signing
+///  delegate is passed on as it came, and a bare code is spelled out as the
+///  message
+impl From<IdError> for AppError {
+    fn from(e: IdError) -> Self {
+        match e {
+            IdError::Delegate(e) => e,
+            e => {
+                let code = e.code();
+                let message = e.detail().unwrap_or_else(|| code.replace('_', " "));
+                AppError::new(code, message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_carry_over_to_app_errors() {
+        let e = AppError::from(IdError::TokenExpired);
+        assert_eq!(e.code, "token_expired");
+        assert_eq!(e.message, "token expired");
+        assert!(!e.retryable);
+
+        let e = AppError::from(IdError::RateLimited(1500));
+        assert_eq!(e.code, app_error::RATE_LIMITED);
+        assert_eq!(e.message, "retry after 1500 ms");
+        assert!(e.retryable);
+    }
+
+    #[test]
+    fn a_sign_timeout_is_retryable() {
+        let e = IdError::Sign(SignError::NetTimeout);
+        assert_eq!(e.code(), app_error::TIMEOUT);
+        assert!(AppError::from(e).retryable);
+
+        let e = AppError::from(IdError::Sign(SignError::RawSigningNotPermitted));
+        assert_eq!(e.code, app_error::NOT_PERMITTED);
+        assert_eq!(e.message, "raw signing not permitted");
+    }
+
+    #[test]
+    fn a_delegate_error_passes_through_unchanged() {
+        let remote = AppError::new("token_revoked", "revoked on the delegate");
+        assert_eq!(AppError::from(IdError::Delegate(remote.clone())), remote);
+    }
+
+    #[test]
+    fn display_adds_the_detail_after_the_code() {
+        assert_eq!(IdError::InvalidSignature.to_string(), "invalid_signature");
+        assert_eq!(
+            IdError::DelegateUnreachable {
+                node: "bob.os".to_string(),
+                detail: "timeout".to_string(),
+            }
+            .to_string(),
+            "delegate_unreachable: bob.os: timeout"
+        );
+        assert_eq!(
+            IdError::UnknownNode("nobody.os".to_string()).to_string(),
+            "unknown_node: nobody.os"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::{HashMap, HashSet};
+
+use hyperware_process_lib::http::server::{send_ws_push, WsMessageType};
+use hyperware_process_lib::LazyLoadBlob;
+
+/// a client that has sent nothing (not even a ping) for this long is dropped
+const CLIENT_IDLE_MS: u64 = 5 * 60 * 1000;
+
+/// most clients tracked at once
+const MAX_CLIENTS: usize = 64;
+
+/// security-relevant happenings pushed to WebSocket clients
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthEventKind {
+    TokenIssued,
+    TokenVerified,
+    TokenRevoked,
+    ChallengeCreated,
+    ChallengeConsumed,
+    RemoteVerification,
+    ProofCreated,
+    ProofVerified,
+    RateLimited,
+}
+
+/// one event as sent to clients
+///
+/// `summary` is redacted: ids and outcomes only, never tokens, messages, or
+///  signatures
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct AuthEvent {
+    pub kind: AuthEventKind,
+    pub timestamp_ms: u64,
+    pub success: bool,
+    pub audience: Option<String>,
+    pub summary: String,
+}
+
+/// what clients send: `{"type": "subscribe", "kinds": [...]}` (all kinds when
+///  `kinds` is omitted) or `{"type": "ping"}` to stay connected
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Subscribe { kinds: Option<Vec<AuthEventKind>> },
+    Ping,
+}
+
+#[derive(Debug)]
+struct Client {
+    /// None means every kind
+    kinds: Option<HashSet<AuthEventKind>>,
+    last_seen_ms: u64,
+}
+
+/// subscribed WebSocket clients, by channel
+#[derive(Default, Debug)]
+pub struct EventHub {
+    clients: HashMap<u32, Client>,
+}
+
+impl EventHub {
+    /// handle a frame from a client; clients are only pushed events once they
+    ///  have subscribed
+    pub fn handle_client_message(
+        &mut self,
+        channel_id: u32,
+        message_type: WsMessageType,
+        bytes: &[u8],
+        now_ms: u64,
+    ) {
+        if message_type == WsMessageType::Close {
+            self.clients.remove(&channel_id);
+            return;
+        }
+        self.prune(now_ms);
+
+        match serde_json::from_slice::<ClientMessage>(bytes) {
+            Ok(ClientMessage::Subscribe { kinds }) => {
+                if self.clients.len() >= MAX_CLIENTS && !self.clients.contains_key(&channel_id) {
+                    return;
+                }
+                self.clients.insert(
+                    channel_id,
+                    Client {
+                        kinds: kinds.map(|kinds| kinds.into_iter().collect()),
+                        last_seen_ms: now_ms,
+                    },
+                );
+            }
+            Ok(ClientMessage::Ping) => {
+                if let Some(client) = self.clients.get_mut(&channel_id) {
+                    client.last_seen_ms = now_ms;
+                }
+            }
+            Err(_) => {}
+        }
+    }
+
+    pub fn emit(&mut self, event: AuthEvent) {
+        self.prune(event.timestamp_ms);
+        if self.clients.is_empty() {
+            return;
+        }
+        let Ok(bytes) = serde_json::to_vec(&event) else {
+            return;
+        };
+
+        for (channel_id, client) in &self.clients {
+            let wanted = client
+                .kinds
+                .as_ref()
+                .map_or(true, |kinds| kinds.contains(&event.kind));
+            if wanted {
+                send_ws_push(
+                    *channel_id,
+                    WsMessageType::Text,
+                    LazyLoadBlob {
+                        mime: Some("application/json".to_string()),
+                        bytes: bytes.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    fn prune(&mut self, now_ms: u64) {
+        self.clients
+            .retain(|_, c| now_ms < c.last_seen_ms.saturating_add(CLIENT_IDLE_MS));
+    }
+}
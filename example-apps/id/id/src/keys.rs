@@ -0,0 +1,109 @@
+use caller_utils::sign::IdentityInfo;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use hyperware_process_lib::{our, Address};
+
+use crate::error::IdError;
+
+/// most of our past keys remembered
+const MAX_KNOWN_KEYS: usize = 16;
+
+/// the key a verification was checked against
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct KeyInfo {
+    pub node: String,
+    pub public_key_hex: String,
+    /// when the sign process last fetched the key from net
+    pub retrieved_at_ms: u64,
+}
+
+impl From<IdentityInfo> for KeyInfo {
+    fn from(identity: IdentityInfo) -> Self {
+        KeyInfo {
+            node: identity.node_name,
+            public_key_hex: identity.public_key_hex,
+            retrieved_at_ms: identity.retrieved_at_ms,
+        }
+    }
+}
+
+/// a signature check, with the key it was checked against when that key is
+///  our own; other nodes' keys are not visible to the id app
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Verification {
+    pub valid: bool,
+    pub key_info: Option<KeyInfo>,
+}
+
+/// one of our node's networking keys, as seen through get_identity
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct KnownKey {
+    pub public_key_hex: String,
+    pub first_seen_ms: u64,
+    pub last_seen_ms: u64,
+}
+
+/// our node's keys, oldest first; the last one is current
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+pub struct KnownKeys {
+    keys: Vec<KnownKey>,
+}
+
+impl KnownKeys {
+    /// note the key we just saw; returns true if it differs from the last one
+    pub fn record(&mut self, public_key_hex: &str, now_ms: u64) -> bool {
+        if let Some(current) = self.keys.last_mut() {
+            if current.public_key_hex == public_key_hex {
+                current.last_seen_ms = now_ms;
+                return false;
+            }
+        }
+        if self.keys.len() >= MAX_KNOWN_KEYS {
+            self.keys.remove(0);
+        }
+        self.keys.push(KnownKey {
+            public_key_hex: public_key_hex.to_string(),
+            first_seen_ms: now_ms,
+            last_seen_ms: now_ms,
+        });
+        self.keys.len() > 1
+    }
+
+    pub fn list(&self) -> Vec<KnownKey> {
+        self.keys.clone()
+    }
+}
+
+/// check a signature our node made through sign() against `public_key_hex`,
+///  without asking net, so signatures from a rotated-out key stay checkable
+///
+/// the signed bytes are [sign-address, id-address, message].concat(), the
+///  layout the sign process and net:distro:sys build together
+pub fn verify_with_key(
+    message: &[u8],
+    signature: &[u8],
+    public_key_hex: &str,
+) -> Result<bool, IdError> {
+    let key_hex = public_key_hex.strip_prefix("0x").unwrap_or(public_key_hex);
+    let key_bytes: [u8; 32] = hex::decode(key_hex)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            IdError::InvalidInput("public_key_hex must be 32 bytes of hex".to_string())
+        })?;
+    let key = VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|_| IdError::InvalidInput("not an ed25519 public key".to_string()))?;
+    let Ok(signature) = Signature::from_slice(signature) else {
+        return Ok(false);
+    };
+
+    let node = our().node.clone();
+    let sign_address = Address::new(&node, ("sign", "sign", "sys"));
+    let id_address = Address::new(&node, our().process.clone());
+    let signed = [
+        sign_address.to_string().as_bytes(),
+        id_address.to_string().as_bytes(),
+        message,
+    ]
+    .concat();
+    Ok(key.verify(&signed, &signature).is_ok())
+}
@@ -1,15 +1,570 @@
-use caller_utils::sign::{sign_local_rpc, verify_local_rpc};
+use std::collections::HashSet;
+
+use app_error::AppError;
+use caller_utils::id::sweep_sessions_local_rpc;
+use caller_utils::sign::{
+    get_identity_local_rpc, sign_file_local_rpc, sign_local_rpc, verify_file_local_rpc,
+    verify_from_local_rpc, verify_local_rpc, IdentityInfo, SignError, SignedEnvelope,
+};
 use hyperprocess_macro::hyperprocess;
-use hyperware_process_lib::logging::{init_logging, Level};
-use hyperware_process_lib::Address;
+use hyperware_app_common::{add_response_header, get_path, send, sleep, source, spawn};
+use hyperware_process_lib::http::server::WsMessageType;
+use hyperware_process_lib::logging::{init_logging, warn, Level};
+use hyperware_process_lib::{our, Address, LazyLoadBlob, ProcessId, Request};
+
+mod assertion;
+mod attestation;
+mod audit;
+mod cache;
+mod card;
+mod challenge;
+mod encoding;
+mod error;
+mod events;
+mod keys;
+mod limit;
+mod png;
+mod policy;
+mod proof;
+mod session;
+mod sidecar;
+mod token;
+
+use assertion::AssertionResult;
+use attestation::Attestation;
+use audit::{IdAuditEntry, IdAuditLog, IdAuditPage};
+use cache::{CacheStats, TokenCache};
+use card::{IdentityCard, PublicIdentityCard, TokenCounts};
+use challenge::{Challenge, Challenges, SignedChallenge, VerifiedIdentity};
+use error::IdError;
+use events::{AuthEvent, AuthEventKind, EventHub};
+use keys::{KeyInfo, KnownKey, KnownKeys, Verification};
+use limit::{LimitClass, RateLimiter, RateLimits};
+use policy::AccessPolicy;
+use proof::{ConsumedProofs, IssuedProofs, ProofBundle, ProofClaims, VerifiedProof};
+use session::{SessionInfo, Sessions};
+use sidecar::SidecarCheck;
+use token::{TokenClaims, VerifiedToken};
 
 #[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
-struct IdState {}
+struct IdState {
+    /// login challenges we created and have not yet forgotten
+    #[serde(skip)]
+    challenges: Challenges,
+    /// ids of tokens that verify_token must reject
+    revoked_tokens: HashSet<String>,
+    /// tokens we issued, kept a while past expiry
+    #[serde(default)]
+    sessions: Sessions,
+    /// WebSocket clients subscribed to auth events
+    #[serde(skip)]
+    events: EventHub,
+    audit: IdAuditLog,
+    /// identity proofs we created, for serving their QR codes
+    #[serde(skip)]
+    issued_proofs: IssuedProofs,
+    /// other nodes' proofs we accepted; each is accepted only once
+    #[serde(default)]
+    consumed_proofs: ConsumedProofs,
+    /// who may sign and which audiences tokens may be issued for
+    #[serde(default)]
+    policy: AccessPolicy,
+    #[serde(default)]
+    rate_limits: RateLimits,
+    /// per-caller buckets for sign, issue_token, and create_challenge;
+    ///  verification is never throttled
+    #[serde(skip)]
+    limiter: RateLimiter,
+    /// node whose id app signs for us in sign() and issue_token(); None signs
+    ///  locally
+    #[serde(default)]
+    signing_delegate: Option<String>,
+    /// recent verify_token signature checks
+    #[serde(default)]
+    verify_cache: TokenCache,
+    /// our node's networking keys over time, so rotations can be explained
+    #[serde(default)]
+    known_keys: KnownKeys,
+}
+
+/// unauthenticated binding (see endpoints); only get_public_identity_card
+///  answers there
+const PUBLIC_PATH: &str = "/public";
+
+/// seconds to wait for a signing delegate before giving up
+const DELEGATE_TIMEOUT_SECS: u64 = 5;
 
 fn make_sign_sys() -> Address {
     Address::new("our", ("sign", "sign", "sys"))
 }
 
+/// every #[http] handler binds on every path, so those not meant for the
+///  unauthenticated binding must refuse requests that arrive on it
+fn require_authenticated_path() -> Result<(), IdError> {
+    match get_path() {
+        Some(path) if path.starts_with(PUBLIC_PATH) => Err(IdError::NotPermitted(
+            "not served on the public path".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// whether `node` is shaped like a Hyperware node name, e.g. `alice.os`:
+///  two or more dot-separated labels of lowercase letters, digits, and hyphens
+fn valid_node_name(node: &str) -> bool {
+    node.len() <= 253
+        && node.split('.').count() >= 2
+        && node.split('.').all(|label| {
+            !label.is_empty()
+                && label
+                    .bytes()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-')
+        })
+}
+
+/// who is calling, for the audit log
+fn caller_label() -> String {
+    if get_path().is_some() {
+        return "http".to_string();
+    }
+    let caller = source();
+    if caller.node == our().node {
+        format!("local:{}", caller.process)
+    } else {
+        format!("remote:{}", caller.node)
+    }
+}
+
+/// audit outcome for a signature check
+fn verify_outcome(result: &Result<bool, IdError>) -> String {
+    match result {
+        Ok(true) => "valid".to_string(),
+        Ok(false) => "invalid".to_string(),
+        Err(_) => audit::outcome(result),
+    }
+}
+
+/// a process id in its canonical `name:package:publisher` form
+fn parse_process_id(process_id: &str) -> Result<String, IdError> {
+    process_id
+        .parse::<ProcessId>()
+        .map(|p| p.to_string())
+        .map_err(|_| IdError::InvalidInput(format!("not a process id: {process_id}")))
+}
+
+/// ask ourselves to sweep sessions every SESSION_SWEEP_INTERVAL_MS; the sweep
+///  runs as a request so it sees, and saves, current state
+async fn sweep_sessions_periodically() {
+    let us = our();
+    loop {
+        if sleep(session::SESSION_SWEEP_INTERVAL_MS).await.is_err() {
+            continue;
+        }
+        if let Err(e) = sweep_sessions_local_rpc(&us).await {
+            warn!("session sweep failed: {e}");
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// sign `message` as our id process through the sign process
+async fn sign_as_id(message: Vec<u8>) -> Result<Vec<u8>, IdError> {
+    match sign_local_rpc(&make_sign_sys(), message).await {
+        Ok(r) => r.map_err(IdError::Sign),
+        Err(e) => Err(IdError::SignUnreachable(e.to_string())),
+    }
+}
+
+/// have the id app on `node` sign `message` through its delegated_sign
+///
+/// an unreachable delegate fails with `delegate_unreachable`; we never fall
+///  back to signing locally, since the result would carry the wrong key
+async fn sign_at_delegate(node: &str, message: Vec<u8>) -> Result<Vec<u8>, IdError> {
+    let body = serde_json::to_vec(&serde_json::json!({ "DelegatedSign": message }))
+        .map_err(|e| IdError::Internal(e.to_string()))?;
+    let request = Request::to(Address::new(node, our().process.clone()))
+        .expects_response(DELEGATE_TIMEOUT_SECS)
+        .body(body);
+    match send::<Result<Vec<u8>, AppError>>(request).await {
+        Ok(result) => result.map_err(IdError::Delegate),
+        Err(e) => Err(IdError::DelegateUnreachable {
+            node: node.to_string(),
+            detail: e.to_string(),
+        }),
+    }
+}
+
+/// check a signature made by the id process on `node`, which may be ours
+async fn verify_as_id(node: String, message: Vec<u8>, signature: Vec<u8>) -> Result<bool, IdError> {
+    match verify_from_local_rpc(&make_sign_sys(), node, message, signature).await {
+        Ok(r) => r.map_err(IdError::Sign),
+        Err(e) => Err(IdError::SignUnreachable(e.to_string())),
+    }
+}
+
+impl IdState {
+    fn record_audit(&mut self, operation: &str, payload_sha256: String, outcome: String) {
+        self.audit.push(IdAuditEntry {
+            timestamp_ms: now_ms(),
+            operation: operation.to_string(),
+            caller: caller_label(),
+            payload_sha256,
+            outcome,
+        });
+    }
+
+    /// HTTP callers need HTTP signing enabled; local callers are checked
+    ///  against the process allow and deny lists
+    fn check_sign_access(&self) -> Result<(), IdError> {
+        if get_path().is_some() {
+            return self.policy.check_http_signing();
+        }
+        self.policy.check_process(&source().process.to_string())
+    }
+
+    /// spend one request from the caller's bucket, announcing it to event
+    ///  subscribers when the bucket is empty
+    fn throttle(&mut self, operation: &str, class: LimitClass) -> Result<(), IdError> {
+        let caller = caller_label();
+        match self
+            .limiter
+            .take(&self.rate_limits, class, &caller, now_ms())
+        {
+            Ok(()) => Ok(()),
+            Err(retry_after_ms) => {
+                self.emit(
+                    AuthEventKind::RateLimited,
+                    false,
+                    None,
+                    format!("{operation} by {caller}"),
+                );
+                Err(IdError::RateLimited(retry_after_ms))
+            }
+        }
+    }
+
+    /// the node whose key sign() and issue_token() use
+    fn signer_node(&self) -> String {
+        self.signing_delegate
+            .clone()
+            .unwrap_or_else(|| our().node.clone())
+    }
+
+    async fn sign_as_signer(&self, message: Vec<u8>) -> Result<Vec<u8>, IdError> {
+        match &self.signing_delegate {
+            Some(node) => sign_at_delegate(node, message).await,
+            None => sign_as_id(message).await,
+        }
+    }
+
+    /// sign as the id app after the access policy and rate limit allow it;
+    ///  an empty message is refused, since a signature over nothing proves nothing
+    async fn sign_checked(
+        &mut self,
+        operation: &str,
+        message: Vec<u8>,
+    ) -> Result<Vec<u8>, IdError> {
+        let payload_sha256 = audit::sha256_hex(&message);
+        let checked = if message.is_empty() {
+            Err(IdError::InvalidInput("message is empty".to_string()))
+        } else {
+            self.check_sign_access()
+                .and_then(|()| self.throttle(operation, LimitClass::Sign))
+        };
+        let result = match checked {
+            Ok(()) => self.sign_as_signer(message).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit(operation, payload_sha256, audit::outcome(&result));
+        result
+    }
+
+    /// sign_checked for a message the caller chose, which may not be one of
+    ///  our own statements
+    async fn sign_raw(&mut self, operation: &str, message: Vec<u8>) -> Result<Vec<u8>, IdError> {
+        if let Err(e) = policy::check_raw_message(&message) {
+            let result = Err(e);
+            self.record_audit(
+                operation,
+                audit::sha256_hex(&message),
+                audit::outcome(&result),
+            );
+            return result;
+        }
+        self.sign_checked(operation, message).await
+    }
+
+    async fn verify_audited(
+        &mut self,
+        operation: &str,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<bool, IdError> {
+        let payload_sha256 = audit::sha256_hex(&message);
+        let result = match verify_local_rpc(&make_sign_sys(), message, signature).await {
+            Ok(r) => r.map_err(IdError::Sign),
+            Err(e) => Err(IdError::SignUnreachable(e.to_string())),
+        };
+        self.record_audit(operation, payload_sha256, verify_outcome(&result));
+        result
+    }
+
+    /// our identity from the sign process, noting the key in known_keys
+    async fn own_identity(&mut self) -> Result<IdentityInfo, IdError> {
+        let identity = match get_identity_local_rpc(&make_sign_sys()).await {
+            Ok(r) => r.map_err(IdError::Sign)?,
+            Err(e) => return Err(IdError::SignUnreachable(e.to_string())),
+        };
+        if self.known_keys.record(&identity.public_key_hex, now_ms()) {
+            warn!(
+                "our networking key changed to {}; signatures made before now need verify_with_key",
+                identity.public_key_hex
+            );
+        }
+        Ok(identity)
+    }
+
+    /// the key behind `node`'s signatures, if it is our node and the sign
+    ///  process can tell us; a failed lookup does not fail the verification
+    async fn key_info_for(&mut self, node: &str) -> Option<KeyInfo> {
+        if node != our().node {
+            return None;
+        }
+        self.own_identity().await.ok().map(KeyInfo::from)
+    }
+
+    /// verify_from on `node`, retrying transient failures with backoff
+    async fn check_assertion(
+        &self,
+        node: String,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<AssertionResult, IdError> {
+        let started_ms = now_ms();
+        let finish = |verified: bool, resolved: bool, attempts: u32| AssertionResult {
+            asserted_node: node.clone(),
+            verified,
+            resolved,
+            latency_ms: now_ms().saturating_sub(started_ms),
+            attempts,
+        };
+        if !valid_node_name(&node) {
+            return Ok(finish(false, false, 0));
+        }
+
+        let mut backoff_ms = assertion::RETRY_BACKOFF_MS;
+        for attempt in 1..=assertion::MAX_ATTEMPTS {
+            let last = attempt == assertion::MAX_ATTEMPTS;
+            let rpc = verify_from_local_rpc(
+                &make_sign_sys(),
+                node.clone(),
+                message.clone(),
+                signature.clone(),
+            )
+            .await;
+            match rpc {
+                Ok(Ok(verified)) => return Ok(finish(verified, true, attempt)),
+                Ok(Err(SignError::InvalidNodeName)) => return Ok(finish(false, false, attempt)),
+                Ok(Err(e)) if last || !assertion::is_transient(&e) => return Err(IdError::Sign(e)),
+                Err(e) if last => return Err(IdError::SignUnreachable(e.to_string())),
+                Ok(Err(_)) | Err(_) => {}
+            }
+            let _ = sleep(backoff_ms).await;
+            backoff_ms *= 2;
+        }
+        unreachable!("the last attempt always returns")
+    }
+
+    fn emit(
+        &mut self,
+        kind: AuthEventKind,
+        success: bool,
+        audience: Option<String>,
+        summary: String,
+    ) {
+        self.events.emit(AuthEvent {
+            kind,
+            timestamp_ms: now_ms(),
+            success,
+            audience,
+            summary,
+        });
+    }
+
+    async fn accept_challenge(
+        &mut self,
+        signed: SignedChallenge,
+    ) -> Result<VerifiedIdentity, IdError> {
+        self.challenges.check(&signed.challenge, now_ms())?;
+
+        let statement = challenge::challenge_statement(&signed.challenge, &signed.node);
+        if !verify_as_id(signed.node.clone(), statement, signed.signature).await? {
+            return Err(IdError::InvalidSignature);
+        }
+
+        // checked again: another response may have been accepted while we
+        //  waited on the sign process
+        let verified_at_ms = now_ms();
+        self.challenges.check(&signed.challenge, verified_at_ms)?;
+        self.challenges.consume(&signed.challenge.challenge_id);
+
+        Ok(VerifiedIdentity {
+            node: signed.node,
+            audience: signed.challenge.audience,
+            verified_at_ms,
+        })
+    }
+
+    async fn mint_token(
+        &mut self,
+        audience: String,
+        ttl_seconds: u64,
+        claims: Vec<(String, String)>,
+    ) -> Result<String, IdError> {
+        self.policy.check_audience(&audience)?;
+        let mut claims =
+            TokenClaims::new(self.signer_node(), audience, ttl_seconds, claims, now_ms())?;
+        if self.signing_delegate.is_some() {
+            claims.delegated_by = Some(our().node.clone());
+        }
+        let payload = token::encode_payload(&claims)?;
+        let signature = self
+            .sign_as_signer(token::token_statement(&payload))
+            .await?;
+
+        self.sessions.insert(SessionInfo::from_claims(&claims));
+        self.emit(
+            AuthEventKind::TokenIssued,
+            true,
+            Some(claims.audience.clone()),
+            format!("token {}", claims.token_id),
+        );
+        Ok(token::format_compact(&payload, &signature))
+    }
+
+    async fn check_token(
+        &mut self,
+        token: &str,
+        expected_audience: &str,
+    ) -> Result<TokenClaims, IdError> {
+        let key = audit::sha256_hex(token.as_bytes());
+        let (claims, signature_ok) = match self.verify_cache.get(&key, now_ms()) {
+            Some(cached) => cached,
+            None => {
+                let (payload, claims, signature) = token::parse_token(token)?;
+                let statement = token::token_statement(&payload);
+                let signature_ok = verify_as_id(claims.node.clone(), statement, signature).await?;
+                self.verify_cache
+                    .insert(key, &claims, signature_ok, now_ms());
+                (claims, signature_ok)
+            }
+        };
+        if !signature_ok {
+            return Err(IdError::InvalidSignature);
+        }
+        token::check_claims(&claims, expected_audience, now_ms())?;
+        if self.revoked_tokens.contains(&claims.token_id) {
+            return Err(IdError::TokenRevoked);
+        }
+        Ok(claims)
+    }
+
+    async fn create_proof(&mut self, ttl_seconds: u64) -> Result<ProofBundle, IdError> {
+        let now = now_ms();
+        let claims = ProofClaims::new(our().node.clone(), ttl_seconds, now)?;
+        let bytes = serde_json::to_vec(&claims).map_err(|e| IdError::Internal(e.to_string()))?;
+        let signature = sign_as_id(proof::proof_statement(&bytes)).await?;
+
+        let bundle = ProofBundle {
+            qr_path: format!("{}{}.png", proof::PROOF_QR_PATH, claims.proof_id),
+            payload: token::format_compact(&bytes, &signature),
+            claims,
+        };
+        self.issued_proofs.insert(bundle.clone(), now)?;
+        Ok(bundle)
+    }
+
+    async fn accept_proof(&mut self, payload: &str) -> Result<VerifiedProof, IdError> {
+        let (bytes, claims, signature) = proof::parse_proof(payload)?;
+        self.consumed_proofs.check(&claims)?;
+        let statement = proof::proof_statement(&bytes);
+        if !verify_as_id(claims.node.clone(), statement, signature).await? {
+            return Err(IdError::InvalidSignature);
+        }
+        let verified_at_ms = now_ms();
+        proof::check_claims(&claims, verified_at_ms)?;
+
+        // consume checks again: the same proof may have been accepted while
+        //  we waited on the sign process
+        self.consumed_proofs.consume(&claims, verified_at_ms)?;
+        Ok(VerifiedProof {
+            node: claims.node,
+            proof_id: claims.proof_id,
+            issued_at_ms: claims.issued_at_ms,
+            verified_at_ms,
+        })
+    }
+
+    async fn sign_file_sidecar(&mut self, path: &str) -> Result<(SignedEnvelope, String), IdError> {
+        self.check_sign_access()?;
+        self.throttle("sign_file_at", LimitClass::Sign)?;
+        let envelope = match sign_file_local_rpc(&make_sign_sys(), path.to_string()).await {
+            Ok(r) => r.map_err(IdError::Sign)?,
+            Err(e) => return Err(IdError::SignUnreachable(e.to_string())),
+        };
+        let json =
+            serde_json::to_vec_pretty(&envelope).map_err(|e| IdError::Internal(e.to_string()))?;
+        let sidecar = sidecar::write_sidecar(path, &json).await?;
+        Ok((envelope, sidecar))
+    }
+
+    async fn identity_card(&self) -> IdentityCard {
+        let mut errors = Vec::new();
+        let identity = match get_identity_local_rpc(&make_sign_sys()).await {
+            Ok(Ok(identity)) => Some(identity),
+            Ok(Err(e)) => {
+                errors.push(format!("sign: {}", IdError::Sign(e)));
+                None
+            }
+            Err(e) => {
+                errors.push(format!("sign unreachable: {e}"));
+                None
+            }
+        };
+
+        let now = now_ms();
+        let mut tokens = TokenCounts {
+            revoked: self.revoked_tokens.len() as u64,
+            ..TokenCounts::default()
+        };
+        for session in self.sessions.iter() {
+            if self.revoked_tokens.contains(&session.token_id) {
+                continue;
+            }
+            if now < session.expires_at_ms {
+                tokens.active += 1;
+            } else {
+                tokens.expired += 1;
+            }
+        }
+
+        IdentityCard {
+            node_name: our().node.clone(),
+            public_key_hex: identity.as_ref().map(|i| i.public_key_hex.clone()),
+            key_algorithm: identity.map(|i| i.key_algorithm),
+            tokens,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            errors,
+        }
+    }
+}
+
 #[hyperprocess(
     name = "id",
     ui = Some(HttpBindingConfig::default()),
@@ -21,32 +576,766 @@ fn make_sign_sys() -> Address {
         Binding::Ws {
             path: "/ws",
             config: WsBindingConfig::default(),
+        },
+        Binding::Http {
+            path: "/proof",
+            config: HttpBindingConfig::default(),
+        },
+        Binding::Http {
+            path: "/public",
+            config: HttpBindingConfig::default().authenticated(false),
         }
     ],
-    save_config = SaveOptions::Never,
+    save_config = SaveOptions::OnDiff,
     wit_world = "id-sys-v0",
 )]
 impl IdState {
     #[init]
     async fn init(&mut self) {
         init_logging(Level::DEBUG, Level::INFO, None, None, None).unwrap();
+        spawn(sweep_sessions_periodically());
+    }
+
+    /// refused with `not_permitted` when the access policy disallows the
+    ///  caller or the message starts with one of our statement tags
+    ///  (`hyperware-id-*`)
+    #[local]
+    #[http]
+    async fn sign(&mut self, message: Vec<u8>) -> Result<Vec<u8>, AppError> {
+        require_authenticated_path()?;
+        self.sign_raw("sign", message).await.map_err(AppError::from)
+    }
+
+    #[http]
+    async fn verify(
+        &mut self,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<Verification, AppError> {
+        require_authenticated_path()?;
+        let valid = self.verify_audited("verify", message, signature).await?;
+        Ok(Verification {
+            valid,
+            key_info: self.key_info_for(&our().node).await,
+        })
     }
 
+    /// sign() for callers that would rather not build byte arrays: the
+    ///  message is `base64` or `hex` text and the signature comes back as base64
     #[http]
-    async fn sign(&mut self, message: Vec<u8>) -> Result<Vec<u8>, String> {
-        let target = make_sign_sys();
-        match sign_local_rpc(&target, message).await {
-            Ok(r) => r,
-            Err(e) => Err(e.to_string()),
+    async fn sign_str(
+        &mut self,
+        message_b64: String,
+        encoding: String,
+    ) -> Result<String, AppError> {
+        require_authenticated_path()?;
+        let message = encoding::decode(&message_b64, &encoding)?;
+        let signature = self.sign_raw("sign_str", message).await?;
+        Ok(encoding::encode_signature(&signature))
+    }
+
+    /// verify() with the message and signature as `base64` or `hex` text
+    #[http]
+    async fn verify_str(
+        &mut self,
+        message_b64: String,
+        signature_b64: String,
+        encoding: String,
+    ) -> Result<Verification, AppError> {
+        require_authenticated_path()?;
+        let message = encoding::decode(&message_b64, &encoding)?;
+        let signature = encoding::decode(&signature_b64, &encoding)?;
+        let valid = self
+            .verify_audited("verify_str", message, signature)
+            .await?;
+        Ok(Verification {
+            valid,
+            key_info: self.key_info_for(&our().node).await,
+        })
+    }
+
+    /// check a signature made through our sign() against an explicitly
+    ///  supplied public key, e.g. one from list_known_keys, without asking net
+    #[local]
+    #[http]
+    async fn verify_with_key(
+        &mut self,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+        public_key_hex: String,
+    ) -> Result<bool, AppError> {
+        require_authenticated_path()?;
+        let result = keys::verify_with_key(&message, &signature, &public_key_hex);
+        self.record_audit(
+            "verify_with_key",
+            audit::sha256_hex(&message),
+            verify_outcome(&result),
+        );
+        result.map_err(AppError::from)
+    }
+
+    /// our node's networking keys as seen so far, oldest first; the last is
+    ///  current
+    #[local]
+    #[http]
+    async fn list_known_keys(&mut self) -> Result<Vec<KnownKey>, AppError> {
+        require_authenticated_path()?;
+        Ok(self.known_keys.list())
+    }
+
+    /// sign `payload` (JSON text) for one domain and purpose, so the signature
+    ///  cannot be replayed as anything else
+    #[local]
+    #[http]
+    async fn sign_attestation(
+        &mut self,
+        domain: String,
+        purpose: String,
+        payload: String,
+        ttl_seconds: Option<u64>,
+    ) -> Result<Attestation, AppError> {
+        require_authenticated_path()?;
+        let mut attestation = Attestation::new(
+            domain,
+            purpose,
+            self.signer_node(),
+            &payload,
+            ttl_seconds,
+            now_ms(),
+        )?;
+        let statement = attestation::attestation_statement(&attestation)?;
+        attestation.signature = self.sign_checked("sign_attestation", statement).await?;
+        Ok(attestation)
+    }
+
+    /// check an attestation's signature, then that it is for exactly
+    ///  `expected_domain` and `expected_purpose` and has not expired
+    ///
+    /// a bad signature comes back with `valid` false; a good one for the wrong
+    ///  context fails with `domain_mismatch` or `purpose_mismatch`, a stale one
+    ///  with `attestation_expired`
+    #[local]
+    #[http]
+    async fn verify_attestation(
+        &mut self,
+        attestation: Attestation,
+        expected_domain: String,
+        expected_purpose: String,
+    ) -> Result<Verification, AppError> {
+        require_authenticated_path()?;
+        let result = match attestation::attestation_statement(&attestation) {
+            Ok(statement) => {
+                match verify_as_id(
+                    attestation.node.clone(),
+                    statement,
+                    attestation.signature.clone(),
+                )
+                .await
+                {
+                    Ok(true) => attestation::check_attestation(
+                        &attestation,
+                        &expected_domain,
+                        &expected_purpose,
+                        now_ms(),
+                    )
+                    .map(|()| true),
+                    other => other,
+                }
+            }
+            Err(e) => Err(e),
+        };
+        self.record_audit(
+            "verify_attestation",
+            audit::sha256_hex(attestation.payload.as_bytes()),
+            verify_outcome(&result),
+        );
+        let valid = result?;
+        Ok(Verification {
+            valid,
+            key_info: self.key_info_for(&attestation.node).await,
+        })
+    }
+
+    /// sign the file at VFS `path`, e.g. in the file explorer's home drive, and
+    ///  write the envelope JSON next to it; returns the sidecar's path
+    ///
+    /// always signed by our own node: a signing delegate cannot read our VFS
+    #[local]
+    #[http]
+    async fn sign_file_at(&mut self, path: String) -> Result<String, AppError> {
+        require_authenticated_path()?;
+        let result = self.sign_file_sidecar(&path).await;
+        let payload_sha256 = match &result {
+            Ok((envelope, _)) => envelope.payload_sha256.clone(),
+            Err(_) => String::new(),
+        };
+        self.record_audit("sign_file_at", payload_sha256, audit::outcome(&result));
+        result.map(|(_, sidecar)| sidecar).map_err(AppError::from)
+    }
+
+    /// re-hash the file at `path` and check it against its sidecar
+    #[local]
+    #[http]
+    async fn verify_file_at(&mut self, path: String) -> Result<SidecarCheck, AppError> {
+        require_authenticated_path()?;
+        let Some(bytes) = sidecar::read_sidecar(&path)? else {
+            return Ok(SidecarCheck::MissingSignature);
+        };
+        let envelope: SignedEnvelope =
+            serde_json::from_slice(&bytes).map_err(|e| IdError::SidecarMalformed(e.to_string()))?;
+        match verify_file_local_rpc(&make_sign_sys(), path, envelope).await {
+            Ok(Ok(true)) => Ok(SidecarCheck::Valid),
+            Ok(Ok(false)) => Ok(SidecarCheck::Modified),
+            Ok(Err(e)) => Err(IdError::Sign(e).into()),
+            Err(e) => Err(IdError::SignUnreachable(e.to_string()).into()),
         }
     }
 
+    /// start a login: the challenge is handed to whoever must prove their node
+    #[local]
+    #[http]
+    async fn create_challenge(
+        &mut self,
+        audience: String,
+        ttl_seconds: u64,
+    ) -> Result<Challenge, AppError> {
+        require_authenticated_path()?;
+        self.throttle("create_challenge", LimitClass::Sign)?;
+        let challenge = self.challenges.create(audience, ttl_seconds, now_ms())?;
+        self.emit(
+            AuthEventKind::ChallengeCreated,
+            true,
+            Some(challenge.audience.clone()),
+            format!("challenge {}", challenge.challenge_id),
+        );
+        Ok(challenge)
+    }
+
+    /// answer one of our challenges as our node
+    #[local]
+    #[http]
+    async fn sign_challenge(&mut self, challenge_id: String) -> Result<SignedChallenge, AppError> {
+        require_authenticated_path()?;
+        let challenge = self.challenges.open(&challenge_id, now_ms())?;
+        let node = our().node.clone();
+        let signature = sign_as_id(challenge::challenge_statement(&challenge, &node)).await?;
+        Ok(SignedChallenge {
+            challenge,
+            node,
+            signature,
+        })
+    }
+
+    /// relying-party side: accept a signed challenge at most once
+    ///
+    /// fails with `not_found`, `challenge_mismatch`, `challenge_expired`,
+    ///  `challenge_reused`, or `invalid_signature`
+    #[local]
     #[http]
-    async fn verify(&mut self, message: Vec<u8>, signature: Vec<u8>) -> Result<bool, String> {
-        let target = make_sign_sys();
-        match verify_local_rpc(&target, message, signature).await {
-            Ok(r) => r,
-            Err(e) => Err(e.to_string()),
+    async fn verify_challenge_response(
+        &mut self,
+        signed: SignedChallenge,
+    ) -> Result<VerifiedIdentity, AppError> {
+        require_authenticated_path()?;
+        let audience = signed.challenge.audience.clone();
+        let summary = format!(
+            "challenge {} answered by {}",
+            signed.challenge.challenge_id, signed.node
+        );
+        let result = self.accept_challenge(signed).await;
+        let summary = match &result {
+            Ok(_) => summary,
+            Err(e) => format!("{summary}: {e}"),
+        };
+        self.emit(
+            AuthEventKind::ChallengeConsumed,
+            result.is_ok(),
+            Some(audience),
+            summary,
+        );
+        result.map_err(AppError::from)
+    }
+
+    /// issue a bearer token for `audience` signed by our node
+    #[local]
+    #[http]
+    async fn issue_token(
+        &mut self,
+        audience: String,
+        ttl_seconds: u64,
+        claims: Vec<(String, String)>,
+    ) -> Result<String, AppError> {
+        require_authenticated_path()?;
+        let result = match self.throttle("issue_token", LimitClass::Tokens) {
+            Ok(()) => self.mint_token(audience, ttl_seconds, claims).await,
+            Err(e) => Err(e),
+        };
+        let payload_sha256 = match &result {
+            Ok(token) => audit::sha256_hex(token.as_bytes()),
+            Err(_) => String::new(),
+        };
+        self.record_audit("issue_token", payload_sha256, audit::outcome(&result));
+        result.map_err(AppError::from)
+    }
+
+    /// check a token's signature, expiry, audience, and our revocation list
+    ///
+    /// fails with `token_malformed`, `invalid_signature`, `audience_mismatch`,
+    ///  `token_not_yet_valid`, `token_expired`, or `token_revoked`
+    #[local]
+    #[http]
+    async fn verify_token(
+        &mut self,
+        token: String,
+        expected_audience: String,
+    ) -> Result<VerifiedToken, AppError> {
+        require_authenticated_path()?;
+        let result = self.check_token(&token, &expected_audience).await;
+        self.record_audit(
+            "verify_token",
+            audit::sha256_hex(token.as_bytes()),
+            audit::outcome(&result),
+        );
+        let summary = match &result {
+            Ok(claims) => format!("token {} from {}", claims.token_id, claims.node),
+            Err(e) => format!("rejected: {e}"),
+        };
+        self.emit(
+            AuthEventKind::TokenVerified,
+            result.is_ok(),
+            Some(expected_audience),
+            summary,
+        );
+        let claims = result?;
+        Ok(VerifiedToken {
+            key_info: self.key_info_for(&claims.node).await,
+            claims,
+        })
+    }
+
+    /// returns false if the token was already revoked
+    #[local]
+    #[http]
+    async fn revoke_token(&mut self, token_id: String) -> Result<bool, AppError> {
+        require_authenticated_path()?;
+        let _ = self.sessions.revoke(&token_id);
+        self.verify_cache.invalidate(&token_id);
+        let newly_revoked = self.revoked_tokens.insert(token_id.clone());
+        if newly_revoked {
+            self.emit(
+                AuthEventKind::TokenRevoked,
+                true,
+                None,
+                format!("token {token_id}"),
+            );
         }
+        Ok(newly_revoked)
+    }
+
+    /// tokens we issued, newest first; expired ones stay listed for a day
+    ///  when `include_expired` is set
+    #[http]
+    async fn list_sessions(&mut self, include_expired: bool) -> Result<Vec<SessionInfo>, AppError> {
+        require_authenticated_path()?;
+        Ok(self.sessions.list(include_expired, now_ms()))
+    }
+
+    /// revoke one of our tokens; verify_token rejects it from the next call
+    ///
+    /// fails with `not_found` for tokens we did not issue; returns false
+    ///  if the session was already revoked
+    #[local]
+    #[http]
+    async fn revoke_session(&mut self, token_id: String) -> Result<bool, AppError> {
+        require_authenticated_path()?;
+        let newly_revoked = self.sessions.revoke(&token_id)?;
+        self.verify_cache.invalidate(&token_id);
+        self.revoked_tokens.insert(token_id.clone());
+        if newly_revoked {
+            self.emit(
+                AuthEventKind::TokenRevoked,
+                true,
+                None,
+                format!("token {token_id}"),
+            );
+        }
+        Ok(newly_revoked)
+    }
+
+    /// revoke every unexpired token we issued; returns how many were revoked
+    #[http]
+    async fn revoke_all_sessions(&mut self) -> Result<u64, AppError> {
+        require_authenticated_path()?;
+        let revoked = self.sessions.revoke_all(now_ms());
+        let count = revoked.len() as u64;
+        for token_id in &revoked {
+            self.verify_cache.invalidate(token_id);
+        }
+        self.revoked_tokens.extend(revoked);
+        if count > 0 {
+            self.emit(
+                AuthEventKind::TokenRevoked,
+                true,
+                None,
+                format!("{count} tokens"),
+            );
+        }
+        Ok(count)
+    }
+
+    /// drop sessions past their grace period; sent by our own sweep loop
+    #[local]
+    async fn sweep_sessions(&mut self) -> Result<u64, AppError> {
+        if source().process != our().process {
+            return Err(
+                IdError::NotPermitted("only our own sweep loop may sweep".to_string()).into(),
+            );
+        }
+        Ok(self.sessions.sweep(&mut self.revoked_tokens, now_ms()) as u64)
+    }
+
+    #[local]
+    #[http]
+    async fn list_revoked(&mut self) -> Result<Vec<String>, AppError> {
+        require_authenticated_path()?;
+        let mut revoked: Vec<String> = self.revoked_tokens.iter().cloned().collect();
+        revoked.sort();
+        Ok(revoked)
+    }
+
+    /// check a signature made through the id app on `node`
+    ///
+    /// a malformed name fails with `invalid_node_name`, a well-formed name the
+    ///  network does not know with `unknown_node`; a bad signature is Ok(false)
+    #[local]
+    #[http]
+    async fn verify_remote(
+        &mut self,
+        node: String,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<bool, AppError> {
+        require_authenticated_path()?;
+        let payload_sha256 = audit::sha256_hex(&message);
+        if !valid_node_name(&node) {
+            let result = Err(IdError::InvalidNodeName(node));
+            self.record_audit("verify_remote", payload_sha256, verify_outcome(&result));
+            return result.map_err(AppError::from);
+        }
+        let result =
+            match verify_from_local_rpc(&make_sign_sys(), node.clone(), message, signature).await {
+                Ok(Err(SignError::InvalidNodeName)) => Err(IdError::UnknownNode(node.clone())),
+                Ok(r) => r.map_err(IdError::Sign),
+                Err(e) => Err(IdError::SignUnreachable(e.to_string())),
+            };
+        let summary = match &result {
+            Ok(true) => format!("valid signature from {node}"),
+            Ok(false) => format!("invalid signature from {node}"),
+            Err(e) => format!("could not verify {node}: {e}"),
+        };
+        self.emit(
+            AuthEventKind::RemoteVerification,
+            result == Ok(true),
+            None,
+            summary,
+        );
+        self.record_audit("verify_remote", payload_sha256, verify_outcome(&result));
+        result.map_err(AppError::from)
+    }
+
+    /// one call for "this blob claims to be from `asserted_node`: is it?",
+    ///  for local apps such as the file explorer; the signature must have been
+    ///  made by the id app on that node
+    ///
+    /// unknown or malformed node names are reported as unresolved rather than
+    ///  as errors; transient net failures are retried before giving up
+    #[local]
+    #[http]
+    async fn verify_assertion(
+        &mut self,
+        asserted_node: String,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<AssertionResult, AppError> {
+        require_authenticated_path()?;
+        let payload_sha256 = audit::sha256_hex(&message);
+        let result = self
+            .check_assertion(asserted_node, message, signature)
+            .await;
+        let outcome = match &result {
+            Ok(r) if !r.resolved => "unresolved".to_string(),
+            Ok(r) => verify_outcome(&Ok(r.verified)),
+            Err(_) => audit::outcome(&result),
+        };
+        self.record_audit("verify_assertion", payload_sha256, outcome);
+        result.map_err(AppError::from)
+    }
+
+    /// a short-lived signed statement that we are our node, for showing to
+    ///  someone in person as a QR code
+    #[local]
+    #[http]
+    async fn create_identity_proof(&mut self, ttl_seconds: u64) -> Result<ProofBundle, AppError> {
+        require_authenticated_path()?;
+        let result = self.create_proof(ttl_seconds).await;
+        let payload_sha256 = match &result {
+            Ok(bundle) => audit::sha256_hex(bundle.payload.as_bytes()),
+            Err(_) => String::new(),
+        };
+        self.record_audit(
+            "create_identity_proof",
+            payload_sha256,
+            audit::outcome(&result),
+        );
+        if let Ok(bundle) = &result {
+            self.emit(
+                AuthEventKind::ProofCreated,
+                true,
+                None,
+                format!("proof {}", bundle.claims.proof_id),
+            );
+        }
+        result.map_err(AppError::from)
+    }
+
+    /// one of our unexpired proofs as a PNG QR code
+    #[http]
+    async fn get_identity_proof_qr(&mut self, proof_id: String) -> Result<Vec<u8>, AppError> {
+        require_authenticated_path()?;
+        let bundle = self.issued_proofs.get(&proof_id, now_ms())?;
+        png::qr_png(&bundle.payload).map_err(AppError::from)
+    }
+
+    /// serves `/proof/<proof_id>.png` so the UI can point an <img> at it
+    #[http]
+    async fn serve_identity_proof_qr(&mut self) -> Result<Vec<u8>, AppError> {
+        let path = get_path().unwrap_or_default();
+        let proof_id = proof::proof_id_from_path(&path)
+            .ok_or_else(|| IdError::NotFound("unknown proof".to_string()))?;
+        let bundle = self.issued_proofs.get(proof_id, now_ms())?;
+        let png = png::qr_png(&bundle.payload)?;
+        add_response_header("Content-Type".to_string(), "image/png".to_string());
+        add_response_header("Cache-Control".to_string(), "no-store".to_string());
+        Ok(png)
+    }
+
+    /// scanning side: accept another node's proof at most once
+    ///
+    /// fails with `proof_malformed`, `invalid_signature`, `proof_not_yet_valid`,
+    ///  `proof_expired`, or `proof_reused`
+    #[local]
+    #[http]
+    async fn verify_identity_proof(&mut self, payload: String) -> Result<VerifiedProof, AppError> {
+        require_authenticated_path()?;
+        let result = self.accept_proof(&payload).await;
+        self.record_audit(
+            "verify_identity_proof",
+            audit::sha256_hex(payload.as_bytes()),
+            audit::outcome(&result),
+        );
+        let summary = match &result {
+            Ok(verified) => format!("proof {} from {}", verified.proof_id, verified.node),
+            Err(e) => format!("rejected: {e}"),
+        };
+        self.emit(AuthEventKind::ProofVerified, result.is_ok(), None, summary);
+        result.map_err(AppError::from)
+    }
+
+    /// sign for a node that delegated its signing to us; `source().node` must
+    ///  be an allowed delegator, and may have us sign raw messages, its
+    ///  attestations, and tokens naming it as `delegated_by`
+    #[remote]
+    async fn delegated_sign(&mut self, message: Vec<u8>) -> Result<Vec<u8>, AppError> {
+        let payload_sha256 = audit::sha256_hex(&message);
+        let delegator = source().node;
+        let result = match self
+            .policy
+            .check_delegator(&delegator)
+            .and_then(|()| policy::check_delegated_message(&message, &delegator))
+            .and_then(|()| self.throttle("delegated_sign", LimitClass::Sign))
+        {
+            Ok(()) => sign_as_id(message).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit("delegated_sign", payload_sha256, audit::outcome(&result));
+        result.map_err(AppError::from)
+    }
+
+    /// have `node`'s id app sign for us in sign() and issue_token(), or sign
+    ///  locally again with None; `node` must allow us as a delegator
+    #[http]
+    async fn set_signing_delegate(&mut self, node: Option<String>) -> Result<(), AppError> {
+        require_authenticated_path()?;
+        if let Some(node) = &node {
+            if !valid_node_name(node) {
+                return Err(IdError::InvalidNodeName(node.clone()).into());
+            }
+            if *node == our().node {
+                return Err(
+                    IdError::InvalidInput("cannot delegate to ourselves".to_string()).into(),
+                );
+            }
+        }
+        self.signing_delegate = node;
+        Ok(())
+    }
+
+    /// nodes that may have us sign for them through delegated_sign
+    #[http]
+    async fn set_allowed_delegators(&mut self, nodes: Vec<String>) -> Result<(), AppError> {
+        require_authenticated_path()?;
+        if let Some(node) = nodes.iter().find(|n| !valid_node_name(n)) {
+            return Err(IdError::InvalidNodeName(node.clone()).into());
+        }
+        self.policy.set_allowed_delegators(nodes);
+        Ok(())
+    }
+
+    /// hits, misses, and evictions of the verify_token cache
+    #[local]
+    #[http]
+    async fn get_cache_stats(&mut self) -> Result<CacheStats, AppError> {
+        require_authenticated_path()?;
+        Ok(self.verify_cache.stats())
+    }
+
+    /// tune or disable reuse of verify_token signature checks; invalid
+    ///  signatures are kept for a quarter of `ttl_seconds`
+    #[http]
+    async fn set_verify_cache(
+        &mut self,
+        enabled: bool,
+        ttl_seconds: u64,
+        max_entries: u32,
+    ) -> Result<(), AppError> {
+        require_authenticated_path()?;
+        self.verify_cache
+            .configure(enabled, ttl_seconds, max_entries);
+        Ok(())
+    }
+
+    /// tokens expiring within `seconds` bypass the verify_token cache
+    #[http]
+    async fn set_verify_cache_expiry_window(&mut self, seconds: u64) -> Result<(), AppError> {
+        require_authenticated_path()?;
+        self.verify_cache.set_expiry_window(seconds);
+        Ok(())
+    }
+
+    #[http]
+    async fn get_rate_limits(&mut self) -> Result<RateLimits, AppError> {
+        require_authenticated_path()?;
+        Ok(self.rate_limits.clone())
+    }
+
+    /// per-caller requests per minute for sign and create_challenge, and for
+    ///  issue_token; 0 turns a limit off
+    #[http]
+    async fn set_rate_limits(
+        &mut self,
+        sign_per_min: u32,
+        tokens_per_min: u32,
+    ) -> Result<(), AppError> {
+        require_authenticated_path()?;
+        self.rate_limits = RateLimits {
+            sign_per_min,
+            tokens_per_min,
+        };
+        Ok(())
+    }
+
+    #[http]
+    async fn get_access_policy(&mut self) -> Result<AccessPolicy, AppError> {
+        require_authenticated_path()?;
+        Ok(self.policy.clone())
+    }
+
+    /// whether sign() answers HTTP requests; on by default
+    #[http]
+    async fn allow_http_signing(&mut self, enabled: bool) -> Result<(), AppError> {
+        require_authenticated_path()?;
+        self.policy.http_signing = enabled;
+        Ok(())
+    }
+
+    /// audiences issue_token may issue for; an empty list allows any
+    #[http]
+    async fn set_allowed_audiences(&mut self, audiences: Vec<String>) -> Result<(), AppError> {
+        require_authenticated_path()?;
+        if audiences.iter().any(|a| a.is_empty()) {
+            return Err(IdError::InvalidInput("audiences must not be empty".to_string()).into());
+        }
+        self.policy.set_allowed_audiences(audiences);
+        Ok(())
+    }
+
+    /// let a local process call sign(); once any process is allowed, only
+    ///  allowed processes may
+    #[http]
+    async fn allow_process(&mut self, process_id: String) -> Result<(), AppError> {
+        require_authenticated_path()?;
+        let process_id = parse_process_id(&process_id)?;
+        self.policy.allow_process(process_id);
+        Ok(())
+    }
+
+    /// stop a local process from calling sign()
+    #[http]
+    async fn deny_process(&mut self, process_id: String) -> Result<(), AppError> {
+        require_authenticated_path()?;
+        let process_id = parse_process_id(&process_id)?;
+        self.policy.deny_process(process_id);
+        Ok(())
+    }
+
+    /// audit entries newest first, optionally only those for one operation
+    #[local]
+    #[http]
+    async fn get_audit(
+        &mut self,
+        offset: u64,
+        limit: u32,
+        operation_filter: Option<String>,
+    ) -> Result<IdAuditPage, AppError> {
+        require_authenticated_path()?;
+        Ok(self.audit.page(offset, limit, operation_filter.as_deref()))
+    }
+
+    /// the whole audit log as CSV text, oldest first
+    #[http]
+    async fn export_audit_csv(&mut self) -> Result<String, AppError> {
+        require_authenticated_path()?;
+        Ok(self.audit.to_csv())
+    }
+
+    /// our node's public key, for sharing with verifiers outside the node
+    #[http]
+    async fn get_identity(&mut self) -> Result<IdentityInfo, AppError> {
+        require_authenticated_path()?;
+        Ok(self.own_identity().await?)
+    }
+
+    /// node name, public key, issued-token counts, and version
+    ///
+    /// an unreachable sign process or net leaves the key fields empty and is
+    ///  reported in `errors` rather than failing the whole card
+    #[http]
+    async fn get_identity_card(&mut self) -> Result<IdentityCard, AppError> {
+        require_authenticated_path()?;
+        Ok(self.identity_card().await)
+    }
+
+    /// the public fields of the identity card, served without authentication
+    #[http]
+    async fn get_public_identity_card(&mut self) -> Result<PublicIdentityCard, AppError> {
+        Ok(self.identity_card().await.into())
+    }
+
+    /// clients send `{"type": "subscribe", "kinds": [...]}` to receive auth
+    ///  events and `{"type": "ping"}` at least every few minutes to keep them
+    #[ws]
+    fn websocket(&mut self, channel_id: u32, message_type: WsMessageType, blob: LazyLoadBlob) {
+        self.events
+            .handle_client_message(channel_id, message_type, &blob.bytes, now_ms());
     }
 }
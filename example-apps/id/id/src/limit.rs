@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+/// most buckets held at once; idle ones are swept first, then the least
+///  recently used
+const MAX_BUCKETS: usize = 1000;
+
+const MINUTE_MS: u64 = 60_000;
+
+/// what a request spends: signing (sign, create_challenge) or issuing tokens
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LimitClass {
+    Sign,
+    Tokens,
+}
+
+/// requests allowed per caller per minute, with bursts up to the same
+///  number; 0 turns a limit off
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RateLimits {
+    pub sign_per_min: u32,
+    pub tokens_per_min: u32,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        RateLimits {
+            sign_per_min: 120,
+            tokens_per_min: 30,
+        }
+    }
+}
+
+impl RateLimits {
+    fn per_min(&self, class: LimitClass) -> u32 {
+        match class {
+            LimitClass::Sign => self.sign_per_min,
+            LimitClass::Tokens => self.tokens_per_min,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_ms: u64,
+}
+
+/// token buckets per (class, caller)
+#[derive(Default, Debug)]
+pub struct RateLimiter {
+    buckets: HashMap<(LimitClass, String), Bucket>,
+}
+
+impl RateLimiter {
+    /// spend one request for `caller`; Err(ms until one is available) if the
+    ///  bucket is empty
+    pub fn take(
+        &mut self,
+        limits: &RateLimits,
+        class: LimitClass,
+        caller: &str,
+        now_ms: u64,
+    ) -> Result<(), u64> {
+        let per_min = limits.per_min(class);
+        if per_min == 0 {
+            return Ok(());
+        }
+        let capacity = per_min as f64;
+        let per_ms = capacity / MINUTE_MS as f64;
+
+        let key = (class, caller.to_string());
+        if self.buckets.len() >= MAX_BUCKETS && !self.buckets.contains_key(&key) {
+            self.make_room(now_ms);
+        }
+
+        let bucket = self.buckets.entry(key).or_insert(Bucket {
+            tokens: capacity,
+            updated_ms: now_ms,
+        });
+        let elapsed = now_ms.saturating_sub(bucket.updated_ms);
+        bucket.tokens = (bucket.tokens + elapsed as f64 * per_ms).min(capacity);
+        bucket.updated_ms = now_ms;
+
+        if bucket.tokens < 1.0 {
+            return Err(((1.0 - bucket.tokens) / per_ms).ceil() as u64);
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// a bucket untouched for a minute has refilled and can be forgotten;
+    ///  if none has, drop the one idle longest
+    fn make_room(&mut self, now_ms: u64) {
+        self.buckets
+            .retain(|_, b| now_ms < b.updated_ms.saturating_add(MINUTE_MS));
+        if self.buckets.len() < MAX_BUCKETS {
+            return;
+        }
+        let oldest = self
+            .buckets
+            .iter()
+            .min_by_key(|(_, b)| b.updated_ms)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.buckets.remove(&key);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::error::IdError;
+
+/// pixels per QR module
+const MODULE_PX: u32 = 6;
+
+/// light modules around the code, as the QR spec asks for
+const QUIET_ZONE_MODULES: u32 = 4;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// largest block deflate can store uncompressed
+const MAX_STORED_BLOCK: usize = 65_535;
+
+/// render `text` as a QR code in a 1-bit grayscale PNG
+pub fn qr_png(text: &str) -> Result<Vec<u8>, IdError> {
+    let code = qrcode::QrCode::with_error_correction_level(text, qrcode::EcLevel::M)
+        .map_err(|e| IdError::Internal(format!("qr: {e}")))?;
+    let modules = code.width() as u32;
+    let dark = code.to_colors();
+
+    let side = (modules + 2 * QUIET_ZONE_MODULES) * MODULE_PX;
+    Ok(bilevel_png(side, side, |x, y| {
+        let mx = (x / MODULE_PX).checked_sub(QUIET_ZONE_MODULES);
+        let my = (y / MODULE_PX).checked_sub(QUIET_ZONE_MODULES);
+        match (mx, my) {
+            (Some(mx), Some(my)) if mx < modules && my < modules => {
+                dark[(my * modules + mx) as usize] == qrcode::Color::Dark
+            }
+            _ => false,
+        }
+    }))
+}
+
+/// a black-and-white PNG; `is_dark(x, y)` picks each pixel
+///
+/// pixel data is stored uncompressed: QR images are small and this keeps us
+///  free of an image or compression dependency
+fn bilevel_png(width: u32, height: u32, is_dark: impl Fn(u32, u32) -> bool) -> Vec<u8> {
+    let row_bytes = width.div_ceil(8) as usize;
+    let mut raw = Vec::with_capacity((row_bytes + 1) * height as usize);
+    for y in 0..height {
+        // filter type: none
+        raw.push(0);
+        let mut row = vec![0xff_u8; row_bytes];
+        for x in 0..width {
+            if is_dark(x, y) {
+                row[(x / 8) as usize] &= !(0x80 >> (x % 8));
+            }
+        }
+        raw.extend_from_slice(&row);
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // bit depth 1, grayscale, deflate, adaptive filtering, no interlace
+    ihdr.extend_from_slice(&[1, 0, 0, 0, 0]);
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    push_chunk(&mut png, b"IHDR", &ihdr);
+    push_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    push_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn push_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// a zlib stream of stored (uncompressed) deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(MAX_STORED_BLOCK).max(1);
+    let mut out = Vec::with_capacity(data.len() + blocks * 5 + 6);
+    // deflate, 32K window, no preset dictionary, fastest
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let is_final = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        out.push(is_final as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffff_u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65_521;
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % MOD;
+        b = (b + a) % MOD;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,252 @@
+use crate::attestation::ATTESTATION_TAG;
+use crate::challenge::{reserved_tag, split_fields};
+use crate::error::IdError;
+use crate::token::{TokenClaims, TOKEN_TAG};
+
+/// who may use the id app's signing endpoints
+///
+/// the defaults allow everything, as before this policy existed; each check
+///  tightens only once it has been configured
+///
+/// lists are kept sorted and deduplicated; WIT has no set type
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AccessPolicy {
+    /// whether sign() answers HTTP requests
+    pub http_signing: bool,
+    /// audiences issue_token may issue for; empty means any
+    pub allowed_audiences: Vec<String>,
+    /// local processes that may call sign(); empty means any not denied
+    pub allowed_processes: Vec<String>,
+    /// local processes that may never call sign()
+    pub denied_processes: Vec<String>,
+    /// nodes whose id app may have us sign for them; empty means none
+    #[serde(default)]
+    pub allowed_delegators: Vec<String>,
+}
+
+impl Default for AccessPolicy {
+    fn default() -> Self {
+        AccessPolicy {
+            http_signing: true,
+            allowed_audiences: Vec::new(),
+            allowed_processes: Vec::new(),
+            denied_processes: Vec::new(),
+            allowed_delegators: Vec::new(),
+        }
+    }
+}
+
+impl AccessPolicy {
+    pub fn check_http_signing(&self) -> Result<(), IdError> {
+        if !self.http_signing {
+            return Err(IdError::NotPermitted(
+                "http signing is disabled".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn check_process(&self, process: &str) -> Result<(), IdError> {
+        let listed = |list: &[String]| list.iter().any(|p| p == process);
+        if listed(&self.denied_processes)
+            || (!self.allowed_processes.is_empty() && !listed(&self.allowed_processes))
+        {
+            return Err(IdError::NotPermitted(format!("process {process}")));
+        }
+        Ok(())
+    }
+
+    pub fn check_audience(&self, audience: &str) -> Result<(), IdError> {
+        if !self.allowed_audiences.is_empty()
+            && !self.allowed_audiences.iter().any(|a| a == audience)
+        {
+            return Err(IdError::NotPermitted(format!("audience {audience}")));
+        }
+        Ok(())
+    }
+
+    pub fn check_delegator(&self, node: &str) -> Result<(), IdError> {
+        if !self.allowed_delegators.iter().any(|n| n == node) {
+            return Err(IdError::NotPermitted(format!("delegator {node}")));
+        }
+        Ok(())
+    }
+
+    /// once any process is allowed, only allowed processes may sign
+    pub fn allow_process(&mut self, process: String) {
+        self.denied_processes.retain(|p| *p != process);
+        insert_sorted(&mut self.allowed_processes, process);
+    }
+
+    pub fn deny_process(&mut self, process: String) {
+        self.allowed_processes.retain(|p| *p != process);
+        insert_sorted(&mut self.denied_processes, process);
+    }
+
+    pub fn set_allowed_delegators(&mut self, mut nodes: Vec<String>) {
+        nodes.sort();
+        nodes.dedup();
+        self.allowed_delegators = nodes;
+    }
+
+    pub fn set_allowed_audiences(&mut self, mut audiences: Vec<String>) {
+        audiences.sort();
+        audiences.dedup();
+        self.allowed_audiences = audiences;
+    }
+}
+
+/// sign() and sign_str() sign what the caller hands them, which must not be
+///  one of our own statements: a token, challenge response, proof, or
+///  attestation signed that way would verify as the real thing
+pub fn check_raw_message(message: &[u8]) -> Result<(), IdError> {
+    match reserved_tag(message) {
+        Some(tag) => Err(IdError::NotPermitted(format!(
+            "{} statements are built by the id app, not signed raw",
+            String::from_utf8_lossy(tag)
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// what an allowed delegator may have us sign: raw messages, as sign() would,
+///  its attestations, and tokens that name it as `delegated_by`; challenge
+///  responses and proofs speak for our node alone and are never delegated
+pub fn check_delegated_message(message: &[u8], delegator: &str) -> Result<(), IdError> {
+    let Some(tag) = reserved_tag(message) else {
+        return Ok(());
+    };
+    if tag == ATTESTATION_TAG {
+        return Ok(());
+    }
+    let refused = || {
+        IdError::NotPermitted(format!(
+            "delegator {delegator} may not have us sign {}",
+            String::from_utf8_lossy(tag)
+        ))
+    };
+    if tag != TOKEN_TAG {
+        return Err(refused());
+    }
+    let fields = split_fields(message).unwrap_or_default();
+    let [_, payload] = fields[..] else {
+        return Err(IdError::TokenMalformed);
+    };
+    let claims: TokenClaims =
+        serde_json::from_slice(payload).map_err(|_| IdError::TokenMalformed)?;
+    if claims.delegated_by.as_deref() != Some(delegator) {
+        return Err(refused());
+    }
+    Ok(())
+}
+
+fn insert_sorted(list: &mut Vec<String>, item: String) {
+    if let Err(at) = list.binary_search(&item) {
+        list.insert(at, item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attestation::{attestation_statement, Attestation};
+    use crate::challenge::{challenge_statement, Challenges};
+    use crate::proof::proof_statement;
+    use crate::token::{encode_payload, token_statement};
+
+    const DELEGATOR: &str = "alice.os";
+
+    fn token(delegated_by: Option<&str>) -> Vec<u8> {
+        let mut claims = TokenClaims::new(
+            "bob.os".to_string(),
+            "example.com".to_string(),
+            60,
+            Vec::new(),
+            0,
+        )
+        .unwrap();
+        claims.delegated_by = delegated_by.map(str::to_string);
+        token_statement(&encode_payload(&claims).unwrap())
+    }
+
+    fn statements() -> Vec<Vec<u8>> {
+        let challenge = Challenges::default()
+            .create("example.com".to_string(), 60, 0)
+            .unwrap();
+        let attestation = Attestation::new(
+            "example.com".to_string(),
+            "login".to_string(),
+            "bob.os".to_string(),
+            "{}",
+            None,
+            0,
+        )
+        .unwrap();
+        vec![
+            token(None),
+            challenge_statement(&challenge, "bob.os"),
+            proof_statement(b"{}"),
+            attestation_statement(&attestation).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn raw_signing_refuses_our_statements() {
+        for statement in statements() {
+            assert!(matches!(
+                check_raw_message(&statement),
+                Err(IdError::NotPermitted(_))
+            ));
+        }
+        // any tag in our namespace, even one not in use yet
+        let mut future = Vec::new();
+        crate::challenge::push_field(&mut future, b"hyperware-id-something-v9");
+        assert!(check_raw_message(&future).is_err());
+    }
+
+    #[test]
+    fn raw_signing_allows_other_messages() {
+        let mut tagged = Vec::new();
+        crate::challenge::push_field(&mut tagged, b"my-app-v1");
+        for message in [
+            &b"hello"[..],
+            b"hyperware-id-token-v1 without a length prefix",
+            &tagged,
+            // a length prefix longer than the message is not a field
+            &[0, 0, 0, 50, b'h'],
+        ] {
+            assert_eq!(check_raw_message(message), Ok(()));
+        }
+    }
+
+    #[test]
+    fn delegators_may_sign_only_their_own_tokens_and_attestations() {
+        let [unowned, challenge, proof, attestation] = statements().try_into().unwrap();
+        assert_eq!(check_delegated_message(b"hello", DELEGATOR), Ok(()));
+        assert_eq!(check_delegated_message(&attestation, DELEGATOR), Ok(()));
+        assert_eq!(
+            check_delegated_message(&token(Some(DELEGATOR)), DELEGATOR),
+            Ok(())
+        );
+        for refused in [unowned, token(Some("mallory.os")), challenge, proof] {
+            assert!(matches!(
+                check_delegated_message(&refused, DELEGATOR),
+                Err(IdError::NotPermitted(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn a_delegated_token_must_be_exactly_tag_and_payload() {
+        let mut trailing = token(Some(DELEGATOR));
+        crate::challenge::push_field(&mut trailing, b"extra");
+        assert_eq!(
+            check_delegated_message(&trailing, DELEGATOR),
+            Err(IdError::TokenMalformed)
+        );
+        assert_eq!(
+            check_delegated_message(&token_statement(b"not json"), DELEGATOR),
+            Err(IdError::TokenMalformed)
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use crate::challenge::push_field;
+use crate::error::IdError;
+
+/// domain tag for signed identity proofs
+pub const PROOF_TAG: &[u8] = b"hyperware-id-proof-v1";
+
+/// longest lifetime a proof may be created with; proofs are meant to be
+///  scanned by someone standing next to you
+pub const MAX_PROOF_TTL_SECONDS: u64 = 600;
+
+/// allowance for clock differences between prover and scanner
+const PROOF_CLOCK_SKEW_MS: u64 = 30_000;
+
+/// most proofs held at once, on either side
+const MAX_PROOFS: usize = 1000;
+
+/// where the UI fetches a proof's QR image: `/proof/<proof_id>.png`
+pub const PROOF_QR_PATH: &str = "/proof/";
+
+/// the signed contents of an identity proof
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProofClaims {
+    pub proof_id: String,
+    pub node: String,
+    pub issued_at_ms: u64,
+    pub expires_at_ms: u64,
+}
+
+/// a proof we created, ready to show as a QR code
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProofBundle {
+    pub claims: ProofClaims,
+    /// what the QR code encodes: `base64url(claims).base64url(signature)`
+    pub payload: String,
+    /// path under the app that serves the QR code as a PNG
+    pub qr_path: String,
+}
+
+/// the result of a successful verify_identity_proof
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VerifiedProof {
+    pub node: String,
+    pub proof_id: String,
+    pub issued_at_ms: u64,
+    pub verified_at_ms: u64,
+}
+
+/// proofs we created, until they expire
+#[derive(Default, Debug)]
+pub struct IssuedProofs {
+    proofs: HashMap<String, ProofBundle>,
+}
+
+impl IssuedProofs {
+    pub fn insert(&mut self, bundle: ProofBundle, now_ms: u64) -> Result<(), IdError> {
+        self.proofs.retain(|_, b| now_ms < b.claims.expires_at_ms);
+        if self.proofs.len() >= MAX_PROOFS {
+            return Err(IdError::TooManyProofs);
+        }
+        self.proofs.insert(bundle.claims.proof_id.clone(), bundle);
+        Ok(())
+    }
+
+    /// an unexpired proof of ours
+    pub fn get(&self, proof_id: &str, now_ms: u64) -> Result<&ProofBundle, IdError> {
+        match self.proofs.get(proof_id) {
+            Some(bundle) if now_ms < bundle.claims.expires_at_ms => Ok(bundle),
+            Some(_) => Err(IdError::ProofExpired),
+            None => Err(IdError::NotFound("unknown proof".to_string())),
+        }
+    }
+}
+
+/// proofs we accepted as the scanning side, kept until they expire so each is
+///  accepted only once
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ConsumedProofs {
+    /// `<node>/<proof_id>` -> expires_at_ms
+    consumed: HashMap<String, u64>,
+}
+
+impl ConsumedProofs {
+    pub fn check(&self, claims: &ProofClaims) -> Result<(), IdError> {
+        if self.consumed.contains_key(&consumed_key(claims)) {
+            return Err(IdError::ProofReused);
+        }
+        Ok(())
+    }
+
+    pub fn consume(&mut self, claims: &ProofClaims, now_ms: u64) -> Result<(), IdError> {
+        self.check(claims)?;
+        self.consumed
+            .retain(|_, expires| now_ms < expires.saturating_add(PROOF_CLOCK_SKEW_MS));
+        if self.consumed.len() >= MAX_PROOFS {
+            return Err(IdError::TooManyProofs);
+        }
+        self.consumed
+            .insert(consumed_key(claims), claims.expires_at_ms);
+        Ok(())
+    }
+}
+
+fn consumed_key(claims: &ProofClaims) -> String {
+    format!("{}/{}", claims.node, claims.proof_id)
+}
+
+impl ProofClaims {
+    pub fn new(node: String, ttl_seconds: u64, now_ms: u64) -> Result<Self, IdError> {
+        if ttl_seconds == 0 || ttl_seconds > MAX_PROOF_TTL_SECONDS {
+            return Err(IdError::InvalidInput(format!(
+                "ttl_seconds must be between 1 and {MAX_PROOF_TTL_SECONDS}"
+            )));
+        }
+        Ok(ProofClaims {
+            proof_id: crate::challenge::random_hex(),
+            node,
+            issued_at_ms: now_ms,
+            expires_at_ms: now_ms + ttl_seconds * 1000,
+        })
+    }
+}
+
+/// bytes signed for a proof: [tag, payload], each length-prefixed
+pub fn proof_statement(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(PROOF_TAG.len() + payload.len() + 8);
+    push_field(&mut bytes, PROOF_TAG);
+    push_field(&mut bytes, payload);
+    bytes
+}
+
+/// split a scanned payload into its signed bytes, decoded claims, and signature
+pub fn parse_proof(payload: &str) -> Result<(Vec<u8>, ProofClaims, Vec<u8>), IdError> {
+    let (bytes, signature) =
+        crate::token::split_compact(payload.trim()).ok_or(IdError::ProofMalformed)?;
+    let claims: ProofClaims =
+        serde_json::from_slice(&bytes).map_err(|_| IdError::ProofMalformed)?;
+    Ok((bytes, claims, signature))
+}
+
+/// time checks, done once the signature is known to be good
+pub fn check_claims(claims: &ProofClaims, now_ms: u64) -> Result<(), IdError> {
+    if claims.issued_at_ms > now_ms.saturating_add(PROOF_CLOCK_SKEW_MS) {
+        return Err(IdError::ProofNotYetValid);
+    }
+    if now_ms > claims.expires_at_ms.saturating_add(PROOF_CLOCK_SKEW_MS) {
+        return Err(IdError::ProofExpired);
+    }
+    Ok(())
+}
+
+/// the proof id in a QR image path, e.g. `/proof/<proof_id>.png`
+pub fn proof_id_from_path(path: &str) -> Option<&str> {
+    path.strip_prefix(PROOF_QR_PATH)?.strip_suffix(".png")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::format_compact;
+
+    fn claims(node: &str) -> ProofClaims {
+        ProofClaims::new(node.to_string(), 60, 1000).unwrap()
+    }
+
+    fn bundle(claims: ProofClaims) -> ProofBundle {
+        let payload = format_compact(&serde_json::to_vec(&claims).unwrap(), b"sig");
+        ProofBundle {
+            qr_path: format!("{PROOF_QR_PATH}{}.png", claims.proof_id),
+            claims,
+            payload,
+        }
+    }
+
+    #[test]
+    fn a_proof_is_accepted_only_once() {
+        let mut consumed = ConsumedProofs::default();
+        let proof = claims("alice.os");
+        assert_eq!(consumed.check(&proof), Ok(()));
+        assert_eq!(consumed.consume(&proof, 1000), Ok(()));
+        assert_eq!(consumed.check(&proof), Err(IdError::ProofReused));
+        assert_eq!(consumed.consume(&proof, 2000), Err(IdError::ProofReused));
+
+        // the same proof id from another node is a different proof
+        let mut other = claims("bob.os");
+        other.proof_id = proof.proof_id.clone();
+        assert_eq!(consumed.consume(&other, 2000), Ok(()));
+    }
+
+    #[test]
+    fn consumed_proofs_are_forgotten_once_they_could_not_verify() {
+        let mut consumed = ConsumedProofs::default();
+        let proof = claims("alice.os");
+        consumed.consume(&proof, 1000).unwrap();
+
+        // still remembered while check_claims would accept it
+        let latest = proof.expires_at_ms + PROOF_CLOCK_SKEW_MS;
+        consumed.consume(&claims("carol.os"), latest - 1).unwrap();
+        assert_eq!(consumed.check(&proof), Err(IdError::ProofReused));
+        assert_eq!(check_claims(&proof, latest), Ok(()));
+
+        consumed.consume(&claims("dave.os"), latest).unwrap();
+        assert_eq!(consumed.check(&proof), Ok(()));
+        assert_eq!(check_claims(&proof, latest + 1), Err(IdError::ProofExpired));
+    }
+
+    #[test]
+    fn consumed_proofs_survive_a_restart() {
+        let mut consumed = ConsumedProofs::default();
+        let proof = claims("alice.os");
+        consumed.consume(&proof, 1000).unwrap();
+        let restored: ConsumedProofs =
+            serde_json::from_slice(&serde_json::to_vec(&consumed).unwrap()).unwrap();
+        assert_eq!(restored.check(&proof), Err(IdError::ProofReused));
+    }
+
+    #[test]
+    fn issued_proofs_expire() {
+        let mut issued = IssuedProofs::default();
+        let proof = claims("alice.os");
+        let proof_id = proof.proof_id.clone();
+        issued.insert(bundle(proof), 1000).unwrap();
+        assert!(issued.get(&proof_id, 60_999).is_ok());
+        assert_eq!(issued.get(&proof_id, 61_000), Err(IdError::ProofExpired));
+        assert_eq!(
+            issued.get("nope", 1000),
+            Err(IdError::NotFound("unknown proof".to_string()))
+        );
+    }
+
+    #[test]
+    fn scanned_payloads_parse_back_to_their_claims() {
+        let proof = claims("alice.os");
+        let bundle = bundle(proof.clone());
+        let (bytes, parsed, signature) = parse_proof(&format!(" {}\n", bundle.payload)).unwrap();
+        assert_eq!(parsed, proof);
+        assert_eq!(bytes, serde_json::to_vec(&proof).unwrap());
+        assert_eq!(signature, b"sig");
+        assert_eq!(
+            proof_id_from_path(&bundle.qr_path),
+            Some(proof.proof_id.as_str())
+        );
+
+        for payload in ["", "no-dot", &format_compact(b"{}", b"sig")] {
+            assert_eq!(
+                parse_proof(payload).map(|_| ()),
+                Err(IdError::ProofMalformed)
+            );
+        }
+    }
+
+    #[test]
+    fn proofs_from_the_future_are_refused() {
+        let mut proof = claims("alice.os");
+        proof.issued_at_ms = 100_000;
+        assert_eq!(check_claims(&proof, 100_000 - PROOF_CLOCK_SKEW_MS), Ok(()));
+        assert_eq!(
+            check_claims(&proof, 100_000 - PROOF_CLOCK_SKEW_MS - 1),
+            Err(IdError::ProofNotYetValid)
+        );
+    }
+}
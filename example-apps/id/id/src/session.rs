@@ -0,0 +1,98 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::IdError;
+use crate::token::TokenClaims;
+
+/// expired sessions stay listable for this long before the sweep drops them
+pub const EXPIRED_SESSION_GRACE_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// how often the sweep runs
+pub const SESSION_SWEEP_INTERVAL_MS: u64 = 10 * 60 * 1000;
+
+/// a token we issued, without the token itself
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SessionInfo {
+    pub token_id: String,
+    pub audience: String,
+    pub issued_at_ms: u64,
+    pub expires_at_ms: u64,
+    /// claim keys only; values may be sensitive
+    pub claim_keys: Vec<String>,
+    pub revoked: bool,
+}
+
+impl SessionInfo {
+    pub fn from_claims(claims: &TokenClaims) -> Self {
+        SessionInfo {
+            token_id: claims.token_id.clone(),
+            audience: claims.audience.clone(),
+            issued_at_ms: claims.issued_at_ms,
+            expires_at_ms: claims.expires_at_ms,
+            claim_keys: claims.claims.iter().map(|(k, _)| k.clone()).collect(),
+            revoked: false,
+        }
+    }
+}
+
+/// tokens we issued, by token_id
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Sessions {
+    sessions: HashMap<String, SessionInfo>,
+}
+
+impl Sessions {
+    pub fn insert(&mut self, session: SessionInfo) {
+        self.sessions.insert(session.token_id.clone(), session);
+    }
+
+    /// newest first; expired sessions only when asked for
+    pub fn list(&self, include_expired: bool, now_ms: u64) -> Vec<SessionInfo> {
+        let mut sessions: Vec<SessionInfo> = self
+            .sessions
+            .values()
+            .filter(|s| include_expired || now_ms < s.expires_at_ms)
+            .cloned()
+            .collect();
+        sessions.sort_by(|a, b| b.issued_at_ms.cmp(&a.issued_at_ms));
+        sessions
+    }
+
+    /// Ok(false) if already revoked
+    pub fn revoke(&mut self, token_id: &str) -> Result<bool, IdError> {
+        let session = self
+            .sessions
+            .get_mut(token_id)
+            .ok_or_else(|| IdError::NotFound("unknown session".to_string()))?;
+        Ok(!std::mem::replace(&mut session.revoked, true))
+    }
+
+    /// revoke every unexpired session; returns the ids newly revoked
+    pub fn revoke_all(&mut self, now_ms: u64) -> Vec<String> {
+        self.sessions
+            .values_mut()
+            .filter(|s| !s.revoked && now_ms < s.expires_at_ms)
+            .map(|s| {
+                s.revoked = true;
+                s.token_id.clone()
+            })
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SessionInfo> {
+        self.sessions.values()
+    }
+
+    /// drop sessions past their grace period, and their entries in `revoked`:
+    ///  such tokens fail verification as expired anyway
+    pub fn sweep(&mut self, revoked: &mut HashSet<String>, now_ms: u64) -> usize {
+        let before = self.sessions.len();
+        self.sessions.retain(|token_id, s| {
+            let keep = now_ms < s.expires_at_ms.saturating_add(EXPIRED_SESSION_GRACE_MS);
+            if !keep {
+                revoked.remove(token_id);
+            }
+            keep
+        });
+        before - self.sessions.len()
+    }
+}
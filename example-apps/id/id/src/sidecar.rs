@@ -0,0 +1,62 @@
+use hyperware_app_common::send;
+use hyperware_process_lib::vfs::{self, vfs_request, VfsAction, VfsResponse};
+
+use crate::error::IdError;
+
+/// seconds to wait for each VFS request
+const VFS_TIMEOUT_SECS: u64 = 5;
+
+/// a signed file's envelope lives next to it, at `<path>.sig.json`
+pub const SIDECAR_SUFFIX: &str = ".sig.json";
+
+/// what verify_file_at found
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SidecarCheck {
+    /// the file is exactly what was signed
+    Valid,
+    /// the file, or the sidecar, no longer matches the signature
+    Modified,
+    /// there is no sidecar next to the file
+    MissingSignature,
+}
+
+pub fn sidecar_path(path: &str) -> String {
+    format!("{path}{SIDECAR_SUFFIX}")
+}
+
+/// write `bytes` as the sidecar for `path`, replacing any old one in a single
+///  rename so readers never see a half-written sidecar
+pub async fn write_sidecar(path: &str, bytes: &[u8]) -> Result<String, IdError> {
+    let sidecar = sidecar_path(path);
+    let staging = format!("{sidecar}.tmp");
+
+    vfs::create_file(&staging, Some(VFS_TIMEOUT_SECS))
+        .and_then(|file| file.write(bytes))
+        .map_err(|e| IdError::Vfs(format!("failed to write {staging}: {e}")))?;
+
+    let request = vfs_request(
+        &staging,
+        VfsAction::Rename {
+            new_path: sidecar.clone(),
+        },
+    )
+    .expects_response(VFS_TIMEOUT_SECS);
+    match send::<VfsResponse>(request).await {
+        Ok(VfsResponse::Ok) => Ok(sidecar),
+        Ok(VfsResponse::Err(e)) => Err(IdError::Vfs(format!("failed to replace {sidecar}: {e:?}"))),
+        Ok(_) => Err(IdError::Vfs("unexpected response from VFS".to_string())),
+        Err(e) => Err(IdError::Vfs(e.to_string())),
+    }
+}
+
+/// the sidecar for `path`, or None if there is none
+pub fn read_sidecar(path: &str) -> Result<Option<Vec<u8>>, IdError> {
+    let sidecar = sidecar_path(path);
+    if vfs::metadata(&sidecar, Some(VFS_TIMEOUT_SECS)).is_err() {
+        return Ok(None);
+    }
+    vfs::open_file(&sidecar, false, Some(VFS_TIMEOUT_SECS))
+        .and_then(|file| file.read())
+        .map(Some)
+        .map_err(|e| IdError::Vfs(format!("failed to read {sidecar}: {e}")))
+}
@@ -0,0 +1,283 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use crate::challenge::push_field;
+use crate::error::IdError;
+
+/// domain tag for signed token payloads
+pub const TOKEN_TAG: &[u8] = b"hyperware-id-token-v1";
+
+/// longest lifetime a token may be issued with: one week
+pub const MAX_TOKEN_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// allowance for clock differences between issuer and verifier
+pub const TOKEN_CLOCK_SKEW_MS: u64 = 30_000;
+
+const MAX_CLAIMS: usize = 32;
+const MAX_CLAIM_BYTES: usize = 256;
+
+/// the signed contents of a bearer token
+///
+/// claims are key/value pairs sorted by key; WIT has no map type
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TokenClaims {
+    pub token_id: String,
+    pub node: String,
+    pub audience: String,
+    pub issued_at_ms: u64,
+    pub expires_at_ms: u64,
+    pub claims: Vec<(String, String)>,
+    /// the node that asked `node` to sign this token on its behalf; absent
+    ///  from tokens that were not delegated, so their payloads are unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegated_by: Option<String>,
+}
+
+/// the result of a successful verify_token
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VerifiedToken {
+    pub claims: TokenClaims,
+    /// the key the signature was checked against, when the issuer is our node
+    pub key_info: Option<crate::keys::KeyInfo>,
+}
+
+impl TokenClaims {
+    pub fn new(
+        node: String,
+        audience: String,
+        ttl_seconds: u64,
+        mut claims: Vec<(String, String)>,
+        now_ms: u64,
+    ) -> Result<Self, IdError> {
+        if audience.is_empty() {
+            return Err(IdError::InvalidInput(
+                "audience must not be empty".to_string(),
+            ));
+        }
+        if ttl_seconds == 0 || ttl_seconds > MAX_TOKEN_TTL_SECONDS {
+            return Err(IdError::InvalidInput(format!(
+                "ttl_seconds must be between 1 and {MAX_TOKEN_TTL_SECONDS}"
+            )));
+        }
+        if claims.len() > MAX_CLAIMS {
+            return Err(IdError::InvalidInput(format!(
+                "at most {MAX_CLAIMS} claims"
+            )));
+        }
+        if claims
+            .iter()
+            .any(|(k, v)| k.is_empty() || k.len() > MAX_CLAIM_BYTES || v.len() > MAX_CLAIM_BYTES)
+        {
+            return Err(IdError::InvalidInput(format!(
+                "claim keys must be non-empty and keys and values at most {MAX_CLAIM_BYTES} bytes"
+            )));
+        }
+        claims.sort();
+        if claims.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(IdError::InvalidInput("duplicate claim key".to_string()));
+        }
+
+        Ok(TokenClaims {
+            token_id: crate::challenge::random_hex(),
+            node,
+            audience,
+            issued_at_ms: now_ms,
+            expires_at_ms: now_ms + ttl_seconds * 1000,
+            claims,
+            delegated_by: None,
+        })
+    }
+}
+
+/// the exact JSON bytes that go into a token; they are signed and decoded as-is,
+///  so verifiers never need to re-serialize
+pub fn encode_payload(claims: &TokenClaims) -> Result<Vec<u8>, IdError> {
+    serde_json::to_vec(claims).map_err(|e| IdError::Internal(e.to_string()))
+}
+
+/// bytes signed for a token: [tag, payload], each length-prefixed
+pub fn token_statement(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(TOKEN_TAG.len() + payload.len() + 8);
+    push_field(&mut bytes, TOKEN_TAG);
+    push_field(&mut bytes, payload);
+    bytes
+}
+
+/// `base64url(payload).base64url(signature)`, unpadded; used for tokens and
+///  identity proofs
+pub fn format_compact(payload: &[u8], signature: &[u8]) -> String {
+    format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(payload),
+        URL_SAFE_NO_PAD.encode(signature)
+    )
+}
+
+/// split a token into its payload bytes, decoded claims, and signature
+pub fn parse_token(token: &str) -> Result<(Vec<u8>, TokenClaims, Vec<u8>), IdError> {
+    let (payload, signature) = split_compact(token).ok_or(IdError::TokenMalformed)?;
+    let claims: TokenClaims =
+        serde_json::from_slice(&payload).map_err(|_| IdError::TokenMalformed)?;
+    Ok((payload, claims, signature))
+}
+
+/// inverse of format_compact
+pub fn split_compact(compact: &str) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (payload, signature) = compact.split_once('.')?;
+    Some((
+        URL_SAFE_NO_PAD.decode(payload).ok()?,
+        URL_SAFE_NO_PAD.decode(signature).ok()?,
+    ))
+}
+
+/// time and audience checks, done once the signature is known to be good
+pub fn check_claims(
+    claims: &TokenClaims,
+    expected_audience: &str,
+    now_ms: u64,
+) -> Result<(), IdError> {
+    if claims.audience != expected_audience {
+        return Err(IdError::AudienceMismatch);
+    }
+    if claims.issued_at_ms > now_ms.saturating_add(TOKEN_CLOCK_SKEW_MS) {
+        return Err(IdError::TokenNotYetValid);
+    }
+    if now_ms > claims.expires_at_ms.saturating_add(TOKEN_CLOCK_SKEW_MS) {
+        return Err(IdError::TokenExpired);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey, Verifier};
+
+    const AUDIENCE: &str = "https://example.com";
+
+    fn claims() -> TokenClaims {
+        TokenClaims::new(
+            "alice.os".to_string(),
+            AUDIENCE.to_string(),
+            60,
+            vec![("role".to_string(), "admin".to_string())],
+            1000,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn a_tampered_token_no_longer_matches_its_signature() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let payload = encode_payload(&claims()).unwrap();
+        let signature = key.sign(&token_statement(&payload)).to_bytes();
+        let token = format_compact(&payload, &signature);
+
+        let (parsed, _, parsed_signature) = parse_token(&token).unwrap();
+        let parsed_signature = ed25519_dalek::Signature::from_slice(&parsed_signature).unwrap();
+        assert!(key
+            .verifying_key()
+            .verify(&token_statement(&parsed), &parsed_signature)
+            .is_ok());
+
+        let mut tampered = claims();
+        tampered.claims = vec![("role".to_string(), "owner".to_string())];
+        let tampered_payload = encode_payload(&tampered).unwrap();
+        let forged = format_compact(&tampered_payload, &signature);
+        let (parsed, parsed_claims, _) = parse_token(&forged).unwrap();
+        assert_eq!(parsed_claims.claims, tampered.claims);
+        assert!(key
+            .verifying_key()
+            .verify(&token_statement(&parsed), &parsed_signature)
+            .is_err());
+    }
+
+    #[test]
+    fn garbage_is_malformed() {
+        let payload = encode_payload(&claims()).unwrap();
+        for token in [
+            String::new(),
+            "no-dot".to_string(),
+            "not base64!.AAAA".to_string(),
+            format_compact(b"not json", b"sig"),
+            format_compact(&payload, b"sig").replacen('.', "", 1),
+        ] {
+            assert_eq!(
+                parse_token(&token).map(|_| ()),
+                Err(IdError::TokenMalformed),
+                "{token}"
+            );
+        }
+    }
+
+    #[test]
+    fn a_token_for_another_audience_is_refused() {
+        assert_eq!(check_claims(&claims(), AUDIENCE, 1000), Ok(()));
+        assert_eq!(
+            check_claims(&claims(), "https://other.example", 1000),
+            Err(IdError::AudienceMismatch)
+        );
+    }
+
+    #[test]
+    fn clock_skew_is_allowed_on_both_ends() {
+        let claims = claims();
+        let earliest = claims.issued_at_ms.saturating_sub(TOKEN_CLOCK_SKEW_MS);
+        assert_eq!(check_claims(&claims, AUDIENCE, earliest), Ok(()));
+
+        let mut later = claims.clone();
+        later.issued_at_ms = 100_000;
+        let earliest = later.issued_at_ms - TOKEN_CLOCK_SKEW_MS;
+        assert_eq!(check_claims(&later, AUDIENCE, earliest), Ok(()));
+        assert_eq!(
+            check_claims(&later, AUDIENCE, earliest - 1),
+            Err(IdError::TokenNotYetValid)
+        );
+
+        let latest = claims.expires_at_ms + TOKEN_CLOCK_SKEW_MS;
+        assert_eq!(check_claims(&claims, AUDIENCE, latest), Ok(()));
+        assert_eq!(
+            check_claims(&claims, AUDIENCE, latest + 1),
+            Err(IdError::TokenExpired)
+        );
+    }
+
+    #[test]
+    fn claims_are_sorted_and_checked() {
+        let claims = TokenClaims::new(
+            "alice.os".to_string(),
+            AUDIENCE.to_string(),
+            60,
+            vec![
+                ("b".to_string(), String::new()),
+                ("a".to_string(), String::new()),
+            ],
+            0,
+        )
+        .unwrap();
+        assert_eq!(claims.claims[0].0, "a");
+
+        let duplicate = vec![
+            ("a".to_string(), "1".to_string()),
+            ("a".to_string(), "2".to_string()),
+        ];
+        for (audience, ttl_seconds, claims) in [
+            ("", 60, Vec::new()),
+            (AUDIENCE, 0, Vec::new()),
+            (AUDIENCE, MAX_TOKEN_TTL_SECONDS + 1, Vec::new()),
+            (AUDIENCE, 60, duplicate),
+            (AUDIENCE, 60, vec![(String::new(), "v".to_string())]),
+        ] {
+            assert!(matches!(
+                TokenClaims::new(
+                    "alice.os".to_string(),
+                    audience.to_string(),
+                    ttl_seconds,
+                    claims,
+                    0
+                ),
+                Err(IdError::InvalidInput(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! sign() cannot forge the id app's own statements: a token statement handed
+//!  to it is refused outright, and its signature over a bare token payload
+//!  does not pass verify_token
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hyperware_process_lib::Address;
+use serde_json::{json, Value};
+
+use crate::{RETRIES, TIMEOUT_SECS};
+use tester_lib::{call_json, call_ok, expect_err_code, step, CodedError, TestError};
+
+const AUDIENCE: &str = "id-test-forgery";
+
+/// the id app's domain tag for token statements
+const TOKEN_TAG: &[u8] = b"hyperware-id-token-v1";
+
+pub fn run(id: &Address, node: &str) -> Result<(), TestError> {
+    // claims a verifier would accept, were the signature good
+    let payload = serde_json::to_vec(&json!({
+        "token_id": "forged",
+        "node": node,
+        "audience": AUDIENCE,
+        "issued_at_ms": 0,
+        "expires_at_ms": u64::MAX,
+        "claims": [],
+    }))
+    .map_err(|e| TestError::Assertion {
+        error: format!("failed to encode claims: {e}"),
+    })?;
+
+    step!("sign refuses a token statement", {
+        let verdict: Result<Vec<u8>, CodedError> = call_json(
+            id,
+            &json!({ "Sign": token_statement(&payload) }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )?;
+        expect_err_code(verdict, "not_permitted")
+    })?;
+
+    step!("a signed bare payload does not verify as a token", {
+        let signature: Vec<u8> =
+            call_ok::<_, _, CodedError>(id, &json!({ "Sign": payload }), TIMEOUT_SECS, RETRIES)?;
+        let token = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(&payload),
+            URL_SAFE_NO_PAD.encode(&signature)
+        );
+        let verdict: Result<Value, CodedError> = call_json(
+            id,
+            &json!({ "VerifyToken": [token, AUDIENCE] }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )?;
+        expect_err_code(verdict, "invalid_signature")
+    })
+}
+
+/// the bytes the id app signs for a token: [tag, payload], each prefixed
+///  with its length as a u32 big-endian
+fn token_statement(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for field in [TOKEN_TAG, payload] {
+        bytes.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(field);
+    }
+    bytes
+}
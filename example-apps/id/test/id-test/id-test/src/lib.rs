@@ -0,0 +1,217 @@
+use crate::hyperware::process::tester::{
+    FailResponse, Request as TesterRequest, Response as TesterResponse, RunRequest,
+};
+use hyperware_process_lib::{
+    await_message, call_init, print_to_terminal, Address, Message, ProcessId, Response,
+};
+use serde_json::json;
+
+mod forgery;
+mod sessions;
+mod token_cache;
+use tester_lib::{call_json, call_ok, expect_err_code, fail, CodedError};
+
+wit_bindgen::generate!({
+    path: "../target/wit",
+    world: "id-test-sys-v0",
+    generate_unused_types: true,
+    additional_derives: [PartialEq, serde::Deserialize, serde::Serialize, process_macros::SerdeJsonInto],
+});
+
+const TIMEOUT_SECS: u64 = 15;
+
+/// extra attempts for a request that times out
+const RETRIES: u32 = 2;
+
+const MESSAGE: &[u8] = b"id-test: signed on the first node, checked on the second";
+
+/// a name that is well-formed but not registered on the fake chain
+const UNKNOWN_NODE: &str = "nobody-here.os";
+
+/// what the driving node asks its peer
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+enum PeerRequest {
+    /// check a signature with the peer's id app
+    Verify {
+        claimed_node: String,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    },
+}
+
+/// the peer's reply: its id app's answer, or why it could not get one
+type PeerVerdict = Result<Result<bool, CodedError>, String>;
+
+call_init!(init);
+fn init(our: Address) {
+    print_to_terminal(0, "begin");
+
+    loop {
+        handle_message(&our);
+    }
+}
+
+fn handle_message(our: &Address) {
+    let message = await_message()
+        .unwrap_or_else(|e| fail_with(format!("failed to receive tester message: {e:?}")));
+
+    if !message.is_request() {
+        fail_with("expected tester request message");
+    }
+
+    let source = message.source();
+    if our.node != source.node {
+        handle_peer_request(our, &message);
+        return;
+    }
+
+    let TesterRequest::Run(RunRequest {
+        input_node_names: node_names,
+        ..
+    }) = message
+        .body()
+        .try_into()
+        .unwrap_or_else(|e| fail_with(format!("failed to decode tester run request: {e:?}")));
+
+    print_to_terminal(0, "id_test: start");
+
+    if our.node != node_names[0] {
+        // the second node only answers PeerRequests from the first
+        Response::new()
+            .body(TesterResponse::Run(Ok(())))
+            .send()
+            .unwrap_or_else(|e| fail_with(format!("failed to send tester ack: {e:?}")));
+        return;
+    }
+
+    let Some(peer_node) = node_names.get(1) else {
+        fail_with(format!("id_test needs two nodes, got {node_names:?}"));
+    };
+    let peer = Address {
+        node: peer_node.clone(),
+        process: our.process.clone(),
+    };
+    let id = id_address(&our.node);
+
+    let signature: Vec<u8> =
+        call_ok::<_, _, CodedError>(&id, &json!({ "Sign": MESSAGE }), TIMEOUT_SECS, RETRIES)
+            .unwrap_or_else(|e| fail_with(format!("sign on the first node: {e}")));
+
+    expect_verdict(
+        "verify on the second node as the signing node",
+        &peer,
+        &our.node,
+        &signature,
+        Ok(true),
+    );
+
+    expect_verdict(
+        "verify on the second node as the wrong node",
+        &peer,
+        peer_node,
+        &signature,
+        Ok(false),
+    );
+
+    let verdict = ask_peer(
+        "verify on the second node as an unknown node",
+        &peer,
+        UNKNOWN_NODE,
+        &signature,
+    );
+    expect_err_code(verdict, "unknown_node").unwrap_or_else(|e| {
+        fail_with(format!("verify on the second node as an unknown node: {e}"))
+    });
+
+    let mut flipped = signature.clone();
+    flipped[0] ^= 0x01;
+    expect_verdict(
+        "verify a flipped signature byte on the second node",
+        &peer,
+        &our.node,
+        &flipped,
+        Ok(false),
+    );
+
+    sessions::run(&id).unwrap_or_else(|e| fail_with(format!("sessions: {e}")));
+    token_cache::run(&id).unwrap_or_else(|e| fail_with(format!("token cache: {e}")));
+    forgery::run(&id, &our.node).unwrap_or_else(|e| fail_with(format!("forgery: {e}")));
+
+    Response::new()
+        .body(TesterResponse::Run(Ok(())))
+        .send()
+        .unwrap_or_else(|e| fail_with(format!("failed to send tester success: {e:?}")));
+}
+
+/// on the second node: check a signature with the local id app and send its
+///  answer back, so failures surface on the driving node with context
+fn handle_peer_request(our: &Address, message: &Message) {
+    let verdict: PeerVerdict = if message.source().process != our.process {
+        Err(format!("unexpected peer {}", message.source()))
+    } else {
+        match serde_json::from_slice::<PeerRequest>(message.body()) {
+            Ok(PeerRequest::Verify {
+                claimed_node,
+                message: signed,
+                signature,
+            }) => call_json(
+                &id_address(&our.node),
+                &json!({ "VerifyRemote": [claimed_node, signed, signature] }),
+                TIMEOUT_SECS,
+                RETRIES,
+            )
+            .map_err(|e| e.to_string()),
+            Err(e) => Err(format!("failed to decode peer request: {e}")),
+        }
+    };
+
+    let body = serde_json::to_vec(&verdict)
+        .unwrap_or_else(|e| fail_with(format!("failed to encode peer verdict: {e}")));
+    Response::new()
+        .body(body)
+        .send()
+        .unwrap_or_else(|e| fail_with(format!("failed to answer peer request: {e:?}")));
+}
+
+fn id_address(node: &str) -> Address {
+    Address {
+        node: node.to_string(),
+        process: ProcessId::new(Some("id"), "id", "sys"),
+    }
+}
+
+fn ask_peer(
+    step: &str,
+    peer: &Address,
+    claimed_node: &str,
+    signature: &[u8],
+) -> Result<bool, CodedError> {
+    let request = PeerRequest::Verify {
+        claimed_node: claimed_node.to_string(),
+        message: MESSAGE.to_vec(),
+        signature: signature.to_vec(),
+    };
+    let verdict: PeerVerdict = call_json(peer, &request, TIMEOUT_SECS, RETRIES)
+        .unwrap_or_else(|e| fail_with(format!("{step}: {e}")));
+    verdict.unwrap_or_else(|e| fail_with(format!("{step}: peer could not verify: {e}")))
+}
+
+fn expect_verdict(
+    step: &str,
+    peer: &Address,
+    claimed_node: &str,
+    signature: &[u8],
+    expected: Result<bool, CodedError>,
+) {
+    let verdict = ask_peer(step, peer, claimed_node, signature);
+    if verdict != expected {
+        fail_with(format!("{step}: expected {expected:?}, got {verdict:?}"));
+    }
+}
+
+fn fail_with(message: impl Into<String>) -> ! {
+    let message = message.into();
+    let log = format!("id_test: error: {message}");
+    print_to_terminal(0, log.as_str());
+    fail!(message);
+}
@@ -0,0 +1,80 @@
+//! sessions: a token issued here verifies until revoke_session, and the
+//!  very next verify_token after the revoke fails
+
+use hyperware_process_lib::Address;
+use serde_json::{json, Value};
+
+use crate::{RETRIES, TIMEOUT_SECS};
+use tester_lib::{assert_eq_ctx, call_json, call_ok, expect_err_code, step, CodedError, TestError};
+
+const AUDIENCE: &str = "id-test-sessions";
+
+/// long enough that the token stays clear of the verify cache's expiry window
+const TOKEN_TTL_SECONDS: u64 = 600;
+
+pub fn run(id: &Address) -> Result<(), TestError> {
+    let token: String = step!(
+        "issue",
+        call_ok::<_, _, CodedError>(
+            id,
+            &json!({ "IssueToken": [AUDIENCE, TOKEN_TTL_SECONDS, [["role", "tester"]]] }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )
+    )?;
+
+    let token_id = step!("verify before revoking", {
+        let verified: Value = call_ok::<_, _, CodedError>(
+            id,
+            &json!({ "VerifyToken": [token, AUDIENCE] }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )?;
+        verified["claims"]["token_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| TestError::Assertion {
+                error: format!("verified token has no token_id: {verified}"),
+            })
+    })?;
+
+    step!("revoke", {
+        let newly_revoked: bool = call_ok::<_, _, CodedError>(
+            id,
+            &json!({ "RevokeSession": token_id }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )?;
+        assert_eq_ctx!(true, newly_revoked, "first revoke_session")
+    })?;
+
+    step!("verify right after revoking", {
+        let verdict: Result<Value, CodedError> = call_json(
+            id,
+            &json!({ "VerifyToken": [token, AUDIENCE] }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )?;
+        expect_err_code(verdict, "token_revoked")
+    })?;
+
+    step!("revoke again", {
+        let newly_revoked: bool = call_ok::<_, _, CodedError>(
+            id,
+            &json!({ "RevokeSession": token_id }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )?;
+        assert_eq_ctx!(false, newly_revoked, "second revoke_session")
+    })?;
+
+    step!("revoke an unknown session", {
+        let verdict: Result<bool, CodedError> = call_json(
+            id,
+            &json!({ "RevokeSession": "no-such-token" }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )?;
+        expect_err_code(verdict, "not_found")
+    })
+}
@@ -0,0 +1,110 @@
+//! verify_token cache: a second check of the same token is answered without
+//!  asking the sign process again, and revoke_session drops the cached entry
+//!
+//! every cache miss is exactly one verification request to sign:sign:sys, so
+//!  the miss counter is the count of sign requests verify_token made
+
+use hyperware_process_lib::Address;
+use serde_json::{json, Value};
+
+use crate::{RETRIES, TIMEOUT_SECS};
+use tester_lib::{assert_eq_ctx, call_json, call_ok, expect_err_code, step, CodedError, TestError};
+
+const AUDIENCE: &str = "id-test-cache";
+
+/// long enough that the token stays clear of the cache's expiry window
+const TOKEN_TTL_SECONDS: u64 = 600;
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+struct CacheStats {
+    hits: u64,
+    misses: u64,
+    entries: u64,
+}
+
+pub fn run(id: &Address) -> Result<(), TestError> {
+    let token: String = step!(
+        "issue",
+        call_ok::<_, _, CodedError>(
+            id,
+            &json!({ "IssueToken": [AUDIENCE, TOKEN_TTL_SECONDS, []] }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )
+    )?;
+
+    let before = stats(id)?;
+    let token_id = step!("first verify asks sign", {
+        let token_id = verify(id, &token)?;
+        let after = stats(id)?;
+        assert_eq_ctx!(
+            before.misses + 1,
+            after.misses,
+            "misses after the first verify"
+        )?;
+        assert_eq_ctx!(before.hits, after.hits, "hits after the first verify")?;
+        Ok::<_, TestError>(token_id)
+    })?;
+
+    let before = stats(id)?;
+    step!("second verify is answered from the cache", {
+        assert_eq_ctx!(
+            token_id,
+            verify(id, &token)?,
+            "token_id on the second verify"
+        )?;
+        let after = stats(id)?;
+        assert_eq_ctx!(
+            before.misses,
+            after.misses,
+            "sign requests (misses) after the second verify"
+        )?;
+        assert_eq_ctx!(before.hits + 1, after.hits, "hits after the second verify")
+    })?;
+
+    let before = stats(id)?;
+    step!("revoke_session drops the cached entry", {
+        let _: bool = call_ok::<_, _, CodedError>(
+            id,
+            &json!({ "RevokeSession": token_id }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )?;
+        let after = stats(id)?;
+        assert_eq_ctx!(before.entries - 1, after.entries, "entries after revoking")
+    })?;
+
+    let before = stats(id)?;
+    step!("verify after revoking asks sign again", {
+        let verdict: Result<Value, CodedError> = call_json(
+            id,
+            &json!({ "VerifyToken": [token, AUDIENCE] }),
+            TIMEOUT_SECS,
+            RETRIES,
+        )?;
+        expect_err_code(verdict, "token_revoked")?;
+        let after = stats(id)?;
+        assert_eq_ctx!(before.misses + 1, after.misses, "misses after revoking")?;
+        assert_eq_ctx!(before.hits, after.hits, "hits after revoking")
+    })
+}
+
+/// verify `token` for AUDIENCE; its token_id
+fn verify(id: &Address, token: &str) -> Result<String, TestError> {
+    let verified: Value = call_ok::<_, _, CodedError>(
+        id,
+        &json!({ "VerifyToken": [token, AUDIENCE] }),
+        TIMEOUT_SECS,
+        RETRIES,
+    )?;
+    verified["claims"]["token_id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| TestError::Assertion {
+            error: format!("verified token has no token_id: {verified}"),
+        })
+}
+
+fn stats(id: &Address) -> Result<CacheStats, TestError> {
+    call_ok::<_, _, CodedError>(id, &json!({ "GetCacheStats": null }), TIMEOUT_SECS, RETRIES)
+}
@@ -2,7 +2,8 @@ import { useState, useEffect, useCallback } from "react";
 import HyperwareClientApi from "@hyperware-ai/client-api";
 import "./App.css";
 import useIdStore from "./store/id";
-import { sign, verify, ApiError } from "../../target/ui/caller-utils";
+import { sign, verify, getIdentity, ApiError } from "../../target/ui/caller-utils";
+import { AppError, IdentityInfo } from "./types/Id";
 
 const BASE_URL = import.meta.env.BASE_URL;
 if (window.our) window.our.process = BASE_URL?.replace("/", "");
@@ -14,11 +15,23 @@ const WEBSOCKET_URL = import.meta.env.DEV
   ? `${PROXY_TARGET.replace('http', 'ws')}`
   : undefined;
 
+// Prefer the handler's error code, e.g. "invalid_signature", over the generic HTTP error
+function describeError(error: unknown): string {
+  if (error instanceof ApiError) {
+    const details = error.details as AppError | undefined;
+    return details?.code ? `${details.code}: ${details.message}` : error.message;
+  }
+  return String(error);
+}
+
 function App() {
   const { messageHistory, addSignedMessage, updateVerificationStatus } = useIdStore();
   const [message, setMessage] = useState("");
   const [nodeConnected, setNodeConnected] = useState(true);
   const [api, setApi] = useState<HyperwareClientApi | undefined>();
+  const [identity, setIdentity] = useState<IdentityInfo | undefined>();
+  const [identityError, setIdentityError] = useState<string | undefined>();
+  const [copied, setCopied] = useState(false);
 
   useEffect(() => {
     // Connect to the Hyperdrive via websocket
@@ -48,6 +61,31 @@ function App() {
     }
   }, []);
 
+  const loadIdentity = useCallback(async () => {
+    try {
+      setIdentity(await getIdentity());
+      setIdentityError(undefined);
+    } catch (error) {
+      console.error(error);
+      setIdentityError(describeError(error));
+    }
+  }, []);
+
+  useEffect(() => {
+    loadIdentity();
+  }, [loadIdentity]);
+
+  const copyIdentity = useCallback(async () => {
+    if (!identity) return;
+    const card = [
+      `node: ${identity.node_name}`,
+      `key (${identity.key_algorithm}): ${identity.public_key_hex}`,
+    ].join("\n");
+    await navigator.clipboard.writeText(card);
+    setCopied(true);
+    setTimeout(() => setCopied(false), 1500);
+  }, [identity]);
+
   const sendMessage = useCallback(
     async (event) => {
       event.preventDefault();
@@ -67,11 +105,7 @@ function App() {
         addSignedMessage(message, signature);
         setMessage("");
       } catch (error) {
-        if (error instanceof ApiError) {
-          console.error("API Error:", error.message, error.details);
-        } else {
-          console.error(error);
-        }
+        console.error("API Error:", describeError(error));
       }
     },
     [message, setMessage, addSignedMessage]
@@ -89,16 +123,12 @@ function App() {
 
       // Send a verification request via the verify function
       try {
-        const isValid = await verify(messageArray, signedMessage.signature);
+        const { valid: isValid } = await verify(messageArray, signedMessage.signature);
 
         // Update the verification status in the store
         updateVerificationStatus(index, isValid);
       } catch (error) {
-        if (error instanceof ApiError) {
-          console.error("API Error:", error.message, error.details);
-        } else {
-          console.error(error);
-        }
+        console.error("API Error:", describeError(error));
       }
     },
     [messageHistory, updateVerificationStatus]
@@ -119,6 +149,29 @@ function App() {
         </div>
       )}
       <h2>Signature Verifier</h2>
+      <div className="card identity-card">
+        <h3 style={{ marginTop: 0, textAlign: 'left' }}>Identity</h3>
+        {identity ? (
+          <>
+            <div className="identity-row">
+              <span className="identity-label">Node</span>
+              <span className="identity-value">{identity.node_name}</span>
+            </div>
+            <div className="identity-row">
+              <span className="identity-label">Public key ({identity.key_algorithm})</span>
+              <span className="identity-value identity-key">{identity.public_key_hex}</span>
+            </div>
+            <button onClick={copyIdentity}>{copied ? 'Copied' : 'Copy'}</button>
+          </>
+        ) : identityError ? (
+          <div className="identity-error">
+            Could not load identity: {identityError}{' '}
+            <button onClick={loadIdentity}>Retry</button>
+          </div>
+        ) : (
+          <div>Loading…</div>
+        )}
+      </div>
       <div className="card">
         <div style={{ border: "1px solid gray", padding: "1em" }}>
           <h3 style={{ marginTop: 0, textAlign: 'left' }}>Message History</h3>
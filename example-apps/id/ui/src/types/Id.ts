@@ -30,3 +30,19 @@ export interface VerifyResponse {
 export interface MessageHistory {
   messages: SignedMessage[]
 }
+
+// Public verification data for our node, as returned by get_identity
+export interface IdentityInfo {
+  node_name: string
+  public_key_hex: string
+  key_algorithm: string
+  retrieved_at_ms: number
+}
+
+// Every id handler fails with a stable code to branch on, a human-readable message,
+// and whether the same request may succeed if sent again later
+export interface AppError {
+  code: string
+  message: string
+  retryable: boolean
+}
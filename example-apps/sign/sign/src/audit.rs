@@ -0,0 +1,59 @@
+use std::collections::VecDeque;
+
+use crate::error::SignError;
+
+/// most entries kept; the oldest are dropped first
+const MAX_AUDIT_ENTRIES: usize = 1000;
+
+/// one sign-family or verification request handled by this process
+///
+/// only a hash and length of the payload are kept, never the payload itself
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AuditEntry {
+    pub timestamp_ms: u64,
+    /// full address of the requester, e.g. `node.os@id:id:sys`
+    pub caller: String,
+    /// handler name, e.g. `sign` or `remote_verify`
+    pub mode: String,
+    pub payload_sha256: String,
+    pub payload_len: u64,
+    /// `ok`, `valid`, `invalid`, or an error code
+    pub outcome: String,
+}
+
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+pub struct AuditLog {
+    entries: VecDeque<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn push(&mut self, entry: AuditEntry) {
+        if self.entries.len() >= MAX_AUDIT_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// the most recent `limit` entries at or after `since_ms`, oldest first
+    pub fn query(&self, since_ms: Option<u64>, limit: u32) -> Vec<AuditEntry> {
+        let matching: Vec<&AuditEntry> = self
+            .entries
+            .iter()
+            .filter(|e| since_ms.map_or(true, |since| e.timestamp_ms >= since))
+            .collect();
+        let skip = matching.len().saturating_sub(limit as usize);
+        matching.into_iter().skip(skip).cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// outcome recorded for a finished sign-family call
+pub fn outcome<T>(result: &Result<T, SignError>) -> String {
+    match result {
+        Ok(_) => "ok".to_string(),
+        Err(e) => e.code().to_string(),
+    }
+}
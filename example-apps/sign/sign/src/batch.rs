@@ -0,0 +1,273 @@
+use hyperware_process_lib::logging::warn;
+use hyperware_process_lib::Address;
+
+use crate::error::SignError;
+use crate::net::Net;
+
+/// most items accepted by a single sign_batch / verify_batch call
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// outcome of one item of verify_batch_detailed
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum VerifyItemOutcome {
+    Valid,
+    /// the signature does not match the message
+    Invalid,
+    /// the item could not be checked; carries a SignError code
+    Error(String),
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VerifyItemResult {
+    /// position of the item in the request
+    pub index: u32,
+    pub outcome: VerifyItemOutcome,
+}
+
+/// per-item outcomes, in request order, with totals
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VerifyBatchReport {
+    pub results: Vec<VerifyItemResult>,
+    pub valid: u32,
+    pub invalid: u32,
+    pub errors: u32,
+}
+
+impl VerifyBatchReport {
+    pub fn push(&mut self, index: usize, outcome: VerifyItemOutcome) {
+        match outcome {
+            VerifyItemOutcome::Valid => self.valid += 1,
+            VerifyItemOutcome::Invalid => self.invalid += 1,
+            VerifyItemOutcome::Error(_) => self.errors += 1,
+        }
+        self.results.push(VerifyItemResult {
+            index: index as u32,
+            outcome,
+        });
+    }
+}
+
+fn check_batch_size(len: usize) -> Result<(), SignError> {
+    if len > MAX_BATCH_SIZE {
+        return Err(SignError::InvalidInput(format!(
+            "batch of {len} items exceeds the limit of {MAX_BATCH_SIZE}"
+        )));
+    }
+    Ok(())
+}
+
+/// sign each message in order on behalf of `source`; a failed item yields an
+///  empty signature and is reported in the log rather than aborting the batch
+pub async fn sign_batch(
+    net: &mut impl Net,
+    source: &Address,
+    messages: Vec<Vec<u8>>,
+) -> Result<Vec<Vec<u8>>, SignError> {
+    check_batch_size(messages.len())?;
+
+    let mut signatures = Vec::with_capacity(messages.len());
+    let mut errors = Vec::new();
+    for (i, message) in messages.into_iter().enumerate() {
+        match crate::sign(net, source, message).await {
+            Ok(signature) => signatures.push(signature),
+            Err(e) => {
+                errors.push(format!("{i}: {e}"));
+                signatures.push(Vec::new());
+            }
+        }
+    }
+    if !errors.is_empty() {
+        warn!("sign_batch: {} item(s) failed: {:?}", errors.len(), errors);
+    }
+    Ok(signatures)
+}
+
+/// verify each (message, signature) pair in order as signed by `signer` on
+///  behalf of `source`; a failed item yields false and is reported in the
+///  log rather than aborting the batch
+pub async fn verify_batch(
+    net: &mut impl Net,
+    signer: &Address,
+    source: &Address,
+    items: Vec<(Vec<u8>, Vec<u8>)>,
+) -> Result<Vec<bool>, SignError> {
+    check_batch_size(items.len())?;
+
+    let mut results = Vec::with_capacity(items.len());
+    let mut errors = Vec::new();
+    for (i, (message, signature)) in items.into_iter().enumerate() {
+        match crate::verify(net, signer.clone(), source, message, signature).await {
+            Ok(is_good) => results.push(is_good),
+            Err(e) => {
+                errors.push(format!("{i}: {e}"));
+                results.push(false);
+            }
+        }
+    }
+    if !errors.is_empty() {
+        warn!(
+            "verify_batch: {} item(s) failed: {:?}",
+            errors.len(),
+            errors
+        );
+    }
+    Ok(results)
+}
+
+/// like verify_batch, but reports why each failed item failed
+///
+/// items with an empty message or signature are errors without asking net:distro:sys
+pub async fn verify_batch_detailed(
+    net: &mut impl Net,
+    signer: &Address,
+    source: &Address,
+    items: Vec<(Vec<u8>, Vec<u8>)>,
+) -> Result<VerifyBatchReport, SignError> {
+    check_batch_size(items.len())?;
+
+    let mut report = VerifyBatchReport::default();
+    for (i, (message, signature)) in items.into_iter().enumerate() {
+        let result = if message.is_empty() || signature.is_empty() {
+            Err(SignError::InvalidInput(
+                "empty message or signature".to_string(),
+            ))
+        } else {
+            crate::verify(net, signer.clone(), source, message, signature).await
+        };
+        let outcome = match result {
+            Ok(true) => VerifyItemOutcome::Valid,
+            Ok(false) => VerifyItemOutcome::Invalid,
+            Err(e) => VerifyItemOutcome::Error(e.code().to_string()),
+        };
+        report.push(i, outcome);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperware_process_lib::ProcessId;
+
+    use super::*;
+    use crate::net::fake::{block_on, FakeNet, FAKE_NODE};
+
+    fn signer() -> Address {
+        Address::new(FAKE_NODE, ProcessId::new(Some("sign"), "sign", "sys"))
+    }
+
+    fn caller() -> Address {
+        Address::new(FAKE_NODE, ProcessId::new(Some("app"), "app", "sys"))
+    }
+
+    fn messages() -> Vec<Vec<u8>> {
+        vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()]
+    }
+
+    /// every message signed through FakeNet on behalf of caller()
+    fn signed(net: &mut FakeNet) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let signatures = block_on(sign_batch(net, &caller(), messages())).unwrap();
+        messages().into_iter().zip(signatures).collect()
+    }
+
+    #[test]
+    fn verify_batch_marks_corrupted_signatures_in_order() {
+        let mut net = FakeNet::default();
+        let mut items = signed(&mut net);
+        items[1].1[0] ^= 0x01;
+        items[2].1.truncate(8);
+
+        let verdicts = block_on(verify_batch(&mut net, &signer(), &caller(), items)).unwrap();
+        assert_eq!(verdicts, vec![true, false, false]);
+    }
+
+    #[test]
+    fn signatures_are_bound_to_the_caller() {
+        let mut net = FakeNet::default();
+        let items = signed(&mut net);
+        let other = Address::new(FAKE_NODE, ProcessId::new(Some("other"), "app", "sys"));
+
+        let verdicts = block_on(verify_batch(&mut net, &signer(), &other, items)).unwrap();
+        assert_eq!(verdicts, vec![false, false, false]);
+    }
+
+    #[test]
+    fn failed_items_do_not_abort_the_batch() {
+        let mut net = FakeNet {
+            timeout_on: b"second".to_vec(),
+            ..FakeNet::default()
+        };
+        let signatures = block_on(sign_batch(&mut net, &caller(), messages())).unwrap();
+        assert_eq!(signatures.len(), 3);
+        assert!(signatures[1].is_empty());
+        assert!(!signatures[0].is_empty() && !signatures[2].is_empty());
+
+        let items = messages().into_iter().zip(signatures).collect();
+        let verdicts = block_on(verify_batch(&mut net, &signer(), &caller(), items)).unwrap();
+        assert_eq!(verdicts, vec![true, false, true]);
+    }
+
+    #[test]
+    fn detailed_report_gives_each_item_its_outcome() {
+        let mut net = FakeNet::default();
+        let mut items = signed(&mut net);
+        items[1].1[0] ^= 0x01;
+        items[2].0.clear();
+        let sent_before = net.requests;
+
+        let report =
+            block_on(verify_batch_detailed(&mut net, &signer(), &caller(), items)).unwrap();
+        let outcomes: Vec<_> = report
+            .results
+            .iter()
+            .map(|r| (r.index, r.outcome.clone()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (0, VerifyItemOutcome::Valid),
+                (1, VerifyItemOutcome::Invalid),
+                (2, VerifyItemOutcome::Error("invalid_input".to_string())),
+            ]
+        );
+        assert_eq!((report.valid, report.invalid, report.errors), (1, 1, 1));
+        assert_eq!(
+            net.requests - sent_before,
+            2,
+            "the empty item never reached net"
+        );
+    }
+
+    #[test]
+    fn detailed_report_carries_net_error_codes() {
+        let mut net = FakeNet::default();
+        let items = signed(&mut net);
+        net.timeout_on = b"third".to_vec();
+
+        let report =
+            block_on(verify_batch_detailed(&mut net, &signer(), &caller(), items)).unwrap();
+        assert_eq!(
+            report.results[2].outcome,
+            VerifyItemOutcome::Error("net_timeout".to_string())
+        );
+        assert_eq!((report.valid, report.invalid, report.errors), (2, 0, 1));
+    }
+
+    #[test]
+    fn batches_over_the_cap_are_refused() {
+        let mut net = FakeNet::default();
+        let too_many = vec![b"m".to_vec(); MAX_BATCH_SIZE + 1];
+        let expected = SignError::InvalidInput(format!(
+            "batch of {} items exceeds the limit of {MAX_BATCH_SIZE}",
+            MAX_BATCH_SIZE + 1
+        ));
+
+        let signed = block_on(sign_batch(&mut net, &caller(), too_many.clone()));
+        assert_eq!(signed, Err(expected.clone()));
+        let items = too_many.into_iter().map(|m| (m.clone(), m)).collect();
+        let verified = block_on(verify_batch(&mut net, &signer(), &caller(), items));
+        assert_eq!(verified, Err(expected));
+        assert_eq!(net.requests, 0, "nothing was sent for a refused batch");
+
+        assert_eq!(check_batch_size(MAX_BATCH_SIZE), Ok(()));
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use hyperware_process_lib::Address;
+
+use crate::envelope::push_field;
+
+/// how long a positive result is reused unless configured otherwise
+const DEFAULT_TTL_SECONDS: u64 = 60;
+
+/// negative results are kept for this fraction of the TTL, so a signature
+///  that failed during a transient problem is retried sooner
+const NEGATIVE_TTL_DIVISOR: u64 = 4;
+
+const DEFAULT_MAX_ENTRIES: u32 = 512;
+
+/// upper bound on the configurable size
+pub const MAX_CACHE_ENTRIES: u32 = 10_000;
+
+#[derive(Debug)]
+struct CachedResult {
+    is_good: bool,
+    expires_at_ms: u64,
+    /// recency counter for least-recently-used eviction
+    last_used: u64,
+}
+
+/// recent outcomes of net:distro:sys verify requests
+///
+/// the settings persist with SignState; the results themselves do not
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct VerifyCache {
+    enabled: bool,
+    ttl_seconds: u64,
+    max_entries: u32,
+    #[serde(skip)]
+    results: HashMap<Vec<u8>, CachedResult>,
+    #[serde(skip)]
+    uses: u64,
+}
+
+impl Default for VerifyCache {
+    fn default() -> Self {
+        VerifyCache {
+            enabled: true,
+            ttl_seconds: DEFAULT_TTL_SECONDS,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            results: HashMap::new(),
+            uses: 0,
+        }
+    }
+}
+
+impl VerifyCache {
+    /// change the settings; disabling or shrinking drops cached results
+    pub fn configure(&mut self, enabled: bool, ttl_seconds: u64, max_entries: u32) {
+        self.enabled = enabled;
+        self.ttl_seconds = ttl_seconds;
+        self.max_entries = max_entries.min(MAX_CACHE_ENTRIES);
+        self.results.clear();
+    }
+
+    pub fn get(&mut self, key: &[u8], now_ms: u64) -> Option<bool> {
+        if !self.enabled {
+            return None;
+        }
+        let cached = self.results.get_mut(key)?;
+        if now_ms >= cached.expires_at_ms {
+            self.results.remove(key);
+            return None;
+        }
+        self.uses += 1;
+        cached.last_used = self.uses;
+        Some(cached.is_good)
+    }
+
+    pub fn insert(&mut self, key: Vec<u8>, is_good: bool, now_ms: u64) {
+        if !self.enabled || self.max_entries == 0 {
+            return;
+        }
+
+        let mut ttl_ms = self.ttl_seconds.saturating_mul(1000);
+        if !is_good {
+            ttl_ms /= NEGATIVE_TTL_DIVISOR;
+        }
+
+        self.results.retain(|_, c| now_ms < c.expires_at_ms);
+        while self.results.len() >= self.max_entries as usize {
+            let Some(lru) = self
+                .results
+                .iter()
+                .min_by_key(|(_, c)| c.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.results.remove(&lru);
+        }
+
+        self.uses += 1;
+        self.results.insert(
+            key,
+            CachedResult {
+                is_good,
+                expires_at_ms: now_ms.saturating_add(ttl_ms),
+                last_used: self.uses,
+            },
+        );
+    }
+}
+
+/// SHA-256 of exactly what net:distro:sys is asked to check
+pub fn cache_key(from: &Address, message: &[u8], signature: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(message.len() + signature.len() + 96);
+    push_field(&mut bytes, from.to_string().as_bytes());
+    push_field(&mut bytes, message);
+    push_field(&mut bytes, signature);
+    Sha256::digest(&bytes).to_vec()
+}
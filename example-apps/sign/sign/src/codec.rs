@@ -0,0 +1,154 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use crate::error::SignError;
+
+/// strict standard base64: canonical padding required, no whitespace
+///
+/// `name` is the argument being decoded, so the error says which one was bad
+pub fn decode_b64(name: &str, value: &str) -> Result<Vec<u8>, SignError> {
+    BASE64
+        .decode(value)
+        .map_err(|e| SignError::InvalidInput(format!("{name} is not valid base64: {e}")))
+}
+
+/// hex in either case
+pub fn decode_hex(name: &str, value: &str) -> Result<Vec<u8>, SignError> {
+    hex::decode(value).map_err(|e| SignError::InvalidInput(format!("{name} is not valid hex: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperware_process_lib::{Address, ProcessId};
+
+    use super::*;
+    use crate::net::fake::{block_on, FakeNet, FAKE_NODE};
+
+    /// every byte value, so the top bit and both base64 specials appear
+    fn high_bytes() -> Vec<u8> {
+        (0..=255u8).rev().collect()
+    }
+
+    fn caller() -> Address {
+        Address::new(FAKE_NODE, ProcessId::new(Some("app"), "app", "sys"))
+    }
+
+    fn signer() -> Address {
+        Address::new(FAKE_NODE, ProcessId::new(Some("sign"), "sign", "sys"))
+    }
+
+    fn invalid_input(result: Result<Vec<u8>, SignError>) -> String {
+        match result {
+            Err(SignError::InvalidInput(message)) => message,
+            other => panic!("expected InvalidInput, got {other:?}"),
+        }
+    }
+
+    /// what sign_b64 then verify_b64 do around the net
+    fn sign_and_verify_b64(message: &[u8]) -> bool {
+        let mut net = FakeNet::default();
+        let message_b64 = BASE64.encode(message);
+
+        let decoded = decode_b64("message_b64", &message_b64).unwrap();
+        let signature = block_on(crate::sign(&mut net, &caller(), decoded)).unwrap();
+        let signature_b64 = BASE64.encode(signature);
+
+        let message = decode_b64("message_b64", &message_b64).unwrap();
+        let signature = decode_b64("signature_b64", &signature_b64).unwrap();
+        block_on(crate::verify(
+            &mut net,
+            signer(),
+            &caller(),
+            message,
+            signature,
+        ))
+        .unwrap()
+    }
+
+    /// what sign_hex then verify_hex do around the net
+    fn sign_and_verify_hex(message: &[u8]) -> bool {
+        let mut net = FakeNet::default();
+        let message_hex = hex::encode(message);
+
+        let decoded = decode_hex("message_hex", &message_hex).unwrap();
+        let signature = block_on(crate::sign(&mut net, &caller(), decoded)).unwrap();
+        let signature_hex = hex::encode(signature);
+
+        let message = decode_hex("message_hex", &message_hex.to_uppercase()).unwrap();
+        let signature = decode_hex("signature_hex", &signature_hex).unwrap();
+        block_on(crate::verify(
+            &mut net,
+            signer(),
+            &caller(),
+            message,
+            signature,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn empty_input_decodes_to_nothing() {
+        assert_eq!(decode_b64("message_b64", ""), Ok(Vec::new()));
+        assert_eq!(decode_hex("message_hex", ""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn empty_messages_sign_and_verify() {
+        assert!(sign_and_verify_b64(b""));
+        assert!(sign_and_verify_hex(b""));
+    }
+
+    #[test]
+    fn high_byte_input_round_trips() {
+        let bytes = high_bytes();
+        assert_eq!(
+            decode_b64("message_b64", &BASE64.encode(&bytes)),
+            Ok(bytes.clone())
+        );
+        assert_eq!(
+            decode_hex("message_hex", &hex::encode(&bytes)),
+            Ok(bytes.clone())
+        );
+        assert_eq!(decode_b64("message_b64", "/+8="), Ok(vec![0xff, 0xef]));
+        assert_eq!(
+            decode_hex("message_hex", "FFef80"),
+            Ok(vec![0xff, 0xef, 0x80])
+        );
+
+        assert!(sign_and_verify_b64(&bytes));
+        assert!(sign_and_verify_hex(&bytes));
+    }
+
+    #[test]
+    fn malformed_base64_names_the_argument() {
+        let message = invalid_input(decode_b64("signature_b64", "not*base64"));
+        assert!(
+            message.starts_with("signature_b64 is not valid base64: "),
+            "{message}"
+        );
+        assert!(message.contains("offset 3"), "{message}");
+
+        // padding is required, and url-safe or whitespace characters are not base64
+        for value in ["YQ", "YQ=", "_-8=", "YW Jj", "YWJj\n"] {
+            let message = invalid_input(decode_b64("message_b64", value));
+            assert!(
+                message.starts_with("message_b64 is not valid base64: "),
+                "{value:?}: {message}"
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_hex_names_the_argument() {
+        let message = invalid_input(decode_hex("signature_hex", "abc"));
+        assert!(
+            message.starts_with("signature_hex is not valid hex: "),
+            "{message}"
+        );
+        let message = invalid_input(decode_hex("message_hex", "0x12"));
+        assert!(
+            message.starts_with("message_hex is not valid hex: "),
+            "{message}"
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use sha2::{Digest, Sha256};
+
+use crate::envelope::push_field;
+use crate::error::SignError;
+
+/// domain tag for hash-then-sign statements
+pub const DIGEST_TAG: &str = "hyperware-sign-digest-v1";
+
+/// length of a SHA-256 digest in bytes
+pub const DIGEST_LEN: usize = 32;
+
+pub fn sha256(bytes: &[u8]) -> Vec<u8> {
+    Sha256::digest(bytes).to_vec()
+}
+
+/// bytes signed in digest mode: [tag, source, digest], each length-prefixed
+///
+/// raw-mode messages start with the ASCII source address while these start
+///  with the big-endian length of the tag (a NUL byte), so a digest-mode
+///  signature can never verify as a raw-mode one or vice versa
+pub fn digest_statement(tag: &str, source: &str, digest: &[u8]) -> Result<Vec<u8>, SignError> {
+    if tag != DIGEST_TAG || digest.len() != DIGEST_LEN {
+        return Err(SignError::EnvelopeFormat);
+    }
+
+    let mut bytes = Vec::with_capacity(tag.len() + source.len() + DIGEST_LEN + 12);
+    push_field(&mut bytes, tag.as_bytes());
+    push_field(&mut bytes, source.as_bytes());
+    push_field(&mut bytes, digest);
+    Ok(bytes)
+}
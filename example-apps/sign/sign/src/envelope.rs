@@ -0,0 +1,178 @@
+use sha2::{Digest, Sha256};
+
+use crate::error::SignError;
+
+/// domain tag prefixed to every envelope encoding so envelope signatures can
+///  never be confused with a plain sign() over the same bytes
+const ENVELOPE_TAG: &[u8] = b"hyperware-sign-envelope";
+
+/// domain tag for envelopes carrying a nonce and expiry
+const FRESH_ENVELOPE_TAG: &[u8] = b"hyperware-sign-fresh-envelope";
+
+/// envelopes issued before versioning; their encodings start with the tag
+pub const ENVELOPE_UNVERSIONED: u16 = 0;
+
+/// signer, time and payload hash
+pub const ENVELOPE_V1: u16 = 1;
+
+/// v1 plus nonce and expiry
+pub const ENVELOPE_V2: u16 = 2;
+
+/// a detached signature bundled with who signed it, when, and over what
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SignedEnvelope {
+    /// encoding the signature covers; absent on envelopes issued before versioning
+    #[serde(default)]
+    pub version: u16,
+    pub signer_node: String,
+    pub signed_at_ms: u64,
+    /// lowercase hex SHA-256 of the payload
+    pub payload_sha256: String,
+    pub signature: Vec<u8>,
+    /// byte length of the payload, bound into file envelopes so truncation is detectable
+    #[serde(default)]
+    pub payload_len: Option<u64>,
+    /// random 128-bit hex nonce, present on sign_with_nonce envelopes
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// end of validity, present on sign_with_nonce envelopes
+    #[serde(default)]
+    pub expires_at_ms: Option<u64>,
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// canonical bytes covered by `envelope`'s signature over `payload`,
+///  chosen by the envelope's version
+pub fn signed_bytes(envelope: &SignedEnvelope, payload: &[u8]) -> Result<Vec<u8>, SignError> {
+    let fresh = match (&envelope.nonce, envelope.expires_at_ms) {
+        (Some(nonce), Some(expires_at_ms)) => Some((nonce.as_str(), expires_at_ms)),
+        (None, None) => None,
+        _ => return Err(SignError::EnvelopeFormat),
+    };
+
+    match (envelope.version, fresh) {
+        (ENVELOPE_UNVERSIONED, None) => Ok(unversioned_bytes(
+            &envelope.signer_node,
+            envelope.signed_at_ms,
+            &envelope.payload_sha256,
+            payload,
+        )),
+        (ENVELOPE_UNVERSIONED, Some((nonce, expires_at_ms))) => Ok(unversioned_fresh_bytes(
+            &envelope.signer_node,
+            envelope.signed_at_ms,
+            expires_at_ms,
+            nonce,
+            &envelope.payload_sha256,
+            payload,
+        )),
+        (ENVELOPE_V1, None) => Ok(v1_bytes(
+            &envelope.signer_node,
+            envelope.signed_at_ms,
+            &envelope.payload_sha256,
+            payload,
+        )),
+        (ENVELOPE_V2, Some((nonce, expires_at_ms))) => Ok(v2_bytes(
+            &envelope.signer_node,
+            envelope.signed_at_ms,
+            expires_at_ms,
+            nonce,
+            &envelope.payload_sha256,
+            payload,
+        )),
+        (ENVELOPE_V1, Some(_)) | (ENVELOPE_V2, None) => Err(SignError::EnvelopeFormat),
+        (version, _) => Err(SignError::UnsupportedEnvelopeVersion(version)),
+    }
+}
+
+/// every field is length-prefixed (u32 big-endian) so no two distinct
+///  envelopes can encode to the same bytes:
+///
+/// [version (u16 BE), tag, signer_node, signed_at_ms (u64 BE), payload_sha256, payload]
+pub fn v1_bytes(
+    signer_node: &str,
+    signed_at_ms: u64,
+    payload_sha256: &str,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(ENVELOPE_TAG.len() + payload.len() + 128);
+    push_field(&mut bytes, &ENVELOPE_V1.to_be_bytes());
+    push_field(&mut bytes, ENVELOPE_TAG);
+    push_field(&mut bytes, signer_node.as_bytes());
+    push_field(&mut bytes, &signed_at_ms.to_be_bytes());
+    push_field(&mut bytes, payload_sha256.as_bytes());
+    push_field(&mut bytes, payload);
+    bytes
+}
+
+/// [version (u16 BE), tag, signer_node, signed_at_ms, expires_at_ms, nonce,
+///  payload_sha256, payload]
+pub fn v2_bytes(
+    signer_node: &str,
+    signed_at_ms: u64,
+    expires_at_ms: u64,
+    nonce: &str,
+    payload_sha256: &str,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(FRESH_ENVELOPE_TAG.len() + payload.len() + 160);
+    push_field(&mut bytes, &ENVELOPE_V2.to_be_bytes());
+    push_field(&mut bytes, FRESH_ENVELOPE_TAG);
+    push_field(&mut bytes, signer_node.as_bytes());
+    push_field(&mut bytes, &signed_at_ms.to_be_bytes());
+    push_field(&mut bytes, &expires_at_ms.to_be_bytes());
+    push_field(&mut bytes, nonce.as_bytes());
+    push_field(&mut bytes, payload_sha256.as_bytes());
+    push_field(&mut bytes, payload);
+    bytes
+}
+
+/// pre-versioning encoding, kept so previously issued envelopes still verify
+///
+/// [tag, signer_node, signed_at_ms (u64 BE), payload_sha256, payload]
+///
+/// versioned encodings start with a 2-byte length prefix where these start
+///  with the tag's, so the two can never collide
+fn unversioned_bytes(
+    signer_node: &str,
+    signed_at_ms: u64,
+    payload_sha256: &str,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(ENVELOPE_TAG.len() + payload.len() + 128);
+    push_field(&mut bytes, ENVELOPE_TAG);
+    push_field(&mut bytes, signer_node.as_bytes());
+    push_field(&mut bytes, &signed_at_ms.to_be_bytes());
+    push_field(&mut bytes, payload_sha256.as_bytes());
+    push_field(&mut bytes, payload);
+    bytes
+}
+
+/// pre-versioning fresh encoding
+///
+/// [tag, signer_node, signed_at_ms, expires_at_ms, nonce, payload_sha256, payload]
+fn unversioned_fresh_bytes(
+    signer_node: &str,
+    signed_at_ms: u64,
+    expires_at_ms: u64,
+    nonce: &str,
+    payload_sha256: &str,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(FRESH_ENVELOPE_TAG.len() + payload.len() + 160);
+    push_field(&mut bytes, FRESH_ENVELOPE_TAG);
+    push_field(&mut bytes, signer_node.as_bytes());
+    push_field(&mut bytes, &signed_at_ms.to_be_bytes());
+    push_field(&mut bytes, &expires_at_ms.to_be_bytes());
+    push_field(&mut bytes, nonce.as_bytes());
+    push_field(&mut bytes, payload_sha256.as_bytes());
+    push_field(&mut bytes, payload);
+    bytes
+}
+
+pub fn push_field(bytes: &mut Vec<u8>, field: &[u8]) {
+    bytes.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(field);
+}
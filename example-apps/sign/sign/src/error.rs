@@ -0,0 +1,121 @@
+use std::fmt;
+
+use app_error::AppError;
+use canonical_json::CanonicalError;
+use hyperware_app_common::AppSendError;
+use hyperware_process_lib::SendErrorKind;
+
+/// errors returned by every sign process handler
+///
+/// callers branch on code(), which is stable across releases; the detail
+///  strings carried by some variants are for humans only
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum SignError {
+    /// net:distro:sys did not answer in time; safe to retry
+    NetTimeout,
+    /// net:distro:sys answered a sign request without attaching the signature
+    NoSignatureBlob,
+    /// net:distro:sys answered with a response of the wrong kind
+    UnexpectedNetResponse,
+    /// the node name is malformed or unknown to the network
+    InvalidNodeName,
+    /// the caller is not allowed to use this handler
+    NotPermitted,
+    /// the caller may not use sign_raw; shares the `not_permitted` code
+    RawSigningNotPermitted,
+    /// the caller made too many requests; retry after this many milliseconds
+    RateLimited(u64),
+    /// an envelope or statement is malformed
+    EnvelopeFormat,
+    /// the envelope was issued in a newer format than this process understands
+    UnsupportedEnvelopeVersion(u16),
+    /// the request itself is unusable (e.g. batch too large)
+    InvalidInput(String),
+    /// a file could not be found or read
+    Vfs(String),
+    Internal(String),
+}
+
+impl SignError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SignError::NetTimeout => "net_timeout",
+            SignError::NoSignatureBlob => "no_signature_blob",
+            SignError::UnexpectedNetResponse => "unexpected_net_response",
+            SignError::InvalidNodeName => "invalid_node_name",
+            SignError::NotPermitted | SignError::RawSigningNotPermitted => "not_permitted",
+            SignError::RateLimited(_) => "rate_limited",
+            SignError::EnvelopeFormat => "envelope_format",
+            SignError::UnsupportedEnvelopeVersion(_) => "unsupported_envelope_version",
+            SignError::InvalidInput(_) => "invalid_input",
+            SignError::Vfs(_) => "vfs",
+            SignError::Internal(_) => "internal",
+        }
+    }
+
+    /// map a failed request to another process; timeouts get their own code
+    ///  so callers can retry them
+    pub fn from_send(e: AppSendError) -> Self {
+        match e {
+            AppSendError::SendError(e) if matches!(e.kind, SendErrorKind::Timeout) => {
+                SignError::NetTimeout
+            }
+            e => SignError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for SignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignError::InvalidInput(detail)
+            | SignError::Vfs(detail)
+            | SignError::Internal(detail) => write!(f, "{}: {detail}", self.code()),
+            SignError::RateLimited(retry_after_ms) => {
+                write!(f, "{}: retry after {retry_after_ms} ms", self.code())
+            }
+            SignError::UnsupportedEnvelopeVersion(version) => {
+                write!(f, "unsupported envelope version {version}")
+            }
+            SignError::RawSigningNotPermitted => {
+                write!(f, "{}: raw signing not permitted", self.code())
+            }
+            _ => write!(f, "{}", self.code()),
+        }
+    }
+}
+
+impl std::error::Error for SignError {}
+
+/// canonical JSON fails only on input that cannot be signed as JSON
+impl From<CanonicalError> for SignError {
+    fn from(e: CanonicalError) -> Self {
+        SignError::InvalidInput(e.to_string())
+    }
+}
+
+impl From<rmp_serde::encode::Error> for SignError {
+    fn from(e: rmp_serde::encode::Error) -> Self {
+        SignError::Internal(e.to_string())
+    }
+}
+
+/// the shared shape the other example apps answer with; sign's own codes
+///  carry over except a network timeout, which takes the shared `timeout`
+///  code so it comes back retryable
+impl From<SignError> for AppError {
+    fn from(e: SignError) -> Self {
+        let code = match e {
+            SignError::NetTimeout => app_error::TIMEOUT,
+            _ => e.code(),
+        };
+        let message = match &e {
+            SignError::InvalidInput(detail)
+            | SignError::Vfs(detail)
+            | SignError::Internal(detail) => detail.clone(),
+            SignError::RawSigningNotPermitted => "raw signing not permitted".to_string(),
+            other => other.to_string(),
+        };
+        AppError::new(code, message)
+    }
+}
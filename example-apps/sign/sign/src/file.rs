@@ -0,0 +1,72 @@
+use sha2::{Digest, Sha256};
+
+use hyperware_process_lib::vfs::{self, vfs_request, FileType, SeekFrom, VfsAction, VfsResponse};
+
+use hyperware_app_common::send;
+
+use crate::envelope::push_field;
+use crate::error::SignError;
+
+/// bytes read from the VFS per chunk while hashing a file
+const HASH_CHUNK_BYTES: u64 = 1024 * 1024;
+
+/// domain tag for file envelopes
+const FILE_ENVELOPE_TAG: &[u8] = b"hyperware-sign-file-envelope";
+
+/// SHA-256 and byte length of the file at `path`, read in chunks
+pub async fn hash_file(path: &str, timeout: u64) -> Result<(Vec<u8>, u64), SignError> {
+    let request = vfs_request(path, VfsAction::Metadata).expects_response(timeout);
+    let meta = match send::<VfsResponse>(request)
+        .await
+        .map_err(SignError::from_send)?
+    {
+        VfsResponse::Metadata(meta) => meta,
+        VfsResponse::Err(e) => {
+            return Err(SignError::Vfs(format!("file not found: {path} ({e:?})")))
+        }
+        _ => return Err(SignError::Vfs("unexpected response from VFS".to_string())),
+    };
+    if meta.file_type == FileType::Directory {
+        return Err(SignError::Vfs(format!(
+            "path is a directory, not a file: {path}"
+        )));
+    }
+
+    let mut file = vfs::open_file(path, false, Some(timeout))
+        .map_err(|e| SignError::Vfs(format!("failed to open {path}: {e}")))?;
+
+    let mut hasher = Sha256::new();
+    let mut offset = 0;
+    while offset < meta.len {
+        let chunk_len = (meta.len - offset).min(HASH_CHUNK_BYTES) as usize;
+        let mut chunk = vec![0u8; chunk_len];
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_at(&mut chunk))
+            .map_err(|e| {
+                SignError::Vfs(format!("failed to read {path} at offset {offset}: {e}"))
+            })?;
+        hasher.update(&chunk);
+        offset += chunk_len as u64;
+    }
+
+    Ok((hasher.finalize().to_vec(), meta.len))
+}
+
+/// SHA-256 of the canonical file-envelope fields; this digest is what gets
+///  signed through the hash-then-sign statement
+///
+/// [tag, signer_node, signed_at_ms (u64 BE), payload_sha256, payload_len (u64 BE)]
+pub fn file_envelope_digest(
+    signer_node: &str,
+    signed_at_ms: u64,
+    payload_sha256: &str,
+    payload_len: u64,
+) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    push_field(&mut bytes, FILE_ENVELOPE_TAG);
+    push_field(&mut bytes, signer_node.as_bytes());
+    push_field(&mut bytes, &signed_at_ms.to_be_bytes());
+    push_field(&mut bytes, payload_sha256.as_bytes());
+    push_field(&mut bytes, &payload_len.to_be_bytes());
+    Sha256::digest(&bytes).to_vec()
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+/// most nonces remembered at once; past this the soonest-expiring are dropped
+const MAX_SEEN_NONCES: usize = 10_000;
+
+/// default allowance for clock differences between signer and verifier
+pub const DEFAULT_CLOCK_SKEW_MS: u64 = 5_000;
+
+/// most clock skew that may be configured
+pub const MAX_CLOCK_SKEW_MS: u64 = 60_000;
+
+/// outcome of verify_fresh
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum VerifyOutcome {
+    Valid,
+    Expired,
+    BadSignature,
+    ReplayedNonce,
+}
+
+/// nonces of fresh envelopes that have already verified, with their expiry
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SeenNonces {
+    expires_at_ms: HashMap<String, u64>,
+}
+
+impl SeenNonces {
+    /// record `nonce` as used; false if it had already been seen
+    pub fn check_and_record(&mut self, nonce: &str, expires_at_ms: u64, now_ms: u64) -> bool {
+        if self.expires_at_ms.contains_key(nonce) {
+            return false;
+        }
+
+        // expired envelopes are rejected before reaching here, so their nonces
+        //  no longer need remembering
+        self.expires_at_ms.retain(|_, expires| *expires >= now_ms);
+        while self.expires_at_ms.len() >= MAX_SEEN_NONCES {
+            let Some(soonest) = self
+                .expires_at_ms
+                .iter()
+                .min_by_key(|(_, expires)| **expires)
+                .map(|(nonce, _)| nonce.clone())
+            else {
+                break;
+            };
+            self.expires_at_ms.remove(&soonest);
+        }
+
+        self.expires_at_ms.insert(nonce.to_string(), expires_at_ms);
+        true
+    }
+}
+
+pub fn random_nonce() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
@@ -0,0 +1,43 @@
+use hyperware_process_lib::net::Identity;
+
+use crate::error::SignError;
+
+/// how long a fetched identity is served from memory
+pub const IDENTITY_CACHE_MS: u64 = 60_000;
+
+/// networking keys on Hyperware are Ed25519
+const KEY_ALGORITHM: &str = "ed25519";
+
+/// what an outside verifier needs to check our node's signatures
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IdentityInfo {
+    pub node_name: String,
+    /// lowercase hex public networking key, without a `0x` prefix
+    pub public_key_hex: String,
+    pub key_algorithm: String,
+    pub retrieved_at_ms: u64,
+}
+
+impl IdentityInfo {
+    pub fn from_peer(peer: Identity, retrieved_at_ms: u64) -> Result<Self, SignError> {
+        let key = peer
+            .networking_key
+            .strip_prefix("0x")
+            .unwrap_or(&peer.networking_key)
+            .to_lowercase();
+        if key.is_empty() || hex::decode(&key).is_err() {
+            return Err(SignError::UnexpectedNetResponse);
+        }
+
+        Ok(IdentityInfo {
+            node_name: peer.name,
+            public_key_hex: key,
+            key_algorithm: KEY_ALGORITHM.to_string(),
+            retrieved_at_ms,
+        })
+    }
+
+    pub fn is_fresh(&self, now_ms: u64) -> bool {
+        now_ms < self.retrieved_at_ms.saturating_add(IDENTITY_CACHE_MS)
+    }
+}
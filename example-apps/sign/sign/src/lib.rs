@@ -1,59 +1,115 @@
-use anyhow::anyhow;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 
 use hyperware_process_lib::logging::{init_logging, Level};
-use hyperware_process_lib::net::{NetAction, NetResponse};
-use hyperware_process_lib::{last_blob, our, LazyLoadBlob, Request};
+use hyperware_process_lib::{our, Address, ProcessId};
 
-use hyperware_app_common::{send_rmp, source};
 use hyperprocess_macro::hyperprocess;
+use hyperware_app_common::source;
 
-#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
-struct SignState {}
+mod audit;
+mod batch;
+mod cache;
+mod codec;
+mod digest;
+mod envelope;
+mod error;
+mod file;
+mod fresh;
+mod identity;
+mod limit;
+mod net;
+mod raw;
+mod session;
+mod timestamp;
 
-async fn sign(message: Vec<u8>) -> anyhow::Result<Vec<u8>> {
-    let message = make_message(&message);
-    let body = rmp_serde::to_vec(&NetAction::Sign)?;
+use audit::{AuditEntry, AuditLog};
+use batch::VerifyBatchReport;
+use cache::VerifyCache;
+use codec::{decode_b64, decode_hex};
+use error::SignError;
+use identity::IdentityInfo;
+use limit::RateLimiter;
+use net::{Net, NetDistro, DEFAULT_NET_TIMEOUT, MAX_NET_TIMEOUT, MIN_NET_TIMEOUT};
+use session::SignSessions;
+use timestamp::{TimestampCheck, TimestampedSignature};
 
-    let req = Request::to(("our", "net", "distro", "sys"))
-        .expects_response(5)
-        .blob(LazyLoadBlob {
-            mime: None,
-            bytes: message,
-        })
-        .body(body);
+use digest::{digest_statement, sha256, DIGEST_TAG};
+use envelope::{
+    sha256_hex, signed_bytes, v1_bytes, v2_bytes, SignedEnvelope, ENVELOPE_V1, ENVELOPE_V2,
+};
+use fresh::{SeenNonces, VerifyOutcome, DEFAULT_CLOCK_SKEW_MS, MAX_CLOCK_SKEW_MS};
 
-    let _resp: NetResponse = send_rmp(req).await?;
+/// remote_verify requests allowed per requesting node per window
+const REMOTE_VERIFY_LIMIT: u32 = 60;
+const REMOTE_VERIFY_WINDOW_MS: u64 = 60_000;
 
-    let Some(signature) = last_blob() else {
-        return Err(anyhow!("no blob"));
-    };
+/// may always manage signing permissions, alongside any configured admin process
+const TERMINAL_PROCESS: &str = "terminal:terminal:sys";
 
-    Ok(signature.bytes)
-}
+/// granted signing on first start so existing id flows keep working
+const DEFAULT_SIGNER: &str = "id:id:sys";
 
-async fn verify(message: Vec<u8>, signature: Vec<u8>) -> anyhow::Result<bool> {
-    let message = make_message(&message);
-    let body = rmp_serde::to_vec(&NetAction::Verify {
-        from: our(),
-        signature,
-    })?;
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+struct SignState {
+    /// processes allowed to call the sign family of handlers
+    signers: HashSet<String>,
+    /// set once DEFAULT_SIGNER has been granted, so revoking it sticks
+    signers_initialized: bool,
+    /// process allowed to manage permissions besides the terminal
+    admin_process: Option<String>,
+    /// processes allowed to call sign_raw
+    raw_signers: HashSet<String>,
+    /// nonces of fresh envelopes that have already been accepted
+    seen_nonces: SeenNonces,
+    /// allowance for signer/verifier clock differences in freshness checks;
+    ///  None means DEFAULT_CLOCK_SKEW_MS
+    clock_skew_ms: Option<u64>,
+    /// per-node limiter for remote_verify
+    remote_verify_limiter: RateLimiter,
+    audit: AuditLog,
+    /// open chunked-signing sessions
+    #[serde(skip)]
+    sessions: SignSessions,
+    /// recent verify outcomes; never consulted by verify_fresh
+    #[serde(default)]
+    verify_cache: VerifyCache,
+    /// seconds to wait for net:distro:sys per request; None means DEFAULT_NET_TIMEOUT
+    #[serde(default)]
+    net_timeout_secs: Option<u64>,
+    /// our node's identity as last fetched by get_identity
+    #[serde(skip)]
+    identity: Option<IdentityInfo>,
+}
 
-    let req = Request::to(("our", "net", "distro", "sys"))
-        .expects_response(5)
-        .blob(LazyLoadBlob {
-            mime: None,
-            bytes: message,
-        })
-        .body(body);
+/// sign `message` on behalf of `source`, whose address is prepended
+async fn sign(
+    net: &mut impl Net,
+    source: &Address,
+    message: Vec<u8>,
+) -> Result<Vec<u8>, SignError> {
+    net.sign(make_message(source, &message)).await
+}
 
-    let resp: NetResponse = send_rmp(req).await?;
+/// check a sign() signature made by `signer` on behalf of `source`
+async fn verify(
+    net: &mut impl Net,
+    signer: Address,
+    source: &Address,
+    message: Vec<u8>,
+    signature: Vec<u8>,
+) -> Result<bool, SignError> {
+    net.verify(signer, make_message(source, &message), signature)
+        .await
+}
 
-    match resp {
-        NetResponse::Verified(is_good) => {
-            Ok(is_good)
-        }
-        _ => Err(anyhow!("weird response")),
-    }
+/// the address of the sign process on `node`: the identity net:distro:sys
+///  attributes our signatures to
+fn sign_address(node: &str) -> Address {
+    Address::new(node, our().process.clone())
 }
 
 /// net:distro:sys prepends the message to sign with the sender of the request
@@ -65,30 +121,953 @@ async fn verify(message: Vec<u8>, signature: Vec<u8>) -> anyhow::Result<bool> {
 /// so final message to be signed looks like
 ///
 /// [sign-address, source, bytes].concat()
-fn make_message(bytes: &Vec<u8>) -> Vec<u8> {
-    [source().to_string().as_bytes(), &bytes].concat()
+fn make_message(source: &Address, bytes: &[u8]) -> Vec<u8> {
+    [source.to_string().as_bytes(), bytes].concat()
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+async fn sign_envelope(net_timeout: u64, message: Vec<u8>) -> Result<SignedEnvelope, SignError> {
+    let signer_node = our().node.clone();
+    let signed_at_ms = now_ms();
+    let payload_sha256 = sha256_hex(&message);
+
+    let bytes = v1_bytes(&signer_node, signed_at_ms, &payload_sha256, &message);
+    let signature = sign(&mut NetDistro::uncached(net_timeout), &source(), bytes).await?;
+
+    Ok(SignedEnvelope {
+        version: ENVELOPE_V1,
+        signer_node,
+        signed_at_ms,
+        payload_sha256,
+        signature,
+        payload_len: None,
+        nonce: None,
+        expires_at_ms: None,
+    })
+}
+
+/// recompute the payload hash and the canonical envelope bytes for the
+///  envelope's version and check the signature against the envelope's signer
+///
+/// a tampered field or payload yields Ok(false), not an error; expiry is
+///  only enforced by verify_fresh
+async fn verify_envelope(
+    net_timeout: u64,
+    cache: Option<&mut VerifyCache>,
+    message: Vec<u8>,
+    envelope: SignedEnvelope,
+) -> Result<bool, SignError> {
+    let bytes = signed_bytes(&envelope, &message)?;
+    if sha256_hex(&message) != envelope.payload_sha256 {
+        return Ok(false);
+    }
+    verify_signed_by(
+        net_timeout,
+        cache,
+        &envelope.signer_node,
+        &bytes,
+        envelope.signature,
+    )
+    .await
+}
+
+/// sign the canonical form of a JSON document so that producer and verifier
+///  agree on the bytes regardless of key order or formatting
+async fn sign_json(net_timeout: u64, json: String) -> Result<SignedEnvelope, SignError> {
+    sign_envelope(
+        net_timeout,
+        canonical_json::canonicalize(&json)?.into_bytes(),
+    )
+    .await
+}
+
+async fn verify_json(
+    net_timeout: u64,
+    cache: Option<&mut VerifyCache>,
+    json: String,
+    envelope: SignedEnvelope,
+) -> Result<bool, SignError> {
+    verify_envelope(
+        net_timeout,
+        cache,
+        canonical_json::canonicalize(&json)?.into_bytes(),
+        envelope,
+    )
+    .await
+}
+
+/// sign with a random nonce and an expiry so the signature cannot be
+///  replayed after `ttl_seconds` or accepted twice by verify_fresh
+async fn sign_with_nonce(
+    net_timeout: u64,
+    message: Vec<u8>,
+    ttl_seconds: u64,
+) -> Result<SignedEnvelope, SignError> {
+    let signer_node = our().node.clone();
+    let signed_at_ms = now_ms();
+    let expires_at_ms = signed_at_ms.saturating_add(ttl_seconds.saturating_mul(1000));
+    let nonce = fresh::random_nonce();
+    let payload_sha256 = sha256_hex(&message);
+
+    let bytes = v2_bytes(
+        &signer_node,
+        signed_at_ms,
+        expires_at_ms,
+        &nonce,
+        &payload_sha256,
+        &message,
+    );
+    let signature = sign(&mut NetDistro::uncached(net_timeout), &source(), bytes).await?;
+
+    Ok(SignedEnvelope {
+        version: ENVELOPE_V2,
+        signer_node,
+        signed_at_ms,
+        payload_sha256,
+        signature,
+        payload_len: None,
+        nonce: Some(nonce),
+        expires_at_ms: Some(expires_at_ms),
+    })
+}
+
+/// check the signature and expiry of a sign_with_nonce envelope
+///
+/// replay detection needs state, so it is left to the handler
+async fn verify_fresh(
+    net_timeout: u64,
+    message: Vec<u8>,
+    envelope: &SignedEnvelope,
+    clock_skew_ms: u64,
+) -> Result<VerifyOutcome, SignError> {
+    let Some(expires_at_ms) = envelope.expires_at_ms else {
+        return Err(SignError::EnvelopeFormat);
+    };
+    let bytes = signed_bytes(envelope, &message)?;
+    if sha256_hex(&message) != envelope.payload_sha256 {
+        return Ok(VerifyOutcome::BadSignature);
+    }
+    // freshness results are never cached: a replay must always reach the nonce check
+    if !verify_signed_by(
+        net_timeout,
+        None,
+        &envelope.signer_node,
+        &bytes,
+        envelope.signature.clone(),
+    )
+    .await?
+    {
+        return Ok(VerifyOutcome::BadSignature);
+    }
+    if now_ms() > expires_at_ms.saturating_add(clock_skew_ms) {
+        return Ok(VerifyOutcome::Expired);
+    }
+    Ok(VerifyOutcome::Valid)
+}
+
+/// verify a signature produced through the sign process on another node
+///
+/// the signer's source prefix cannot be observed from here, so it is
+///  reconstructed as `node@<our caller's process>`: a signature made by
+///  id:id:sys on node A verifies when id:id:sys on node B asks about node A
+async fn verify_from(
+    net_timeout: u64,
+    cache: Option<&mut VerifyCache>,
+    node: String,
+    message: Vec<u8>,
+    signature: Vec<u8>,
+) -> Result<bool, SignError> {
+    if node != our().node && !net::knows_node(net_timeout, &node).await? {
+        return Err(SignError::InvalidNodeName);
+    }
+    verify_signed_by(net_timeout, cache, &node, &message, signature).await
+}
+
+/// verify `signature` over `bytes` as signed by our caller's process on `node`
+async fn verify_signed_by(
+    net_timeout: u64,
+    cache: Option<&mut VerifyCache>,
+    node: &str,
+    bytes: &[u8],
+    signature: Vec<u8>,
+) -> Result<bool, SignError> {
+    let signer_source = Address::new(node, source().process.clone());
+    net::verify(
+        net_timeout,
+        cache,
+        sign_address(node),
+        make_message(&signer_source, bytes),
+        signature,
+    )
+    .await
+}
+
+/// hash-then-sign: only the SHA-256 of `message` is sent to net:distro:sys,
+///  wrapped in a domain-separated statement with the caller's address
+async fn sign_digest(net_timeout: u64, message: Vec<u8>) -> Result<Vec<u8>, SignError> {
+    let statement = digest_statement(DIGEST_TAG, &source().to_string(), &sha256(&message))?;
+    net::sign(net_timeout, statement).await
+}
+
+async fn verify_digest(
+    net_timeout: u64,
+    cache: Option<&mut VerifyCache>,
+    message: Vec<u8>,
+    signature: Vec<u8>,
+) -> Result<bool, SignError> {
+    let statement = digest_statement(DIGEST_TAG, &source().to_string(), &sha256(&message))?;
+    net::verify(net_timeout, cache, our(), statement, signature).await
+}
+
+/// sign a file in the VFS without the caller shipping its bytes
+///
+/// the file is hashed in chunks; the signature covers a hash-then-sign
+///  statement over the file envelope fields, including the byte length
+async fn sign_file(net_timeout: u64, path: String) -> Result<SignedEnvelope, SignError> {
+    let (digest, payload_len) = file::hash_file(&path, 5).await?;
+    sign_file_digest(net_timeout, digest, payload_len).await
+}
+
+/// sign a file envelope for a payload already hashed, in chunks, elsewhere
+///
+/// the result verifies with verify_file against the same bytes stored in the VFS
+async fn sign_file_digest(
+    net_timeout: u64,
+    digest: Vec<u8>,
+    payload_len: u64,
+) -> Result<SignedEnvelope, SignError> {
+    let signer_node = our().node.clone();
+    let signed_at_ms = now_ms();
+    let payload_sha256 = hex::encode(digest);
+
+    let envelope_digest =
+        file::file_envelope_digest(&signer_node, signed_at_ms, &payload_sha256, payload_len);
+    let statement = digest_statement(DIGEST_TAG, &source().to_string(), &envelope_digest)?;
+    let signature = net::sign(net_timeout, statement).await?;
+
+    Ok(SignedEnvelope {
+        version: ENVELOPE_V1,
+        signer_node,
+        signed_at_ms,
+        payload_sha256,
+        signature,
+        payload_len: Some(payload_len),
+        nonce: None,
+        expires_at_ms: None,
+    })
+}
+
+/// re-hash the file at `path` and check it against a sign_file envelope
+///
+/// a modified or truncated file yields Ok(false), not an error
+async fn verify_file(
+    net_timeout: u64,
+    cache: Option<&mut VerifyCache>,
+    path: String,
+    envelope: SignedEnvelope,
+) -> Result<bool, SignError> {
+    let Some(expected_len) = envelope.payload_len else {
+        // not a file envelope
+        return Err(SignError::EnvelopeFormat);
+    };
+    // file envelopes have one layout, covered by its own tag; v1 is the
+    //  only version issued for them
+    if envelope.version > ENVELOPE_V1 {
+        return Err(SignError::UnsupportedEnvelopeVersion(envelope.version));
+    }
+    let (digest, payload_len) = file::hash_file(&path, 5).await?;
+    if payload_len != expected_len || hex::encode(digest) != envelope.payload_sha256 {
+        return Ok(false);
+    }
+
+    let envelope_digest = file::file_envelope_digest(
+        &envelope.signer_node,
+        envelope.signed_at_ms,
+        &envelope.payload_sha256,
+        payload_len,
+    );
+    let signer_source = Address::new(&envelope.signer_node, source().process.clone());
+    let statement = digest_statement(DIGEST_TAG, &signer_source.to_string(), &envelope_digest)?;
+
+    net::verify(
+        net_timeout,
+        cache,
+        sign_address(&envelope.signer_node),
+        statement,
+        envelope.signature,
+    )
+    .await
+}
+
+/// sign `message` with the current time bound into the signed bytes
+async fn sign_and_timestamp(
+    net_timeout: u64,
+    message: Vec<u8>,
+) -> Result<TimestampedSignature, SignError> {
+    let issued_at = timestamp::format_rfc3339_ms(now_ms());
+    let statement =
+        timestamp::timestamp_statement(&source().to_string(), &issued_at, &sha256(&message));
+    let signature = net::sign(net_timeout, statement).await?;
+
+    Ok(TimestampedSignature {
+        signer_node: our().node.clone(),
+        issued_at,
+        payload_sha256: sha256_hex(&message),
+        signature,
+    })
+}
+
+/// check a sign_and_timestamp signature and return when it was issued
+///
+/// a malformed issued_at, or one further in the future than `clock_skew_ms`,
+///  is an error; a signature that does not match is Ok with is_good false
+async fn verify_timestamped(
+    net_timeout: u64,
+    cache: Option<&mut VerifyCache>,
+    message: Vec<u8>,
+    timestamped: TimestampedSignature,
+    clock_skew_ms: u64,
+) -> Result<TimestampCheck, SignError> {
+    let issued_at_ms = timestamp::parse_rfc3339_ms(&timestamped.issued_at)?;
+    if issued_at_ms > now_ms().saturating_add(clock_skew_ms) {
+        return Err(SignError::InvalidInput(format!(
+            "issued_at {} is in the future",
+            timestamped.issued_at
+        )));
+    }
+
+    let digest = sha256(&message);
+    if hex::encode(&digest) != timestamped.payload_sha256 {
+        return Ok(TimestampCheck {
+            is_good: false,
+            issued_at_ms,
+        });
+    }
+
+    let signer_source = Address::new(&timestamped.signer_node, source().process.clone());
+    let statement =
+        timestamp::timestamp_statement(&signer_source.to_string(), &timestamped.issued_at, &digest);
+    let is_good = net::verify(
+        net_timeout,
+        cache,
+        sign_address(&timestamped.signer_node),
+        statement,
+        timestamped.signature,
+    )
+    .await?;
+
+    Ok(TimestampCheck {
+        is_good,
+        issued_at_ms,
+    })
+}
+
+/// our node's public verification data, as net:distro:sys knows it
+async fn get_identity(net_timeout: u64) -> Result<IdentityInfo, SignError> {
+    let node = our().node.clone();
+    let Some(peer) = net::peer(net_timeout, &node).await? else {
+        // net always knows our own node
+        return Err(SignError::UnexpectedNetResponse);
+    };
+    IdentityInfo::from_peer(peer, now_ms())
+}
+
+fn parse_process_id(process_id: &str) -> Result<String, SignError> {
+    ProcessId::from_str(process_id)
+        .map(|p| p.to_string())
+        .map_err(|e| SignError::InvalidInput(format!("invalid process id {process_id}: {e}")))
+}
+
+impl SignState {
+    /// only the terminal or the configured admin process may manage permissions
+    fn require_admin(&self) -> Result<(), SignError> {
+        let caller = source().process.to_string();
+        if caller == TERMINAL_PROCESS || self.admin_process.as_deref() == Some(caller.as_str()) {
+            Ok(())
+        } else {
+            Err(SignError::NotPermitted)
+        }
+    }
+
+    fn net_timeout(&self) -> u64 {
+        self.net_timeout_secs.unwrap_or(DEFAULT_NET_TIMEOUT)
+    }
+
+    /// net:distro:sys with the configured timeout, verifying through the cache
+    fn net(&mut self) -> NetDistro<'_> {
+        NetDistro {
+            timeout: self.net_timeout(),
+            cache: Some(&mut self.verify_cache),
+        }
+    }
+
+    /// sign() behind the allowlist, recorded in the audit log under `mode`
+    async fn sign_audited(&mut self, mode: &str, message: Vec<u8>) -> Result<Vec<u8>, SignError> {
+        let payload_sha256 = sha256_hex(&message);
+        let payload_len = message.len() as u64;
+        let result = match self.require_signer() {
+            Ok(()) => sign(&mut self.net(), &source(), message).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit(mode, payload_sha256, payload_len, &result);
+        result
+    }
+
+    /// log a sign-family call, including ones refused by require_signer
+    fn record_audit<T>(
+        &mut self,
+        mode: &str,
+        payload_sha256: String,
+        payload_len: u64,
+        result: &Result<T, SignError>,
+    ) {
+        self.audit.push(AuditEntry {
+            timestamp_ms: now_ms(),
+            caller: source().to_string(),
+            mode: mode.to_string(),
+            payload_sha256,
+            payload_len,
+            outcome: audit::outcome(result),
+        });
+    }
+
+    /// only allowlisted processes may produce signatures in our node's name
+    fn require_signer(&self) -> Result<(), SignError> {
+        if self.signers.contains(&source().process.to_string()) {
+            Ok(())
+        } else {
+            Err(SignError::NotPermitted)
+        }
+    }
 }
 
 #[hyperprocess(
     name = "sign",
     ui = None,
     endpoints = vec![],
-    save_config = SaveOptions::Never,
+    save_config = SaveOptions::OnDiff,
     wit_world = "sign-sys-v0",
 )]
 impl SignState {
     #[init]
     async fn init(&mut self) {
         init_logging(Level::DEBUG, Level::INFO, None, None, None).unwrap();
+
+        if !self.signers_initialized {
+            self.signers.insert(DEFAULT_SIGNER.to_string());
+            self.signers_initialized = true;
+        }
+    }
+
+    #[local]
+    async fn sign(&mut self, message: Vec<u8>) -> Result<Vec<u8>, SignError> {
+        self.sign_audited("sign", message).await
+    }
+
+    /// sign() for callers that can only pass strings: base64 in, base64 out
+    #[local]
+    async fn sign_b64(&mut self, message_b64: String) -> Result<String, SignError> {
+        let message = decode_b64("message_b64", &message_b64)?;
+        let signature = self.sign_audited("sign_b64", message).await?;
+        Ok(BASE64.encode(signature))
+    }
+
+    /// sign() for callers that can only pass strings: hex in, lowercase hex out
+    #[local]
+    async fn sign_hex(&mut self, message_hex: String) -> Result<String, SignError> {
+        let message = decode_hex("message_hex", &message_hex)?;
+        let signature = self.sign_audited("sign_hex", message).await?;
+        Ok(hex::encode(signature))
+    }
+
+    /// sign bytes exactly as given, for interop with non-Hyperware verifiers
+    ///
+    /// only allowed signers also granted via grant_raw_signing may use this;
+    ///  anyone else gets "raw signing not permitted"
+    #[local]
+    async fn sign_raw(&mut self, message: Vec<u8>) -> Result<Vec<u8>, SignError> {
+        let payload_sha256 = sha256_hex(&message);
+        let payload_len = message.len() as u64;
+        let caller = source().process.to_string();
+        let result = match raw::check_raw_signer(&self.signers, &self.raw_signers, &caller) {
+            Ok(()) => raw::sign_raw(&mut self.net(), message).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit("sign_raw", payload_sha256, payload_len, &result);
+        result
+    }
+
+    /// start a chunked signing session for a payload too large to send at once
+    #[local]
+    async fn begin_sign_session(&mut self) -> Result<String, SignError> {
+        let result = match self.require_signer() {
+            Ok(()) => self.sessions.begin(&source().to_string(), now_ms()),
+            Err(e) => Err(e),
+        };
+        self.record_audit("begin_sign_session", String::new(), 0, &result);
+        result
+    }
+
+    #[local]
+    async fn sign_session_update(
+        &mut self,
+        session_id: String,
+        chunk: Vec<u8>,
+    ) -> Result<(), SignError> {
+        self.require_signer()?;
+        self.sessions
+            .update(&source().to_string(), &session_id, &chunk, now_ms())
+    }
+
+    /// sign everything fed to the session as a file envelope and close it
+    ///
+    /// if signing fails the session stays open so the caller can retry
+    #[local]
+    async fn finish_sign_session(
+        &mut self,
+        session_id: String,
+    ) -> Result<SignedEnvelope, SignError> {
+        let owner = source().to_string();
+        let digest = match self.require_signer() {
+            Ok(()) => self.sessions.digest(&owner, &session_id, now_ms()),
+            Err(e) => Err(e),
+        };
+        let (payload_sha256, payload_len) = match &digest {
+            Ok((digest, len)) => (hex::encode(digest), *len),
+            Err(_) => (String::new(), 0),
+        };
+        let result = match digest {
+            Ok((digest, len)) => sign_file_digest(self.net_timeout(), digest, len).await,
+            Err(e) => Err(e),
+        };
+        if result.is_ok() {
+            self.sessions.remove(&owner, &session_id);
+        }
+        self.record_audit("finish_sign_session", payload_sha256, payload_len, &result);
+        result
+    }
+
+    #[local]
+    async fn cancel_sign_session(&mut self, session_id: String) -> Result<bool, SignError> {
+        Ok(self.sessions.remove(&source().to_string(), &session_id))
+    }
+
+    #[local]
+    async fn grant_signer(&mut self, process_id: String) -> Result<(), SignError> {
+        self.require_admin()?;
+        self.signers.insert(parse_process_id(&process_id)?);
+        Ok(())
+    }
+
+    #[local]
+    async fn revoke_signer(&mut self, process_id: String) -> Result<bool, SignError> {
+        self.require_admin()?;
+        Ok(self.signers.remove(&parse_process_id(&process_id)?))
+    }
+
+    #[local]
+    async fn list_signers(&mut self) -> Result<Vec<String>, SignError> {
+        self.require_admin()?;
+        let mut signers: Vec<String> = self.signers.iter().cloned().collect();
+        signers.sort();
+        Ok(signers)
+    }
+
+    /// the most recent `limit` sign-family calls at or after `since_ms`, oldest first
+    #[local]
+    async fn get_sign_audit(
+        &mut self,
+        since_ms: Option<u64>,
+        limit: u32,
+    ) -> Result<Vec<AuditEntry>, SignError> {
+        self.require_admin()?;
+        Ok(self.audit.query(since_ms, limit))
+    }
+
+    #[local]
+    async fn clear_sign_audit(&mut self) -> Result<(), SignError> {
+        self.require_admin()?;
+        self.audit.clear();
+        Ok(())
+    }
+
+    /// designate the process (besides the terminal) that may manage permissions
+    #[local]
+    async fn set_admin_process(&mut self, process_id: Option<String>) -> Result<(), SignError> {
+        if source().process.to_string() != TERMINAL_PROCESS {
+            return Err(SignError::NotPermitted);
+        }
+        self.admin_process = process_id.as_deref().map(parse_process_id).transpose()?;
+        Ok(())
+    }
+
+    #[local]
+    async fn grant_raw_signing(&mut self, process_id: String) -> Result<(), SignError> {
+        self.require_admin()?;
+        self.raw_signers.insert(parse_process_id(&process_id)?);
+        Ok(())
+    }
+
+    #[local]
+    async fn verify(&mut self, message: Vec<u8>, signature: Vec<u8>) -> Result<bool, SignError> {
+        verify(&mut self.net(), our(), &source(), message, signature).await
+    }
+
+    #[local]
+    async fn verify_b64(
+        &mut self,
+        message_b64: String,
+        signature_b64: String,
+    ) -> Result<bool, SignError> {
+        let message = decode_b64("message_b64", &message_b64)?;
+        let signature = decode_b64("signature_b64", &signature_b64)?;
+        verify(&mut self.net(), our(), &source(), message, signature).await
+    }
+
+    #[local]
+    async fn verify_hex(
+        &mut self,
+        message_hex: String,
+        signature_hex: String,
+    ) -> Result<bool, SignError> {
+        let message = decode_hex("message_hex", &message_hex)?;
+        let signature = decode_hex("signature_hex", &signature_hex)?;
+        verify(&mut self.net(), our(), &source(), message, signature).await
+    }
+
+    /// the public key and algorithm outside verifiers should check our
+    ///  signatures against; cached for IDENTITY_CACHE_MS
+    #[local]
+    async fn get_identity(&mut self) -> Result<IdentityInfo, SignError> {
+        if let Some(identity) = &self.identity {
+            if identity.is_fresh(now_ms()) {
+                return Ok(identity.clone());
+            }
+        }
+        let identity = get_identity(self.net_timeout()).await?;
+        self.identity = Some(identity.clone());
+        Ok(identity)
+    }
+
+    /// let another node ask whether our node really signed something
+    ///
+    /// read-only: verifies against our own identity, assuming the signature
+    ///  was made by the requester's process on our node
+    #[remote]
+    async fn remote_verify(
+        &mut self,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<bool, SignError> {
+        let requester = source();
+        let payload_sha256 = sha256_hex(&message);
+        let payload_len = message.len() as u64;
+
+        let result = match self.remote_verify_limiter.check(
+            &requester.node,
+            REMOTE_VERIFY_LIMIT,
+            REMOTE_VERIFY_WINDOW_MS,
+            now_ms(),
+        ) {
+            Err(retry_after_ms) => Err(SignError::RateLimited(retry_after_ms)),
+            Ok(()) => {
+                verify_signed_by(
+                    self.net_timeout(),
+                    Some(&mut self.verify_cache),
+                    &our().node,
+                    &message,
+                    signature,
+                )
+                .await
+            }
+        };
+
+        self.audit.push(AuditEntry {
+            timestamp_ms: now_ms(),
+            caller: requester.to_string(),
+            mode: "remote_verify".to_string(),
+            payload_sha256,
+            payload_len,
+            outcome: match &result {
+                Ok(true) => "valid".to_string(),
+                Ok(false) => "invalid".to_string(),
+                Err(e) => e.code().to_string(),
+            },
+        });
+        result
+    }
+
+    #[local]
+    async fn sign_digest(&mut self, message: Vec<u8>) -> Result<Vec<u8>, SignError> {
+        let payload_sha256 = sha256_hex(&message);
+        let payload_len = message.len() as u64;
+        let result = match self.require_signer() {
+            Ok(()) => sign_digest(self.net_timeout(), message).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit("sign_digest", payload_sha256, payload_len, &result);
+        result
+    }
+
+    /// sign with the issue time bound into the signature, for audit trails
+    #[local]
+    async fn sign_and_timestamp(
+        &mut self,
+        message: Vec<u8>,
+    ) -> Result<TimestampedSignature, SignError> {
+        let payload_sha256 = sha256_hex(&message);
+        let payload_len = message.len() as u64;
+        let result = match self.require_signer() {
+            Ok(()) => sign_and_timestamp(self.net_timeout(), message).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit("sign_and_timestamp", payload_sha256, payload_len, &result);
+        result
+    }
+
+    /// future timestamps are tolerated up to the configured clock skew
+    #[local]
+    async fn verify_timestamped(
+        &mut self,
+        message: Vec<u8>,
+        timestamped: TimestampedSignature,
+    ) -> Result<TimestampCheck, SignError> {
+        let clock_skew_ms = self.clock_skew_ms.unwrap_or(DEFAULT_CLOCK_SKEW_MS);
+        verify_timestamped(
+            self.net_timeout(),
+            Some(&mut self.verify_cache),
+            message,
+            timestamped,
+            clock_skew_ms,
+        )
+        .await
+    }
+
+    #[local]
+    async fn verify_digest(
+        &mut self,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<bool, SignError> {
+        verify_digest(
+            self.net_timeout(),
+            Some(&mut self.verify_cache),
+            message,
+            signature,
+        )
+        .await
+    }
+
+    #[local]
+    async fn sign_file(&mut self, path: String) -> Result<SignedEnvelope, SignError> {
+        let result = match self.require_signer() {
+            Ok(()) => sign_file(self.net_timeout(), path).await,
+            Err(e) => Err(e),
+        };
+        // the file's hash is only known once it has been read
+        let (payload_sha256, payload_len) = match &result {
+            Ok(envelope) => (
+                envelope.payload_sha256.clone(),
+                envelope.payload_len.unwrap_or(0),
+            ),
+            Err(_) => (String::new(), 0),
+        };
+        self.record_audit("sign_file", payload_sha256, payload_len, &result);
+        result
+    }
+
+    #[local]
+    async fn verify_file(
+        &mut self,
+        path: String,
+        envelope: SignedEnvelope,
+    ) -> Result<bool, SignError> {
+        verify_file(
+            self.net_timeout(),
+            Some(&mut self.verify_cache),
+            path,
+            envelope,
+        )
+        .await
+    }
+
+    #[local]
+    async fn sign_batch(&mut self, messages: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, SignError> {
+        let payload_sha256 = sha256_hex(&messages.concat());
+        let payload_len = messages.iter().map(|m| m.len() as u64).sum();
+        let result = match self.require_signer() {
+            Ok(()) => batch::sign_batch(&mut self.net(), &source(), messages).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit("sign_batch", payload_sha256, payload_len, &result);
+        result
+    }
+
+    #[local]
+    async fn verify_batch(
+        &mut self,
+        items: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<Vec<bool>, SignError> {
+        batch::verify_batch(&mut self.net(), &our(), &source(), items).await
+    }
+
+    #[local]
+    async fn verify_batch_detailed(
+        &mut self,
+        items: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<VerifyBatchReport, SignError> {
+        batch::verify_batch_detailed(&mut self.net(), &our(), &source(), items).await
+    }
+
+    /// `json` is JSON text; WIT has no type for arbitrary JSON values
+    #[local]
+    async fn sign_json(&mut self, json: String) -> Result<SignedEnvelope, SignError> {
+        let payload_sha256 = sha256_hex(json.as_bytes());
+        let payload_len = json.len() as u64;
+        let result = match self.require_signer() {
+            Ok(()) => sign_json(self.net_timeout(), json).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit("sign_json", payload_sha256, payload_len, &result);
+        result
+    }
+
+    #[local]
+    async fn verify_json(
+        &mut self,
+        json: String,
+        envelope: SignedEnvelope,
+    ) -> Result<bool, SignError> {
+        verify_json(
+            self.net_timeout(),
+            Some(&mut self.verify_cache),
+            json,
+            envelope,
+        )
+        .await
+    }
+
+    #[local]
+    async fn sign_with_nonce(
+        &mut self,
+        message: Vec<u8>,
+        ttl_seconds: u64,
+    ) -> Result<SignedEnvelope, SignError> {
+        let payload_sha256 = sha256_hex(&message);
+        let payload_len = message.len() as u64;
+        let result = match self.require_signer() {
+            Ok(()) => sign_with_nonce(self.net_timeout(), message, ttl_seconds).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit("sign_with_nonce", payload_sha256, payload_len, &result);
+        result
+    }
+
+    /// verify a sign_with_nonce envelope; each nonce is accepted only once
+    #[local]
+    async fn verify_fresh(
+        &mut self,
+        message: Vec<u8>,
+        envelope: SignedEnvelope,
+    ) -> Result<VerifyOutcome, SignError> {
+        let clock_skew_ms = self.clock_skew_ms.unwrap_or(DEFAULT_CLOCK_SKEW_MS);
+        let outcome = verify_fresh(self.net_timeout(), message, &envelope, clock_skew_ms).await?;
+        if outcome != VerifyOutcome::Valid {
+            return Ok(outcome);
+        }
+
+        let (Some(nonce), Some(expires_at_ms)) = (&envelope.nonce, envelope.expires_at_ms) else {
+            return Err(SignError::EnvelopeFormat);
+        };
+        let expires_at_ms = expires_at_ms.saturating_add(clock_skew_ms);
+        if !self
+            .seen_nonces
+            .check_and_record(nonce, expires_at_ms, now_ms())
+        {
+            return Ok(VerifyOutcome::ReplayedNonce);
+        }
+        Ok(VerifyOutcome::Valid)
+    }
+
+    /// tune or disable reuse of recent verification results; negative results
+    ///  are kept for a quarter of `ttl_seconds`
+    #[local]
+    async fn set_verify_cache(
+        &mut self,
+        enabled: bool,
+        ttl_seconds: u64,
+        max_entries: u32,
+    ) -> Result<(), SignError> {
+        self.require_admin()?;
+        self.verify_cache
+            .configure(enabled, ttl_seconds, max_entries);
+        Ok(())
+    }
+
+    /// set how long each request to net:distro:sys may take, in seconds;
+    ///  batches apply it per item
+    #[local]
+    async fn set_net_timeout(&mut self, seconds: u64) -> Result<(), SignError> {
+        self.require_admin()?;
+        self.net_timeout_secs = Some(seconds.clamp(MIN_NET_TIMEOUT, MAX_NET_TIMEOUT));
+        Ok(())
+    }
+
+    /// set the clock skew tolerated by verify_fresh, in seconds
+    #[local]
+    async fn set_clock_skew(&mut self, seconds: u64) -> Result<(), SignError> {
+        self.require_admin()?;
+        self.clock_skew_ms = Some(seconds.saturating_mul(1000).min(MAX_CLOCK_SKEW_MS));
+        Ok(())
+    }
+
+    #[local]
+    async fn verify_from(
+        &mut self,
+        node: String,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<bool, SignError> {
+        verify_from(
+            self.net_timeout(),
+            Some(&mut self.verify_cache),
+            node,
+            message,
+            signature,
+        )
+        .await
     }
 
     #[local]
-    async fn sign(&mut self, message: Vec<u8>) -> Result<Vec<u8>, String> {
-        sign(message).await.map_err(|e| e.to_string())
+    async fn sign_envelope(&mut self, message: Vec<u8>) -> Result<SignedEnvelope, SignError> {
+        let payload_sha256 = sha256_hex(&message);
+        let payload_len = message.len() as u64;
+        let result = match self.require_signer() {
+            Ok(()) => sign_envelope(self.net_timeout(), message).await,
+            Err(e) => Err(e),
+        };
+        self.record_audit("sign_envelope", payload_sha256, payload_len, &result);
+        result
     }
 
     #[local]
-    async fn verify(&mut self, message: Vec<u8>, signature: Vec<u8>) -> Result<bool, String> {
-        verify(message, signature).await.map_err(|e| e.to_string())
+    async fn verify_envelope(
+        &mut self,
+        message: Vec<u8>,
+        envelope: SignedEnvelope,
+    ) -> Result<bool, SignError> {
+        verify_envelope(
+            self.net_timeout(),
+            Some(&mut self.verify_cache),
+            message,
+            envelope,
+        )
+        .await
     }
 }
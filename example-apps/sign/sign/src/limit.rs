@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+/// distinct callers tracked before stale windows are swept
+const MAX_TRACKED_CALLERS: usize = 1000;
+
+/// fixed-window request counter per caller
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RateLimiter {
+    /// caller -> (window start, requests in window)
+    windows: HashMap<String, (u64, u32)>,
+}
+
+impl RateLimiter {
+    /// count a request from `caller`; Err(ms until the window resets) if
+    ///  `limit` requests were already made in the current window
+    pub fn check(
+        &mut self,
+        caller: &str,
+        limit: u32,
+        window_ms: u64,
+        now_ms: u64,
+    ) -> Result<(), u64> {
+        if self.windows.len() >= MAX_TRACKED_CALLERS && !self.windows.contains_key(caller) {
+            self.windows
+                .retain(|_, (start, _)| now_ms < start.saturating_add(window_ms));
+        }
+
+        let (start, count) = self
+            .windows
+            .entry(caller.to_string())
+            .or_insert((now_ms, 0));
+        if now_ms >= start.saturating_add(window_ms) {
+            *start = now_ms;
+            *count = 0;
+        }
+        if *count >= limit {
+            return Err(start.saturating_add(window_ms) - now_ms);
+        }
+        *count += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,344 @@
+use hyperware_process_lib::net::{Identity, NetAction, NetResponse};
+use hyperware_process_lib::{last_blob, Address, LazyLoadBlob, Request};
+
+use hyperware_app_common::send_rmp;
+
+use crate::cache::{cache_key, VerifyCache};
+use crate::error::SignError;
+
+/// seconds to wait for net:distro:sys unless configured otherwise
+pub const DEFAULT_NET_TIMEOUT: u64 = 5;
+
+/// bounds for the configurable timeout, in seconds
+pub const MIN_NET_TIMEOUT: u64 = 1;
+pub const MAX_NET_TIMEOUT: u64 = 60;
+
+/// what signing and verifying need from net:distro:sys; tests stand a fake
+///  in for it
+pub trait Net {
+    /// a signature over exactly `message` (net prepends our sign address)
+    async fn sign(&mut self, message: Vec<u8>) -> Result<Vec<u8>, SignError>;
+
+    /// whether `signature` is over exactly `message` as signed by `from`
+    async fn verify(
+        &mut self,
+        from: Address,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<bool, SignError>;
+}
+
+/// net:distro:sys itself, given `timeout` seconds per request; verification
+///  goes through `cache` when there is one
+pub struct NetDistro<'a> {
+    pub timeout: u64,
+    pub cache: Option<&'a mut VerifyCache>,
+}
+
+impl NetDistro<'_> {
+    pub fn uncached(timeout: u64) -> Self {
+        NetDistro {
+            timeout,
+            cache: None,
+        }
+    }
+}
+
+impl Net for NetDistro<'_> {
+    async fn sign(&mut self, message: Vec<u8>) -> Result<Vec<u8>, SignError> {
+        sign(self.timeout, message).await
+    }
+
+    async fn verify(
+        &mut self,
+        from: Address,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<bool, SignError> {
+        verify(
+            self.timeout,
+            self.cache.as_deref_mut(),
+            from,
+            message,
+            signature,
+        )
+        .await
+    }
+}
+
+fn net_request(timeout: u64) -> Request {
+    Request::to(("our", "net", "distro", "sys")).expects_response(timeout)
+}
+
+/// ask net:distro:sys to sign exactly `message` (net prepends our sign address)
+pub async fn sign(timeout: u64, message: Vec<u8>) -> Result<Vec<u8>, SignError> {
+    let body = rmp_serde::to_vec(&NetAction::Sign)?;
+
+    let req = net_request(timeout)
+        .blob(LazyLoadBlob {
+            mime: None,
+            bytes: message,
+        })
+        .body(body);
+
+    let resp: NetResponse = send_rmp(req).await.map_err(SignError::from_send)?;
+    signed_from_response(resp)?;
+
+    let Some(signature) = last_blob() else {
+        return Err(SignError::NoSignatureBlob);
+    };
+
+    Ok(signature.bytes)
+}
+
+/// ask net:distro:sys to verify `signature` over exactly `message` as signed by `from`
+///
+/// with a cache, a recent result for the same triple is returned without asking
+pub async fn verify(
+    timeout: u64,
+    mut cache: Option<&mut VerifyCache>,
+    from: Address,
+    message: Vec<u8>,
+    signature: Vec<u8>,
+) -> Result<bool, SignError> {
+    let key = cache
+        .is_some()
+        .then(|| cache_key(&from, &message, &signature));
+    if let (Some(cache), Some(key)) = (cache.as_deref_mut(), &key) {
+        if let Some(is_good) = cache.get(key, crate::now_ms()) {
+            return Ok(is_good);
+        }
+    }
+
+    let body = rmp_serde::to_vec(&NetAction::Verify { from, signature })?;
+
+    let req = net_request(timeout)
+        .blob(LazyLoadBlob {
+            mime: None,
+            bytes: message,
+        })
+        .body(body);
+
+    let resp: NetResponse = send_rmp(req).await.map_err(SignError::from_send)?;
+    let is_good = verified_from_response(resp)?;
+
+    if let (Some(cache), Some(key)) = (cache, key) {
+        cache.insert(key, is_good, crate::now_ms());
+    }
+    Ok(is_good)
+}
+
+/// ask net:distro:sys whether `node` is a known identity
+pub async fn knows_node(timeout: u64, node: &str) -> Result<bool, SignError> {
+    Ok(peer(timeout, node).await?.is_some())
+}
+
+/// ask net:distro:sys for the identity `node` has on the network
+pub async fn peer(timeout: u64, node: &str) -> Result<Option<Identity>, SignError> {
+    let body = rmp_serde::to_vec(&NetAction::GetPeer(node.to_string()))?;
+
+    let resp: NetResponse = send_rmp(net_request(timeout).body(body))
+        .await
+        .map_err(SignError::from_send)?;
+    peer_from_response(resp)
+}
+
+/// the error for a response of the wrong kind to whatever we asked
+///
+/// matched without a wildcard so a new NetResponse variant has to be given
+///  a code here
+pub fn mismatch_error(resp: &NetResponse) -> SignError {
+    match resp {
+        // net refused to handle the request at all
+        NetResponse::Rejected(_) => SignError::NotPermitted,
+        // net looked a node up and does not know it
+        NetResponse::Peer(None) => SignError::InvalidNodeName,
+        NetResponse::Accepted(_)
+        | NetResponse::Peers(_)
+        | NetResponse::Peer(Some(_))
+        | NetResponse::Diagnostics(_)
+        | NetResponse::Signed
+        | NetResponse::Verified(_) => SignError::UnexpectedNetResponse,
+    }
+}
+
+pub fn signed_from_response(resp: NetResponse) -> Result<(), SignError> {
+    match resp {
+        NetResponse::Signed => Ok(()),
+        other => Err(mismatch_error(&other)),
+    }
+}
+
+pub fn verified_from_response(resp: NetResponse) -> Result<bool, SignError> {
+    match resp {
+        NetResponse::Verified(is_good) => Ok(is_good),
+        other => Err(mismatch_error(&other)),
+    }
+}
+
+pub fn peer_from_response(resp: NetResponse) -> Result<Option<Identity>, SignError> {
+    match resp {
+        NetResponse::Peer(peer) => Ok(peer),
+        other => Err(mismatch_error(&other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperware_process_lib::net::NodeRouting;
+
+    use super::*;
+
+    fn identity() -> Identity {
+        Identity {
+            name: "fake.os".to_string(),
+            networking_key: "0xabcd".to_string(),
+            routing: NodeRouting::Routers(vec![]),
+        }
+    }
+
+    /// one of every variant, with the code a mismatch maps to
+    fn every_response() -> Vec<(NetResponse, &'static str)> {
+        vec![
+            (
+                NetResponse::Accepted("fake.os".to_string()),
+                "unexpected_net_response",
+            ),
+            (
+                NetResponse::Rejected("fake.os".to_string()),
+                "not_permitted",
+            ),
+            (
+                NetResponse::Peers(vec![identity()]),
+                "unexpected_net_response",
+            ),
+            (NetResponse::Peer(None), "invalid_node_name"),
+            (
+                NetResponse::Peer(Some(identity())),
+                "unexpected_net_response",
+            ),
+            (
+                NetResponse::Diagnostics("ok".to_string()),
+                "unexpected_net_response",
+            ),
+            (NetResponse::Signed, "unexpected_net_response"),
+            (NetResponse::Verified(true), "unexpected_net_response"),
+        ]
+    }
+
+    #[test]
+    fn each_variant_maps_to_its_code() {
+        for (resp, code) in every_response() {
+            assert_eq!(mismatch_error(&resp).code(), code, "{resp:?}");
+        }
+    }
+
+    #[test]
+    fn sign_accepts_only_signed() {
+        for (resp, code) in every_response() {
+            let expected = match resp {
+                NetResponse::Signed => Ok(()),
+                _ => Err(code),
+            };
+            assert_eq!(signed_from_response(resp).map_err(|e| e.code()), expected);
+        }
+    }
+
+    #[test]
+    fn verify_accepts_only_verified() {
+        for (resp, code) in every_response() {
+            let expected = match resp {
+                NetResponse::Verified(is_good) => Ok(is_good),
+                _ => Err(code),
+            };
+            assert_eq!(verified_from_response(resp).map_err(|e| e.code()), expected);
+        }
+        assert_eq!(
+            verified_from_response(NetResponse::Verified(false)),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn peer_lookup_accepts_either_peer_answer() {
+        for (resp, code) in every_response() {
+            let expected = match &resp {
+                NetResponse::Peer(peer) => Ok(peer.as_ref().map(|p| p.name.clone())),
+                _ => Err(code),
+            };
+            let peer = peer_from_response(resp).map(|p| p.map(|p| p.name));
+            assert_eq!(peer.map_err(|e| e.code()), expected);
+        }
+    }
+}
+
+/// stand-ins for net:distro:sys in unit tests
+#[cfg(test)]
+pub mod fake {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    /// the node FakeNet signs as
+    pub const FAKE_NODE: &str = "fake.os";
+
+    /// signs as FAKE_NODE with a hash standing in for a signature; any
+    ///  message ending in `timeout_on` times out instead
+    #[derive(Default)]
+    pub struct FakeNet {
+        pub timeout_on: Vec<u8>,
+        /// requests that reached the fake, as net:distro:sys would count them
+        pub requests: u32,
+    }
+
+    impl FakeNet {
+        fn signature(node: &str, message: &[u8]) -> Vec<u8> {
+            Sha256::new()
+                .chain_update(node.as_bytes())
+                .chain_update(message)
+                .finalize()
+                .to_vec()
+        }
+
+        fn check(&mut self, message: &[u8]) -> Result<(), SignError> {
+            self.requests += 1;
+            if !self.timeout_on.is_empty() && message.ends_with(&self.timeout_on) {
+                return Err(SignError::NetTimeout);
+            }
+            Ok(())
+        }
+    }
+
+    impl Net for FakeNet {
+        async fn sign(&mut self, message: Vec<u8>) -> Result<Vec<u8>, SignError> {
+            self.check(&message)?;
+            Ok(Self::signature(FAKE_NODE, &message))
+        }
+
+        async fn verify(
+            &mut self,
+            from: Address,
+            message: Vec<u8>,
+            signature: Vec<u8>,
+        ) -> Result<bool, SignError> {
+            self.check(&message)?;
+            Ok(signature == Self::signature(&from.node, &message))
+        }
+    }
+
+    /// run a future that never waits, as everything on FakeNet is
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("a FakeNet future waited"),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+
+use crate::error::SignError;
+use crate::net::Net;
+
+/// sign_raw is for processes that are allowed signers and were also granted
+///  raw signing; anyone else is refused without reaching the net
+pub fn check_raw_signer(
+    signers: &HashSet<String>,
+    raw_signers: &HashSet<String>,
+    caller: &str,
+) -> Result<(), SignError> {
+    if signers.contains(caller) && raw_signers.contains(caller) {
+        Ok(())
+    } else {
+        Err(SignError::RawSigningNotPermitted)
+    }
+}
+
+/// sign exactly `message` without prepending the caller's address
+///
+/// net:distro:sys still prepends the sign address per its own convention,
+///  so verifiers must check against [sign-address, message]
+pub async fn sign_raw(net: &mut impl Net, message: Vec<u8>) -> Result<Vec<u8>, SignError> {
+    net.sign(message).await
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperware_process_lib::{Address, ProcessId};
+
+    use super::*;
+    use crate::net::fake::{block_on, FakeNet, FAKE_NODE};
+
+    const CALLER: &str = "app:app:sys";
+
+    fn caller() -> Address {
+        Address::new(FAKE_NODE, CALLER.parse::<ProcessId>().unwrap())
+    }
+
+    fn set(processes: &[&str]) -> HashSet<String> {
+        processes.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn sign_and_sign_raw_differ_for_the_same_input() {
+        let mut net = FakeNet::default();
+        let message = b"the same bytes".to_vec();
+
+        let signed = block_on(crate::sign(&mut net, &caller(), message.clone())).unwrap();
+        let raw = block_on(sign_raw(&mut net, message.clone())).unwrap();
+        assert_ne!(signed, raw);
+
+        // each verifies only as what it is
+        let signer = Address::new(FAKE_NODE, "sign:sign:sys".parse::<ProcessId>().unwrap());
+        let as_raw = |net: &mut FakeNet, signature: Vec<u8>| {
+            block_on(net.verify(signer.clone(), message.clone(), signature)).unwrap()
+        };
+        assert!(as_raw(&mut net, raw.clone()));
+        assert!(!as_raw(&mut net, signed.clone()));
+        let as_signed = |net: &mut FakeNet, signature: Vec<u8>| {
+            block_on(crate::verify(
+                net,
+                signer.clone(),
+                &caller(),
+                message.clone(),
+                signature,
+            ))
+            .unwrap()
+        };
+        assert!(as_signed(&mut net, signed));
+        assert!(!as_signed(&mut net, raw));
+    }
+
+    #[test]
+    fn granted_signers_may_sign_raw() {
+        assert_eq!(
+            check_raw_signer(&set(&[CALLER]), &set(&[CALLER]), CALLER),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn unauthorized_callers_are_refused() {
+        let refused = [
+            // a signer that was never granted raw signing
+            (set(&[CALLER]), set(&[])),
+            // a raw grant for a process no longer allowed to sign at all
+            (set(&[]), set(&[CALLER])),
+            (set(&["other:app:sys"]), set(&["other:app:sys"])),
+        ];
+        for (signers, raw_signers) in refused {
+            let e = check_raw_signer(&signers, &raw_signers, CALLER).unwrap_err();
+            assert_eq!(e, SignError::RawSigningNotPermitted);
+            assert_eq!(e.code(), "not_permitted");
+            assert_eq!(e.to_string(), "not_permitted: raw signing not permitted");
+            assert_eq!(
+                app_error::AppError::from(e).message,
+                "raw signing not permitted"
+            );
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::error::SignError;
+
+/// a session untouched for this long is dropped along with its hasher
+pub const SESSION_IDLE_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+
+/// most sessions open at once across all callers
+const MAX_SESSIONS: usize = 256;
+
+/// an in-progress chunked signing: the payload is never held, only its hash
+#[derive(Debug)]
+struct SignSession {
+    /// full address of the caller that began the session
+    owner: String,
+    hasher: Sha256,
+    len: u64,
+    last_active_ms: u64,
+}
+
+/// open chunked-signing sessions, keyed by session id
+///
+/// hashers are not serializable, so sessions do not survive a restart
+#[derive(Default, Debug)]
+pub struct SignSessions {
+    sessions: HashMap<String, SignSession>,
+}
+
+impl SignSessions {
+    pub fn begin(&mut self, owner: &str, now_ms: u64) -> Result<String, SignError> {
+        self.expire(now_ms);
+        if self.sessions.len() >= MAX_SESSIONS {
+            return Err(SignError::InvalidInput(format!(
+                "too many open sign sessions (limit {MAX_SESSIONS})"
+            )));
+        }
+
+        let session_id = crate::fresh::random_nonce();
+        self.sessions.insert(
+            session_id.clone(),
+            SignSession {
+                owner: owner.to_string(),
+                hasher: Sha256::new(),
+                len: 0,
+                last_active_ms: now_ms,
+            },
+        );
+        Ok(session_id)
+    }
+
+    pub fn update(
+        &mut self,
+        owner: &str,
+        session_id: &str,
+        chunk: &[u8],
+        now_ms: u64,
+    ) -> Result<(), SignError> {
+        let session = self.get_mut(owner, session_id, now_ms)?;
+        session.hasher.update(chunk);
+        session.len += chunk.len() as u64;
+        Ok(())
+    }
+
+    /// SHA-256 and length of everything fed so far; the session stays open
+    ///  so a failed signature can be retried without resending the payload
+    pub fn digest(
+        &mut self,
+        owner: &str,
+        session_id: &str,
+        now_ms: u64,
+    ) -> Result<(Vec<u8>, u64), SignError> {
+        let session = self.get_mut(owner, session_id, now_ms)?;
+        Ok((session.hasher.clone().finalize().to_vec(), session.len))
+    }
+
+    /// drop a session; false if `owner` had no such session
+    pub fn remove(&mut self, owner: &str, session_id: &str) -> bool {
+        match self.sessions.get(session_id) {
+            Some(session) if session.owner == owner => {
+                self.sessions.remove(session_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// a session belonging to another caller is reported exactly like a
+    ///  missing one, so session ids cannot be probed
+    fn get_mut(
+        &mut self,
+        owner: &str,
+        session_id: &str,
+        now_ms: u64,
+    ) -> Result<&mut SignSession, SignError> {
+        self.expire(now_ms);
+        match self.sessions.get_mut(session_id) {
+            Some(session) if session.owner == owner => {
+                session.last_active_ms = now_ms;
+                Ok(session)
+            }
+            _ => Err(SignError::InvalidInput(format!(
+                "unknown or expired sign session {session_id}"
+            ))),
+        }
+    }
+
+    fn expire(&mut self, now_ms: u64) {
+        self.sessions
+            .retain(|_, s| now_ms < s.last_active_ms.saturating_add(SESSION_IDLE_TIMEOUT_MS));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::envelope::push_field;
+use crate::error::SignError;
+
+/// domain tag for sign_and_timestamp statements
+const TIMESTAMP_TAG: &[u8] = b"hyperware-sign-timestamp-v1";
+
+const MS_PER_DAY: u64 = 86_400_000;
+
+/// a signature whose issue time is part of the signed bytes
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TimestampedSignature {
+    pub signer_node: String,
+    /// RFC 3339 UTC with millisecond precision, e.g. `2026-10-15T09:30:00.000Z`;
+    ///  signed exactly as written here
+    pub issued_at: String,
+    /// lowercase hex SHA-256 of the payload
+    pub payload_sha256: String,
+    pub signature: Vec<u8>,
+}
+
+/// outcome of verify_timestamped
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TimestampCheck {
+    pub is_good: bool,
+    /// issued_at as milliseconds since the Unix epoch, for the caller's own
+    ///  age policy
+    pub issued_at_ms: u64,
+}
+
+/// bytes signed by sign_and_timestamp: [tag, source, issued_at, digest],
+///  each length-prefixed
+pub fn timestamp_statement(source: &str, issued_at: &str, digest: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(TIMESTAMP_TAG.len() + source.len() + 96);
+    push_field(&mut bytes, TIMESTAMP_TAG);
+    push_field(&mut bytes, source.as_bytes());
+    push_field(&mut bytes, issued_at.as_bytes());
+    push_field(&mut bytes, digest);
+    bytes
+}
+
+/// format as `YYYY-MM-DDTHH:MM:SS.sssZ`
+pub fn format_rfc3339_ms(ms: u64) -> String {
+    let (year, month, day) = civil_from_days((ms / MS_PER_DAY) as i64);
+    let ms_of_day = ms % MS_PER_DAY;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000,
+    )
+}
+
+/// parse the exact form produced by format_rfc3339_ms
+///
+/// the string is signed verbatim, so other RFC 3339 spellings of the same
+///  instant (offsets, other precisions, lowercase `t`/`z`) are rejected rather
+///  than normalized
+pub fn parse_rfc3339_ms(s: &str) -> Result<u64, SignError> {
+    let invalid = || {
+        SignError::InvalidInput(format!(
+            "issued_at is not a canonical RFC 3339 timestamp: {s}"
+        ))
+    };
+
+    let b = s.as_bytes();
+    if !s.is_ascii()
+        || b.len() != 24
+        || b[4] != b'-'
+        || b[7] != b'-'
+        || b[10] != b'T'
+        || b[13] != b':'
+        || b[16] != b':'
+        || b[19] != b'.'
+        || b[23] != b'Z'
+    {
+        return Err(invalid());
+    }
+    let num = |range: std::ops::Range<usize>| -> Result<u64, SignError> {
+        let digits = &s[range];
+        if !digits.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        digits.parse().map_err(|_| invalid())
+    };
+
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second, millis) = (num(11..13)?, num(14..16)?, num(17..19)?, num(20..23)?);
+    if year < 1970
+        || !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(invalid());
+    }
+
+    let days = days_from_civil(year as i64, month as u32, day as u32) as u64;
+    Ok(days * MS_PER_DAY + hour * 3_600_000 + minute * 60_000 + second * 1000 + millis)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// days since 1970-01-01 for a proleptic Gregorian date (H. Hinnant's algorithm)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,240 @@
+// INTONATION STATISTICS
+// Aggregates computed in a single pass over a stored pitch log
+//
+// All percentages and averages are per voiced frame. The browser reports frames
+// at a steady rate, so frame counts are a good proxy for time.
+
+use serde::{Deserialize, Serialize};
+
+use crate::midi::MIN_CONFIDENCE;
+use crate::pitch_log::PitchLogInfo;
+use crate::{note_label, note_name, Key, PitchFrame, Scale};
+
+/// A frame counts as "in tune" when within this many cents of its target
+pub const IN_TUNE_CENTS: f32 = 10.0;
+
+/// Per-scale-degree breakdown
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DegreeStats {
+    /// Scale degree, 1-based (1 = root)
+    pub degree: u8,
+    /// Pitch-class name of the degree in the session's key, e.g. "F#"
+    pub note_name: String,
+    pub frames: u64,
+    pub avg_abs_cents: f32,
+    pub in_tune_pct: f32,
+}
+
+/// Digest of a whole pitch log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IntonationSummary {
+    pub log_id: String,
+    pub key: Key,
+    pub scale: Scale,
+    /// Number of voiced frames the statistics are based on
+    pub frames: u64,
+    pub avg_abs_cents: f32,
+    /// Percentage of voiced frames within ±IN_TUNE_CENTS of the target
+    pub in_tune_pct: f32,
+    /// Note (with octave, e.g. "A4") with the largest average correction
+    pub worst_note: Option<String>,
+    pub worst_note_avg_cents: f32,
+    pub degrees: Vec<DegreeStats>,
+    /// Voiced frames whose target note is outside the session's scale
+    pub out_of_scale_frames: u64,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Tally {
+    frames: u64,
+    abs_cents: f64,
+    in_tune: u64,
+}
+
+impl Tally {
+    fn add(&mut self, cents_off: f32) {
+        self.frames += 1;
+        self.abs_cents += cents_off.abs() as f64;
+        if cents_off.abs() <= IN_TUNE_CENTS {
+            self.in_tune += 1;
+        }
+    }
+
+    fn avg_abs_cents(&self) -> f32 {
+        if self.frames == 0 {
+            0.0
+        } else {
+            (self.abs_cents / self.frames as f64) as f32
+        }
+    }
+
+    fn in_tune_pct(&self) -> f32 {
+        if self.frames == 0 {
+            0.0
+        } else {
+            (self.in_tune as f64 * 100.0 / self.frames as f64) as f32
+        }
+    }
+}
+
+/// Streaming accumulator: feed frames one at a time, then call finish()
+pub struct IntonationAccumulator {
+    root: u8,
+    intervals: &'static [u8],
+    overall: Tally,
+    by_note: [Tally; 128],
+    by_degree: [Tally; 7],
+    out_of_scale: u64,
+}
+
+impl IntonationAccumulator {
+    pub fn new(key: &Key, scale: &Scale) -> Self {
+        Self {
+            root: key.semitone(),
+            intervals: scale.intervals(),
+            overall: Tally::default(),
+            by_note: [Tally::default(); 128],
+            by_degree: [Tally::default(); 7],
+            out_of_scale: 0,
+        }
+    }
+
+    pub fn add(&mut self, frame: &PitchFrame) {
+        if frame.confidence < MIN_CONFIDENCE || frame.detected_hz <= 0.0 {
+            return;
+        }
+        let note = frame.target_note.min(127);
+
+        self.overall.add(frame.cents_off);
+        self.by_note[note as usize].add(frame.cents_off);
+
+        let offset = (note % 12 + 12 - self.root) % 12;
+        match self.intervals.iter().position(|i| *i == offset) {
+            Some(degree) => self.by_degree[degree].add(frame.cents_off),
+            None => self.out_of_scale += 1,
+        }
+    }
+
+    pub fn finish(self, info: PitchLogInfo) -> IntonationSummary {
+        let worst = self
+            .by_note
+            .iter()
+            .enumerate()
+            .filter(|(_, tally)| tally.frames > 0)
+            .max_by(|(_, a), (_, b)| a.avg_abs_cents().total_cmp(&b.avg_abs_cents()));
+
+        let degrees = self
+            .intervals
+            .iter()
+            .zip(self.by_degree.iter())
+            .enumerate()
+            .map(|(i, (interval, tally))| DegreeStats {
+                degree: i as u8 + 1,
+                note_name: note_name((self.root + interval) % 12).to_string(),
+                frames: tally.frames,
+                avg_abs_cents: tally.avg_abs_cents(),
+                in_tune_pct: tally.in_tune_pct(),
+            })
+            .collect();
+
+        IntonationSummary {
+            log_id: info.id,
+            key: info.key,
+            scale: info.scale,
+            frames: self.overall.frames,
+            avg_abs_cents: self.overall.avg_abs_cents(),
+            in_tune_pct: self.overall.in_tune_pct(),
+            worst_note: worst.map(|(note, _)| note_label(note as u8)),
+            worst_note_avg_cents: worst.map_or(0.0, |(_, tally)| tally.avg_abs_cents()),
+            degrees,
+            out_of_scale_frames: self.out_of_scale,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> PitchLogInfo {
+        PitchLogInfo {
+            id: "1000".to_string(),
+            created_at_ms: 1000,
+            key: Key::D,
+            scale: Scale::Major,
+            size_bytes: 0,
+        }
+    }
+
+    fn frame(target_note: u8, cents_off: f32, confidence: f32) -> PitchFrame {
+        PitchFrame {
+            time_ms: 0,
+            detected_hz: 440.0,
+            cents_off,
+            target_note,
+            confidence,
+        }
+    }
+
+    #[test]
+    fn no_frames_give_an_empty_summary() {
+        let summary = IntonationAccumulator::new(&Key::D, &Scale::Major).finish(info());
+        assert_eq!(summary.log_id, "1000");
+        assert_eq!(summary.frames, 0);
+        assert_eq!(summary.avg_abs_cents, 0.0);
+        assert_eq!(summary.in_tune_pct, 0.0);
+        assert_eq!(summary.worst_note, None);
+        assert_eq!(summary.worst_note_avg_cents, 0.0);
+        assert_eq!(summary.out_of_scale_frames, 0);
+
+        // every degree is still listed, so the UI can draw an empty chart
+        let names: Vec<&str> = summary
+            .degrees
+            .iter()
+            .map(|d| d.note_name.as_str())
+            .collect();
+        assert_eq!(names, ["D", "E", "F#", "G", "A", "B", "C#"]);
+        assert!(summary
+            .degrees
+            .iter()
+            .all(|d| d.frames == 0 && d.in_tune_pct == 0.0));
+    }
+
+    #[test]
+    fn one_frame_is_the_whole_summary() {
+        let mut accumulator = IntonationAccumulator::new(&Key::D, &Scale::Major);
+        // A4 is the fifth degree of D major
+        accumulator.add(&frame(69, -12.5, 0.9));
+        let summary = accumulator.finish(info());
+
+        assert_eq!(summary.frames, 1);
+        assert_eq!(summary.avg_abs_cents, 12.5);
+        assert_eq!(summary.in_tune_pct, 0.0);
+        assert_eq!(summary.worst_note.as_deref(), Some("A4"));
+        assert_eq!(summary.worst_note_avg_cents, 12.5);
+        assert_eq!(summary.out_of_scale_frames, 0);
+
+        let fifth = &summary.degrees[4];
+        assert_eq!((fifth.degree, fifth.frames), (5, 1));
+        assert_eq!(fifth.avg_abs_cents, 12.5);
+        assert_eq!(summary.degrees.iter().map(|d| d.frames).sum::<u64>(), 1);
+    }
+
+    #[test]
+    fn unvoiced_frames_are_skipped() {
+        let mut accumulator = IntonationAccumulator::new(&Key::D, &Scale::Major);
+        accumulator.add(&frame(69, 0.0, MIN_CONFIDENCE / 2.0));
+        accumulator.add(&PitchFrame {
+            detected_hz: 0.0,
+            ..frame(69, 0.0, 0.9)
+        });
+        // C4 is not in D major
+        accumulator.add(&frame(60, 5.0, 0.9));
+        let summary = accumulator.finish(info());
+
+        assert_eq!(summary.frames, 1);
+        assert_eq!(summary.in_tune_pct, 100.0);
+        assert_eq!(summary.out_of_scale_frames, 1);
+        assert_eq!(summary.worst_note.as_deref(), Some("C4"));
+    }
+}
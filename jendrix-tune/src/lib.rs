@@ -7,12 +7,29 @@
 // - Processing: AudioWorklet nodes for pitch detection and correction
 
 // CRITICAL IMPORTS
+use app_error::AppError;
 use hyperprocess_macro::hyperprocess;
-use hyperware_process_lib::{homepage::add_to_homepage, our, println};
+use hyperware_process_lib::hyperapp::{add_response_header, get_path};
+use hyperware_process_lib::{homepage::add_to_homepage, our, println, vfs::create_drive};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+mod intonation;
+mod midi;
+mod pitch_log;
+
+use intonation::{IntonationAccumulator, IntonationSummary};
+use pitch_log::{PitchLogHeader, PitchLogInfo, PitchLogPage};
 
 const ICON: &str = include_str!("./icon");
 
+/// Maximum number of telemetry frames kept in memory (~10 minutes at 60 fps)
+const MAX_TELEMETRY_FRAMES: usize = 36_000;
+
+/// Layout version of the saved AppState; bump it and extend `migrate`
+/// whenever a saved field is added or changes meaning
+const STATE_SCHEMA_VERSION: u32 = 1;
+
 // =============================================================================
 // AUDIO PARAMETER TYPES
 // =============================================================================
@@ -40,6 +57,44 @@ impl Default for Key {
     }
 }
 
+impl Key {
+    /// Semitones above C (C = 0 ... B = 11)
+    pub fn semitone(&self) -> u8 {
+        match self {
+            Key::C => 0,
+            Key::CSharp => 1,
+            Key::D => 2,
+            Key::DSharp => 3,
+            Key::E => 4,
+            Key::F => 5,
+            Key::FSharp => 6,
+            Key::G => 7,
+            Key::GSharp => 8,
+            Key::A => 9,
+            Key::ASharp => 10,
+            Key::B => 11,
+        }
+    }
+}
+
+/// Pitch-class name for a semitone above C (0 = "C" ... 11 = "B")
+pub fn note_name(pitch_class: u8) -> &'static str {
+    const NAMES: [&str; 12] = [
+        "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+    ];
+    NAMES[(pitch_class % 12) as usize]
+}
+
+/// Note name with octave for a MIDI note number (60 = "C4")
+pub fn note_label(note: u8) -> String {
+    format!("{}{}", note_name(note % 12), note as i32 / 12 - 1)
+}
+
+/// Equal-tempered frequency of a MIDI note number (A4 = 440 Hz)
+pub fn note_hz(note: u8) -> f32 {
+    440.0 * 2f32.powf((note as f32 - 69.0) / 12.0)
+}
+
 /// Scale type (major or minor)
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Scale {
@@ -53,14 +108,48 @@ impl Default for Scale {
     }
 }
 
+impl Scale {
+    /// Semitone offsets of each scale degree from the root
+    pub fn intervals(&self) -> &'static [u8] {
+        match self {
+            Scale::Major => &[0, 2, 4, 5, 7, 9, 11],
+            Scale::Minor => &[0, 2, 3, 5, 7, 8, 10],
+        }
+    }
+}
+
+/// One frame of pitch telemetry reported by the browser's pitch detector
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PitchFrame {
+    /// Capture time in milliseconds (browser clock)
+    pub time_ms: u64,
+
+    /// Detected fundamental frequency in Hz (0.0 when unvoiced)
+    pub detected_hz: f32,
+
+    /// Deviation of the detected pitch from the target note, in cents
+    pub cents_off: f32,
+
+    /// Corrected target note as a MIDI note number
+    pub target_note: u8,
+
+    /// Detector confidence: 0.0 (noise) to 1.0 (clean periodic signal)
+    pub confidence: f32,
+}
+
 // =============================================================================
 // APP STATE
 // =============================================================================
 
 /// Main application state holding all pitch correction parameters
 /// These parameters are persisted and synchronized with the frontend
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct AppState {
+    /// STATE_SCHEMA_VERSION of the build that saved this state; states saved
+    /// before the field existed load as 0
+    #[serde(default)]
+    schema_version: u32,
+
     /// Musical key for pitch correction (C, C#, D, etc.)
     key: Key,
 
@@ -83,6 +172,56 @@ pub struct AppState {
 
     /// Audio bypass (pass-through without processing)
     bypass: bool,
+
+    /// Bumped by every parameter change, so clients can tell whether they
+    /// missed one
+    #[serde(default)]
+    revision: u64,
+
+    /// Recent corrected-pitch telemetry, oldest first (in-memory only)
+    #[serde(skip)]
+    telemetry: VecDeque<PitchFrame>,
+
+    /// VFS drive holding pitch logs (set on init)
+    #[serde(skip)]
+    log_drive: String,
+
+    /// Pitch log for the current practice session, created on first upload
+    #[serde(skip)]
+    active_log: Option<String>,
+}
+
+/// A fresh install starts from musical defaults; a restart loads the saved
+/// state instead, so these must not be re-applied in init
+impl Default for AppState {
+    fn default() -> Self {
+        AppState {
+            schema_version: STATE_SCHEMA_VERSION,
+            key: Key::C,
+            scale: Scale::Major,
+            retune_speed: 0.5,      // Medium speed (balanced)
+            humanize: 0.1,          // Slight natural variation
+            mix: 1.0,               // 100% wet (full effect)
+            formant_preserve: true, // Preserve vocal character
+            bypass: false,          // Effect enabled
+            revision: 0,
+            telemetry: VecDeque::new(),
+            log_drive: String::new(),
+            active_log: None,
+        }
+    }
+}
+
+impl AppState {
+    /// Bring a state saved by an older build up to STATE_SCHEMA_VERSION
+    fn migrate(&mut self) {
+        if self.schema_version < 1 {
+            // Version 0 predates schema_version and revision; serde has
+            // already defaulted both, and no other field changed meaning
+            println!("🎵 Migrating saved state from schema version 0");
+        }
+        self.schema_version = STATE_SCHEMA_VERSION;
+    }
 }
 
 /// Status response for frontend queries
@@ -95,6 +234,7 @@ pub struct Status {
     mix: f32,
     formant_preserve: bool,
     bypass: bool,
+    revision: u64,
     node: String,
 }
 
@@ -110,25 +250,30 @@ pub struct Status {
             path: "/api",
             config: hyperware_process_lib::http::server::HttpBindingConfig::new(false, false, false, None),
         },
+        hyperware_process_lib::hyperapp::Binding::Http {
+            path: "/pitchlog/*",
+            config: hyperware_process_lib::http::server::HttpBindingConfig::new(false, false, false, None),
+        },
     ],
     save_config = hyperware_process_lib::hyperapp::SaveOptions::OnDiff,
     wit_world = "jendrix-tune-template-dot-os-v0"
 )]
 impl AppState {
-    /// Initialize the pitch correction app with sensible defaults
+    /// Start up on a fresh default state or on the state saved before a restart
     #[init]
     async fn initialize(&mut self) {
         // Register with Hyperware homepage
         add_to_homepage("Jendrix Tune", Some(ICON), Some("/"), None);
 
-        // Initialize pitch correction parameters with musical defaults
-        self.key = Key::C;
-        self.scale = Scale::Major;
-        self.retune_speed = 0.5; // Medium speed (balanced)
-        self.humanize = 0.1; // Slight natural variation
-        self.mix = 1.0; // 100% wet (full effect)
-        self.formant_preserve = true; // Preserve vocal character
-        self.bypass = false; // Effect enabled
+        // Drive for per-session pitch logs
+        match create_drive(our().package_id(), "pitch-logs", Some(5)) {
+            Ok(path) => self.log_drive = path,
+            Err(e) => println!("🎵 Failed to create pitch log drive: {:?}", e),
+        }
+
+        // Parameters come from Default or the saved state; only the layout
+        // may need updating
+        self.migrate();
 
         println!(
             "🎵 Jendrix Tune initialized on node: {} | Key: {:?} {} | Retune: {:.1}",
@@ -149,7 +294,7 @@ impl AppState {
     /// Get current pitch correction status and all parameters
     #[local]
     #[http]
-    async fn get_status(&self) -> Result<Status, String> {
+    async fn get_status(&self) -> Result<Status, AppError> {
         Ok(Status {
             key: self.key.clone(),
             scale: self.scale.clone(),
@@ -158,6 +303,7 @@ impl AppState {
             mix: self.mix,
             formant_preserve: self.formant_preserve,
             bypass: self.bypass,
+            revision: self.revision,
             node: our().node.clone(),
         })
     }
@@ -170,8 +316,9 @@ impl AppState {
     /// Example: { "set_key": "C" } or { "set_key": "FSharp" }
     #[local]
     #[http]
-    async fn set_key(&mut self, key: Key) -> Result<(), String> {
+    async fn set_key(&mut self, key: Key) -> Result<(), AppError> {
         self.key = key.clone();
+        self.revision += 1;
         println!("🎵 Key changed to: {:?}", key);
         Ok(())
     }
@@ -179,8 +326,9 @@ impl AppState {
     /// Set the scale type (Major or Minor)
     #[local]
     #[http]
-    async fn set_scale(&mut self, scale: Scale) -> Result<(), String> {
+    async fn set_scale(&mut self, scale: Scale) -> Result<(), AppError> {
         self.scale = scale.clone();
+        self.revision += 1;
         println!(
             "🎵 Scale changed to: {:?} {:?}",
             self.key, self.scale
@@ -191,8 +339,9 @@ impl AppState {
     /// Set retune speed (0.0 = instant/robotic, 1.0 = slow/natural)
     #[local]
     #[http]
-    async fn set_retune_speed(&mut self, speed: f32) -> Result<(), String> {
+    async fn set_retune_speed(&mut self, speed: f32) -> Result<(), AppError> {
         self.retune_speed = speed.clamp(0.0, 1.0);
+        self.revision += 1;
         println!("🎵 Retune speed: {:.2}", self.retune_speed);
         Ok(())
     }
@@ -200,8 +349,9 @@ impl AppState {
     /// Set humanize amount (0.0 = perfect tuning, 1.0 = natural vibrato)
     #[local]
     #[http]
-    async fn set_humanize(&mut self, amount: f32) -> Result<(), String> {
+    async fn set_humanize(&mut self, amount: f32) -> Result<(), AppError> {
         self.humanize = amount.clamp(0.0, 1.0);
+        self.revision += 1;
         println!("🎵 Humanize: {:.2}", self.humanize);
         Ok(())
     }
@@ -209,8 +359,9 @@ impl AppState {
     /// Set wet/dry mix (0.0 = 100% dry, 1.0 = 100% wet)
     #[local]
     #[http]
-    async fn set_mix(&mut self, mix: f32) -> Result<(), String> {
+    async fn set_mix(&mut self, mix: f32) -> Result<(), AppError> {
         self.mix = mix.clamp(0.0, 1.0);
+        self.revision += 1;
         println!("🎵 Mix: {:.1}% wet", self.mix * 100.0);
         Ok(())
     }
@@ -218,8 +369,9 @@ impl AppState {
     /// Toggle formant preservation
     #[local]
     #[http]
-    async fn set_formant_preserve(&mut self, enabled: bool) -> Result<(), String> {
+    async fn set_formant_preserve(&mut self, enabled: bool) -> Result<(), AppError> {
         self.formant_preserve = enabled;
+        self.revision += 1;
         println!(
             "🎵 Formant preserve: {}",
             if enabled { "ON" } else { "OFF" }
@@ -230,8 +382,9 @@ impl AppState {
     /// Toggle bypass (true = pass-through, false = processing enabled)
     #[local]
     #[http]
-    async fn set_bypass(&mut self, bypass: bool) -> Result<(), String> {
+    async fn set_bypass(&mut self, bypass: bool) -> Result<(), AppError> {
         self.bypass = bypass;
+        self.revision += 1;
         println!("🎵 Bypass: {}", if bypass { "ON" } else { "OFF" });
         Ok(())
     }
@@ -242,7 +395,7 @@ impl AppState {
     async fn update_all_params(
         &mut self,
         params: (Key, Scale, f32, f32, f32, bool, bool),
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let (key, scale, retune_speed, humanize, mix, formant_preserve, bypass) = params;
 
         self.key = key;
@@ -252,10 +405,282 @@ impl AppState {
         self.mix = mix.clamp(0.0, 1.0);
         self.formant_preserve = formant_preserve;
         self.bypass = bypass;
+        self.revision += 1;
 
         println!("🎵 All parameters updated");
         Ok(())
     }
+
+    // =========================================================================
+    // PITCH TELEMETRY ENDPOINTS
+    // =========================================================================
+
+    /// Append a batch of pitch frames from the browser's detector
+    /// Frames are kept in memory only, capped at MAX_TELEMETRY_FRAMES
+    #[local]
+    #[http]
+    async fn record_pitch_frames(&mut self, frames: Vec<PitchFrame>) -> Result<(), AppError> {
+        for frame in frames {
+            // Keep the buffer time-ordered even if a batch arrives late
+            let position = self
+                .telemetry
+                .iter()
+                .rposition(|f| f.time_ms <= frame.time_ms)
+                .map_or(0, |i| i + 1);
+            self.telemetry.insert(position, frame);
+        }
+        while self.telemetry.len() > MAX_TELEMETRY_FRAMES {
+            self.telemetry.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Export the last `recording_window_ms` of corrected pitch as a MIDI file
+    /// Returns the bytes of a single-track (format 0) Standard MIDI File
+    #[local]
+    #[http]
+    async fn export_midi(&self, recording_window_ms: u64) -> Result<Vec<u8>, AppError> {
+        let Some(last) = self.telemetry.back() else {
+            return Err(AppError::not_found("No pitch telemetry recorded yet"));
+        };
+        let window_start = last.time_ms.saturating_sub(recording_window_ms);
+
+        let frames: Vec<PitchFrame> = self
+            .telemetry
+            .iter()
+            .filter(|f| f.time_ms >= window_start)
+            .cloned()
+            .collect();
+
+        let notes = midi::segment_notes(&frames);
+        println!(
+            "🎵 Exporting MIDI: {} frames -> {} notes",
+            frames.len(),
+            notes.len()
+        );
+        Ok(midi::write_smf(&notes))
+    }
+
+    // =========================================================================
+    // PITCH LOG ENDPOINTS
+    // =========================================================================
+
+    /// Start a new pitch log session, capturing the current key and scale
+    /// Returns the new log id
+    #[local]
+    #[http]
+    async fn start_pitch_log(&mut self) -> Result<String, AppError> {
+        // Two starts in the same millisecond each get their own log
+        let created_at_ms = now_ms();
+        let mut timestamp = created_at_ms;
+        while pitch_log::log_exists(&self.log_drive, &timestamp.to_string())? {
+            timestamp += 1;
+        }
+        let id = timestamp.to_string();
+        pitch_log::create_log(
+            &self.log_drive,
+            &PitchLogHeader {
+                version: pitch_log::LOG_FORMAT_VERSION,
+                id: id.clone(),
+                created_at_ms,
+                key: self.key.clone(),
+                scale: self.scale.clone(),
+            },
+        )?;
+        self.active_log = Some(id.clone());
+        println!("🎵 Started pitch log {}", id);
+        Ok(id)
+    }
+
+    /// Append a batch of frames to the current session's pitch log
+    /// A session log is started automatically if none is active
+    #[local]
+    #[http]
+    async fn log_pitch_frames(&mut self, frames: Vec<PitchFrame>) -> Result<String, AppError> {
+        let id = match self.active_log.clone() {
+            Some(id) => id,
+            None => self.start_pitch_log().await?,
+        };
+        pitch_log::append_frames(&self.log_drive, &id, &frames)?;
+        Ok(id)
+    }
+
+    /// List stored pitch logs, newest first
+    #[local]
+    #[http]
+    async fn list_pitch_logs(&self) -> Result<Vec<PitchLogInfo>, AppError> {
+        pitch_log::list_logs(&self.log_drive).map_err(AppError::from)
+    }
+
+    /// Get a page of frames from a pitch log, sorted by time
+    /// At most pitch_log::MAX_PAGE_FRAMES frames are returned, whatever the limit
+    #[local]
+    #[http]
+    async fn get_pitch_log(
+        &self,
+        id: String,
+        offset: u64,
+        limit: u64,
+    ) -> Result<PitchLogPage, AppError> {
+        let mut frames = Vec::new();
+        let info = pitch_log::scan_log(&self.log_drive, &id, |frame| frames.push(frame))?;
+        let (frames, total) = pitch_log::page_frames(frames, offset, limit);
+
+        Ok(PitchLogPage {
+            info,
+            frames,
+            total,
+        })
+    }
+
+    /// Summarize intonation over a whole pitch log
+    /// Streams through the stored log without loading it all at once
+    #[local]
+    #[http]
+    async fn get_intonation_summary(&self, log_id: String) -> Result<IntonationSummary, AppError> {
+        let info = pitch_log::read_info(&self.log_drive, &log_id)?;
+        let mut accumulator = IntonationAccumulator::new(&info.key, &info.scale);
+        let info = pitch_log::scan_log(&self.log_drive, &log_id, |frame| accumulator.add(&frame))?;
+        Ok(accumulator.finish(info))
+    }
+
+    /// Export a pitch log as CSV (one row per frame, in stored order)
+    #[local]
+    #[http]
+    async fn export_pitch_log_csv(&self, log_id: String) -> Result<Vec<u8>, AppError> {
+        pitch_log::export_csv(&self.log_drive, &log_id).map_err(AppError::from)
+    }
+
+    /// Browser download of a pitch log as CSV: GET /pitchlog/<id>.csv
+    #[http]
+    async fn download_pitch_log_csv(&self) -> Result<Vec<u8>, AppError> {
+        let request_path =
+            get_path().ok_or_else(|| AppError::invalid_input("No request path provided"))?;
+        let log_id = request_path
+            .strip_prefix("/pitchlog/")
+            .map(|name| name.strip_suffix(".csv").unwrap_or(name))
+            .ok_or_else(|| AppError::invalid_input("Invalid pitch log path"))?
+            .to_string();
+
+        let csv = pitch_log::export_csv(&self.log_drive, &log_id)?;
+
+        add_response_header(
+            "Content-Disposition".to_string(),
+            format!("attachment; filename=\"pitchlog-{}.csv\"", log_id),
+        );
+        add_response_header("Content-Type".to_string(), "text/csv".to_string());
+
+        Ok(csv)
+    }
+
+    /// Delete a stored pitch log
+    #[local]
+    #[http]
+    async fn delete_pitch_log(&mut self, id: String) -> Result<(), AppError> {
+        pitch_log::delete_log(&self.log_drive, &id).await?;
+        if self.active_log.as_deref() == Some(id.as_str()) {
+            self.active_log = None;
+        }
+        println!("🎵 Deleted pitch log {}", id);
+        Ok(())
+    }
+}
+
+/// Current wall-clock time in milliseconds since the Unix epoch
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What a restart does to the state: the copy saved after the last change
+    // is loaded back and migrated by init
+    fn restart(state: &AppState) -> AppState {
+        load(&serde_json::to_vec(state).unwrap())
+    }
+
+    fn load(saved: &[u8]) -> AppState {
+        let mut state: AppState = serde_json::from_slice(saved).unwrap();
+        state.migrate();
+        state
+    }
+
+    fn saved_fields(state: &AppState) -> serde_json::Value {
+        serde_json::to_value(state).unwrap()
+    }
+
+    fn populated() -> AppState {
+        let mut state = AppState {
+            key: Key::FSharp,
+            scale: Scale::Minor,
+            retune_speed: 0.25,
+            humanize: 0.75,
+            mix: 0.5,
+            formant_preserve: false,
+            bypass: true,
+            revision: 7,
+            log_drive: "/jendrix-tune:template.os/pitch-logs".to_string(),
+            active_log: Some("1700000000000".to_string()),
+            ..AppState::default()
+        };
+        state.telemetry.push_back(PitchFrame {
+            time_ms: 10,
+            detected_hz: 440.0,
+            cents_off: 0.0,
+            target_note: 69,
+            confidence: 0.9,
+        });
+        state
+    }
+
+    #[test]
+    fn saved_state_survives_a_restart() {
+        let before = populated();
+        let after = restart(&before);
+        assert_eq!(saved_fields(&before), saved_fields(&after));
+        assert_eq!(after.key, Key::FSharp);
+        assert_eq!(after.revision, 7);
+    }
+
+    #[test]
+    fn unsaved_fields_start_empty_after_a_restart() {
+        let after = restart(&populated());
+        assert!(after.telemetry.is_empty());
+        assert_eq!(after.log_drive, "");
+        assert_eq!(after.active_log, None);
+    }
+
+    #[test]
+    fn state_saved_before_schema_versions_migrates() {
+        let saved = serde_json::json!({
+            "key": "A",
+            "scale": "Minor",
+            "retune_speed": 0.25,
+            "humanize": 0.75,
+            "mix": 0.5,
+            "formant_preserve": false,
+            "bypass": true,
+        });
+        let state = load(saved.to_string().as_bytes());
+        assert_eq!(state.schema_version, STATE_SCHEMA_VERSION);
+        assert_eq!(state.revision, 0);
+        assert_eq!((state.key, state.scale), (Key::A, Scale::Minor));
+        assert_eq!(state.retune_speed, 0.25);
+        assert!(state.bypass);
+    }
+
+    #[test]
+    fn migrating_a_current_state_changes_nothing() {
+        let mut state = populated();
+        let before = saved_fields(&state);
+        state.migrate();
+        assert_eq!(saved_fields(&state), before);
+    }
 }
 
 // =============================================================================
@@ -0,0 +1,335 @@
+// MIDI EXPORT
+// Turns the corrected-pitch telemetry stream into a Standard MIDI File
+//
+// Segmentation rules:
+// - A note starts once the same target note has held for more than NOTE_ONSET_MS
+// - A note ends when the target note changes, confidence drops, or frames stop arriving
+// - Only one note sounds at a time, so every note-on is closed before the next one
+
+use crate::PitchFrame;
+
+/// A stable note must hold this long before it becomes a note event
+pub const NOTE_ONSET_MS: u64 = 80;
+
+/// Frames below this confidence are treated as silence
+pub const MIN_CONFIDENCE: f32 = 0.5;
+
+/// A hole in the frame stream longer than this is treated as silence
+pub const MAX_FRAME_GAP_MS: u64 = 100;
+
+/// Ticks per quarter note written to the MIDI header
+const TICKS_PER_QUARTER: u16 = 480;
+
+/// Fixed export tempo: 120 BPM (microseconds per quarter note)
+const TEMPO_US_PER_QUARTER: u32 = 500_000;
+
+/// Velocity used for every exported note
+const NOTE_VELOCITY: u8 = 100;
+
+/// A single segmented note, in milliseconds relative to the start of the window
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteEvent {
+    pub note: u8,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// Segment a time-ordered frame stream into non-overlapping note events
+pub fn segment_notes(frames: &[PitchFrame]) -> Vec<NoteEvent> {
+    let Some(first) = frames.first() else {
+        return Vec::new();
+    };
+    let origin = first.time_ms;
+
+    let mut notes = Vec::new();
+    // (note, first time it was seen) for the note currently being held
+    let mut candidate: Option<(u8, u64)> = None;
+    // (note, onset) once the candidate has been held long enough
+    let mut active: Option<(u8, u64)> = None;
+    let mut last_time = origin;
+
+    for frame in frames {
+        let time = frame.time_ms;
+
+        // A gap in the stream ends whatever was sounding at the last frame we saw
+        if time.saturating_sub(last_time) > MAX_FRAME_GAP_MS {
+            close_note(&mut notes, &mut active, last_time, origin);
+            candidate = None;
+        }
+        last_time = time;
+
+        let voiced = frame.confidence >= MIN_CONFIDENCE && frame.detected_hz > 0.0;
+        if !voiced {
+            close_note(&mut notes, &mut active, time, origin);
+            candidate = None;
+            continue;
+        }
+
+        match candidate {
+            Some((note, _)) if note == frame.target_note => {}
+            _ => {
+                // Note changed: end the sounding note and start timing the new one
+                close_note(&mut notes, &mut active, time, origin);
+                candidate = Some((frame.target_note, time));
+            }
+        }
+
+        if let (Some((note, since)), None) = (candidate, active) {
+            if time - since > NOTE_ONSET_MS {
+                active = Some((note, since));
+            }
+        }
+    }
+
+    close_note(&mut notes, &mut active, last_time, origin);
+    notes
+}
+
+fn close_note(notes: &mut Vec<NoteEvent>, active: &mut Option<(u8, u64)>, end: u64, origin: u64) {
+    if let Some((note, start)) = active.take() {
+        if end > start {
+            notes.push(NoteEvent {
+                note,
+                start_ms: start - origin,
+                end_ms: end - origin,
+            });
+        }
+    }
+}
+
+/// Write note events as a single-track (format 0) Standard MIDI File
+pub fn write_smf(notes: &[NoteEvent]) -> Vec<u8> {
+    let mut track = Vec::new();
+
+    // Tempo meta event at tick 0
+    write_vlq(&mut track, 0);
+    track.extend_from_slice(&[0xFF, 0x51, 0x03]);
+    track.extend_from_slice(&TEMPO_US_PER_QUARTER.to_be_bytes()[1..]);
+
+    let mut last_tick = 0u32;
+    for note in notes {
+        let on_tick = ms_to_ticks(note.start_ms);
+        let off_tick = ms_to_ticks(note.end_ms).max(on_tick + 1);
+
+        write_vlq(&mut track, on_tick.saturating_sub(last_tick));
+        track.extend_from_slice(&[0x90, note.note & 0x7F, NOTE_VELOCITY]);
+
+        write_vlq(&mut track, off_tick - on_tick);
+        track.extend_from_slice(&[0x80, note.note & 0x7F, 0]);
+
+        last_tick = off_tick;
+    }
+
+    // End of track
+    write_vlq(&mut track, 0);
+    track.extend_from_slice(&[0xFF, 0x2F, 0x00]);
+
+    let mut smf = Vec::with_capacity(22 + track.len());
+    smf.extend_from_slice(b"MThd");
+    smf.extend_from_slice(&6u32.to_be_bytes());
+    smf.extend_from_slice(&0u16.to_be_bytes()); // format 0
+    smf.extend_from_slice(&1u16.to_be_bytes()); // one track
+    smf.extend_from_slice(&TICKS_PER_QUARTER.to_be_bytes());
+    smf.extend_from_slice(b"MTrk");
+    smf.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    smf.extend_from_slice(&track);
+    smf
+}
+
+fn ms_to_ticks(ms: u64) -> u32 {
+    let ticks = ms * 1000 * TICKS_PER_QUARTER as u64 / TEMPO_US_PER_QUARTER as u64;
+    // Delta times are limited to four VLQ bytes
+    ticks.min(0x0FFF_FFFF) as u32
+}
+
+/// MIDI variable-length quantity: 7 bits per byte, high bit set on all but the last
+fn write_vlq(out: &mut Vec<u8>, mut value: u32) {
+    let mut buffer = [0u8; 5];
+    let mut len = 0;
+    loop {
+        buffer[len] = (value & 0x7F) as u8;
+        len += 1;
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+    }
+    for (i, byte) in buffer[..len].iter().enumerate().rev() {
+        let continuation = if i > 0 { 0x80 } else { 0 };
+        out.push(byte | continuation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One voiced frame of `note` at `time_ms`
+    fn frame(time_ms: u64, note: u8, confidence: f32) -> PitchFrame {
+        PitchFrame {
+            time_ms,
+            detected_hz: 440.0,
+            cents_off: 0.0,
+            target_note: note,
+            confidence,
+        }
+    }
+
+    /// Frames of `note` every 10 ms from `from_ms` through `to_ms`
+    fn held(from_ms: u64, to_ms: u64, note: u8) -> Vec<PitchFrame> {
+        (from_ms..=to_ms)
+            .step_by(10)
+            .map(|time| frame(time, note, 0.9))
+            .collect()
+    }
+
+    fn note(note: u8, start_ms: u64, end_ms: u64) -> NoteEvent {
+        NoteEvent {
+            note,
+            start_ms,
+            end_ms,
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        On(u8),
+        Off(u8),
+    }
+
+    /// The note events of a format 0 file written by write_smf, with their
+    /// absolute ticks; panics unless the track ends with an end-of-track event
+    fn parse_smf(smf: &[u8]) -> Vec<(u32, Event)> {
+        assert_eq!(&smf[..4], b"MThd");
+        assert_eq!(&smf[8..14], &[0, 0, 0, 1, 1, 0xE0]);
+        assert_eq!(&smf[14..18], b"MTrk");
+        let len = u32::from_be_bytes(smf[18..22].try_into().unwrap()) as usize;
+        let track = &smf[22..];
+        assert_eq!(track.len(), len);
+
+        let mut events = Vec::new();
+        let mut tick = 0u32;
+        let mut i = 0;
+        loop {
+            let mut delta = 0u32;
+            loop {
+                let byte = track[i];
+                i += 1;
+                delta = (delta << 7) | (byte & 0x7F) as u32;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            tick += delta;
+            match track[i] {
+                0xFF => {
+                    let (kind, len) = (track[i + 1], track[i + 2] as usize);
+                    i += 3 + len;
+                    if kind == 0x2F {
+                        assert_eq!(i, track.len(), "end of track is the last event");
+                        return events;
+                    }
+                }
+                0x90 => {
+                    events.push((tick, Event::On(track[i + 1])));
+                    i += 3;
+                }
+                0x80 => {
+                    events.push((tick, Event::Off(track[i + 1])));
+                    i += 3;
+                }
+                status => panic!("unexpected status byte {status:#x}"),
+            }
+        }
+    }
+
+    #[test]
+    fn note_changes_round_trip_through_smf() {
+        let mut frames = held(0, 200, 60);
+        frames.extend(held(210, 400, 64));
+
+        let notes = segment_notes(&frames);
+        assert_eq!(notes, vec![note(60, 0, 210), note(64, 210, 400)]);
+        assert_eq!(
+            parse_smf(&write_smf(&notes)),
+            vec![
+                (0, Event::On(60)),
+                (ms_to_ticks(210), Event::Off(60)),
+                (ms_to_ticks(210), Event::On(64)),
+                (ms_to_ticks(400), Event::Off(64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn gap_in_frames_splits_a_held_note() {
+        let mut frames = held(0, 200, 60);
+        frames.extend(held(200 + MAX_FRAME_GAP_MS + 50, 550, 60));
+
+        assert_eq!(
+            segment_notes(&frames),
+            vec![note(60, 0, 200), note(60, 350, 550)]
+        );
+    }
+
+    #[test]
+    fn gap_at_the_limit_does_not_split() {
+        let mut frames = held(0, 200, 60);
+        frames.extend(held(200 + MAX_FRAME_GAP_MS, 400, 60));
+
+        assert_eq!(segment_notes(&frames), vec![note(60, 0, 400)]);
+    }
+
+    #[test]
+    fn low_confidence_frames_are_silence() {
+        let mut frames = held(0, 90, 60);
+        frames.extend(
+            (100..=150)
+                .step_by(10)
+                .map(|time| frame(time, 60, MIN_CONFIDENCE / 2.0)),
+        );
+        frames.extend(held(160, 300, 60));
+
+        assert_eq!(
+            segment_notes(&frames),
+            vec![note(60, 0, 100), note(60, 160, 300)]
+        );
+
+        let quiet: Vec<PitchFrame> = (0..=500)
+            .step_by(10)
+            .map(|time| frame(time, 60, MIN_CONFIDENCE / 2.0))
+            .collect();
+        assert_eq!(segment_notes(&quiet), vec![]);
+    }
+
+    #[test]
+    fn no_note_left_sounding_at_end_of_track() {
+        // The stream stops mid-note, after an onset shorter than NOTE_ONSET_MS
+        let mut frames = held(0, 300, 60);
+        frames.extend(held(310, 310 + NOTE_ONSET_MS - 10, 62));
+        frames.extend(held(400, 600, 64));
+
+        let events = parse_smf(&write_smf(&segment_notes(&frames)));
+        let mut sounding = None;
+        for (_, event) in &events {
+            match *event {
+                Event::On(note) => {
+                    assert_eq!(sounding, None, "note-on while {sounding:?} sounds");
+                    sounding = Some(note);
+                }
+                Event::Off(note) => {
+                    assert_eq!(sounding, Some(note), "note-off for a silent note");
+                    sounding = None;
+                }
+            }
+        }
+        assert_eq!(sounding, None, "a note is still sounding at end of track");
+        assert_eq!(events.last(), Some(&(ms_to_ticks(600), Event::Off(64))));
+    }
+
+    #[test]
+    fn no_frames_write_an_empty_track() {
+        assert_eq!(segment_notes(&[]), vec![]);
+        assert_eq!(parse_smf(&write_smf(&[])), vec![]);
+    }
+}
@@ -0,0 +1,416 @@
+// PITCH LOG STORAGE
+// Per-session pitch logs stored as files in the app's VFS drive
+//
+// File format (JSON lines):
+//   line 1:  PitchLogHeader  {"version":1,"id":...,"created_at_ms":...,"key":...,"scale":...}
+//   line 2+: PitchFrame      one frame per line, in arrival order
+//
+// Frames are appended batch by batch, so the file is not guaranteed to be
+// time-ordered; readers that need ordering sort after reading.
+
+use hyperware_process_lib::hyperapp::send;
+use hyperware_process_lib::println;
+use hyperware_process_lib::vfs::{self, vfs_request, SeekFrom, VfsAction, VfsResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::{note_hz, note_label, Key, PitchFrame, Scale};
+
+/// Current on-disk log format version, written into every header
+pub const LOG_FORMAT_VERSION: u32 = 1;
+
+/// Maximum size of a single pitch log file
+pub const MAX_LOG_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Bytes read from the VFS per chunk when scanning a log
+const READ_CHUNK_BYTES: u64 = 64 * 1024;
+
+/// Most frames returned by one page of a log
+pub const MAX_PAGE_FRAMES: u64 = 10_000;
+
+/// VFS request timeout in seconds
+const VFS_TIMEOUT: u64 = 5;
+
+/// First line of every pitch log file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PitchLogHeader {
+    pub version: u32,
+    pub id: String,
+    pub created_at_ms: u64,
+    /// Key and scale in effect when the session started
+    pub key: Key,
+    pub scale: Scale,
+}
+
+/// Summary of a stored log for listing
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PitchLogInfo {
+    pub id: String,
+    pub created_at_ms: u64,
+    pub key: Key,
+    pub scale: Scale,
+    pub size_bytes: u64,
+}
+
+/// One page of frames from a stored log, sorted by time
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PitchLogPage {
+    pub info: PitchLogInfo,
+    pub frames: Vec<PitchFrame>,
+    /// Total number of frames in the log
+    pub total: u64,
+}
+
+pub fn log_path(drive: &str, id: &str) -> Result<String, String> {
+    // Ids are generated by us as timestamps; reject anything else so an id
+    // can never point outside the drive
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("invalid_input: Invalid pitch log id: {}", id));
+    }
+    Ok(format!("{}/{}.jsonl", drive, id))
+}
+
+/// Whether a log with this id is already stored
+pub fn log_exists(drive: &str, id: &str) -> Result<bool, String> {
+    let path = log_path(drive, id)?;
+    Ok(vfs::metadata(&path, Some(VFS_TIMEOUT)).is_ok())
+}
+
+/// Create a new log file with its header line
+///
+/// Replaces any log already stored under the same id; callers pick a free id
+/// with log_exists first
+pub fn create_log(drive: &str, header: &PitchLogHeader) -> Result<(), String> {
+    let path = log_path(drive, &header.id)?;
+    let file = vfs::create_file(&path, Some(VFS_TIMEOUT))
+        .map_err(|e| format!("Failed to create pitch log: {}", e))?;
+
+    file.write(&header_line(header)?)
+        .map_err(|e| format!("Failed to write pitch log header: {}", e))
+}
+
+/// Append a batch of frames, refusing batches that would push the log over MAX_LOG_BYTES
+pub fn append_frames(drive: &str, id: &str, frames: &[PitchFrame]) -> Result<(), String> {
+    let path = log_path(drive, id)?;
+    let mut file = vfs::open_file(&path, false, Some(VFS_TIMEOUT))
+        .map_err(|e| format!("Failed to open pitch log {}: {}", id, e))?;
+
+    let batch = frame_lines(frames)?;
+    let current = file
+        .metadata()
+        .map_err(|e| format!("Failed to get metadata: {}", e))?
+        .len;
+    check_room(id, current, batch.len() as u64)?;
+
+    file.append(&batch)
+        .map_err(|e| format!("Failed to append to pitch log {}: {}", id, e))
+}
+
+/// Stream through a log in fixed-size chunks, calling `on_frame` for every frame
+///
+/// Only one chunk plus one partial line is held in memory at a time.
+pub fn scan_log(
+    drive: &str,
+    id: &str,
+    mut on_frame: impl FnMut(PitchFrame),
+) -> Result<PitchLogInfo, String> {
+    let path = log_path(drive, id)?;
+    let mut file = vfs::open_file(&path, false, Some(VFS_TIMEOUT))
+        .map_err(|e| format!("not_found: Pitch log {} not found: {}", id, e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Failed to get metadata: {}", e))?
+        .len;
+
+    let mut header: Option<PitchLogHeader> = None;
+    let mut pending: Vec<u8> = Vec::new();
+    let mut offset = 0;
+
+    let mut handle_line = |line: &[u8], header: &mut Option<PitchLogHeader>| -> Result<(), String> {
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            return Ok(());
+        }
+        match header {
+            None => *header = Some(parse_header(line)?),
+            Some(_) => {
+                let frame: PitchFrame = serde_json::from_slice(line)
+                    .map_err(|e| format!("Corrupt frame in pitch log {}: {}", id, e))?;
+                on_frame(frame);
+            }
+        }
+        Ok(())
+    };
+
+    while offset < len {
+        let chunk_len = (len - offset).min(READ_CHUNK_BYTES) as usize;
+        let mut chunk = vec![0u8; chunk_len];
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| format!("Failed to seek pitch log: {}", e))?;
+        file.read_at(&mut chunk)
+            .map_err(|e| format!("Failed to read pitch log: {}", e))?;
+        offset += chunk_len as u64;
+
+        pending.extend_from_slice(&chunk);
+        while let Some(newline) = pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=newline).collect();
+            handle_line(&line[..line.len() - 1], &mut header)?;
+        }
+    }
+    handle_line(&pending, &mut header)?;
+
+    let header = header.ok_or_else(|| format!("Pitch log {} has no header", id))?;
+    Ok(info_from_header(header, len))
+}
+
+/// Header row of the CSV export
+pub const CSV_HEADER: &str = "time_ms,detected_hz,target_hz,cents_off,target_note,confidence\n";
+
+/// Render a log as CSV, writing rows as frames are scanned so no frame list is built
+pub fn export_csv(drive: &str, id: &str) -> Result<Vec<u8>, String> {
+    let mut csv = CSV_HEADER.as_bytes().to_vec();
+    scan_log(drive, id, |frame| {
+        // All fields are numbers or plain note names, so nothing needs escaping
+        csv.extend_from_slice(
+            format!(
+                "{},{:.2},{:.2},{:.1},{},{:.3}\n",
+                frame.time_ms,
+                frame.detected_hz,
+                note_hz(frame.target_note),
+                frame.cents_off,
+                note_label(frame.target_note),
+                frame.confidence,
+            )
+            .as_bytes(),
+        );
+    })?;
+    Ok(csv)
+}
+
+/// Read only the header line of a log
+pub fn read_info(drive: &str, id: &str) -> Result<PitchLogInfo, String> {
+    let path = log_path(drive, id)?;
+    let mut file = vfs::open_file(&path, false, Some(VFS_TIMEOUT))
+        .map_err(|e| format!("not_found: Pitch log {} not found: {}", id, e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Failed to get metadata: {}", e))?
+        .len;
+
+    let mut chunk = vec![0u8; len.min(READ_CHUNK_BYTES) as usize];
+    file.seek(SeekFrom::Start(0))
+        .map_err(|e| format!("Failed to seek pitch log: {}", e))?;
+    file.read_at(&mut chunk)
+        .map_err(|e| format!("Failed to read pitch log: {}", e))?;
+
+    let end = chunk
+        .iter()
+        .position(|b| *b == b'\n')
+        .unwrap_or(chunk.len());
+    Ok(info_from_header(parse_header(&chunk[..end])?, len))
+}
+
+/// List every log in the drive, newest first
+pub fn list_logs(drive: &str) -> Result<Vec<PitchLogInfo>, String> {
+    let dir = vfs::Directory {
+        path: drive.to_string(),
+        timeout: VFS_TIMEOUT,
+    };
+    let entries = dir
+        .read()
+        .map_err(|e| format!("Failed to read pitch log directory: {}", e))?;
+
+    let mut logs = Vec::new();
+    for entry in entries {
+        let Some(id) = entry
+            .path
+            .split('/')
+            .next_back()
+            .and_then(|name| name.strip_suffix(".jsonl"))
+        else {
+            continue;
+        };
+        match read_info(drive, id) {
+            Ok(info) => logs.push(info),
+            Err(e) => println!("🎵 Skipping unreadable pitch log {}: {}", id, e),
+        }
+    }
+    logs.sort_by_key(|info| std::cmp::Reverse(info.created_at_ms));
+    Ok(logs)
+}
+
+pub async fn delete_log(drive: &str, id: &str) -> Result<(), String> {
+    let path = log_path(drive, id)?;
+    let request = vfs_request(&path, VfsAction::RemoveFile).expects_response(VFS_TIMEOUT);
+
+    match send::<VfsResponse>(request).await {
+        Ok(VfsResponse::Ok) => Ok(()),
+        Ok(VfsResponse::Err(e)) => Err(format!("Failed to delete pitch log {}: {:?}", id, e)),
+        Ok(_) => Err("Unexpected response from VFS".to_string()),
+        Err(e) => Err(format!("Failed to send VFS request: {}", e)),
+    }
+}
+
+/// The first line of a log file, newline included
+fn header_line(header: &PitchLogHeader) -> Result<Vec<u8>, String> {
+    let mut line = serde_json::to_vec(header).map_err(|e| e.to_string())?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// One line per frame, each ending in a newline
+fn frame_lines(frames: &[PitchFrame]) -> Result<Vec<u8>, String> {
+    let mut lines = Vec::new();
+    for frame in frames {
+        serde_json::to_writer(&mut lines, frame).map_err(|e| e.to_string())?;
+        lines.push(b'\n');
+    }
+    Ok(lines)
+}
+
+/// Refuse a batch that would push a log of `current` bytes over MAX_LOG_BYTES
+fn check_room(id: &str, current: u64, batch_bytes: u64) -> Result<(), String> {
+    if current + batch_bytes > MAX_LOG_BYTES {
+        return Err(format!(
+            "invalid_input: Pitch log {} is full ({} byte limit)",
+            id, MAX_LOG_BYTES
+        ));
+    }
+    Ok(())
+}
+
+/// Sort a log's frames by time and cut out one page of at most
+/// MAX_PAGE_FRAMES, returning it with the total frame count
+pub fn page_frames(mut frames: Vec<PitchFrame>, offset: u64, limit: u64) -> (Vec<PitchFrame>, u64) {
+    // Batches may have been uploaded out of order
+    frames.sort_by_key(|f| f.time_ms);
+    let total = frames.len() as u64;
+    let page = frames
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit.min(MAX_PAGE_FRAMES) as usize)
+        .collect();
+    (page, total)
+}
+
+fn parse_header(line: &[u8]) -> Result<PitchLogHeader, String> {
+    // Check the version before the full shape so newer formats fail clearly
+    let raw: serde_json::Value =
+        serde_json::from_slice(line).map_err(|e| format!("Corrupt pitch log header: {}", e))?;
+    let version = raw.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version == 0 || version > LOG_FORMAT_VERSION as u64 {
+        return Err(format!("Unsupported pitch log version {}", version));
+    }
+    serde_json::from_value(raw).map_err(|e| format!("Corrupt pitch log header: {}", e))
+}
+
+fn info_from_header(header: PitchLogHeader, size_bytes: u64) -> PitchLogInfo {
+    PitchLogInfo {
+        id: header.id,
+        created_at_ms: header.created_at_ms,
+        key: header.key,
+        scale: header.scale,
+        size_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> PitchLogHeader {
+        PitchLogHeader {
+            version: LOG_FORMAT_VERSION,
+            id: "1000".to_string(),
+            created_at_ms: 1000,
+            key: Key::A,
+            scale: Scale::Minor,
+        }
+    }
+
+    fn frame(time_ms: u64) -> PitchFrame {
+        PitchFrame {
+            time_ms,
+            detected_hz: 440.0,
+            cents_off: -3.5,
+            target_note: 69,
+            confidence: 0.9,
+        }
+    }
+
+    #[test]
+    fn logs_are_a_header_line_then_one_line_per_frame() {
+        let mut log = header_line(&header()).unwrap();
+        log.extend(frame_lines(&[frame(10), frame(20)]).unwrap());
+        let text = String::from_utf8(log).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            r#"{"version":1,"id":"1000","created_at_ms":1000,"key":"A","scale":"Minor"}"#
+        );
+        assert_eq!(parse_header(lines[0].as_bytes()), Ok(header()));
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            serde_json::from_str::<PitchFrame>(lines[2]).unwrap(),
+            frame(20)
+        );
+        assert!(text.ends_with('\n'));
+    }
+
+    #[test]
+    fn headers_from_a_newer_format_are_refused() {
+        let newer = format!(
+            r#"{{"version":{},"id":"1000","created_at_ms":1000,"key":"A","scale":"Minor","extra":true}}"#,
+            LOG_FORMAT_VERSION + 1
+        );
+        assert_eq!(
+            parse_header(newer.as_bytes()),
+            Err(format!(
+                "Unsupported pitch log version {}",
+                LOG_FORMAT_VERSION + 1
+            ))
+        );
+        // a header without a version predates versioning and is not ours
+        assert_eq!(
+            parse_header(br#"{"id":"1000"}"#),
+            Err("Unsupported pitch log version 0".to_string())
+        );
+        assert!(parse_header(b"not json")
+            .unwrap_err()
+            .starts_with("Corrupt pitch log header"));
+    }
+
+    #[test]
+    fn batches_may_fill_a_log_exactly_to_the_cap() {
+        assert_eq!(check_room("1000", MAX_LOG_BYTES - 10, 10), Ok(()));
+        assert_eq!(
+            check_room("1000", MAX_LOG_BYTES - 10, 11),
+            Err(format!(
+                "invalid_input: Pitch log 1000 is full ({} byte limit)",
+                MAX_LOG_BYTES
+            ))
+        );
+    }
+
+    #[test]
+    fn pages_are_sorted_and_capped() {
+        let frames = vec![frame(30), frame(10), frame(20)];
+        let (page, total) = page_frames(frames, 1, 1);
+        assert_eq!((page, total), (vec![frame(20)], 3));
+
+        let frames = (0..MAX_PAGE_FRAMES + 5).map(frame).collect();
+        let (page, total) = page_frames(frames, 0, u64::MAX);
+        assert_eq!(page.len() as u64, MAX_PAGE_FRAMES);
+        assert_eq!(total, MAX_PAGE_FRAMES + 5);
+    }
+
+    #[test]
+    fn ids_must_be_digits() {
+        assert_eq!(
+            log_path("/drive", "1000"),
+            Ok("/drive/1000.jsonl".to_string())
+        );
+        for id in ["", "../x", "10a"] {
+            assert!(log_path("/drive", id).is_err(), "{id}");
+        }
+    }
+}
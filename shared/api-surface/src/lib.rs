@@ -0,0 +1,260 @@
+//! the API surface of a hyperprocess, read from the WIT interface the
+//!  hyperprocess macro generates for it, as canonical text
+//!
+//! one line per type and one per handler signature, sorted, with comments
+//!  and formatting dropped, so two renders differ only when a caller would
+//!  notice: a handler added, removed or retyped, or a type changed
+//!
+//! ```text
+//! interface file-explorer
+//! type file-info = record { name: string, path: string, ... }
+//! fn copy-file [local] (source: string, destination: string) -> result<file-info, app-error>
+//! ```
+
+/// what the macro appends to a handler's name to name its signature record
+const SIGNATURE_KINDS: &[&str] = &["http", "local", "remote", "ws"];
+
+/// the argument the macro adds to every signature record for the address
+///  or path the call is sent to; not part of the handler's own arguments
+const TARGET_FIELD: &str = "target";
+
+const RETURN_FIELD: &str = "returning";
+
+/// the canonical surface of the single `interface` in `wit`
+pub fn render(wit: &str) -> Result<String, String> {
+    let source = strip_comments(wit);
+    let mut scanner = Scanner::new(&source);
+
+    let mut interface = None;
+    let mut types = Vec::new();
+    let mut functions = Vec::new();
+    while let Some(word) = scanner.word() {
+        match word.as_str() {
+            "package" | "use" | "import" | "export" | "include" => scanner.skip_statement(),
+            "interface" => {
+                if interface.is_some() {
+                    return Err("more than one interface".to_string());
+                }
+                interface = Some(scanner.name()?);
+                scanner.expect('{')?;
+            }
+            "world" => {
+                scanner.name()?;
+                scanner.block()?;
+            }
+            "type" => {
+                let name = scanner.name()?;
+                scanner.expect('=')?;
+                let alias = scanner.until(';')?;
+                types.push(format!("type {name} = {}", normalize(&alias)));
+            }
+            "record" => {
+                let name = scanner.name()?;
+                let fields = split_items(&scanner.block()?);
+                match signature_of(&name) {
+                    Some((function, kind)) => {
+                        functions.push(render_signature(function, kind, &fields)?)
+                    }
+                    None => types.push(format!("type {name} = record {}", braced(&fields))),
+                }
+            }
+            kind @ ("variant" | "enum" | "flags") => {
+                let name = scanner.name()?;
+                let cases = split_items(&scanner.block()?);
+                types.push(format!("type {name} = {kind} {}", braced(&cases)));
+            }
+            "resource" => return Err("resources are not part of a hyperprocess API".to_string()),
+            "}" => {}
+            other => return Err(format!("unexpected `{other}`")),
+        }
+    }
+
+    let interface = interface.ok_or("no interface")?;
+    types.sort();
+    functions.sort();
+    let mut lines = vec![format!("interface {interface}")];
+    lines.extend(types);
+    lines.extend(functions);
+    Ok(lines.join("\n") + "\n")
+}
+
+/// `copy-file-signature-local` is the signature of copy-file's local handler
+fn signature_of(record: &str) -> Option<(&str, &str)> {
+    let (function, kind) = record.rsplit_once("-signature-")?;
+    SIGNATURE_KINDS.contains(&kind).then_some((function, kind))
+}
+
+fn render_signature(function: &str, kind: &str, fields: &[String]) -> Result<String, String> {
+    let mut arguments = Vec::new();
+    let mut returning = None;
+    for field in fields {
+        let (name, ty) = field
+            .split_once(':')
+            .ok_or_else(|| format!("{function} [{kind}]: field without a type: {field}"))?;
+        match name.trim() {
+            TARGET_FIELD => {}
+            RETURN_FIELD => returning = Some(ty.trim().to_string()),
+            name => arguments.push(format!("{name}: {}", ty.trim())),
+        }
+    }
+    let returning =
+        returning.ok_or_else(|| format!("{function} [{kind}]: no `{RETURN_FIELD}` field"))?;
+    Ok(format!(
+        "fn {function} [{kind}] ({}) -> {returning}",
+        arguments.join(", ")
+    ))
+}
+
+fn braced(items: &[String]) -> String {
+    if items.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{ {} }}", items.join(", "))
+    }
+}
+
+/// `a: list< u8 >` and `a:list<u8>` both become `a: list<u8>`
+fn normalize(item: &str) -> String {
+    let mut out = String::new();
+    let mut previous_word = false;
+    let mut chars = item.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if is_word_char(c) {
+            if previous_word {
+                out.push(' ');
+            }
+            out.push(c);
+            while let Some(&next) = chars.peek().filter(|&&next| is_word_char(next)) {
+                out.push(next);
+                chars.next();
+            }
+            previous_word = true;
+        } else {
+            out.push(c);
+            if c == ':' || c == ',' {
+                out.push(' ');
+            }
+            previous_word = false;
+        }
+    }
+    out.trim_end().to_string()
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '%' || c == '_'
+}
+
+/// the comma-separated items of a block body, split only at the top level
+///  so `result<a, b>` stays whole
+fn split_items(body: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut depth = 0i32;
+    let mut current = String::new();
+    for c in body.chars() {
+        match c {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    items.push(current);
+    items
+        .iter()
+        .map(|item| normalize(item))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn strip_comments(wit: &str) -> String {
+    wit.lines()
+        .map(|line| line.split_once("//").map_or(line, |(code, _)| code))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+struct Scanner<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(source: &'a str) -> Self {
+        Scanner { rest: source }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    /// the next keyword or name, or a lone `}`
+    fn word(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        if let Some(rest) = self.rest.strip_prefix('}') {
+            self.rest = rest;
+            return Some("}".to_string());
+        }
+        let end = self
+            .rest
+            .find(|c: char| !is_word_char(c))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return None;
+        }
+        let (word, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(word.to_string())
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.word() {
+            Some(name) if name != "}" => Ok(name.trim_start_matches('%').to_string()),
+            _ => Err(format!("expected a name before {:?}", self.preview())),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                Ok(())
+            }
+            None => Err(format!("expected `{c}` before {:?}", self.preview())),
+        }
+    }
+
+    /// everything up to `end`, which is consumed
+    fn until(&mut self, end: char) -> Result<String, String> {
+        let (text, rest) = self
+            .rest
+            .split_once(end)
+            .ok_or_else(|| format!("missing `{end}` after {:?}", self.preview()))?;
+        self.rest = rest;
+        Ok(text.to_string())
+    }
+
+    fn skip_statement(&mut self) {
+        self.rest = self.rest.split_once(';').map_or("", |(_, rest)| rest);
+    }
+
+    /// the body of a `{ ... }` block
+    fn block(&mut self) -> Result<String, String> {
+        self.expect('{')?;
+        let body = self.until('}')?;
+        if body.contains('{') {
+            return Err(format!("nested block in {body:?}"));
+        }
+        Ok(body)
+    }
+
+    fn preview(&self) -> String {
+        self.rest.chars().take(40).collect()
+    }
+}
@@ -0,0 +1,116 @@
+//! every example app's API surface must match its snapshot in snapshots/
+//!
+//! the surface is read from the WIT the hyperprocess macro writes to each
+//!  app's api/ directory when it is built; that WIT is committed, so a fresh
+//!  checkout passes without a build. After a deliberate change, rebuild the
+//!  app with kit, record the new surface with
+//!  `UPDATE_API_SNAPSHOTS=1 cargo test -p api-surface` and commit both diffs
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// set to re-record the snapshots instead of checking them
+const UPDATE_ENV: &str = "UPDATE_API_SNAPSHOTS";
+
+/// (snapshot name, package directory from the repo root, WIT interface)
+#[rustfmt::skip]
+const APPS: &[(&str, &str, &str)] = &[
+    ("file-explorer", "example-apps/file-explorer", "file-explorer"),
+    ("jendrix-tune", ".", "jendrix-tune"),
+    ("sign", "example-apps/sign", "sign"),
+    ("id", "example-apps/id", "id"),
+];
+
+fn repo_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+/// the macro writes the interface to the package's api/ directory; kit also
+///  gathers it into target/wit
+fn generated_wit(package: &str, interface: &str) -> Option<PathBuf> {
+    let package = repo_root().join(package);
+    ["api", "target/wit"]
+        .iter()
+        .map(|dir| package.join(dir).join(format!("{interface}.wit")))
+        .find(|path| path.is_file())
+}
+
+/// lines only in the snapshot and lines only in the current surface; both
+///  are sorted sets of lines, so this is the whole difference
+fn diff(recorded: &str, current: &str) -> String {
+    let recorded_lines: Vec<&str> = recorded.lines().collect();
+    let current_lines: Vec<&str> = current.lines().collect();
+    let mut out = String::new();
+    for line in &recorded_lines {
+        if !current_lines.contains(line) {
+            out.push_str(&format!("  - {line}\n"));
+        }
+    }
+    for line in &current_lines {
+        if !recorded_lines.contains(line) {
+            out.push_str(&format!("  + {line}\n"));
+        }
+    }
+    out
+}
+
+#[test]
+fn api_surfaces_match_snapshots() {
+    let update = std::env::var_os(UPDATE_ENV).is_some();
+    let mut failures = Vec::new();
+    let mut unbuilt = Vec::new();
+
+    for &(name, package, interface) in APPS {
+        let Some(wit_path) = generated_wit(package, interface) else {
+            unbuilt.push((name, package, interface));
+            continue;
+        };
+        let wit = fs::read_to_string(&wit_path)
+            .unwrap_or_else(|e| panic!("failed to read {}: {e}", wit_path.display()));
+        let current = match api_surface::render(&wit) {
+            Ok(current) => current,
+            Err(e) => {
+                failures.push(format!("{name}: cannot read {}: {e}", wit_path.display()));
+                continue;
+            }
+        };
+
+        let snapshot_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("snapshots")
+            .join(format!("{name}.txt"));
+        if update {
+            fs::write(&snapshot_path, &current)
+                .unwrap_or_else(|e| panic!("failed to write {}: {e}", snapshot_path.display()));
+            continue;
+        }
+        match fs::read_to_string(&snapshot_path) {
+            Ok(recorded) if recorded == current => {}
+            Ok(recorded) => failures.push(format!(
+                "{name}: the API surface changed; if that was meant, re-record with {UPDATE_ENV}=1\n{}",
+                diff(&recorded, &current)
+            )),
+            Err(_) => failures.push(format!(
+                "{name}: no snapshot at {}; record one with {UPDATE_ENV}=1",
+                snapshot_path.display()
+            )),
+        }
+    }
+
+    if !unbuilt.is_empty() {
+        failures.push(unbuilt_message(&unbuilt));
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+/// one failure for every app without generated WIT, saying how to make it
+fn unbuilt_message(unbuilt: &[(&str, &str, &str)]) -> String {
+    let mut message = String::from(
+        "no generated WIT for these apps; build each with kit, then commit its api/ directory:\n",
+    );
+    for (name, package, interface) in unbuilt {
+        message.push_str(&format!(
+            "  {name}: `kit build {package}` writes {package}/api/{interface}.wit\n"
+        ));
+    }
+    message
+}
@@ -0,0 +1,140 @@
+//! the error every example app hands back to HTTP and local callers
+//!
+//! `code` is stable and meant for programs to branch on; the codes below are
+//!  shared so the same failure reads the same in every app, and apps may add
+//!  their own (e.g. id's `token_expired`); `message` is for people and may
+//!  change; `retryable` says whether sending the same request again later
+//!  may succeed
+
+use std::fmt;
+
+pub const NOT_FOUND: &str = "not_found";
+pub const INVALID_INPUT: &str = "invalid_input";
+pub const NOT_PERMITTED: &str = "not_permitted";
+pub const RATE_LIMITED: &str = "rate_limited";
+pub const TIMEOUT: &str = "timeout";
+pub const INTERNAL: &str = "internal";
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AppError {
+    pub code: String,
+    pub message: String,
+    pub retryable: bool,
+}
+
+impl AppError {
+    /// `retryable` follows from the code: only timeouts and rate limits pass
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        AppError {
+            code: code.to_string(),
+            message: message.into(),
+            retryable: code == TIMEOUT || code == RATE_LIMITED,
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::new(NOT_FOUND, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        AppError::new(INVALID_INPUT, message)
+    }
+
+    pub fn not_permitted(message: impl Into<String>) -> Self {
+        AppError::new(NOT_PERMITTED, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::new(INTERNAL, message)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// the codes above; the only ones a plain string error can carry
+pub const SHARED_CODES: &[&str] = &[
+    NOT_FOUND,
+    INVALID_INPUT,
+    NOT_PERMITTED,
+    RATE_LIMITED,
+    TIMEOUT,
+    INTERNAL,
+];
+
+/// internal helpers report errors as `code` or `code: detail`; a shared code
+///  becomes `code` and the detail, or the code spelled out, the message;
+///  anything else, e.g. `vfs: file gone`, is free text and becomes an
+///  `internal` error; apps with codes of their own convert from a typed
+///  error instead, as sign does from SignError
+impl From<String> for AppError {
+    fn from(e: String) -> Self {
+        match e.split_once(": ") {
+            Some((code, detail)) if SHARED_CODES.contains(&code) => AppError::new(code, detail),
+            None if SHARED_CODES.contains(&e.as_str()) => AppError::new(&e, e.replace('_', " ")),
+            _ => AppError::internal(e),
+        }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(e: &str) -> Self {
+        e.to_string().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_timeouts_and_rate_limits_are_retryable() {
+        for code in SHARED_CODES {
+            let retryable = *code == TIMEOUT || *code == RATE_LIMITED;
+            assert_eq!(AppError::new(code, "x").retryable, retryable, "{code}");
+        }
+        assert!(!AppError::new("token_expired", "x").retryable);
+    }
+
+    #[test]
+    fn shared_codes_are_parsed() {
+        assert_eq!(
+            AppError::from("not_found: no such file".to_string()),
+            AppError::not_found("no such file")
+        );
+        assert_eq!(
+            AppError::from("rate_limited"),
+            AppError::new(RATE_LIMITED, "rate limited")
+        );
+        assert!(AppError::from("timeout: net was slow").retryable);
+    }
+
+    #[test]
+    fn other_prefixes_stay_free_text() {
+        for text in [
+            "vfs: file gone",
+            "token_expired",
+            "Failed to open file: NoReadCap",
+            "internal",
+        ] {
+            let e = AppError::from(text);
+            assert_eq!(e.code, INTERNAL, "{text}");
+            if text != INTERNAL {
+                assert_eq!(e.message, text);
+            }
+        }
+    }
+
+    #[test]
+    fn display_puts_the_code_first() {
+        assert_eq!(
+            AppError::invalid_input("bad name").to_string(),
+            "invalid_input: bad name"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! canonical JSON, so that whoever signs a JSON document and whoever checks
+//!  the signature agree on its bytes; sign's sign_json and id's attestations
+//!  both use it, so either side can check the other's bytes
+//!
+//! object keys are sorted recursively by their UTF-8 bytes, there is no
+//!  insignificant whitespace, strings are escaped as serde_json does, and
+//!  integers and floats are kept distinct (`1` vs `1.0`)
+
+use std::fmt;
+
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CanonicalError {
+    /// the text is not JSON; JSON text cannot carry NaN, Infinity, or
+    ///  non-string keys, so input that tries to (or has numbers out of f64
+    ///  range) ends up here
+    InvalidJson(String),
+    /// a value built in code holds a NaN or infinite number
+    NonFinite,
+}
+
+impl fmt::Display for CanonicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanonicalError::InvalidJson(detail) => write!(f, "invalid JSON: {detail}"),
+            CanonicalError::NonFinite => write!(f, "non-finite numbers cannot be signed"),
+        }
+    }
+}
+
+impl std::error::Error for CanonicalError {}
+
+/// canonical JSON text for the JSON text `json`
+pub fn canonicalize(json: &str) -> Result<String, CanonicalError> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|e| CanonicalError::InvalidJson(e.to_string()))?;
+    let mut out = String::with_capacity(json.len());
+    write_canonical(&value, &mut out)?;
+    Ok(out)
+}
+
+/// canonical JSON text for `value`
+pub fn to_canonical(value: &Value) -> Result<String, CanonicalError> {
+    let mut out = String::new();
+    write_canonical(value, &mut out)?;
+    Ok(out)
+}
+
+fn write_canonical(value: &Value, out: &mut String) -> Result<(), CanonicalError> {
+    match value {
+        // serde_json writes these, and escapes strings, the canonical way
+        Value::Null | Value::Bool(_) | Value::String(_) => out.push_str(&value.to_string()),
+        Value::Number(n) => {
+            if n.as_f64().is_some_and(|f| !f.is_finite()) {
+                return Err(CanonicalError::NonFinite);
+            }
+            out.push_str(&n.to_string());
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push(':');
+                write_canonical(item, out)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn canonical(json: &str) -> String {
+        canonicalize(json).unwrap_or_else(|e| panic!("{json}: {e}"))
+    }
+
+    fn assert_rejected(json: &str) {
+        match canonicalize(json) {
+            Err(CanonicalError::InvalidJson(_)) => {}
+            other => panic!("{json}: expected InvalidJson, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn nested_object_keys_are_sorted_at_every_level() {
+        assert_eq!(
+            canonical(r#"{ "b": {"z": 1, "a": {"y": true, "x": null}}, "a": [] }"#),
+            r#"{"a":[],"b":{"a":{"x":null,"y":true},"z":1}}"#,
+        );
+    }
+
+    #[test]
+    fn keys_sort_by_utf8_bytes() {
+        // "Z" < "a" < "é" by bytes, whatever a locale would say
+        assert_eq!(
+            canonical(r#"{"é": 3, "a": 2, "Z": 1}"#),
+            r#"{"Z":1,"a":2,"é":3}"#,
+        );
+    }
+
+    #[test]
+    fn arrays_keep_their_order() {
+        assert_eq!(
+            canonical(r#"[3, 1, [ {"b": 2, "a": 1} ], "two"]"#),
+            r#"[3,1,[{"a":1,"b":2}],"two"]"#,
+        );
+        assert_eq!(canonical("[ ]"), "[]");
+    }
+
+    #[test]
+    fn non_ascii_strings_are_written_as_utf8() {
+        assert_eq!(canonical(r#""héllo 世""#), "\"héllo 世\"");
+        // \u escapes in the input come out as the characters they name
+        assert_eq!(canonical(r#""h\u00e9llo \u4e16""#), "\"héllo 世\"");
+        assert_eq!(canonical(r#""\ud83d\ude00""#), "\"😀\"");
+    }
+
+    #[test]
+    fn control_characters_and_quotes_are_escaped() {
+        assert_eq!(
+            canonical(r#""tab\there \"q\" \u0001""#),
+            r#""tab\there \"q\" \u0001""#,
+        );
+    }
+
+    #[test]
+    fn integers_and_floats_stay_distinct() {
+        assert_eq!(canonical("1"), "1");
+        assert_eq!(canonical("1.0"), "1.0");
+        assert_ne!(canonical("1"), canonical("1.0"));
+        assert_eq!(canonical("1e2"), "100.0");
+        assert_eq!(
+            canonical(r#"{"n": 18446744073709551615}"#),
+            r#"{"n":18446744073709551615}"#
+        );
+    }
+
+    #[test]
+    fn nan_and_infinity_are_rejected() {
+        for json in ["NaN", "Infinity", "-Infinity", r#"{"n": NaN}"#] {
+            assert_rejected(json);
+        }
+        // out of f64 range would otherwise have to become Infinity
+        assert_rejected("1e400");
+        assert_rejected("[-1e400]");
+    }
+
+    #[test]
+    fn independently_built_equal_values_give_identical_bytes() {
+        let built_one_way = json!({
+            "name": "ünïcode",
+            "tags": ["a", "b"],
+            "meta": {"version": 2, "ratio": 0.5, "owner": null},
+        });
+        let mut built_another = serde_json::Map::new();
+        built_another.insert(
+            "meta".to_string(),
+            json!({"owner": null, "ratio": 0.5, "version": 2}),
+        );
+        built_another.insert("tags".to_string(), json!(["a", "b"]));
+        built_another.insert("name".to_string(), json!("\u{fc}n\u{ef}code"));
+
+        let pretty = serde_json::to_string_pretty(&built_one_way).unwrap();
+        let compact = serde_json::to_string(&Value::Object(built_another)).unwrap();
+        assert_ne!(pretty, compact);
+        assert_eq!(
+            canonical(&pretty).into_bytes(),
+            canonical(&compact).into_bytes()
+        );
+    }
+
+    #[test]
+    fn canonical_output_is_a_fixed_point() {
+        let once = canonical(r#"{"b": [1.5, {"d": "x", "c": false}], "a": "é"}"#);
+        assert_eq!(canonical(&once), once);
+    }
+}
@@ -0,0 +1,172 @@
+//! which jendrix-tune instances a tester exercises, so a fork published
+//!  under another package name, or several instances at once, can be tested
+//!  without editing the testers
+//!
+//! the config is a JSON object, every field optional:
+//!
+//! ```json
+//! {
+//!     "package": "jendrix-tune",
+//!     "publisher": "template.os",
+//!     "processes": ["jendrix-tune"],
+//!     "max_call_ms": 50
+//! }
+//! ```
+//!
+//! it is read from the blob attached to the tester's RunRequest when there is
+//!  one; the RunRequest itself carries no free-form payload, so without a
+//!  blob it is fixed at build time like the explorer fuzz seed:
+//!  `JENDRIX_TEST_CONFIG='{"publisher":"fork.os"}' kit t ...`
+//!
+//! jendrix-tune is not public: any other package must also be added to the
+//!  capabilities in each tester's pkg/manifest.json
+
+use std::time::Duration;
+
+use hyperware_process_lib::{Address, ProcessId};
+use serde_json::{Map, Value};
+
+const DEFAULT_PACKAGE: &str = "jendrix-tune";
+
+const DEFAULT_PUBLISHER: &str = "template.os";
+
+const FIELDS: &[&str] = &["package", "publisher", "processes", "max_call_ms"];
+
+/// one jendrix-tune instance under test
+pub struct Target {
+    pub address: Address,
+    /// every call to the instance must answer within this, when set
+    pub max_call: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct TestConfig {
+    pub package: String,
+    pub publisher: String,
+    pub processes: Vec<String>,
+    pub max_call_ms: Option<u64>,
+}
+
+impl Default for TestConfig {
+    fn default() -> Self {
+        TestConfig {
+            package: DEFAULT_PACKAGE.to_string(),
+            publisher: DEFAULT_PUBLISHER.to_string(),
+            processes: vec![DEFAULT_PACKAGE.to_string()],
+            max_call_ms: None,
+        }
+    }
+}
+
+impl TestConfig {
+    /// the config in `blob`, else the one given at build time, else the
+    ///  defaults; errors name the field that is wrong
+    pub fn load(blob: Option<&[u8]>) -> Result<Self, String> {
+        match blob.filter(|bytes| !bytes.is_empty()) {
+            Some(bytes) => Self::parse(bytes),
+            None => match option_env!("JENDRIX_TEST_CONFIG") {
+                Some(json) if !json.trim().is_empty() => Self::parse(json.as_bytes()),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let object = match serde_json::from_slice(bytes) {
+            Ok(Value::Object(object)) => object,
+            Ok(other) => return Err(format!("config must be a JSON object, got {other}")),
+            Err(e) => return Err(format!("config is not JSON: {e}")),
+        };
+        if let Some(unknown) = object.keys().find(|key| !FIELDS.contains(&key.as_str())) {
+            return Err(format!(
+                "config field `{unknown}` is not one of {}",
+                FIELDS.join(", ")
+            ));
+        }
+
+        let defaults = Self::default();
+        let package = name_field(&object, "package")?.unwrap_or(defaults.package);
+        let publisher = publisher_field(&object)?.unwrap_or(defaults.publisher);
+        let processes = match object.get("processes") {
+            None => vec![package.clone()],
+            Some(Value::Array(items)) if !items.is_empty() => items
+                .iter()
+                .map(|item| match item {
+                    Value::String(name) if is_name(name) => Ok(name.clone()),
+                    other => Err(format!(
+                        "config field `processes`: {other} is not a process name"
+                    )),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(other) => {
+                return Err(format!(
+                    "config field `processes` must be a non-empty list of names, got {other}"
+                ))
+            }
+        };
+        let max_call_ms = match object.get("max_call_ms") {
+            None | Some(Value::Null) => None,
+            Some(value) => match value.as_u64() {
+                Some(ms) if ms > 0 => Some(ms),
+                _ => {
+                    return Err(format!(
+                        "config field `max_call_ms` must be a positive whole number, got {value}"
+                    ))
+                }
+            },
+        };
+
+        Ok(TestConfig {
+            package,
+            publisher,
+            processes,
+            max_call_ms,
+        })
+    }
+
+    pub fn targets(&self, node: &str) -> Vec<Target> {
+        self.processes
+            .iter()
+            .map(|process| Target {
+                address: Address {
+                    node: node.to_string(),
+                    process: ProcessId::new(Some(process), &self.package, &self.publisher),
+                },
+                max_call: self.max_call_ms.map(Duration::from_millis),
+            })
+            .collect()
+    }
+}
+
+fn name_field(object: &Map<String, Value>, field: &str) -> Result<Option<String>, String> {
+    match object.get(field) {
+        None => Ok(None),
+        Some(Value::String(name)) if is_name(name) => Ok(Some(name.clone())),
+        Some(other) => Err(format!(
+            "config field `{field}` must be a lowercase name like `jendrix-tune`, got {other}"
+        )),
+    }
+}
+
+/// a publisher is a node name: dot-separated names, e.g. `template.os`
+fn publisher_field(object: &Map<String, Value>) -> Result<Option<String>, String> {
+    match object.get("publisher") {
+        None => Ok(None),
+        Some(Value::String(publisher))
+            if publisher.contains('.') && publisher.split('.').all(is_name) =>
+        {
+            Ok(Some(publisher.clone()))
+        }
+        Some(other) => Err(format!(
+            "config field `publisher` must be a node name like `template.os`, got {other}"
+        )),
+    }
+}
+
+/// the characters a process or package name may use
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
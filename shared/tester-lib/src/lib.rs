@@ -0,0 +1,346 @@
+//! helpers every tester package shares: typed calls with retries, the
+//!  TestError they fail with, and the step!, assert_eq_ctx! and fail! macros
+//!
+//! fail! answers the tester with the `Response`, `TesterResponse` and
+//!  `FailResponse` the calling package imports from its own WIT bindings
+
+pub mod config;
+
+use std::cell::RefCell;
+use std::fmt;
+
+use hyperware_process_lib::{timer, Address, Request, SendErrorKind};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// wait before the first retry of a timed-out request; doubled for each one after
+const RETRY_BACKOFF_MS: u64 = 250;
+
+#[macro_export]
+macro_rules! fail {
+    ($test:expr) => {
+        Response::new()
+            .body(TesterResponse::Run(Err(FailResponse {
+                test: $test.into(),
+                file: file!().into(),
+                line: line!(),
+                column: column!(),
+            })))
+            .send()
+            .unwrap();
+        panic!("")
+    };
+    ($test:expr, $file:expr, $line:expr, $column:expr) => {
+        Response::new()
+            .body(TesterResponse::Run(Err(FailResponse {
+                test: $test.into(),
+                file: $file.into(),
+                line: $line,
+                column: $column,
+            })))
+            .send()
+            .unwrap();
+        panic!("")
+    };
+}
+
+/// check `actual` against `expected`, both formatted with Debug on mismatch;
+///  evaluates to `Result<(), TestError>`
+///
+/// ```ignore
+/// assert_eq_ctx!(Key::FSharp, status.key, "key after set_key")?;
+/// ```
+#[macro_export]
+macro_rules! assert_eq_ctx {
+    ($expected:expr, $actual:expr, $step:expr) => {
+        match (&$expected, &$actual) {
+            (expected, actual) if *expected == *actual => Ok(()),
+            (expected, actual) => Err($crate::TestError::Mismatch {
+                step: ($step).to_string(),
+                expected: format!("{expected:?}"),
+                actual: format!("{actual:?}"),
+            }),
+        }
+    };
+}
+
+/// run `body`, a `Result` whose error is Display, as the named step: a
+///  failure comes back as TestError::Step prefixed with the name, a success is
+///  listed in step_summary; steps nest, and a test that gives up with fail!
+///  inside a step can name it with current_step
+///
+/// ```ignore
+/// let status: Status = step!("read status", expect_ok(&address, body))?;
+/// ```
+#[macro_export]
+macro_rules! step {
+    ($name:expr, $body:expr) => {{
+        let name: String = ($name).into();
+        $crate::begin_step(&name);
+        let result = $body;
+        $crate::end_step(result.is_ok());
+        result.map_err(|e| $crate::TestError::Step {
+            step: name,
+            error: e.to_string(),
+        })
+    }};
+}
+
+/// the steps entered and passed so far in this run
+#[derive(Default)]
+struct StepLog {
+    /// names of the steps we are inside, outermost first
+    open: Vec<String>,
+    /// full names of the steps that passed, in the order they finished
+    passed: Vec<String>,
+}
+
+thread_local! {
+    static STEPS: RefCell<StepLog> = RefCell::new(StepLog::default());
+}
+
+/// forget the steps of a previous run
+pub fn reset_steps() {
+    STEPS.with(|steps| *steps.borrow_mut() = StepLog::default());
+}
+
+pub fn begin_step(name: &str) {
+    STEPS.with(|steps| steps.borrow_mut().open.push(name.to_string()));
+}
+
+pub fn end_step(passed: bool) {
+    STEPS.with(|steps| {
+        let mut steps = steps.borrow_mut();
+        let full_name = steps.open.join(" > ");
+        steps.open.pop();
+        if passed {
+            steps.passed.push(full_name);
+        }
+    });
+}
+
+/// the step we are inside, e.g. `instance > set key`, if any
+pub fn current_step() -> Option<String> {
+    STEPS.with(|steps| {
+        let steps = steps.borrow();
+        (!steps.open.is_empty()).then(|| steps.open.join(" > "))
+    })
+}
+
+/// `message`, prefixed with the step it happened in
+pub fn in_current_step(message: String) -> String {
+    match current_step() {
+        Some(step) => format!("{step}: {message}"),
+        None => message,
+    }
+}
+
+/// one line per passed step, for the terminal after a run or a failure
+pub fn step_summary() -> String {
+    STEPS.with(|steps| {
+        let steps = steps.borrow();
+        let mut summary = format!("{} steps passed", steps.passed.len());
+        for step in &steps.passed {
+            summary.push_str("\n  ok: ");
+            summary.push_str(step);
+        }
+        summary
+    })
+}
+
+/// why a request made through call_json or call_ok did not give the test
+///  what it wanted; every variant names the request, e.g. `GetStatus`
+#[derive(Debug)]
+pub enum TestError {
+    Encode {
+        request: String,
+        error: String,
+    },
+    Send {
+        request: String,
+        error: String,
+    },
+    TimedOut {
+        request: String,
+        attempts: u32,
+    },
+    UnexpectedRequest {
+        request: String,
+    },
+    Decode {
+        request: String,
+        error: String,
+    },
+    /// the app answered with its own `Err`
+    Returned {
+        request: String,
+        error: String,
+    },
+    /// a response was not the one expected
+    Assertion {
+        error: String,
+    },
+    /// assert_eq_ctx! found `actual` where `expected` should be
+    Mismatch {
+        step: String,
+        expected: String,
+        actual: String,
+    },
+    /// something inside step! failed
+    Step {
+        step: String,
+        error: String,
+    },
+}
+
+impl fmt::Display for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestError::Encode { request, error } => {
+                write!(f, "{request}: failed to encode: {error}")
+            }
+            TestError::Send { request, error } => write!(f, "{request}: failed to send: {error}"),
+            TestError::TimedOut { request, attempts } => {
+                write!(f, "{request}: timed out after {attempts} attempts")
+            }
+            TestError::UnexpectedRequest { request } => {
+                write!(f, "{request}: got a request instead of a response")
+            }
+            TestError::Decode { request, error } => {
+                write!(f, "{request}: failed to decode response: {error}")
+            }
+            TestError::Returned { request, error } => {
+                write!(f, "{request} returned error: {error}")
+            }
+            TestError::Assertion { error } => write!(f, "{error}"),
+            TestError::Mismatch {
+                step,
+                expected,
+                actual,
+            } => write!(f, "{step}: expected {expected}, got {actual}"),
+            TestError::Step { step, error } => write!(f, "{step}: {error}"),
+        }
+    }
+}
+
+/// the shared AppError every example app answers with
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CodedError {
+    pub code: String,
+    pub message: String,
+    #[serde(default)]
+    pub retryable: bool,
+}
+
+impl fmt::Display for CodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+/// the hyperapp method a JSON body calls: the key of `{"Method": args}`, or
+///  the whole body for a bare `"Method"`
+pub fn request_name(body: &Value) -> String {
+    match body {
+        Value::Object(map) if map.len() == 1 => map.keys().next().cloned().unwrap_or_default(),
+        Value::String(name) => name.clone(),
+        _ => "request".to_string(),
+    }
+}
+
+/// send `body` as JSON and decode the JSON response, retrying up to `retries`
+///  more times, with backoff, when the target does not answer in time
+pub fn call_json<Req: Serialize, Resp: DeserializeOwned>(
+    address: &Address,
+    body: &Req,
+    timeout_secs: u64,
+    retries: u32,
+) -> Result<Resp, TestError> {
+    let body = serde_json::to_value(body).map_err(|e| TestError::Encode {
+        request: "request".to_string(),
+        error: e.to_string(),
+    })?;
+    let request = request_name(&body);
+    let payload = serde_json::to_vec(&body).map_err(|e| TestError::Encode {
+        request: request.clone(),
+        error: e.to_string(),
+    })?;
+
+    let mut backoff_ms = RETRY_BACKOFF_MS;
+    for attempt in 0..=retries {
+        let sent = Request::to(address.clone())
+            .body(payload.clone())
+            .send_and_await_response(timeout_secs)
+            .map_err(|e| TestError::Send {
+                request: request.clone(),
+                error: format!("{e:?}"),
+            })?;
+        match sent {
+            Ok(response) => {
+                if response.is_request() {
+                    return Err(TestError::UnexpectedRequest { request });
+                }
+                return serde_json::from_slice(response.body()).map_err(|e| TestError::Decode {
+                    request,
+                    error: e.to_string(),
+                });
+            }
+            Err(e) if matches!(e.kind, SendErrorKind::Timeout) => {
+                if attempt < retries {
+                    let _ = timer::set_and_await_timer(backoff_ms);
+                    backoff_ms *= 2;
+                }
+            }
+            Err(e) => {
+                return Err(TestError::Send {
+                    request,
+                    error: format!("{:?}", e.kind),
+                })
+            }
+        }
+    }
+    Err(TestError::TimedOut {
+        request,
+        attempts: retries + 1,
+    })
+}
+
+/// call_json for a hyperapp method returning `Result<T, E>`, with an `Err`
+///  from the app reported as TestError::Returned
+pub fn call_ok<Req, T, E>(
+    address: &Address,
+    body: &Req,
+    timeout_secs: u64,
+    retries: u32,
+) -> Result<T, TestError>
+where
+    Req: Serialize,
+    T: DeserializeOwned,
+    E: DeserializeOwned + fmt::Display,
+{
+    let request = serde_json::to_value(body)
+        .map(|body| request_name(&body))
+        .unwrap_or_else(|_| "request".to_string());
+    call_json::<Req, Result<T, E>>(address, body, timeout_secs, retries)?.map_err(|e| {
+        TestError::Returned {
+            request,
+            error: e.to_string(),
+        }
+    })
+}
+
+/// require that a response is the structured error `code`
+pub fn expect_err_code<T: fmt::Debug>(
+    resp: Result<T, CodedError>,
+    code: &str,
+) -> Result<(), TestError> {
+    match resp {
+        Err(e) if e.code == code => Ok(()),
+        Err(e) => Err(TestError::Assertion {
+            error: format!("expected error {code}, got {e}"),
+        }),
+        Ok(value) => Err(TestError::Assertion {
+            error: format!("expected error {code}, got Ok({value:?})"),
+        }),
+    }
+}
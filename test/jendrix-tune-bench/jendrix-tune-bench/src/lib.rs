@@ -0,0 +1,169 @@
+use crate::hyperware::process::tester::{
+    FailResponse, Request as TesterRequest, Response as TesterResponse, RunRequest,
+};
+use hyperware_process_lib::{
+    await_message, call_init, print_to_terminal, Address, ProcessId, Response,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+use tester_lib::{call_ok, fail, CodedError};
+
+wit_bindgen::generate!({
+    path: "../target/wit",
+    world: "jendrix-tune-bench-template-dot-os-v0",
+    generate_unused_types: true,
+    additional_derives: [PartialEq, serde::Deserialize, serde::Serialize, process_macros::SerdeJsonInto],
+});
+
+const TIMEOUT_SECS: u64 = 15;
+
+/// a benchmark must not hide slow calls behind retries
+const RETRIES: u32 = 0;
+
+/// sequential calls per measured request
+const CALLS: usize = 500;
+
+/// p95 budget per call when none is given
+const DEFAULT_P95_MS: u64 = 50;
+
+/// the p95 budget per call; the tester's RunRequest carries no free-form
+///  payload, so it is fixed at build time like the explorer fuzz seed:
+///  `JENDRIX_BENCH_P95_MS=20 kit t ...`
+fn p95_budget() -> Duration {
+    let ms = option_env!("JENDRIX_BENCH_P95_MS")
+        .and_then(|ms| ms.parse().ok())
+        .unwrap_or(DEFAULT_P95_MS);
+    Duration::from_millis(ms)
+}
+
+/// per-call latencies of one request, sorted
+struct Latencies(Vec<Duration>);
+
+impl Latencies {
+    fn measure(mut call: impl FnMut(usize)) -> Self {
+        let mut samples: Vec<Duration> = (0..CALLS)
+            .map(|i| {
+                let start = Instant::now();
+                call(i);
+                start.elapsed()
+            })
+            .collect();
+        samples.sort();
+        Latencies(samples)
+    }
+
+    /// nearest-rank percentile
+    fn percentile(&self, p: usize) -> Duration {
+        let rank = (p * self.0.len()).div_ceil(100).max(1);
+        self.0[rank - 1]
+    }
+
+    fn mean(&self) -> Duration {
+        self.0.iter().sum::<Duration>() / self.0.len() as u32
+    }
+
+    fn summary(&self, name: &str) -> String {
+        format!(
+            "{name}: {} calls, min {:?} p50 {:?} p95 {:?} p99 {:?} max {:?} mean {:?}",
+            self.0.len(),
+            self.0[0],
+            self.percentile(50),
+            self.percentile(95),
+            self.percentile(99),
+            self.0[self.0.len() - 1],
+            self.mean(),
+        )
+    }
+}
+
+call_init!(init);
+fn init(our: Address) {
+    print_to_terminal(0, "begin");
+
+    loop {
+        handle_message(&our);
+    }
+}
+
+fn handle_message(our: &Address) {
+    let message = await_message()
+        .unwrap_or_else(|e| fail_with(format!("failed to receive tester message: {e:?}")));
+
+    if !message.is_request() {
+        fail_with("expected tester request message");
+    }
+
+    let source = message.source();
+    if our.node != source.node {
+        fail_with(format!("rejecting foreign message from {:?}", source));
+    }
+
+    let TesterRequest::Run(RunRequest {
+        input_node_names: node_names,
+        ..
+    }) = message
+        .body()
+        .try_into()
+        .unwrap_or_else(|e| fail_with(format!("failed to decode tester run request: {e:?}")));
+
+    print_to_terminal(0, "jendrix_tune_bench: start");
+
+    if our.node != node_names[0] {
+        Response::new()
+            .body(TesterResponse::Run(Ok(())))
+            .send()
+            .unwrap_or_else(|e| fail_with(format!("failed to send tester ack: {e:?}")));
+        return;
+    }
+
+    let jendrix_tune = Address {
+        node: our.node.clone(),
+        process: ProcessId::new(Some("jendrix-tune"), "jendrix-tune", "template.os"),
+    };
+
+    // alternate between two values so every call is a real change
+    let set_mix = Latencies::measure(|i| {
+        let mix = if i % 2 == 0 { 0.25 } else { 0.75 };
+        expect_ok::<()>(&jendrix_tune, json!({ "SetMix": mix }));
+    });
+    let get_status = Latencies::measure(|_| {
+        expect_ok::<Value>(&jendrix_tune, json!({ "GetStatus": null }));
+    });
+
+    let budget = p95_budget();
+    let mut over_budget = Vec::new();
+    for (name, latencies) in [("set_mix", &set_mix), ("get_status", &get_status)] {
+        print_to_terminal(
+            0,
+            &format!("jendrix_tune_bench: {}", latencies.summary(name)),
+        );
+        if latencies.percentile(95) > budget {
+            over_budget.push(format!(
+                "{name} p95 {:?} is over the {budget:?} budget",
+                latencies.percentile(95)
+            ));
+        }
+    }
+    if !over_budget.is_empty() {
+        fail_with(over_budget.join("; "));
+    }
+
+    Response::new()
+        .body(TesterResponse::Run(Ok(())))
+        .send()
+        .unwrap_or_else(|e| fail_with(format!("failed to send tester success: {e:?}")));
+}
+
+fn expect_ok<T: DeserializeOwned>(address: &Address, body: Value) -> T {
+    call_ok::<_, T, CodedError>(address, &body, TIMEOUT_SECS, RETRIES)
+        .unwrap_or_else(|e| fail_with(e.to_string()))
+}
+
+fn fail_with(message: impl Into<String>) -> ! {
+    let message = message.into();
+    let log = format!("jendrix_tune_bench: error: {message}");
+    print_to_terminal(0, log.as_str());
+    fail!(message);
+}
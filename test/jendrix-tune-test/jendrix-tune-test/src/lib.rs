@@ -1,12 +1,14 @@
+use crate::hyperware::process::jendrix_tune::{Key, Scale, Status};
 use crate::hyperware::process::tester::{
     FailResponse, Request as TesterRequest, Response as TesterResponse, RunRequest,
 };
-use hyperware_process_lib::{
-    await_message, call_init, print_to_terminal, Address, ProcessId, Request, Response,
-};
-use serde_json::json;
+use hyperware_process_lib::{await_message, call_init, print_to_terminal, Address, Response};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::time::Instant;
 
-mod tester_lib;
+use tester_lib::config::{Target, TestConfig};
+use tester_lib::{assert_eq_ctx, call_ok, fail, step, CodedError, TestError};
 
 wit_bindgen::generate!({
     path: "../target/wit",
@@ -15,6 +17,11 @@ wit_bindgen::generate!({
     additional_derives: [PartialEq, serde::Deserialize, serde::Serialize, process_macros::SerdeJsonInto],
 });
 
+const TIMEOUT_SECS: u64 = 15;
+
+/// extra attempts for a request that times out
+const RETRIES: u32 = 2;
+
 call_init!(init);
 fn init(our: Address) {
     print_to_terminal(0, "begin");
@@ -37,6 +44,10 @@ fn handle_message(our: &Address) {
         fail_with(format!("rejecting foreign message from {:?}", source));
     }
 
+    // read before the body, while the Run request's blob is still current
+    let config = TestConfig::load(message.blob().as_ref().map(|blob| blob.bytes.as_slice()))
+        .unwrap_or_else(|e| fail_with(e));
+
     let TesterRequest::Run(RunRequest {
         input_node_names: node_names,
         ..
@@ -55,34 +66,19 @@ fn handle_message(our: &Address) {
         return;
     }
 
-    let our_jendrix_tune_address = Address {
-        node: our.node.clone(),
-        process: ProcessId::new(
-            Some("jendrix-tune"),
-            "jendrix-tune",
-            "template.os",
-        ),
-    };
-
-    increment_counter(&our_jendrix_tune_address, 1);
-
-    let local_messages = get_messages(&our_jendrix_tune_address);
-    if !local_messages
-        .iter()
-        .any(|m| m == "Counter incremented by 1")
-    {
-        fail_with(format!(
-            "local jendrix-tune did not store expected message: {local_messages:?}"
-        ));
-    }
-
-    let status = get_status(&our_jendrix_tune_address);
-    let counter = status.counter;
-    if counter != 1 {
-        fail_with(format!(
-            "local jendrix-tune did not increment correctly: {counter:?}"
-        ));
+    tester_lib::reset_steps();
+    for target in config.targets(&our.node) {
+        print_to_terminal(
+            0,
+            &format!("jendrix_tune_test: testing {}", target.address.process),
+        );
+        step!(target.address.process.to_string(), test_instance(&target))
+            .unwrap_or_else(|e| fail_with(e.to_string()));
     }
+    print_to_terminal(
+        0,
+        &format!("jendrix_tune_test: {}", tester_lib::step_summary()),
+    );
 
     Response::new()
         .body(TesterResponse::Run(Ok(())))
@@ -90,84 +86,81 @@ fn handle_message(our: &Address) {
         .unwrap_or_else(|e| fail_with(format!("failed to send tester success: {e:?}")));
 }
 
-fn increment_counter(address: &Address, amount: u32) {
-    let body = json!({"IncrementCounter": amount});
-    let body = serde_json::to_vec(&body).unwrap();
-    let request = Request::to(address).body(body);
-    let response = request
-        .send_and_await_response(15)
-        .unwrap_or_else(|e| fail_with(format!("failed to send add_message request: {e:?}")))
-        .unwrap_or_else(|_| fail_with("add_message returned no response"));
-
-    if response.is_request() {
-        fail!("increment_counter returned a request");
-    }
-
-    let result: Result<u32, String> = serde_json::from_slice(response.body())
-        .unwrap_or_else(|e| fail_with(format!("failed to decode increment_counter response: {e}")));
-
-    match result {
-        Ok(counter) => {
-            if counter == 0 {
-                fail_with("increment_counter returned zero after increment");
-            }
-        }
-        Err(err) => fail_with(format!("increment_counter returned error: {err}")),
-    }
+/// the defaults, a set that takes effect, and clamping, against one instance
+fn test_instance(target: &Target) -> Result<(), TestError> {
+    let status = step!("initial status", get_status(target))?;
+    assert_eq_ctx!(
+        (Key::C, Scale::Major),
+        (status.key, status.scale),
+        "initial key and scale"
+    )?;
+    expect_close("initial retune_speed", status.retune_speed, 0.5)?;
+    expect_close("initial mix", status.mix, 1.0)?;
+
+    step!("set key", set_key(target, Key::FSharp))?;
+    step!("set retune_speed", set_retune_speed(target, 0.2))?;
+
+    let status = step!("status after set", get_status(target))?;
+    assert_eq_ctx!(Key::FSharp, status.key, "key after set")?;
+    expect_close("retune_speed after set", status.retune_speed, 0.2)?;
+    expect_close("mix after set", status.mix, 1.0)?;
+
+    step!("retune_speed above range", check_clamped(target, 1.7, 1.0))?;
+    step!("retune_speed below range", check_clamped(target, -0.5, 0.0))?;
+    Ok(())
 }
 
-fn get_messages(address: &Address) -> Vec<String> {
-    let payload = serde_json::to_vec(&json!({ "GetMessages": serde_json::Value::Null }))
-        .unwrap_or_else(|e| fail_with(format!("failed to encode get_messages payload: {e}")));
-
-    let response = Request::new()
-        .target(address.clone())
-        .body(payload)
-        .send_and_await_response(15)
-        .unwrap_or_else(|e| fail_with(format!("failed to send get_messages request: {e:?}")))
-        .unwrap_or_else(|_| fail_with("get_messages returned no response"));
-
-    if response.is_request() {
-        fail_with("get_messages returned a request");
-    }
-
-    let result: Result<Vec<String>, String> = serde_json::from_slice(response.body())
-        .unwrap_or_else(|e| fail_with(format!("failed to decode get_messages response: {e}")));
+/// set an out-of-range retune_speed and check what it was clamped to
+fn check_clamped(target: &Target, speed: f32, expected: f32) -> Result<(), TestError> {
+    set_retune_speed(target, speed)?;
+    let status = get_status(target)?;
+    expect_close("clamped retune_speed", status.retune_speed, expected)
+}
 
-    match result {
-        Ok(messages) => messages,
-        Err(err) => fail_with(format!("get_messages returned error: {err}")),
-    }
+fn get_status(target: &Target) -> Result<Status, TestError> {
+    expect_ok(target, json!({ "GetStatus": null }))
 }
 
-fn get_status(address: &Address) -> crate::hyperware::process::app::Status {
-    let payload = serde_json::to_vec(&json!({ "GetStatus": serde_json::Value::Null }))
-        .unwrap_or_else(|e| fail_with(format!("failed to encode get_status payload: {e}")));
+fn set_key(target: &Target, key: Key) -> Result<(), TestError> {
+    expect_ok(target, json!({ "SetKey": key }))
+}
 
-    let response = Request::new()
-        .target(address.clone())
-        .body(payload)
-        .send_and_await_response(15)
-        .unwrap_or_else(|e| fail_with(format!("failed to send get_status request: {e:?}")))
-        .unwrap_or_else(|_| fail_with("get_status returned no response"));
+fn set_retune_speed(target: &Target, speed: f32) -> Result<(), TestError> {
+    expect_ok(target, json!({ "SetRetuneSpeed": speed }))
+}
 
-    if response.is_request() {
-        fail_with("get_status returned a request");
+/// a call that must succeed, within the target's latency budget when it has one
+fn expect_ok<T: DeserializeOwned>(target: &Target, body: Value) -> Result<T, TestError> {
+    let start = Instant::now();
+    let value = call_ok::<_, T, CodedError>(&target.address, &body, TIMEOUT_SECS, RETRIES)?;
+    let elapsed = start.elapsed();
+    match target.max_call {
+        Some(max_call) if elapsed > max_call => Err(TestError::Assertion {
+            error: format!("{body} took {elapsed:?}, over the {max_call:?} budget"),
+        }),
+        _ => Ok(value),
     }
+}
 
-    let result: Result<crate::hyperware::process::app::Status, String> =
-        serde_json::from_slice(response.body())
-            .unwrap_or_else(|e| fail_with(format!("failed to decode get_status response: {e}")));
-
-    match result {
-        Ok(status) => status,
-        Err(err) => fail_with(format!("get_status returned error: {err}")),
+/// parameters are f32 and go through JSON, so compare with a little slack
+fn expect_close(step: &str, actual: f32, expected: f32) -> Result<(), TestError> {
+    if (actual - expected).abs() > 1e-4 {
+        return Err(TestError::Mismatch {
+            step: step.to_string(),
+            expected: format!("{expected:?}"),
+            actual: format!("{actual:?}"),
+        });
     }
+    Ok(())
 }
 
+/// give up, naming the step we were in and listing the ones that passed
 fn fail_with(message: impl Into<String>) -> ! {
-    let message = message.into();
-    let log = format!("jendrix_tune_test: error: {message}");
+    let message = tester_lib::in_current_step(message.into());
+    let log = format!(
+        "jendrix_tune_test: error: {message}\n{}",
+        tester_lib::step_summary()
+    );
     print_to_terminal(0, log.as_str());
     fail!(message);
 }
@@ -0,0 +1,227 @@
+use crate::hyperware::process::tester::{
+    FailResponse, Request as TesterRequest, Response as TesterResponse, RunRequest,
+};
+use hyperware_process_lib::{
+    await_message, call_init, print_to_terminal, Address, ProcessId, Request, Response,
+    SendErrorKind,
+};
+use serde_json::Value;
+
+use tester_lib::{call_json, fail, CodedError};
+
+wit_bindgen::generate!({
+    path: "../target/wit",
+    world: "negative-test-template-dot-os-v0",
+    generate_unused_types: true,
+    additional_derives: [PartialEq, serde::Deserialize, serde::Serialize, process_macros::SerdeJsonInto],
+});
+
+const TIMEOUT_SECS: u64 = 15;
+
+/// how long to wait for an answer that should never come
+const DROPPED_TIMEOUT_SECS: u64 = 3;
+
+/// extra attempts for a survival probe that times out
+const RETRIES: u32 = 2;
+
+#[derive(Clone, Copy, Debug)]
+enum App {
+    JendrixTune,
+    Explorer,
+    Sign,
+    Id,
+}
+
+impl App {
+    fn address(self, node: &str) -> Address {
+        let process = match self {
+            App::JendrixTune => ProcessId::new(Some("jendrix-tune"), "jendrix-tune", "template.os"),
+            App::Explorer => ProcessId::new(Some("explorer"), "file-explorer", "sys"),
+            App::Sign => ProcessId::new(Some("sign"), "sign", "sys"),
+            App::Id => ProcessId::new(Some("id"), "id", "sys"),
+        };
+        Address {
+            node: node.to_string(),
+            process,
+        }
+    }
+
+    /// a request that must succeed; sent after every bad one to show the
+    ///  process is still alive
+    fn probe(self) -> &'static str {
+        match self {
+            App::JendrixTune => r#"{"GetStatus":null}"#,
+            App::Explorer => r#"{"ListDirectory":"/"}"#,
+            App::Sign => r#"{"GetIdentity":null}"#,
+            App::Id => r#"{"Sign":[1]}"#,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Expect {
+    /// a handler ran and answered `{"Err": ...}`
+    Err,
+    /// the body never reaches a handler: hyperapp logs requests it cannot
+    ///  decode and does not answer them, so a timeout is expected here, and an
+    ///  `Err` answer is accepted too; what matters is the probe afterwards
+    Dropped,
+}
+
+/// the bad-input corpus: app, case name, raw request body, expected outcome;
+///  one line per case
+#[rustfmt::skip]
+const CASES: &[(App, &str, &str, Expect)] = &[
+    (App::JendrixTune, "truncated json", r#"{"SetKey":"#, Expect::Dropped),
+    (App::JendrixTune, "not json at all", "\u{0}\u{1}garbage", Expect::Dropped),
+    (App::JendrixTune, "unknown key", r#"{"SetKey":"HSharp"}"#, Expect::Dropped),
+    (App::JendrixTune, "unknown scale", r#"{"SetScale":"Lydian"}"#, Expect::Dropped),
+    (App::JendrixTune, "retune speed as a string", r#"{"SetRetuneSpeed":"fast"}"#, Expect::Dropped),
+    (App::JendrixTune, "unknown method", r#"{"IncrementCounter":1}"#, Expect::Dropped),
+    (App::JendrixTune, "missing pitch log", r#"{"ExportPitchLogCsv":"no-such-log"}"#, Expect::Err),
+    (App::JendrixTune, "pitch log id escaping the drive", r#"{"GetIntonationSummary":"../../etc"}"#, Expect::Err),
+    (App::Explorer, "truncated json", r#"{"ReadFile":"/file-explorer:sys/ho"#, Expect::Dropped),
+    (App::Explorer, "unknown auth scheme", r#"{"ShareFile":["/file-explorer:sys/home/x","Secret",null]}"#, Expect::Dropped),
+    (App::Explorer, "path with an embedded NUL", r#"{"ReadFile":"/file-explorer:sys/home/a\u0000b"}"#, Expect::Err),
+    (App::Explorer, "empty path", r#"{"ReadFile":""}"#, Expect::Err),
+    (App::Explorer, "missing directory", r#"{"ListDirectory":"/file-explorer:sys/home/no-such-dir"}"#, Expect::Err),
+    (App::Explorer, "delete a missing file", r#"{"DeleteFile":"/file-explorer:sys/home/no-such-file"}"#, Expect::Err),
+    (App::Sign, "truncated json", r#"{"Sign":[1,2"#, Expect::Dropped),
+    (App::Sign, "bytes as a string", r#"{"Sign":"hello"}"#, Expect::Dropped),
+    (App::Sign, "empty message from a non-signer", r#"{"Sign":[]}"#, Expect::Err),
+    (App::Sign, "invalid base64", r#"{"SignB64":"not base64!!"}"#, Expect::Err),
+    (App::Sign, "invalid hex", r#"{"SignHex":"zz"}"#, Expect::Err),
+    (App::Sign, "malformed node name", r#"{"VerifyFrom":["Not A Node",[1],[2]]}"#, Expect::Err),
+    (App::Id, "truncated json", r#"{"VerifyRemote":["a.os","#, Expect::Dropped),
+    (App::Id, "empty message", r#"{"Sign":[]}"#, Expect::Err),
+    (App::Id, "malformed node name", r#"{"VerifyRemote":["no dots",[1],[2]]}"#, Expect::Err),
+    (App::Id, "node name with a NUL", r#"{"VerifyRemote":["a\u0000b.os",[1],[2]]}"#, Expect::Err),
+];
+
+/// one missing thing per app that can be missing: every app must report it
+///  with the same shared `not_found` code
+#[rustfmt::skip]
+const NOT_FOUND_CASES: &[(App, &str, &str)] = &[
+    (App::JendrixTune, "missing pitch log", r#"{"GetIntonationSummary":"1"}"#),
+    (App::Explorer, "missing file", r#"{"ReadFile":"/file-explorer:sys/home/no-such-file"}"#),
+    (App::Id, "unknown challenge", r#"{"SignChallenge":"no-such-challenge"}"#),
+];
+
+call_init!(init);
+fn init(our: Address) {
+    print_to_terminal(0, "begin");
+
+    loop {
+        handle_message(&our);
+    }
+}
+
+fn handle_message(our: &Address) {
+    let message = await_message()
+        .unwrap_or_else(|e| fail_with(format!("failed to receive tester message: {e:?}")));
+
+    if !message.is_request() {
+        fail_with("expected tester request message");
+    }
+
+    let source = message.source();
+    if our.node != source.node {
+        fail_with(format!("rejecting foreign message from {:?}", source));
+    }
+
+    let TesterRequest::Run(RunRequest {
+        input_node_names: node_names,
+        ..
+    }) = message
+        .body()
+        .try_into()
+        .unwrap_or_else(|e| fail_with(format!("failed to decode tester run request: {e:?}")));
+
+    print_to_terminal(0, "negative_test: start");
+
+    if our.node != node_names[0] {
+        Response::new()
+            .body(TesterResponse::Run(Ok(())))
+            .send()
+            .unwrap_or_else(|e| fail_with(format!("failed to send tester ack: {e:?}")));
+        return;
+    }
+
+    for &(app, name, body, expect) in CASES {
+        let address = app.address(&our.node);
+        if let Err(e) = run_case(&address, body, expect) {
+            fail_with(format!("{app:?} {name}: {e}"));
+        }
+        if let Err(e) = probe(&address, app.probe()) {
+            fail_with(format!("{app:?} did not survive {name}: {e}"));
+        }
+    }
+
+    for &(app, name, body) in NOT_FOUND_CASES {
+        match error_code(&app.address(&our.node), body) {
+            Ok(code) if code == app_error::NOT_FOUND => {}
+            Ok(code) => fail_with(format!(
+                "{app:?} {name}: expected code {}, got {code}",
+                app_error::NOT_FOUND
+            )),
+            Err(e) => fail_with(format!("{app:?} {name}: {e}")),
+        }
+    }
+
+    Response::new()
+        .body(TesterResponse::Run(Ok(())))
+        .send()
+        .unwrap_or_else(|e| fail_with(format!("failed to send tester success: {e:?}")));
+}
+
+fn run_case(address: &Address, body: &str, expect: Expect) -> Result<(), String> {
+    let timeout = match expect {
+        Expect::Err => TIMEOUT_SECS,
+        Expect::Dropped => DROPPED_TIMEOUT_SECS,
+    };
+    let sent = Request::to(address.clone())
+        .body(body.as_bytes().to_vec())
+        .send_and_await_response(timeout)
+        .map_err(|e| format!("failed to send: {e:?}"))?;
+
+    let response = match (sent, expect) {
+        (Ok(response), _) => response,
+        (Err(e), Expect::Dropped) if matches!(e.kind, SendErrorKind::Timeout) => return Ok(()),
+        (Err(e), Expect::Dropped) => return Err(format!("expected no answer, got {:?}", e.kind)),
+        (Err(e), Expect::Err) => return Err(format!("expected an Err answer, got {:?}", e.kind)),
+    };
+    if response.is_request() {
+        return Err("got a request instead of a response".to_string());
+    }
+    match serde_json::from_slice::<Value>(response.body()) {
+        Ok(Value::Object(result)) if result.contains_key("Err") => Ok(()),
+        Ok(other) => Err(format!("expected an Err answer, got {other}")),
+        Err(e) => Err(format!("answer is not JSON: {e}")),
+    }
+}
+
+fn probe(address: &Address, body: &str) -> Result<(), String> {
+    let body: Value = serde_json::from_str(body).map_err(|e| format!("bad probe: {e}"))?;
+    match call_json::<Value, Value>(address, &body, TIMEOUT_SECS, RETRIES) {
+        Ok(Value::Object(result)) if result.contains_key("Ok") => Ok(()),
+        Ok(other) => Err(format!("probe answered {other}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// the code of the error a request must fail with
+fn error_code(address: &Address, body: &str) -> Result<String, String> {
+    let body: Value = serde_json::from_str(body).map_err(|e| format!("bad case: {e}"))?;
+    match call_json::<Value, Result<Value, CodedError>>(address, &body, TIMEOUT_SECS, RETRIES) {
+        Ok(Err(e)) => Ok(e.code),
+        Ok(Ok(value)) => Err(format!("expected an error, got Ok({value})")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn fail_with(message: impl Into<String>) -> ! {
+    let message = message.into();
+    let log = format!("negative_test: error: {message}");
+    print_to_terminal(0, log.as_str());
+    fail!(message);
+}
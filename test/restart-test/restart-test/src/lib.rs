@@ -0,0 +1,224 @@
+use crate::hyperware::process::jendrix_tune::{Key, Scale, Status};
+use crate::hyperware::process::tester::{
+    FailResponse, Request as TesterRequest, Response as TesterResponse, RunRequest,
+};
+use hyperware_process_lib::{
+    await_message, call_init, print_to_terminal, Address, ProcessId, Response,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+mod restart;
+
+use tester_lib::config::TestConfig;
+use tester_lib::{assert_eq_ctx, call_json, call_ok, fail, step, CodedError, TestError};
+
+wit_bindgen::generate!({
+    path: "../target/wit",
+    world: "restart-test-template-dot-os-v0",
+    generate_unused_types: true,
+    additional_derives: [PartialEq, serde::Deserialize, serde::Serialize, process_macros::SerdeJsonInto],
+});
+
+const TIMEOUT_SECS: u64 = 15;
+
+/// extra attempts for a request that times out; a restarted process may
+///  still be starting up
+const RETRIES: u32 = 4;
+
+const SHARE_DIR: &str = "/file-explorer:sys/home/restart-test";
+
+const SHARED_BYTES: &[u8] = b"restart-test: shared before the restart\n";
+
+call_init!(init);
+fn init(our: Address) {
+    print_to_terminal(0, "begin");
+
+    loop {
+        handle_message(&our);
+    }
+}
+
+fn handle_message(our: &Address) {
+    let message = await_message()
+        .unwrap_or_else(|e| fail_with(format!("failed to receive tester message: {e:?}")));
+
+    if !message.is_request() {
+        fail_with("expected tester request message");
+    }
+
+    let source = message.source();
+    if our.node != source.node {
+        fail_with(format!("rejecting foreign message from {:?}", source));
+    }
+
+    // read before the body, while the Run request's blob is still current
+    let config = TestConfig::load(message.blob().as_ref().map(|blob| blob.bytes.as_slice()))
+        .unwrap_or_else(|e| fail_with(e));
+
+    let TesterRequest::Run(RunRequest {
+        input_node_names: node_names,
+        ..
+    }) = message
+        .body()
+        .try_into()
+        .unwrap_or_else(|e| fail_with(format!("failed to decode tester run request: {e:?}")));
+
+    print_to_terminal(0, "restart_test: start");
+
+    if our.node != node_names[0] {
+        Response::new()
+            .body(TesterResponse::Run(Ok(())))
+            .send()
+            .unwrap_or_else(|e| fail_with(format!("failed to send tester ack: {e:?}")));
+        return;
+    }
+
+    tester_lib::reset_steps();
+    for target in config.targets(&our.node) {
+        step!(
+            target.address.process.to_string(),
+            jendrix_survives_a_restart(&target.address)
+        )
+        .unwrap_or_else(|e| fail_with(e.to_string()));
+    }
+    let explorer = Address {
+        node: our.node.clone(),
+        process: ProcessId::new(Some("explorer"), "file-explorer", "sys"),
+    };
+    step!("explorer", shares_survive_a_restart(&explorer))
+        .unwrap_or_else(|e| fail_with(e.to_string()));
+    print_to_terminal(0, &format!("restart_test: {}", tester_lib::step_summary()));
+
+    Response::new()
+        .body(TesterResponse::Run(Ok(())))
+        .send()
+        .unwrap_or_else(|e| fail_with(format!("failed to send tester success: {e:?}")));
+}
+
+/// distinctive parameters, none of them a default, come back unchanged after
+///  a restart; a state saved by an older build is covered by jendrix-tune's
+///  own migrate tests
+fn jendrix_survives_a_restart(jendrix: &Address) -> Result<(), TestError> {
+    step!(
+        "set key",
+        call::<()>(jendrix, json!({ "SetKey": Key::FSharp }))
+    )?;
+    // a preset is applied as one update of every parameter
+    step!(
+        "apply a preset",
+        call::<()>(
+            jendrix,
+            json!({ "UpdateAllParams": [Key::FSharp, Scale::Minor, 0.2, 0.7, 0.6, false, true] })
+        )
+    )?;
+    let before: Status = step!("status before", call(jendrix, json!({ "GetStatus": null })))?;
+
+    step!("restart", restart::restart(&jendrix.node, &jendrix.process))?;
+
+    let after: Status = step!("status after", call(jendrix, json!({ "GetStatus": null })))?;
+    assert_eq_ctx!(
+        (before.key, before.scale),
+        (after.key, after.scale),
+        "key and scale after restart"
+    )?;
+    assert_eq_ctx!(
+        (before.formant_preserve, before.bypass),
+        (after.formant_preserve, after.bypass),
+        "switches after restart"
+    )?;
+    assert_eq_ctx!(before.revision, after.revision, "revision after restart")?;
+    expect_close(
+        "retune_speed after restart",
+        after.retune_speed,
+        before.retune_speed,
+    )?;
+    expect_close("humanize after restart", after.humanize, before.humanize)?;
+    expect_close("mix after restart", after.mix, before.mix)
+}
+
+/// a share keeps its link, access, limit and download count across a
+///  restart, and the link still serves the file
+fn shares_survive_a_restart(explorer: &Address) -> Result<(), TestError> {
+    let _ = call_json::<_, Result<Value, CodedError>>(
+        explorer,
+        &json!({ "DeleteDirectory": [SHARE_DIR, true] }),
+        TIMEOUT_SECS,
+        RETRIES,
+    )?;
+    let path = format!("{SHARE_DIR}/kept.txt");
+    step!("create a file", create_file(explorer, &path))?;
+    let link: String = step!(
+        "share it",
+        call(explorer, json!({ "ShareFile": [path, "Public", 5] }))
+    )?;
+    step!("download it once", fetch(explorer, &link))?;
+    let before = step!("share before", share_of(explorer, &path))?;
+
+    step!(
+        "restart",
+        restart::restart(&explorer.node, &explorer.process)
+    )?;
+
+    let after = step!("share after", share_of(explorer, &path))?;
+    assert_eq_ctx!(before, after, "share after restart")?;
+    step!("download it again", fetch(explorer, &link))?;
+
+    step!(
+        "clean up",
+        call::<bool>(explorer, json!({ "DeleteDirectory": [SHARE_DIR, true] }))
+    )?;
+    Ok(())
+}
+
+fn create_file(explorer: &Address, path: &str) -> Result<(), TestError> {
+    call::<Value>(explorer, json!({ "CreateDirectory": SHARE_DIR }))?;
+    call::<Value>(
+        explorer,
+        json!({ "CreateFile": [path, SHARED_BYTES, null] }),
+    )?;
+    Ok(())
+}
+
+fn fetch(explorer: &Address, link: &str) -> Result<(), TestError> {
+    let bytes: Vec<u8> = call(explorer, json!({ "FetchSharedFile": link }))?;
+    assert_eq_ctx!(SHARED_BYTES, bytes.as_slice(), "shared file contents")
+}
+
+/// `path`'s entry in list_shares
+fn share_of(explorer: &Address, path: &str) -> Result<Value, TestError> {
+    let shares: Vec<Value> = call(explorer, json!({ "ListShares": null }))?;
+    shares
+        .into_iter()
+        .find(|share| share["path"] == path)
+        .ok_or_else(|| TestError::Assertion {
+            error: format!("{path} missing from list_shares"),
+        })
+}
+
+fn call<T: DeserializeOwned>(address: &Address, body: Value) -> Result<T, TestError> {
+    call_ok::<_, T, CodedError>(address, &body, TIMEOUT_SECS, RETRIES)
+}
+
+/// parameters are f32 and go through JSON, so compare with a little slack
+fn expect_close(step: &str, actual: f32, expected: f32) -> Result<(), TestError> {
+    if (actual - expected).abs() > 1e-4 {
+        return Err(TestError::Mismatch {
+            step: step.to_string(),
+            expected: format!("{expected:?}"),
+            actual: format!("{actual:?}"),
+        });
+    }
+    Ok(())
+}
+
+/// give up, naming the step we were in and listing the ones that passed
+fn fail_with(message: impl Into<String>) -> ! {
+    let message = tester_lib::in_current_step(message.into());
+    let log = format!(
+        "restart_test: error: {message}\n{}",
+        tester_lib::step_summary()
+    );
+    print_to_terminal(0, log.as_str());
+    fail!(message);
+}
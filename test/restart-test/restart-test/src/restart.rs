@@ -0,0 +1,98 @@
+//! restart a process the way a reboot does: kill it, then initialize and run
+//!  it again from its own process map entry, so its init loads the state it
+//!  saved
+//!
+//! killing a process revokes every capability it issued, so each process
+//!  that held one, this tester among them, is granted it again before the
+//!  process runs
+
+use std::collections::HashSet;
+
+use hyperware_process_lib::kernel_types::{
+    KernelCommand, KernelPrint, KernelPrintResponse, KernelResponse,
+};
+use hyperware_process_lib::{Address, Capability, ProcessId, Request};
+
+use tester_lib::{call_json, TestError};
+
+const KERNEL_TIMEOUT_SECS: u64 = 15;
+
+pub fn restart(node: &str, process: &ProcessId) -> Result<(), TestError> {
+    let processes = match kernel(node, KernelCommand::Debug(KernelPrint::ProcessMap))? {
+        KernelResponse::Debug(KernelPrintResponse::ProcessMap(processes)) => processes,
+        other => return Err(unexpected("ProcessMap", &other)),
+    };
+    let Some(entry) = processes.get(process).cloned() else {
+        return Err(TestError::Assertion {
+            error: format!("{process} is not in the process map"),
+        });
+    };
+    // the capabilities `process` issued, by the process holding them
+    let issued: Vec<(ProcessId, Vec<Capability>)> = processes
+        .iter()
+        .filter(|(holder, _)| *holder != process)
+        .map(|(holder, persisted)| {
+            let caps = persisted
+                .capabilities
+                .iter()
+                .filter(|cap| cap.issuer.process == *process)
+                .cloned()
+                .collect::<Vec<_>>();
+            (holder.clone(), caps)
+        })
+        .filter(|(_, caps)| !caps.is_empty())
+        .collect();
+
+    match kernel(node, KernelCommand::KillProcess(process.clone()))? {
+        KernelResponse::KilledProcess(_) => {}
+        other => return Err(unexpected("KillProcess", &other)),
+    }
+    let initialize = KernelCommand::InitializeProcess {
+        id: process.clone(),
+        wasm_bytes_handle: entry.wasm_bytes_handle,
+        wit_version: entry.wit_version,
+        on_exit: entry.on_exit,
+        initial_capabilities: entry.capabilities.into_iter().collect::<HashSet<_>>(),
+        public: entry.public,
+    };
+    match kernel(node, initialize)? {
+        KernelResponse::InitializedProcess => {}
+        other => return Err(unexpected("InitializeProcess", &other)),
+    }
+    for (holder, capabilities) in issued {
+        let grant = KernelCommand::GrantCapabilities {
+            target: holder,
+            capabilities,
+        };
+        Request::to(kernel_address(node))
+            .body(serde_json::to_vec(&grant).map_err(|e| TestError::Encode {
+                request: "GrantCapabilities".to_string(),
+                error: e.to_string(),
+            })?)
+            .send()
+            .map_err(|e| TestError::Send {
+                request: "GrantCapabilities".to_string(),
+                error: format!("{e:?}"),
+            })?;
+    }
+    match kernel(node, KernelCommand::RunProcess(process.clone()))? {
+        KernelResponse::StartedProcess => Ok(()),
+        other => Err(unexpected("RunProcess", &other)),
+    }
+}
+
+fn kernel(node: &str, command: KernelCommand) -> Result<KernelResponse, TestError> {
+    // a kernel command is not retried: a second kill or initialize would not
+    //  do the same thing as the first
+    call_json(&kernel_address(node), &command, KERNEL_TIMEOUT_SECS, 0)
+}
+
+fn kernel_address(node: &str) -> Address {
+    Address::new(node, ("kernel", "distro", "sys"))
+}
+
+fn unexpected(command: &str, response: &KernelResponse) -> TestError {
+    TestError::Assertion {
+        error: format!("kernel answered {command} with {response:?}"),
+    }
+}
@@ -0,0 +1,243 @@
+use crate::hyperware::process::file_explorer::FileInfo;
+use crate::hyperware::process::id::{Attestation, SidecarCheck};
+use crate::hyperware::process::sign::SignedEnvelope;
+use crate::hyperware::process::tester::{
+    FailResponse, Request as TesterRequest, Response as TesterResponse, RunRequest,
+};
+use hyperware_process_lib::{
+    await_message, call_init, print_to_terminal, Address, ProcessId, Response,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use tester_lib::{call_json, call_ok, fail, CodedError, TestError};
+
+wit_bindgen::generate!({
+    path: "../target/wit",
+    world: "rpc-chain-test-template-dot-os-v0",
+    generate_unused_types: true,
+    additional_derives: [PartialEq, serde::Deserialize, serde::Serialize, process_macros::SerdeJsonInto],
+});
+
+const TIMEOUT_SECS: u64 = 15;
+
+/// extra attempts for a request that times out
+const RETRIES: u32 = 2;
+
+/// how long to wait for an answer to a request the target cannot decode
+const MISMATCH_TIMEOUT_SECS: u64 = 3;
+
+const CHAIN_DIR: &str = "/file-explorer:sys/home/rpc-chain-test";
+
+const MESSAGE: &[u8] = b"rpc-chain-test: signed by id, checked by sign, kept by file-explorer\n";
+
+/// where id's sign_file_at puts the envelope for a file
+const SIDECAR_SUFFIX: &str = ".sig.json";
+
+call_init!(init);
+fn init(our: Address) {
+    print_to_terminal(0, "begin");
+
+    loop {
+        handle_message(&our);
+    }
+}
+
+fn handle_message(our: &Address) {
+    let message = await_message()
+        .unwrap_or_else(|e| fail_with(format!("failed to receive tester message: {e:?}")));
+
+    if !message.is_request() {
+        fail_with("expected tester request message");
+    }
+
+    let source = message.source();
+    if our.node != source.node {
+        fail_with(format!("rejecting foreign message from {:?}", source));
+    }
+
+    let TesterRequest::Run(RunRequest {
+        input_node_names: node_names,
+        ..
+    }) = message
+        .body()
+        .try_into()
+        .unwrap_or_else(|e| fail_with(format!("failed to decode tester run request: {e:?}")));
+
+    print_to_terminal(0, "rpc_chain_test: start");
+
+    if our.node != node_names[0] {
+        Response::new()
+            .body(TesterResponse::Run(Ok(())))
+            .send()
+            .unwrap_or_else(|e| fail_with(format!("failed to send tester ack: {e:?}")));
+        return;
+    }
+
+    let id = Address {
+        node: our.node.clone(),
+        process: ProcessId::new(Some("id"), "id", "sys"),
+    };
+    let explorer = Address {
+        node: our.node.clone(),
+        process: ProcessId::new(Some("explorer"), "file-explorer", "sys"),
+    };
+
+    let envelope = run_chain(our, &id, &explorer).unwrap_or_else(|e| fail_with(e));
+    check_mismatch(&id, envelope).unwrap_or_else(|e| fail_with(format!("type mismatch: {e}")));
+
+    Response::new()
+        .body(TesterResponse::Run(Ok(())))
+        .send()
+        .unwrap_or_else(|e| fail_with(format!("failed to send tester success: {e:?}")));
+}
+
+/// id signs, sign verifies on id's behalf, the explorer stores the message,
+///  the signature and the envelope, and everything read back verifies again;
+///  returns the envelope as decoded from the explorer
+fn run_chain(our: &Address, id: &Address, explorer: &Address) -> Result<SignedEnvelope, String> {
+    // hop 1: id asks sign:sign:sys for a signature attributed to id
+    let signature: Vec<u8> = expect_ok(id, json!({ "Sign": MESSAGE }));
+    if signature.is_empty() {
+        return Err("id sign returned an empty signature".to_string());
+    }
+
+    // hop 2: id hands it to sign's verify_from over its caller_utils binding
+    if !verify_remote(id, &our.node, MESSAGE, &signature) {
+        return Err("sign rejected the signature id just made".to_string());
+    }
+
+    // hop 3: the explorer stores the message and the detached signature
+    let _ = call_json::<Value, Value>(
+        explorer,
+        &json!({ "DeleteDirectory": CHAIN_DIR }),
+        TIMEOUT_SECS,
+        RETRIES,
+    );
+    let _: FileInfo = expect_ok(explorer, json!({ "CreateDirectory": CHAIN_DIR }));
+    let message_path = format!("{CHAIN_DIR}/message.txt");
+    let signature_path = format!("{CHAIN_DIR}/message.sig");
+    let stored: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [message_path, MESSAGE, "Overwrite"] }),
+    );
+    if stored.size != MESSAGE.len() as u64 || stored.is_directory {
+        return Err(format!("create_file of the message answered {stored:?}"));
+    }
+    let _: FileInfo = expect_ok(
+        explorer,
+        json!({ "CreateFile": [signature_path, signature, "Overwrite"] }),
+    );
+
+    // hop 4: id has sign envelope the stored file and writes the envelope
+    //  next to it
+    let sidecar: String = expect_ok(id, json!({ "SignFileAt": message_path }));
+    if sidecar != format!("{message_path}{SIDECAR_SUFFIX}") {
+        return Err(format!("sign_file_at wrote its envelope to {sidecar}"));
+    }
+
+    // everything comes back through the explorer as the bytes that went in
+    let read_message: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": message_path }));
+    if read_message != MESSAGE {
+        return Err(format!("read back a different message: {read_message:?}"));
+    }
+    let read_signature: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": signature_path }));
+    if read_signature != signature {
+        return Err("read back a different signature".to_string());
+    }
+    let envelope_bytes: Vec<u8> = expect_ok(explorer, json!({ "ReadFile": sidecar }));
+    let envelope: SignedEnvelope = serde_json::from_slice(&envelope_bytes)
+        .map_err(|e| format!("the envelope does not decode as sign's SignedEnvelope: {e}"))?;
+    check_envelope(&envelope, &our.node)?;
+
+    // and verifies again
+    if !verify_remote(id, &our.node, &read_message, &read_signature) {
+        return Err("sign rejected the signature read back from the explorer".to_string());
+    }
+    let check: SidecarCheck = expect_ok(id, json!({ "VerifyFileAt": message_path }));
+    if check != SidecarCheck::Valid {
+        return Err(format!("verify_file_at of the stored message: {check:?}"));
+    }
+
+    // a changed file or a changed signature must no longer verify
+    let mut tampered = read_message.clone();
+    tampered.extend_from_slice(b"tampered\n");
+    let _: FileInfo = expect_ok(explorer, json!({ "UpdateFile": [message_path, tampered] }));
+    let check: SidecarCheck = expect_ok(id, json!({ "VerifyFileAt": message_path }));
+    if check != SidecarCheck::Modified {
+        return Err(format!("verify_file_at of a modified message: {check:?}"));
+    }
+    let mut flipped = read_signature;
+    flipped[0] ^= 0x01;
+    if verify_remote(id, &our.node, &read_message, &flipped) {
+        return Err("sign accepted a signature with a flipped bit".to_string());
+    }
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": CHAIN_DIR }));
+    Ok(envelope)
+}
+
+/// the envelope sign_file_at wrote must describe the message it was made for
+fn check_envelope(envelope: &SignedEnvelope, node: &str) -> Result<(), String> {
+    let expected_sha256 = hex::encode(Sha256::digest(MESSAGE));
+    if envelope.signer_node != node
+        || envelope.payload_sha256 != expected_sha256
+        || envelope.payload_len != Some(MESSAGE.len() as u64)
+        || envelope.signature.is_empty()
+    {
+        return Err(format!(
+            "envelope does not match the stored message ({expected_sha256}): {envelope:?}"
+        ));
+    }
+    Ok(())
+}
+
+/// a body of the wrong shape must fail cleanly on both sides: decoding an
+///  answer into the wrong generated type is an error, not a panic, and a
+///  request the target cannot decode is refused or dropped without taking the
+///  target down
+fn check_mismatch(id: &Address, envelope: SignedEnvelope) -> Result<(), String> {
+    let envelope_json = serde_json::to_value(&envelope).map_err(|e| e.to_string())?;
+    if let Ok(attestation) = serde_json::from_value::<Attestation>(envelope_json.clone()) {
+        return Err(format!(
+            "a SignedEnvelope decoded as an Attestation: {attestation:?}"
+        ));
+    }
+
+    // an envelope where verify_attestation takes an attestation
+    let body = json!({ "VerifyAttestation": [envelope_json, "rpc-chain-test", "mismatch"] });
+    match call_json::<Value, Result<Value, CodedError>>(id, &body, MISMATCH_TIMEOUT_SECS, 0) {
+        Ok(Err(_)) | Err(TestError::TimedOut { .. }) => {}
+        Ok(Ok(value)) => {
+            return Err(format!(
+                "id accepted an envelope as an attestation: {value}"
+            ));
+        }
+        Err(e) => return Err(e.to_string()),
+    }
+
+    // and id still answers afterwards
+    let signature: Vec<u8> = expect_ok(id, json!({ "Sign": MESSAGE }));
+    if signature.is_empty() {
+        return Err("id sign returned an empty signature after the mismatch".to_string());
+    }
+    Ok(())
+}
+
+/// id's verify_remote, which goes through sign's verify_from
+fn verify_remote(id: &Address, node: &str, message: &[u8], signature: &[u8]) -> bool {
+    expect_ok(id, json!({ "VerifyRemote": [node, message, signature] }))
+}
+
+fn expect_ok<T: DeserializeOwned>(address: &Address, body: Value) -> T {
+    call_ok::<_, T, CodedError>(address, &body, TIMEOUT_SECS, RETRIES)
+        .unwrap_or_else(|e| fail_with(e.to_string()))
+}
+
+fn fail_with(message: impl Into<String>) -> ! {
+    let message = message.into();
+    let log = format!("rpc_chain_test: error: {message}");
+    print_to_terminal(0, log.as_str());
+    fail!(message);
+}
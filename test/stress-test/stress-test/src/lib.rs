@@ -0,0 +1,256 @@
+use crate::hyperware::process::tester::{
+    FailResponse, Request as TesterRequest, Response as TesterResponse, RunRequest,
+};
+use hyperware_process_lib::{
+    await_message, call_init, print_to_terminal, Address, ProcessId, Request, Response,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use tester_lib::{call_json, call_ok, fail, CodedError};
+
+wit_bindgen::generate!({
+    path: "../target/wit",
+    world: "stress-test-template-dot-os-v0",
+    generate_unused_types: true,
+    additional_derives: [PartialEq, serde::Deserialize, serde::Serialize, process_macros::SerdeJsonInto],
+});
+
+const TIMEOUT_SECS: u64 = 30;
+
+/// extra attempts for the sequential setup and check requests
+const RETRIES: u32 = 2;
+
+/// files created in parallel, each interleaved with a list_directory
+const FILES: usize = 50;
+
+/// set_key calls in parallel, each interleaved with a get_status
+const KEY_CHANGES: usize = 100;
+
+const STRESS_DIR: &str = "/file-explorer:sys/home/stress-test";
+
+const KEYS: &[&str] = &["C", "CSharp", "D", "DSharp", "E", "F", "FSharp", "G"];
+
+/// the fields of a directory entry this test looks at
+#[derive(Debug, serde::Deserialize)]
+struct FileInfo {
+    name: String,
+    path: String,
+}
+
+/// the fields of jendrix-tune's status this test looks at
+#[derive(Debug, serde::Deserialize)]
+struct TunerStatus {
+    revision: u64,
+}
+
+call_init!(init);
+fn init(our: Address) {
+    print_to_terminal(0, "begin");
+
+    loop {
+        handle_message(&our);
+    }
+}
+
+fn handle_message(our: &Address) {
+    let message = await_message()
+        .unwrap_or_else(|e| fail_with(format!("failed to receive tester message: {e:?}")));
+
+    if !message.is_request() {
+        fail_with("expected tester request message");
+    }
+
+    let source = message.source();
+    if our.node != source.node {
+        fail_with(format!("rejecting foreign message from {:?}", source));
+    }
+
+    let TesterRequest::Run(RunRequest {
+        input_node_names: node_names,
+        ..
+    }) = message
+        .body()
+        .try_into()
+        .unwrap_or_else(|e| fail_with(format!("failed to decode tester run request: {e:?}")));
+
+    print_to_terminal(0, "stress_test: start");
+
+    if our.node != node_names[0] {
+        Response::new()
+            .body(TesterResponse::Run(Ok(())))
+            .send()
+            .unwrap_or_else(|e| fail_with(format!("failed to send tester ack: {e:?}")));
+        return;
+    }
+
+    let explorer = Address {
+        node: our.node.clone(),
+        process: ProcessId::new(Some("explorer"), "file-explorer", "sys"),
+    };
+    let jendrix_tune = Address {
+        node: our.node.clone(),
+        process: ProcessId::new(Some("jendrix-tune"), "jendrix-tune", "template.os"),
+    };
+
+    stress_explorer(&explorer).unwrap_or_else(|e| fail_with(format!("explorer: {e}")));
+    stress_jendrix_tune(&jendrix_tune).unwrap_or_else(|e| fail_with(format!("jendrix-tune: {e}")));
+
+    Response::new()
+        .body(TesterResponse::Run(Ok(())))
+        .send()
+        .unwrap_or_else(|e| fail_with(format!("failed to send tester success: {e:?}")));
+}
+
+/// create FILES files at once while listing the directory, then check every
+///  file is listed exactly once
+fn stress_explorer(explorer: &Address) -> Result<(), String> {
+    // a previous run that failed part way may have left the directory behind
+    let _ = call_json::<Value, Value>(
+        explorer,
+        &json!({ "DeleteDirectory": STRESS_DIR }),
+        TIMEOUT_SECS,
+        RETRIES,
+    );
+    let _: Value = expect_ok(explorer, json!({ "CreateDirectory": STRESS_DIR }));
+
+    let names: Vec<String> = (0..FILES).map(|i| format!("file-{i:02}.txt")).collect();
+    let bodies: Vec<Value> = names
+        .iter()
+        .flat_map(|name| {
+            [
+                json!({ "CreateFile": [format!("{STRESS_DIR}/{name}"), name.as_bytes(), "Overwrite"] }),
+                json!({ "ListDirectory": STRESS_DIR }),
+            ]
+        })
+        .collect();
+    for (body, answer) in bodies.iter().zip(fire_all(explorer, &bodies)?) {
+        answer.map_err(|e| format!("{body} failed under load: {e}"))?;
+    }
+
+    let listing: Vec<FileInfo> = expect_ok(explorer, json!({ "ListDirectory": STRESS_DIR }));
+    for name in &names {
+        let listed = listing
+            .iter()
+            .filter(|info| info.name == *name && info.path.ends_with(&format!("/{name}")))
+            .count();
+        if listed != 1 {
+            return Err(format!("{name} listed {listed} times in {listing:?}"));
+        }
+    }
+    if listing.len() != FILES {
+        return Err(format!(
+            "expected {FILES} entries, got {}: {listing:?}",
+            listing.len()
+        ));
+    }
+
+    let _: bool = expect_ok(explorer, json!({ "DeleteDirectory": STRESS_DIR }));
+    Ok(())
+}
+
+/// change the key KEY_CHANGES times at once while reading the status, then
+///  check the revision moved by exactly the number of changes that succeeded
+fn stress_jendrix_tune(jendrix_tune: &Address) -> Result<(), String> {
+    let before: TunerStatus = expect_ok(jendrix_tune, json!({ "GetStatus": null }));
+
+    let bodies: Vec<Value> = (0..KEY_CHANGES)
+        .flat_map(|i| {
+            [
+                json!({ "SetKey": KEYS[i % KEYS.len()] }),
+                json!({ "GetStatus": null }),
+            ]
+        })
+        .collect();
+    let mut mutations = 0;
+    for (body, answer) in bodies.iter().zip(fire_all(jendrix_tune, &bodies)?) {
+        answer.map_err(|e| format!("{body} failed under load: {e}"))?;
+        if body.get("SetKey").is_some() {
+            mutations += 1;
+        }
+    }
+
+    let after: TunerStatus = expect_ok(jendrix_tune, json!({ "GetStatus": null }));
+    if after.revision != before.revision + mutations {
+        return Err(format!(
+            "revision went from {} to {} over {mutations} successful set_key calls",
+            before.revision, after.revision
+        ));
+    }
+    Ok(())
+}
+
+/// send every body without waiting in between, then collect the answers in
+///  whatever order they arrive; answers come back in the order of `bodies`,
+///  and a request that got no answer at all fails the whole batch
+fn fire_all(address: &Address, bodies: &[Value]) -> Result<Vec<Result<Value, CodedError>>, String> {
+    for (i, body) in bodies.iter().enumerate() {
+        let bytes =
+            serde_json::to_vec(body).map_err(|e| format!("failed to encode {body}: {e}"))?;
+        Request::to(address.clone())
+            .body(bytes)
+            .context((i as u64).to_le_bytes().to_vec())
+            .expects_response(TIMEOUT_SECS)
+            .send()
+            .map_err(|e| format!("failed to send {body}: {e:?}"))?;
+    }
+
+    let mut answers: Vec<Option<Result<Value, CodedError>>> = vec![None; bodies.len()];
+    let mut dropped = Vec::new();
+    for _ in 0..bodies.len() {
+        match await_message() {
+            Ok(message) if message.is_request() => {
+                return Err(format!("unexpected request from {}", message.source()));
+            }
+            Ok(message) => {
+                let i = batch_index(message.context(), bodies.len())?;
+                let answer = serde_json::from_slice(message.body())
+                    .map_err(|e| format!("undecodable answer to {}: {e}", bodies[i]))?;
+                if answers[i].replace(answer).is_some() {
+                    return Err(format!("{} was answered twice", bodies[i]));
+                }
+            }
+            Err(e) => dropped.push(batch_index(e.context(), bodies.len())?),
+        }
+    }
+    if !dropped.is_empty() {
+        dropped.sort();
+        return Err(format!(
+            "{} of {} requests got no answer, e.g. {}",
+            dropped.len(),
+            bodies.len(),
+            bodies[dropped[0]]
+        ));
+    }
+
+    answers
+        .into_iter()
+        .enumerate()
+        .map(|(i, answer)| answer.ok_or_else(|| format!("{} was never answered", bodies[i])))
+        .collect()
+}
+
+/// which request of a fire_all batch a response or send error belongs to
+fn batch_index(context: Option<&[u8]>, len: usize) -> Result<usize, String> {
+    let bytes: [u8; 8] = context
+        .and_then(|context| context.try_into().ok())
+        .ok_or_else(|| format!("answer with a foreign context {context:?}"))?;
+    let i = u64::from_le_bytes(bytes) as usize;
+    if i < len {
+        Ok(i)
+    } else {
+        Err(format!("answer for request {i} of a batch of {len}"))
+    }
+}
+
+fn expect_ok<T: DeserializeOwned>(address: &Address, body: Value) -> T {
+    call_ok::<_, T, CodedError>(address, &body, TIMEOUT_SECS, RETRIES)
+        .unwrap_or_else(|e| fail_with(e.to_string()))
+}
+
+fn fail_with(message: impl Into<String>) -> ! {
+    let message = message.into();
+    let log = format!("stress_test: error: {message}");
+    print_to_terminal(0, log.as_str());
+    fail!(message);
+}